@@ -0,0 +1,13 @@
+-- Migration: Reliable aggregate computation
+-- Adds an organization timezone for period boundaries and per-domain watermarks
+-- recording the last completed aggregate period, so missed periods can be caught up.
+
+ALTER TABLE organizations ADD COLUMN timezone VARCHAR(64) NOT NULL DEFAULT 'UTC';
+
+CREATE TABLE aggregate_watermarks (
+    domain_id UUID NOT NULL REFERENCES domains(id) ON DELETE CASCADE,
+    period_type VARCHAR(20) NOT NULL CHECK (period_type IN ('hour', 'day', 'week', 'month')),
+    last_period_start TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ DEFAULT NOW(),
+    PRIMARY KEY (domain_id, period_type)
+);
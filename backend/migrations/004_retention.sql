@@ -0,0 +1,136 @@
+-- Migration: Data retention policies
+-- Per-organization retention overrides (NULL means use the global default) and
+-- optional monthly range partitioning of uptime_snapshots.
+
+CREATE TABLE retention_policies (
+    organization_id UUID PRIMARY KEY REFERENCES organizations(id) ON DELETE CASCADE,
+    uptime_snapshots_days INTEGER CHECK (uptime_snapshots_days > 0),
+    ssl_cert_snapshots_days INTEGER CHECK (ssl_cert_snapshots_days > 0),
+    domain_dns_snapshots_days INTEGER CHECK (domain_dns_snapshots_days > 0),
+    alerts_days INTEGER CHECK (alerts_days > 0),
+    hourly_aggregates_days INTEGER CHECK (hourly_aggregates_days > 0),
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    updated_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+CREATE TRIGGER update_retention_policies_updated_at BEFORE UPDATE ON retention_policies
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE INDEX idx_alerts_created ON alerts(created_at);
+CREATE INDEX idx_uptime_agg_type_period ON uptime_aggregates(period_type, period_start);
+
+-- ============================================================================
+-- Optional partitioning of uptime_snapshots
+-- ============================================================================
+
+-- Whether uptime_snapshots has been converted to a partitioned table
+CREATE OR REPLACE FUNCTION uptime_snapshots_is_partitioned()
+RETURNS BOOLEAN AS $$
+    SELECT EXISTS(
+        SELECT 1 FROM pg_partitioned_table pt
+        INNER JOIN pg_class c ON c.oid = pt.partrelid
+        WHERE c.relname = 'uptime_snapshots'
+    );
+$$ LANGUAGE sql;
+
+-- Create monthly partitions from from_month up to months_ahead months from now
+CREATE OR REPLACE FUNCTION ensure_uptime_snapshot_partitions(from_month TIMESTAMPTZ, months_ahead INTEGER)
+RETURNS void AS $$
+DECLARE
+    month_start TIMESTAMP := date_trunc('month', from_month AT TIME ZONE 'UTC');
+    last_month TIMESTAMP := date_trunc('month', NOW() AT TIME ZONE 'UTC') + make_interval(months => months_ahead);
+    partition_name TEXT;
+BEGIN
+    IF NOT uptime_snapshots_is_partitioned() THEN
+        RETURN;
+    END IF;
+
+    WHILE month_start <= last_month LOOP
+        partition_name := 'uptime_snapshots_' || to_char(month_start, 'YYYYMM');
+        IF to_regclass(partition_name) IS NULL THEN
+            EXECUTE format(
+                'CREATE TABLE %I PARTITION OF uptime_snapshots FOR VALUES FROM (%L) TO (%L)',
+                partition_name,
+                month_start AT TIME ZONE 'UTC',
+                (month_start + INTERVAL '1 month') AT TIME ZONE 'UTC'
+            );
+        END IF;
+        month_start := month_start + INTERVAL '1 month';
+    END LOOP;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Convert uptime_snapshots into a table range-partitioned by month.
+-- Copies existing rows, so run it during a quiet period on large tables.
+CREATE OR REPLACE FUNCTION partition_uptime_snapshots()
+RETURNS void AS $$
+DECLARE
+    oldest TIMESTAMPTZ;
+BEGIN
+    IF uptime_snapshots_is_partitioned() THEN
+        RETURN;
+    END IF;
+
+    ALTER TABLE uptime_snapshots RENAME TO uptime_snapshots_legacy;
+    ALTER TABLE uptime_snapshots_legacy RENAME CONSTRAINT uptime_snapshots_pkey TO uptime_snapshots_legacy_pkey;
+    ALTER INDEX idx_uptime_snapshots_domain_time RENAME TO idx_uptime_snapshots_legacy_domain_time;
+    ALTER INDEX idx_uptime_snapshots_status RENAME TO idx_uptime_snapshots_legacy_status;
+
+    CREATE TABLE uptime_snapshots (
+        id UUID NOT NULL DEFAULT gen_random_uuid(),
+        domain_id UUID NOT NULL REFERENCES domains(id) ON DELETE CASCADE,
+        check_time TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+        status_code INTEGER,
+        response_time_ms INTEGER,
+        is_up BOOLEAN NOT NULL,
+        error_type VARCHAR(100),
+        consecutive_failures INTEGER DEFAULT 0,
+        PRIMARY KEY (id, check_time)
+    ) PARTITION BY RANGE (check_time);
+
+    CREATE INDEX idx_uptime_snapshots_domain_time ON uptime_snapshots(domain_id, check_time DESC);
+    CREATE INDEX idx_uptime_snapshots_status ON uptime_snapshots(is_up, check_time);
+    CREATE TABLE uptime_snapshots_default PARTITION OF uptime_snapshots DEFAULT;
+
+    SELECT MIN(check_time) INTO oldest FROM uptime_snapshots_legacy;
+    PERFORM ensure_uptime_snapshot_partitions(COALESCE(oldest, NOW()), 3);
+
+    INSERT INTO uptime_snapshots (
+        id, domain_id, check_time, status_code, response_time_ms,
+        is_up, error_type, consecutive_failures
+    )
+    SELECT
+        id, domain_id, check_time, status_code, response_time_ms,
+        is_up, error_type, consecutive_failures
+    FROM uptime_snapshots_legacy;
+
+    DROP TABLE uptime_snapshots_legacy;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Drop monthly partitions that end before the cutoff. Returns the number dropped.
+CREATE OR REPLACE FUNCTION drop_uptime_snapshot_partitions_before(cutoff TIMESTAMPTZ)
+RETURNS INTEGER AS $$
+DECLARE
+    part RECORD;
+    partition_end TIMESTAMPTZ;
+    dropped INTEGER := 0;
+BEGIN
+    FOR part IN
+        SELECT c.relname
+        FROM pg_inherits i
+        INNER JOIN pg_class c ON c.oid = i.inhrelid
+        INNER JOIN pg_class p ON p.oid = i.inhparent
+        WHERE p.relname = 'uptime_snapshots'
+          AND c.relname ~ '^uptime_snapshots_[0-9]{6}$'
+    LOOP
+        partition_end := (to_date(right(part.relname, 6), 'YYYYMM') + INTERVAL '1 month') AT TIME ZONE 'UTC';
+        IF partition_end <= cutoff THEN
+            EXECUTE format('DROP TABLE %I', part.relname);
+            dropped := dropped + 1;
+        END IF;
+    END LOOP;
+
+    RETURN dropped;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,45 @@
+-- Migration: Service level objectives
+-- SLO definitions per domain and an hourly history of their evaluated state
+-- (SLI, remaining error budget and burn rates), computed from uptime_aggregates.
+
+CREATE TABLE slos (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    domain_id UUID NOT NULL REFERENCES domains(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    -- Percentage of good checks, e.g. 99.9
+    availability_target DOUBLE PRECISION NOT NULL CHECK (availability_target > 0 AND availability_target < 100),
+    -- Optional latency objective: hours whose percentile exceeds the threshold count as bad
+    latency_threshold_ms INTEGER CHECK (latency_threshold_ms > 0),
+    latency_percentile VARCHAR(3) NOT NULL DEFAULT 'p95' CHECK (latency_percentile IN ('p95', 'p99')),
+    window_days INTEGER NOT NULL DEFAULT 30 CHECK (window_days BETWEEN 1 AND 365),
+    fast_burn_threshold DOUBLE PRECISION NOT NULL DEFAULT 14.4 CHECK (fast_burn_threshold > 0),
+    slow_burn_threshold DOUBLE PRECISION NOT NULL DEFAULT 6 CHECK (slow_burn_threshold > 0),
+    is_enabled BOOLEAN NOT NULL DEFAULT true,
+    -- Last evaluated burn state, used to alert only on changes
+    burn_state VARCHAR(20) NOT NULL DEFAULT 'ok' CHECK (burn_state IN ('ok', 'slow_burn', 'fast_burn')),
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    updated_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+CREATE INDEX idx_slos_domain ON slos(domain_id);
+
+CREATE TRIGGER update_slos_updated_at BEFORE UPDATE ON slos
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE slo_snapshots (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    slo_id UUID NOT NULL REFERENCES slos(id) ON DELETE CASCADE,
+    -- End of the last hourly aggregate included
+    as_of TIMESTAMPTZ NOT NULL,
+    total_checks BIGINT NOT NULL,
+    good_checks BIGINT NOT NULL,
+    sli_percentage DOUBLE PRECISION,
+    -- Percentage of the error budget left over the window (negative when exhausted)
+    error_budget_remaining DOUBLE PRECISION NOT NULL,
+    burn_rate_1h DOUBLE PRECISION NOT NULL,
+    burn_rate_6h DOUBLE PRECISION NOT NULL,
+    burn_state VARCHAR(20) NOT NULL CHECK (burn_state IN ('ok', 'slow_burn', 'fast_burn')),
+    UNIQUE(slo_id, as_of)
+);
+
+CREATE INDEX idx_slo_snapshots_slo_time ON slo_snapshots(slo_id, as_of DESC);
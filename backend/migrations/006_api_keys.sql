@@ -0,0 +1,26 @@
+-- Migration: Organization-scoped API keys
+-- Keys are stored as SHA-256 hashes like refresh tokens; only a short prefix is
+-- kept in clear text so users can tell their keys apart.
+
+CREATE TABLE api_keys (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    created_by UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    key_prefix VARCHAR(16) NOT NULL,
+    key_hash VARCHAR(255) UNIQUE NOT NULL,
+    scopes VARCHAR(20)[] NOT NULL DEFAULT '{read}'
+        CHECK (scopes <@ ARRAY['read', 'write', 'trigger-check', 'admin']::VARCHAR(20)[]),
+    expires_at TIMESTAMPTZ,
+    last_used_at TIMESTAMPTZ,
+    last_used_ip VARCHAR(45),
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    updated_at TIMESTAMPTZ DEFAULT NOW(),
+    revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_api_keys_org ON api_keys(organization_id);
+CREATE INDEX idx_api_keys_hash ON api_keys(key_hash);
+
+CREATE TRIGGER update_api_keys_updated_at BEFORE UPDATE ON api_keys
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,46 @@
+-- Migration: Access token revocation
+-- Access tokens carry the user's token_version; bumping it invalidates every
+-- access token issued before. Individual tokens (e.g. on logout) are denied by jti.
+
+ALTER TABLE users ADD COLUMN token_version INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE revoked_access_tokens (
+    jti UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- Expiry of the token itself; the row can be dropped afterwards
+    expires_at TIMESTAMPTZ NOT NULL,
+    revoked_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+CREATE INDEX idx_revoked_access_tokens_expires ON revoked_access_tokens(expires_at);
+
+-- Password changes and deactivation invalidate outstanding access tokens
+CREATE OR REPLACE FUNCTION bump_user_token_version()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.password_hash IS DISTINCT FROM OLD.password_hash
+        OR NEW.is_active IS DISTINCT FROM OLD.is_active THEN
+        NEW.token_version = OLD.token_version + 1;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER bump_users_token_version BEFORE UPDATE ON users
+    FOR EACH ROW EXECUTE FUNCTION bump_user_token_version();
+
+-- So do membership removal and role changes
+CREATE OR REPLACE FUNCTION bump_member_token_version()
+RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE users SET token_version = token_version + 1 WHERE id = OLD.user_id;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER bump_member_token_version_on_delete AFTER DELETE ON organization_members
+    FOR EACH ROW EXECUTE FUNCTION bump_member_token_version();
+
+CREATE TRIGGER bump_member_token_version_on_role_change AFTER UPDATE OF role ON organization_members
+    FOR EACH ROW WHEN (NEW.role IS DISTINCT FROM OLD.role)
+    EXECUTE FUNCTION bump_member_token_version();
@@ -0,0 +1,20 @@
+-- Migration: Password reset and email verification
+-- Single-use, time-limited tokens sent by email. Only their SHA-256 hash is stored.
+
+ALTER TABLE users ADD COLUMN email_verified_at TIMESTAMPTZ;
+
+-- Existing accounts predate verification and are considered verified
+UPDATE users SET email_verified_at = created_at;
+
+CREATE TABLE user_tokens (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    purpose VARCHAR(30) NOT NULL CHECK (purpose IN ('password_reset', 'email_verification')),
+    token_hash VARCHAR(255) NOT NULL UNIQUE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+CREATE INDEX idx_user_tokens_user ON user_tokens(user_id, purpose);
+CREATE INDEX idx_user_tokens_expires ON user_tokens(expires_at);
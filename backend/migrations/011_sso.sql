@@ -0,0 +1,51 @@
+-- Migration: OpenID Connect single sign-on
+-- Each organization can configure one OIDC provider. Users provisioned through
+-- SSO have no password; their provider identity is linked in user_identities.
+
+ALTER TABLE users ALTER COLUMN password_hash DROP NOT NULL;
+
+CREATE TABLE sso_configs (
+    organization_id UUID PRIMARY KEY REFERENCES organizations(id) ON DELETE CASCADE,
+    issuer VARCHAR(500) NOT NULL,
+    client_id VARCHAR(255) NOT NULL,
+    client_secret VARCHAR(500) NOT NULL,
+    -- Email domains the provider may sign in, lowercase
+    allowed_email_domains VARCHAR(255)[] NOT NULL,
+    -- Role given to users joining the organization through SSO
+    default_role VARCHAR(20) NOT NULL DEFAULT 'member'
+        CHECK (default_role IN ('admin', 'member', 'viewer')),
+    enabled BOOLEAN NOT NULL DEFAULT true,
+    -- Members other than the owner cannot log in with a password
+    enforce_sso BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    updated_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+CREATE TRIGGER update_sso_configs_updated_at BEFORE UPDATE ON sso_configs
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- Provider identities linked to users
+CREATE TABLE user_identities (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    issuer VARCHAR(500) NOT NULL,
+    subject VARCHAR(255) NOT NULL,
+    email VARCHAR(255) NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    last_login_at TIMESTAMPTZ,
+    UNIQUE(issuer, subject)
+);
+
+CREATE INDEX idx_user_identities_user ON user_identities(user_id);
+
+-- Pending authorization requests, consumed by the callback
+CREATE TABLE sso_login_states (
+    state_hash VARCHAR(255) PRIMARY KEY,
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    nonce VARCHAR(255) NOT NULL,
+    pkce_verifier VARCHAR(255) NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+CREATE INDEX idx_sso_login_states_expires ON sso_login_states(expires_at);
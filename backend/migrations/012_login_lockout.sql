@@ -0,0 +1,6 @@
+-- Migration: Progressive lockout after failed logins
+-- Counts consecutive failed password and second factor attempts; once the
+-- threshold is reached, every further failure doubles the lockout.
+
+ALTER TABLE users ADD COLUMN failed_login_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE users ADD COLUMN locked_until TIMESTAMPTZ;
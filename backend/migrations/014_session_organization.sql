@@ -0,0 +1,6 @@
+-- Migration: Current organization of a session
+-- Access tokens are scoped to one organization. The session remembers it, so
+-- that tokens issued on refresh stay scoped to the organization the user
+-- switched to.
+
+ALTER TABLE sessions ADD COLUMN organization_id UUID REFERENCES organizations(id) ON DELETE SET NULL;
@@ -0,0 +1,41 @@
+-- Migration: Fine-grained permissions
+-- Organizations can define custom roles as named permission sets. A member with
+-- a custom role gets exactly its permissions instead of those of the built-in
+-- role. Members can also be restricted to a subset of the organization's
+-- domain groups.
+
+CREATE TABLE organization_roles (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    description TEXT,
+    permissions VARCHAR(50)[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    updated_at TIMESTAMPTZ DEFAULT NOW(),
+    UNIQUE(organization_id, name)
+);
+
+CREATE TRIGGER update_organization_roles_updated_at BEFORE UPDATE ON organization_roles
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE domain_groups (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    UNIQUE(organization_id, name)
+);
+
+ALTER TABLE domains ADD COLUMN group_id UUID REFERENCES domain_groups(id) ON DELETE SET NULL;
+CREATE INDEX idx_domains_group ON domains(group_id);
+
+-- Roles still assigned to members can't be deleted, so that removing a custom
+-- role never silently widens a member's access to their built-in role
+ALTER TABLE organization_members
+    ADD COLUMN custom_role_id UUID REFERENCES organization_roles(id),
+    -- NULL means the member can access every domain of the organization
+    ADD COLUMN domain_group_ids UUID[];
+
+ALTER TABLE alerts
+    ADD COLUMN acknowledged_at TIMESTAMPTZ,
+    ADD COLUMN acknowledged_by UUID REFERENCES users(id) ON DELETE SET NULL;
@@ -0,0 +1,22 @@
+-- Migration: Audit log
+-- Administrative actions are recorded with their actor and the fields they
+-- changed. Events outlive the organization, API key or user they refer to, so
+-- those columns are not foreign keys (except the acting user, kept for joins).
+
+CREATE TABLE audit_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    organization_id UUID NOT NULL,
+    actor_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    actor_api_key_id UUID,
+    action VARCHAR(100) NOT NULL,
+    target_type VARCHAR(50) NOT NULL,
+    target_id UUID,
+    -- Changed fields as {"field": {"before": ..., "after": ...}}
+    changes JSONB NOT NULL DEFAULT '{}'::jsonb,
+    ip_address VARCHAR(45),
+    user_agent TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_audit_events_org_time ON audit_events(organization_id, created_at DESC, id DESC);
+CREATE INDEX idx_audit_events_target ON audit_events(target_id);
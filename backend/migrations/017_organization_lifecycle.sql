@@ -0,0 +1,23 @@
+-- Migration: Ownership transfer and organization soft-delete
+-- Deleting an organization only marks it; monitoring pauses and the owner can
+-- restore it until purge_after, when the purge job removes it for good.
+-- Ownership moves to another member once they accept a pending transfer.
+
+ALTER TABLE organizations
+    ADD COLUMN deleted_at TIMESTAMPTZ,
+    ADD COLUMN purge_after TIMESTAMPTZ;
+
+CREATE INDEX idx_organizations_purge_after ON organizations(purge_after) WHERE deleted_at IS NOT NULL;
+
+-- At most one pending transfer per organization; rows are removed once the
+-- transfer is accepted, declined or cancelled
+CREATE TABLE ownership_transfers (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    organization_id UUID NOT NULL UNIQUE REFERENCES organizations(id) ON DELETE CASCADE,
+    from_user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    to_user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_ownership_transfers_to_user ON ownership_transfers(to_user_id);
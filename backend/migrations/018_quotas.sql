@@ -0,0 +1,14 @@
+-- Migration: Organization quotas
+-- Limits are set per organization by the operator; max_monitors already
+-- existed but was never enforced.
+
+UPDATE organizations SET max_monitors = 10 WHERE max_monitors IS NULL;
+
+ALTER TABLE organizations
+    ALTER COLUMN max_monitors SET NOT NULL,
+    -- Shortest interval a monitor may be checked at
+    ADD COLUMN min_check_interval_seconds INTEGER NOT NULL DEFAULT 60,
+    -- Longest retention period the organization may configure
+    ADD COLUMN max_retention_days INTEGER NOT NULL DEFAULT 365,
+    -- Maximum number of usable (neither revoked nor expired) API keys
+    ADD COLUMN max_api_keys INTEGER NOT NULL DEFAULT 10;
@@ -0,0 +1,43 @@
+-- Migration: Domain tags, nested domain groups and alert routes
+-- Domains carry key/value tags and groups can be nested. Both can be used to
+-- filter domain listings, to route alerts to webhooks and to split the public
+-- status page into sections.
+
+-- Removing a group moves its subgroups up to its parent (see delete_domain_group)
+ALTER TABLE domain_groups ADD COLUMN parent_id UUID REFERENCES domain_groups(id) ON DELETE SET NULL;
+CREATE INDEX idx_domain_groups_parent ON domain_groups(parent_id);
+
+-- Groups and all of their descendants
+CREATE FUNCTION domain_group_subtree(root_ids UUID[]) RETURNS UUID[] AS $$
+    WITH RECURSIVE subtree AS (
+        SELECT id FROM domain_groups WHERE id = ANY(root_ids)
+        UNION
+        SELECT g.id FROM domain_groups g JOIN subtree s ON g.parent_id = s.id
+    )
+    SELECT COALESCE(array_agg(id), '{}') FROM subtree
+$$ LANGUAGE sql STABLE STRICT;
+
+ALTER TABLE domains ADD COLUMN tags JSONB NOT NULL DEFAULT '{}'::jsonb;
+CREATE INDEX idx_domains_tags ON domains USING GIN (tags);
+
+-- Alerts of matching domains are posted to the route's webhook; alerts no route
+-- matches go to the organization's webhook
+CREATE TABLE alert_routes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    -- Only domains in this group or its subgroups (NULL for any group)
+    group_id UUID REFERENCES domain_groups(id) ON DELETE CASCADE,
+    -- Only domains having all of these tags
+    tags JSONB NOT NULL DEFAULT '{}'::jsonb,
+    min_severity VARCHAR(20) NOT NULL DEFAULT 'info' CHECK (min_severity IN ('info', 'warning', 'critical')),
+    webhook_url VARCHAR(2048) NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    updated_at TIMESTAMPTZ DEFAULT NOW(),
+    UNIQUE(organization_id, name)
+);
+
+CREATE TRIGGER update_alert_routes_updated_at BEFORE UPDATE ON alert_routes
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE INDEX idx_alerts_undelivered ON alerts(created_at) WHERE webhook_sent_at IS NULL;
@@ -0,0 +1,3 @@
+-- Migration: Pausing monitors
+-- The scheduler skips a paused monitor until paused_until has passed
+ALTER TABLE monitors ADD COLUMN paused_until TIMESTAMPTZ;
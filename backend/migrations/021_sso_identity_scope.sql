@@ -0,0 +1,48 @@
+-- Migration: Organization-scoped SSO identities
+-- A provider identity only signs in to the organization whose SSO config it
+-- came through. An existing account is linked to an identity only once its
+-- owner confirms by email; provisioned marks accounts created by the provider,
+-- which is then trusted with their second factor.
+
+ALTER TABLE user_identities
+    ADD COLUMN organization_id UUID REFERENCES organizations(id) ON DELETE CASCADE,
+    ADD COLUMN provisioned BOOLEAN NOT NULL DEFAULT false;
+
+-- Attach existing identities to the organization of their provider; identities
+-- of no current member can't be attributed and must be linked again
+UPDATE user_identities i
+SET organization_id = (
+    SELECT c.organization_id
+    FROM sso_configs c
+    JOIN organization_members m ON m.organization_id = c.organization_id AND m.user_id = i.user_id
+    WHERE c.issuer = i.issuer
+    ORDER BY m.created_at
+    LIMIT 1
+);
+DELETE FROM user_identities WHERE organization_id IS NULL;
+
+-- Accounts without a password can only have been created through SSO
+UPDATE user_identities i
+SET provisioned = true
+FROM users u
+WHERE u.id = i.user_id AND u.password_hash IS NULL;
+
+ALTER TABLE user_identities
+    ALTER COLUMN organization_id SET NOT NULL,
+    DROP CONSTRAINT user_identities_issuer_subject_key,
+    ADD CONSTRAINT user_identities_org_issuer_subject_key UNIQUE (organization_id, issuer, subject);
+
+-- Identities waiting for the account owner to confirm the link by email.
+-- Only the SHA-256 hash of the emailed token is stored.
+CREATE TABLE sso_link_requests (
+    token_hash VARCHAR(255) PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    issuer VARCHAR(500) NOT NULL,
+    subject VARCHAR(255) NOT NULL,
+    email VARCHAR(255) NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+CREATE INDEX idx_sso_link_requests_expires ON sso_link_requests(expires_at);
@@ -0,0 +1,5 @@
+-- Migration: Single sign-on sessions
+-- Organizations that enforce SSO only refuse sessions that their own identity
+-- provider did not sign in, instead of every password login of their members.
+
+ALTER TABLE sessions ADD COLUMN sso_organization_id UUID REFERENCES organizations(id) ON DELETE SET NULL;
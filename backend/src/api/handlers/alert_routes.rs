@@ -0,0 +1,181 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+    Json as JsonPayload,
+};
+use uuid::Uuid;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::api::routes::AppState;
+use crate::audit::Auditor;
+use crate::auth::{perm, OrgAccess};
+use crate::db::models::{AlertRoute, AuditAction, UpsertAlertRoute};
+use crate::db::queries;
+use crate::error::{AppError, AppResult};
+use crate::tags;
+
+// Response types
+#[derive(serde::Serialize, ToSchema)]
+pub struct AlertRouteResponse {
+    pub data: AlertRoute,
+}
+
+#[derive(serde::Serialize, ToSchema)]
+pub struct AlertRoutesResponse {
+    pub data: Vec<AlertRoute>,
+}
+
+/// Validate a route and check that its name is not used by another route
+async fn check_route_input(
+    state: &AppState,
+    organization_id: Uuid,
+    payload: &UpsertAlertRoute,
+    route_id: Option<Uuid>,
+) -> AppResult<()> {
+    payload.validate()
+        .map_err(|e| AppError::validation(format!("Invalid input: {}", e)))?;
+    tags::validate(&payload.tags)?;
+
+    if let Some(group_id) = payload.group_id {
+        queries::find_domain_group(&state.pool, organization_id, group_id).await?
+            .ok_or_else(|| AppError::validation("Domain group not found"))?;
+    }
+    if queries::alert_route_name_taken(&state.pool, organization_id, &payload.name, route_id).await? {
+        return Err(AppError::validation("An alert route with this name already exists"));
+    }
+
+    Ok(())
+}
+
+/// List alert routes
+///
+/// Alerts go to the webhook of every route matching their domain and severity;
+/// alerts no route matches go to the organization's webhook.
+#[utoipa::path(
+    get,
+    path = "/api/organizations/{id}/alert-routes",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID")
+    ),
+    responses(
+        (status = 200, description = "获取成功", body = AlertRoutesResponse),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权限管理告警路由")
+    )
+)]
+pub async fn list_alert_routes(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    _org: OrgAccess<perm::OrganizationManage>,
+) -> AppResult<impl IntoResponse> {
+    let routes = queries::list_alert_routes(&state.pool, id).await?;
+
+    Ok(Json(AlertRoutesResponse { data: routes }))
+}
+
+/// Create an alert route
+#[utoipa::path(
+    post,
+    path = "/api/organizations/{id}/alert-routes",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID")
+    ),
+    request_body = UpsertAlertRoute,
+    responses(
+        (status = 201, description = "创建成功", body = AlertRouteResponse),
+        (status = 400, description = "请求参数错误或路由名已存在"),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权限管理告警路由")
+    )
+)]
+pub async fn create_alert_route(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    _org: OrgAccess<perm::OrganizationManage>,
+    audit: Auditor,
+    JsonPayload(payload): JsonPayload<UpsertAlertRoute>,
+) -> AppResult<impl IntoResponse> {
+    check_route_input(&state, id, &payload, None).await?;
+
+    let route = queries::create_alert_route(&state.pool, id, &payload).await?;
+    audit.created(id, AuditAction::AlertRouteCreated, route.id, &route).await;
+
+    Ok((StatusCode::CREATED, Json(AlertRouteResponse { data: route })))
+}
+
+/// Replace an alert route
+#[utoipa::path(
+    put,
+    path = "/api/organizations/{id}/alert-routes/{route_id}",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID"),
+        ("route_id" = Uuid, Path, description = "告警路由ID")
+    ),
+    request_body = UpsertAlertRoute,
+    responses(
+        (status = 200, description = "更新成功", body = AlertRouteResponse),
+        (status = 400, description = "请求参数错误或路由名已存在"),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权限管理告警路由"),
+        (status = 404, description = "告警路由不存在")
+    )
+)]
+pub async fn update_alert_route(
+    State(state): State<AppState>,
+    Path((id, route_id)): Path<(Uuid, Uuid)>,
+    _org: OrgAccess<perm::OrganizationManage>,
+    audit: Auditor,
+    JsonPayload(payload): JsonPayload<UpsertAlertRoute>,
+) -> AppResult<impl IntoResponse> {
+    let before = queries::find_alert_route(&state.pool, id, route_id).await?
+        .ok_or_else(|| AppError::not_found("Alert route not found"))?;
+    check_route_input(&state, id, &payload, Some(route_id)).await?;
+
+    let route = queries::update_alert_route(&state.pool, id, route_id, &payload).await?
+        .ok_or_else(|| AppError::not_found("Alert route not found"))?;
+    audit.updated(id, AuditAction::AlertRouteUpdated, route_id, &before, &route).await;
+
+    Ok(Json(AlertRouteResponse { data: route }))
+}
+
+/// Delete an alert route
+#[utoipa::path(
+    delete,
+    path = "/api/organizations/{id}/alert-routes/{route_id}",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID"),
+        ("route_id" = Uuid, Path, description = "告警路由ID")
+    ),
+    responses(
+        (status = 204, description = "删除成功"),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权限管理告警路由"),
+        (status = 404, description = "告警路由不存在")
+    )
+)]
+pub async fn delete_alert_route(
+    State(state): State<AppState>,
+    Path((id, route_id)): Path<(Uuid, Uuid)>,
+    _org: OrgAccess<perm::OrganizationManage>,
+    audit: Auditor,
+) -> AppResult<impl IntoResponse> {
+    let route = queries::find_alert_route(&state.pool, id, route_id).await?
+        .ok_or_else(|| AppError::not_found("Alert route not found"))?;
+
+    if !queries::delete_alert_route(&state.pool, id, route_id).await? {
+        return Err(AppError::not_found("Alert route not found"));
+    }
+    audit.deleted(id, AuditAction::AlertRouteDeleted, route_id, &route).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,233 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+    Json as JsonPayload,
+};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::api::routes::AppState;
+use crate::audit::Auditor;
+use crate::auth::{display_prefix, generate_api_key, perm, sha256_hash, OrgAccess};
+use crate::db::models::{ApiKey, AuditAction, ApiKeyScope, CreateApiKey};
+use crate::db::queries;
+use crate::error::{AppError, AppResult};
+
+// Request types
+#[derive(serde::Deserialize, Validate, ToSchema)]
+pub struct UpdateApiKeyRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub name: Option<String>,
+    #[validate(length(min = 1))]
+    pub scopes: Option<Vec<ApiKeyScope>>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+// Response types
+#[derive(serde::Serialize, ToSchema)]
+pub struct ApiKeyResponse {
+    pub data: ApiKey,
+}
+
+#[derive(serde::Serialize, ToSchema)]
+pub struct ApiKeysResponse {
+    pub data: Vec<ApiKey>,
+}
+
+#[derive(serde::Serialize, ToSchema)]
+pub struct ApiKeyCreatedResponse {
+    pub data: ApiKey,
+    /// The full key; it is only returned once
+    pub key: String,
+}
+
+fn validate_expiry(expires_at: Option<DateTime<Utc>>) -> AppResult<()> {
+    match expires_at {
+        Some(exp) if exp <= Utc::now() => Err(AppError::validation("Expiry must be in the future")),
+        _ => Ok(()),
+    }
+}
+
+/// List organization API keys
+#[utoipa::path(
+    get,
+    path = "/api/organizations/{id}/api-keys",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID")
+    ),
+    responses(
+        (status = 200, description = "获取成功", body = ApiKeysResponse),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权限管理 API Key")
+    )
+)]
+pub async fn list_api_keys(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    _org: OrgAccess<perm::ApiKeysManage>,
+) -> AppResult<impl IntoResponse> {
+    let keys = queries::list_organization_api_keys(&state.pool, id).await?;
+
+    Ok(Json(ApiKeysResponse { data: keys }))
+}
+
+/// Create an organization API key
+#[utoipa::path(
+    post,
+    path = "/api/organizations/{id}/api-keys",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID")
+    ),
+    request_body = CreateApiKey,
+    responses(
+        (status = 201, description = "创建成功，完整 Key 仅返回一次", body = ApiKeyCreatedResponse),
+        (status = 400, description = "请求参数错误"),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权限管理 API Key 或超出 API Key 配额")
+    )
+)]
+pub async fn create_api_key(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    org: OrgAccess<perm::ApiKeysManage>,
+    audit: Auditor,
+    JsonPayload(payload): JsonPayload<CreateApiKey>,
+) -> AppResult<impl IntoResponse> {
+    payload.validate()
+        .map_err(|e| AppError::validation(format!("Invalid input: {}", e)))?;
+    validate_expiry(payload.expires_at)?;
+    org.access.require_grantable(ApiKeyScope::role_for(&payload.scopes).permissions())?;
+
+    let key = generate_api_key();
+    let api_key = queries::create_api_key(
+        &state.pool,
+        id,
+        org.auth.user_id,
+        &display_prefix(&key),
+        &sha256_hash(&key),
+        &payload,
+    ).await?;
+    audit.created(id, AuditAction::ApiKeyCreated, api_key.id, &api_key).await;
+
+    Ok((StatusCode::CREATED, Json(ApiKeyCreatedResponse { data: api_key, key })))
+}
+
+/// Get an organization API key
+#[utoipa::path(
+    get,
+    path = "/api/organizations/{id}/api-keys/{key_id}",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID"),
+        ("key_id" = Uuid, Path, description = "API Key ID")
+    ),
+    responses(
+        (status = 200, description = "获取成功", body = ApiKeyResponse),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权限管理 API Key"),
+        (status = 404, description = "API Key 不存在")
+    )
+)]
+pub async fn get_api_key(
+    State(state): State<AppState>,
+    Path((id, key_id)): Path<(Uuid, Uuid)>,
+    _org: OrgAccess<perm::ApiKeysManage>,
+) -> AppResult<impl IntoResponse> {
+    let api_key = queries::find_api_key(&state.pool, id, key_id).await?
+        .ok_or_else(|| AppError::not_found("API key not found"))?;
+
+    Ok(Json(ApiKeyResponse { data: api_key }))
+}
+
+/// Update an organization API key
+#[utoipa::path(
+    put,
+    path = "/api/organizations/{id}/api-keys/{key_id}",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID"),
+        ("key_id" = Uuid, Path, description = "API Key ID")
+    ),
+    request_body = UpdateApiKeyRequest,
+    responses(
+        (status = 200, description = "更新成功", body = ApiKeyResponse),
+        (status = 400, description = "请求参数错误"),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权限管理 API Key"),
+        (status = 404, description = "API Key 不存在")
+    )
+)]
+pub async fn update_api_key(
+    State(state): State<AppState>,
+    Path((id, key_id)): Path<(Uuid, Uuid)>,
+    org: OrgAccess<perm::ApiKeysManage>,
+    audit: Auditor,
+    JsonPayload(payload): JsonPayload<UpdateApiKeyRequest>,
+) -> AppResult<impl IntoResponse> {
+    payload.validate()
+        .map_err(|e| AppError::validation(format!("Invalid input: {}", e)))?;
+    validate_expiry(payload.expires_at)?;
+
+    let existing = queries::find_api_key(&state.pool, id, key_id).await?
+        .ok_or_else(|| AppError::not_found("API key not found"))?;
+
+    if existing.revoked_at.is_some() {
+        return Err(AppError::validation("Revoked API keys cannot be updated"));
+    }
+
+    if let Some(scopes) = &payload.scopes {
+        org.access.require_grantable(ApiKeyScope::role_for(scopes).permissions())?;
+    }
+
+    let before = existing.clone();
+    let name = payload.name.unwrap_or(existing.name);
+    let scopes = payload.scopes.map_or(existing.scopes, |s| CreateApiKey::scope_names(&s));
+    let expires_at = payload.expires_at.or(existing.expires_at);
+
+    let api_key = queries::update_api_key(&state.pool, key_id, &name, &scopes, expires_at).await?;
+    audit.updated(id, AuditAction::ApiKeyUpdated, key_id, &before, &api_key).await;
+
+    Ok(Json(ApiKeyResponse { data: api_key }))
+}
+
+/// Revoke an organization API key
+#[utoipa::path(
+    delete,
+    path = "/api/organizations/{id}/api-keys/{key_id}",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID"),
+        ("key_id" = Uuid, Path, description = "API Key ID")
+    ),
+    responses(
+        (status = 204, description = "吊销成功"),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权限管理 API Key"),
+        (status = 404, description = "API Key 不存在")
+    )
+)]
+pub async fn revoke_api_key(
+    State(state): State<AppState>,
+    Path((id, key_id)): Path<(Uuid, Uuid)>,
+    _org: OrgAccess<perm::ApiKeysManage>,
+    audit: Auditor,
+) -> AppResult<impl IntoResponse> {
+    let before = queries::find_api_key(&state.pool, id, key_id).await?
+        .ok_or_else(|| AppError::not_found("API key not found"))?;
+
+    queries::revoke_api_key(&state.pool, key_id).await?;
+    let after = queries::find_api_key(&state.pool, id, key_id).await?;
+    audit.updated(id, AuditAction::ApiKeyRevoked, key_id, &before, &after).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
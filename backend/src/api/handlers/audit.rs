@@ -0,0 +1,137 @@
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Json},
+};
+use serde::Deserialize;
+use sqlx::PgPool;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::api::routes::AppState;
+use crate::auth::{perm, OrgAccess};
+use crate::db::models::{AuditEvent, AuditEventFilter};
+use crate::db::queries;
+use crate::error::{AppError, AppResult};
+
+/// Events fetched per query while exporting
+const EXPORT_BATCH_SIZE: i64 = 1000;
+
+#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+#[into_params(parameter_in = Query)]
+pub struct AuditPageQuery {
+    /// Events per page (1-500)
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    /// `next_cursor` of the previous page
+    pub before: Option<Uuid>,
+}
+
+fn default_limit() -> i64 {
+    50
+}
+
+#[derive(serde::Serialize, ToSchema)]
+pub struct AuditLogResponse {
+    pub data: Vec<AuditEvent>,
+    /// Cursor of the next page, absent on the last page
+    pub next_cursor: Option<Uuid>,
+}
+
+/// List the audit log of an organization
+///
+/// Events are returned newest first.
+#[utoipa::path(
+    get,
+    path = "/api/organizations/{id}/audit-log",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID"),
+        AuditEventFilter,
+        AuditPageQuery
+    ),
+    responses(
+        (status = 200, description = "获取成功", body = AuditLogResponse),
+        (status = 400, description = "请求参数错误"),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权限查看审计日志")
+    )
+)]
+pub async fn list_audit_log(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    _org: OrgAccess<perm::AuditRead>,
+    Query(filter): Query<AuditEventFilter>,
+    Query(page): Query<AuditPageQuery>,
+) -> AppResult<impl IntoResponse> {
+    if !(1..=500).contains(&page.limit) {
+        return Err(AppError::validation("limit must be between 1 and 500"));
+    }
+
+    let events = queries::list_audit_events(&state.pool, id, &filter, page.before, page.limit).await?;
+    let next_cursor = if events.len() as i64 == page.limit {
+        events.last().map(|e| e.id)
+    } else {
+        None
+    };
+
+    Ok(Json(AuditLogResponse { data: events, next_cursor }))
+}
+
+/// Export the audit log of an organization
+///
+/// Streams every matching event as JSON lines, newest first.
+#[utoipa::path(
+    get,
+    path = "/api/organizations/{id}/audit-log/export",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID"),
+        AuditEventFilter
+    ),
+    responses(
+        (status = 200, description = "导出成功（JSON Lines）", content_type = "application/x-ndjson"),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权限查看审计日志")
+    )
+)]
+pub async fn export_audit_log(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    _org: OrgAccess<perm::AuditRead>,
+    Query(filter): Query<AuditEventFilter>,
+) -> AppResult<impl IntoResponse> {
+    let stream = futures::stream::try_unfold(
+        (state.pool, filter, None, false),
+        move |(pool, filter, before, done): (PgPool, AuditEventFilter, Option<Uuid>, bool)| async move {
+            if done {
+                return Ok(None);
+            }
+
+            let events = queries::list_audit_events(&pool, id, &filter, before, EXPORT_BATCH_SIZE).await?;
+            let done = (events.len() as i64) < EXPORT_BATCH_SIZE;
+            let before = events.last().map(|e| e.id);
+
+            let mut chunk = Vec::new();
+            for event in &events {
+                serde_json::to_writer(&mut chunk, event)
+                    .map_err(|e| AppError::internal(format!("Failed to encode audit event: {}", e)))?;
+                chunk.push(b'\n');
+            }
+
+            Ok::<_, AppError>(Some((chunk, (pool, filter, before, done || before.is_none()))))
+        },
+    );
+
+    let filename = format!("attachment; filename=\"audit-log-{}.jsonl\"", id);
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/x-ndjson".to_string()),
+            (header::CONTENT_DISPOSITION, filename),
+        ],
+        Body::from_stream(stream),
+    ))
+}
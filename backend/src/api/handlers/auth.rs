@@ -1,25 +1,27 @@
 use axum::{
-    extract::State,
+    extract::{Path, State},
     http::StatusCode,
     response::{IntoResponse, Json},
 };
 use serde_json::json;
 use uuid::Uuid;
-use chrono::{Utc, Duration as ChronoDuration};
-use sha2::{Sha256, Digest};
+use chrono::{DateTime, Utc, Duration as ChronoDuration};
 use utoipa::ToSchema;
 
 use crate::api::routes::AppState;
+use crate::audit::Auditor;
+use crate::db::models::{AuditAction, MemberRole, Organization, RefreshToken, Session, User, UserTokenPurpose};
 use crate::db::queries;
-use crate::auth::{hash_password, verify_password};
+use crate::api::handlers::two_factor::verify_second_factor;
+use crate::auth::{
+    generate_user_token, hash_password, sha256_hash, verify_password, AuthExtractor, ClientInfo,
+    MFA_CHALLENGE_ROLE, REFRESH_TOKEN_ROLE, USER_ROLE,
+};
+use crate::mailer::{self, Email};
 use crate::AppError;
 
-/// Helper function to compute SHA-256 hash
-fn sha256_hash(input: &str) -> String {
-    let mut hasher = Sha256::new();
-    hasher.update(input.as_bytes());
-    format!("{:x}", hasher.finalize())
-}
+/// Minimum length of a new password
+const MIN_PASSWORD_LENGTH: usize = 8;
 
 /// Request/response types for auth endpoints
 #[derive(serde::Deserialize, ToSchema)]
@@ -27,12 +29,28 @@ pub struct RegisterRequestJson {
     pub email: String,
     pub password: String,
     pub full_name: Option<String>,
+    /// Name of the device, shown in the session list
+    pub device_name: Option<String>,
+    /// Token from an invitation email; the user joins its organization
+    pub invitation_token: Option<String>,
 }
 
 #[derive(serde::Deserialize, ToSchema)]
 pub struct LoginRequestJson {
     pub email: String,
     pub password: String,
+    /// Name of the device, shown in the session list
+    pub device_name: Option<String>,
+}
+
+#[derive(serde::Deserialize, ToSchema)]
+pub struct LoginTwoFactorRequest {
+    /// Challenge token returned by `/api/auth/login`
+    pub mfa_token: String,
+    /// Current TOTP code, or a recovery code
+    pub code: String,
+    /// Name of the device, shown in the session list
+    pub device_name: Option<String>,
 }
 
 #[derive(serde::Deserialize, ToSchema)]
@@ -40,6 +58,63 @@ pub struct RefreshTokenRequest {
     pub refresh_token: String,
 }
 
+#[derive(serde::Deserialize, ToSchema)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+#[derive(serde::Deserialize, ToSchema)]
+pub struct EmailRequest {
+    pub email: String,
+}
+
+#[derive(serde::Deserialize, ToSchema)]
+pub struct ResetPasswordRequest {
+    /// Token from the password reset link
+    pub token: String,
+    pub new_password: String,
+}
+
+#[derive(serde::Deserialize, ToSchema)]
+pub struct VerifyEmailRequest {
+    /// Token from the verification link
+    pub token: String,
+}
+
+#[derive(serde::Deserialize, ToSchema)]
+pub struct SwitchOrganizationRequest {
+    pub organization_id: Uuid,
+}
+
+#[derive(serde::Deserialize, ToSchema)]
+pub struct LogoutRequest {
+    /// Refresh token of the session to end, when the access token has no session
+    pub refresh_token: Option<String>,
+}
+
+#[derive(serde::Serialize, ToSchema)]
+pub struct SessionResponse {
+    #[serde(flatten)]
+    pub session: Session,
+    /// Whether this is the session of the calling access token
+    pub current: bool,
+}
+
+#[derive(serde::Serialize, ToSchema)]
+pub struct SessionsResponse {
+    pub data: Vec<SessionResponse>,
+}
+
+/// Access token scoped to another organization; the refresh token stays valid
+#[derive(serde::Serialize, ToSchema)]
+pub struct SwitchOrganizationResponse {
+    pub access_token: String,
+    pub organization: Organization,
+    /// Role of the user in the organization, also carried in the token's `role` claim
+    pub role: MemberRole,
+}
+
 #[derive(serde::Serialize, ToSchema)]
 pub struct AuthResponse {
     pub access_token: String,
@@ -47,11 +122,25 @@ pub struct AuthResponse {
     pub user: UserResponse,
 }
 
+/// Password accepted; the login must be completed with a second factor
+#[derive(serde::Serialize, ToSchema)]
+pub struct MfaChallengeResponse {
+    /// Short-lived token to send to `/api/auth/login/2fa`
+    pub mfa_token: String,
+}
+
+/// Registration awaiting email verification; tokens are issued on login once verified
+#[derive(serde::Serialize, ToSchema)]
+pub struct VerificationPendingResponse {
+    pub user: UserResponse,
+}
+
 #[derive(serde::Serialize, ToSchema)]
 pub struct UserResponse {
     pub id: Uuid,
     pub email: String,
     pub full_name: Option<String>,
+    pub email_verified: bool,
     pub created_at: String,
     pub last_login_at: Option<String>,
 }
@@ -62,12 +151,227 @@ impl From<crate::db::models::User> for UserResponse {
             id: user.id,
             email: user.email,
             full_name: user.full_name,
+            email_verified: user.email_verified_at.is_some(),
             created_at: user.created_at.to_rfc3339(),
             last_login_at: user.last_login_at.map(|t| t.to_rfc3339()),
         }
     }
 }
 
+/// Expiry of a refresh token issued now
+///
+/// Sessions expire with their latest refresh token.
+fn refresh_token_expiry(state: &AppState) -> Result<DateTime<Utc>, AppError> {
+    Ok(Utc::now() + ChronoDuration::from_std(state.config.auth.refresh_token_duration)
+        .map_err(|e| AppError::internal(format!("Invalid duration: {}", e)))?)
+}
+
+/// Start a new session for a login
+///
+/// `sso_organization_id` is the organization whose identity provider signed
+/// the user in, which lets the session into that organization if it enforces SSO.
+async fn start_session(
+    state: &AppState,
+    user_id: Uuid,
+    sso_organization_id: Option<Uuid>,
+    device_name: Option<&str>,
+    client: &ClientInfo,
+) -> Result<Session, AppError> {
+    queries::create_session(
+        &state.pool,
+        user_id,
+        device_name.map(str::trim).filter(|name| !name.is_empty()),
+        client.ip.as_deref(),
+        client.user_agent.as_deref(),
+        sso_organization_id,
+        refresh_token_expiry(state)?,
+    ).await
+}
+
+/// Generate an access token scoped to an organization, carrying the user's role in it
+///
+/// Falls back to the user's first organization when `org_id` is unset or the
+/// user is no longer a member of it. The session remembers the organization, so
+/// that tokens issued on refresh stay scoped to it.
+async fn issue_access_token(
+    state: &AppState,
+    user: &User,
+    org_id: Option<Uuid>,
+    session_id: Option<Uuid>,
+) -> Result<(String, Option<(Uuid, MemberRole)>), AppError> {
+    let mut membership = match org_id {
+        Some(org_id) => queries::get_user_role(&state.pool, org_id, user.id).await?.map(|role| (org_id, role)),
+        None => None,
+    };
+    if membership.is_none() {
+        if let Some(org) = queries::list_user_organizations(&state.pool, user.id).await?.first() {
+            membership = queries::get_user_role(&state.pool, org.id, user.id).await?.map(|role| (org.id, role));
+        }
+    }
+
+    let org_id = membership.as_ref().map(|(org_id, _)| *org_id);
+    let role = membership.as_ref().map_or_else(|| USER_ROLE.to_string(), |(_, role)| role.to_string());
+    let access_token = state.jwt_service.generate_access_token(
+        user.id,
+        org_id,
+        &role,
+        session_id,
+        user.token_version,
+    )?;
+
+    if let Some(session_id) = session_id {
+        queries::set_session_organization(&state.pool, session_id, org_id).await?;
+    }
+
+    Ok((access_token, membership))
+}
+
+/// Generate an access token and store a new refresh token in a session
+async fn issue_tokens(
+    state: &AppState,
+    user: &User,
+    org_id: Option<Uuid>,
+    session_id: Uuid,
+) -> Result<(String, String, RefreshToken), AppError> {
+    let (access_token, _) = issue_access_token(state, user, org_id, Some(session_id)).await?;
+    let refresh_token = state.jwt_service.generate_refresh_token(user.id, session_id)?;
+
+    // Hash refresh token and store
+    let token_hash = sha256_hash(&refresh_token);
+    let expires_at = refresh_token_expiry(state)?;
+    let stored = queries::create_refresh_token(&state.pool, user.id, session_id, &token_hash, expires_at).await?;
+
+    Ok((access_token, refresh_token, stored))
+}
+
+/// Revoke the token family of a refresh token that was presented after being rotated
+///
+/// Tokens issued before sessions existed have no family, so every session of the user is revoked.
+async fn revoke_token_family(state: &AppState, token: &RefreshToken) -> Result<(), AppError> {
+    tracing::warn!("Refresh token reuse detected for user {}", token.user_id);
+
+    match token.session_id {
+        Some(session_id) => {
+            queries::revoke_session(&state.pool, token.user_id, session_id).await?;
+        }
+        None => {
+            queries::revoke_all_user_sessions(&state.pool, token.user_id).await?;
+            queries::revoke_all_user_tokens(&state.pool, token.user_id).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Check the length of a new password
+fn validate_new_password(password: &str) -> Result<(), AppError> {
+    if password.len() < MIN_PASSWORD_LENGTH {
+        return Err(AppError::validation(format!(
+            "Password must be at least {} characters",
+            MIN_PASSWORD_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+/// Create a single-use token for a user and email them its link
+async fn send_user_token(state: &AppState, user: &User, purpose: UserTokenPurpose) -> Result<(), AppError> {
+    let (valid_for, path) = match purpose {
+        UserTokenPurpose::PasswordReset => (state.config.auth.password_reset_ttl, "reset-password"),
+        UserTokenPurpose::EmailVerification => (state.config.auth.email_verification_ttl, "verify-email"),
+    };
+
+    let token = generate_user_token();
+    let expires_at = Utc::now() + ChronoDuration::from_std(valid_for)
+        .map_err(|e| AppError::internal(format!("Invalid duration: {}", e)))?;
+    queries::create_user_token(&state.pool, user.id, purpose, &sha256_hash(&token), expires_at).await?;
+
+    let link = format!("{}/{}?token={}", state.config.server.public_url.trim_end_matches('/'), path, token);
+    let email = match purpose {
+        UserTokenPurpose::PasswordReset => Email::password_reset(&user.email, &link, valid_for),
+        UserTokenPurpose::EmailVerification => Email::email_verification(&user.email, &link, valid_for),
+    };
+    mailer::send_in_background(state.mailer.clone(), email);
+
+    Ok(())
+}
+
+/// Start a session for an authenticated user and issue their tokens
+///
+/// The access token is scoped to `org_id`, or else to the user's first
+/// organization. `sso_organization_id` is set for logins through an
+/// organization's identity provider.
+pub(crate) async fn complete_login(
+    state: &AppState,
+    user: User,
+    org_id: Option<Uuid>,
+    sso_organization_id: Option<Uuid>,
+    device_name: Option<&str>,
+    client: &ClientInfo,
+) -> Result<AuthResponse, AppError> {
+    // Update last login
+    let _ = queries::update_last_login(&state.pool, user.id).await;
+    queries::reset_failed_logins(&state.pool, user.id).await?;
+
+    // Generate tokens
+    let session = start_session(state, user.id, sso_organization_id, device_name, client).await?;
+    let (access_token, refresh_token, _) = issue_tokens(state, &user, org_id, session.id).await?;
+
+    Ok(AuthResponse {
+        access_token,
+        refresh_token,
+        user: UserResponse::from(user),
+    })
+}
+
+/// Refuse logins to an account locked after repeated failures
+fn check_lockout(user: &User) -> Result<(), AppError> {
+    match user.locked_until {
+        Some(until) if until > Utc::now() => Err(AppError::rate_limited(
+            "Too many failed login attempts, please try again later",
+            (until - Utc::now()).to_std().unwrap_or_default(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Count a failed password or second factor against the account
+async fn record_login_failure(state: &AppState, user: &User) -> Result<(), AppError> {
+    let config = &state.config.rate_limit;
+    if !config.enabled || config.lockout_threshold == 0 {
+        return Ok(());
+    }
+
+    let locked_until = queries::record_failed_login(
+        &state.pool,
+        user.id,
+        i32::try_from(config.lockout_threshold).unwrap_or(i32::MAX),
+        i64::try_from(config.lockout_base.as_secs()).unwrap_or(i64::MAX),
+        i64::try_from(config.lockout_max.as_secs()).unwrap_or(i64::MAX),
+    ).await?;
+
+    if let Some(until) = locked_until.filter(|until| *until > Utc::now()) {
+        tracing::warn!("Locked account {} until {} after repeated failed logins", user.id, until);
+    }
+
+    Ok(())
+}
+
+/// Check a password against a user's hash; users provisioned through SSO have none
+pub(crate) fn check_password(user: &User, password: &str) -> Result<bool, AppError> {
+    user.password_hash
+        .as_deref()
+        .map_or(Ok(false), |hash| verify_password(password, hash))
+}
+
+/// Reject API keys on endpoints that manage the caller's own login sessions
+pub(crate) fn require_user_token(auth: &AuthExtractor) -> Result<(), AppError> {
+    if auth.0.api_key.is_some() {
+        return Err(AppError::authorization("API keys cannot manage sessions"));
+    }
+    Ok(())
+}
+
 /// Register a new user
 #[utoipa::path(
     post,
@@ -76,11 +380,14 @@ impl From<crate::db::models::User> for UserResponse {
     request_body = RegisterRequestJson,
     responses(
         (status = 201, description = "注册成功", body = AuthResponse),
-        (status = 400, description = "请求参数错误", body = crate::error::ErrorResponse)
+        (status = 202, description = "注册成功，需验证邮箱后登录", body = VerificationPendingResponse),
+        (status = 400, description = "请求参数错误，或邀请无效、已过期", body = crate::error::ErrorResponse),
+        (status = 429, description = "请求过于频繁", body = crate::error::ErrorResponse)
     )
 )]
 pub async fn register(
     State(state): State<AppState>,
+    client: ClientInfo,
     Json(payload): Json<RegisterRequestJson>,
 ) -> Result<impl IntoResponse, AppError> {
     // Check if user already exists
@@ -92,6 +399,38 @@ pub async fn register(
     // Hash password
     let password_hash = hash_password(&payload.password, state.config.auth.bcrypt_rounds)?;
 
+    // The invitation was emailed to the address, so it needs no further verification
+    if let Some(token) = payload.invitation_token.as_deref() {
+        let (user, invitation) = queries::register_invited_user(
+            &state.pool,
+            &sha256_hash(token.trim()),
+            &payload.email,
+            &password_hash,
+            payload.full_name.as_deref(),
+        ).await?
+        .ok_or_else(|| AppError::validation("Invalid or expired invitation for this email address"))?;
+
+        Auditor::new(state.pool.clone(), Some(user.id), client.clone())
+            .updated(
+                invitation.organization_id,
+                AuditAction::InvitationAccepted,
+                invitation.id,
+                &json!({ "accepted_at": null, "accepted_by": null }),
+                &json!({ "accepted_at": invitation.accepted_at, "accepted_by": invitation.accepted_by }),
+            )
+            .await;
+
+        let response = complete_login(
+            &state,
+            user,
+            Some(invitation.organization_id),
+            None,
+            payload.device_name.as_deref(),
+            &client,
+        ).await?;
+        return Ok((StatusCode::CREATED, Json(response)).into_response());
+    }
+
     // Create user
     let user = queries::create_user(
         &state.pool,
@@ -100,15 +439,16 @@ pub async fn register(
         payload.full_name.as_deref(),
     ).await?;
 
-    // Generate tokens
-    let access_token = state.jwt_service.generate_access_token(user.id, None, "user")?;
-    let refresh_token = state.jwt_service.generate_refresh_token(user.id)?;
+    send_user_token(&state, &user, UserTokenPurpose::EmailVerification).await?;
 
-    // Hash refresh token and store
-    let token_hash = sha256_hash(&refresh_token);
-    let expires_at = Utc::now() + ChronoDuration::from_std(state.config.auth.refresh_token_duration)
-        .map_err(|e| AppError::internal(format!("Invalid duration: {}", e)))?;
-    queries::create_refresh_token(&state.pool, user.id, &token_hash, expires_at).await?;
+    if state.config.auth.require_email_verification {
+        let response = VerificationPendingResponse { user: UserResponse::from(user) };
+        return Ok((StatusCode::ACCEPTED, Json(response)).into_response());
+    }
+
+    // Generate tokens
+    let session = start_session(&state, user.id, None, payload.device_name.as_deref(), &client).await?;
+    let (access_token, refresh_token, _) = issue_tokens(&state, &user, None, session.id).await?;
 
     let response = AuthResponse {
         access_token,
@@ -116,7 +456,7 @@ pub async fn register(
         user: UserResponse::from(user),
     };
 
-    Ok((StatusCode::CREATED, Json(response)))
+    Ok((StatusCode::CREATED, Json(response)).into_response())
 }
 
 /// Login
@@ -127,49 +467,79 @@ pub async fn register(
     request_body = LoginRequestJson,
     responses(
         (status = 200, description = "登录成功", body = AuthResponse),
-        (status = 401, description = "认证失败", body = crate::error::ErrorResponse)
+        (status = 202, description = "需要两步验证", body = MfaChallengeResponse),
+        (status = 401, description = "认证失败", body = crate::error::ErrorResponse),
+        (status = 403, description = "邮箱尚未验证", body = crate::error::ErrorResponse),
+        (status = 429, description = "请求过于频繁或账号已被临时锁定", body = crate::error::ErrorResponse)
     )
 )]
 pub async fn login(
     State(state): State<AppState>,
+    client: ClientInfo,
     Json(payload): Json<LoginRequestJson>,
 ) -> Result<impl IntoResponse, AppError> {
     // Find user
     let user = queries::find_user_by_email(&state.pool, &payload.email).await?
         .ok_or_else(|| AppError::auth("Invalid email or password"))?;
 
+    check_lockout(&user)?;
+
     // Verify password
-    let is_valid = verify_password(&payload.password, &user.password_hash)?;
+    let is_valid = check_password(&user, &payload.password)?;
     if !is_valid {
+        record_login_failure(&state, &user).await?;
         return Err(AppError::auth("Invalid email or password"));
     }
 
-    // Update last login
-    let _ = queries::update_last_login(&state.pool, user.id).await;
+    if state.config.auth.require_email_verification && user.email_verified_at.is_none() {
+        return Err(AppError::authorization("Email address has not been verified"));
+    }
 
-    // Get user's default organization (first one)
-    let orgs = queries::list_user_organizations(&state.pool, user.id).await?;
-    let org_id = orgs.first().map(|org| org.id);
+    if user.totp_enabled_at.is_some() {
+        let mfa_token = state.jwt_service.generate_mfa_token(user.id, None, user.token_version)?;
+        return Ok((StatusCode::ACCEPTED, Json(MfaChallengeResponse { mfa_token })).into_response());
+    }
 
-    // Generate tokens
-    let access_token = state.jwt_service.generate_access_token(
-        user.id,
-        org_id,
-        "user",
-    )?;
-    let refresh_token = state.jwt_service.generate_refresh_token(user.id)?;
+    let response = complete_login(&state, user, None, None, payload.device_name.as_deref(), &client).await?;
 
-    // Hash refresh token and store
-    let token_hash = sha256_hash(&refresh_token);
-    let expires_at = Utc::now() + ChronoDuration::from_std(state.config.auth.refresh_token_duration)
-        .map_err(|e| AppError::internal(format!("Invalid duration: {}", e)))?;
-    queries::create_refresh_token(&state.pool, user.id, &token_hash, expires_at).await?;
+    Ok(Json(response).into_response())
+}
 
-    let response = AuthResponse {
-        access_token,
-        refresh_token,
-        user: UserResponse::from(user),
-    };
+/// Complete a login with a second factor
+#[utoipa::path(
+    post,
+    path = "/api/auth/login/2fa",
+    tag = "认证",
+    request_body = LoginTwoFactorRequest,
+    responses(
+        (status = 200, description = "登录成功", body = AuthResponse),
+        (status = 401, description = "挑战令牌无效或已过期，或验证码错误", body = crate::error::ErrorResponse),
+        (status = 429, description = "请求过于频繁或账号已被临时锁定", body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn login_two_factor(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Json(payload): Json<LoginTwoFactorRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let claims = state.jwt_service.validate_token(&payload.mfa_token)?;
+    if claims.role != MFA_CHALLENGE_ROLE {
+        return Err(AppError::auth("Invalid MFA token"));
+    }
+
+    // A password change since the challenge was issued invalidates it
+    let user = queries::find_user_by_id(&state.pool, claims.sub).await?
+        .filter(|user| user.token_version == claims.ver)
+        .ok_or_else(|| AppError::auth("Invalid MFA token"))?;
+    check_lockout(&user)?;
+
+    if !verify_second_factor(&state, &user, &payload.code).await? {
+        record_login_failure(&state, &user).await?;
+        return Err(AppError::auth("Invalid two-factor code"));
+    }
+
+    // Challenges of SSO logins carry the organization of the identity provider
+    let response = complete_login(&state, user, claims.org, claims.org, payload.device_name.as_deref(), &client).await?;
 
     Ok(Json(response))
 }
@@ -182,22 +552,23 @@ pub async fn login(
     request_body = RefreshTokenRequest,
     responses(
         (status = 200, description = "刷新成功", body = AuthResponse),
-        (status = 401, description = "刷新令牌无效或已过期", body = crate::error::ErrorResponse)
+        (status = 401, description = "刷新令牌无效、已过期或被重复使用", body = crate::error::ErrorResponse)
     )
 )]
 pub async fn refresh_token(
     State(state): State<AppState>,
+    client: ClientInfo,
     Json(payload): Json<RefreshTokenRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     // Validate refresh token
     let claims = state.jwt_service.validate_token(&payload.refresh_token)?;
 
     // Check if it's actually a refresh token
-    if claims.role != "refresh" {
+    if claims.role != REFRESH_TOKEN_ROLE {
         return Err(AppError::auth("Invalid refresh token"));
     }
 
-    // Verify token exists in database and is not revoked
+    // Verify token exists in database
     let token_hash = sha256_hash(&payload.refresh_token);
     let stored_token = queries::find_refresh_token(&state.pool, &token_hash).await?
         .ok_or_else(|| AppError::auth("Invalid or expired refresh token"))?;
@@ -207,30 +578,38 @@ pub async fn refresh_token(
         return Err(AppError::auth("Token mismatch"));
     }
 
+    // A rotated token being presented again means it was stolen, or the legitimate
+    // client is replaying it: end the whole token family either way
+    if stored_token.is_revoked {
+        if stored_token.replaced_by.is_some() {
+            revoke_token_family(&state, &stored_token).await?;
+        }
+        return Err(AppError::auth("Invalid or expired refresh token"));
+    }
+
+    // Tokens issued before sessions existed get a session on their first refresh
+    let session = match stored_token.session_id {
+        Some(session_id) => queries::find_active_session(&state.pool, session_id).await?
+            .ok_or_else(|| AppError::auth("Session has been revoked"))?,
+        None => start_session(&state, stored_token.user_id, None, None, &client).await?,
+    };
+    let session_id = session.id;
+
     // Find user
     let user = queries::find_user_by_id(&state.pool, claims.sub).await?
         .ok_or_else(|| AppError::auth("User not found"))?;
 
-    // Get user's default organization
-    let orgs = queries::list_user_organizations(&state.pool, user.id).await?;
-    let org_id = orgs.first().map(|org| org.id);
+    // Generate new tokens, staying in the session's organization
+    let (access_token, new_refresh_token, new_token) =
+        issue_tokens(&state, &user, session.organization_id, session_id).await?;
 
-    // Generate new tokens
-    let access_token = state.jwt_service.generate_access_token(
-        user.id,
-        org_id,
-        "user",
-    )?;
-    let new_refresh_token = state.jwt_service.generate_refresh_token(user.id)?;
-
-    // Revoke old token
-    queries::revoke_refresh_token(&state.pool, stored_token.id).await?;
+    // Revoke old token; losing a race against a concurrent refresh counts as reuse
+    if !queries::rotate_refresh_token(&state.pool, stored_token.id, new_token.id).await? {
+        revoke_token_family(&state, &RefreshToken { session_id: Some(session_id), ..stored_token }).await?;
+        return Err(AppError::auth("Invalid or expired refresh token"));
+    }
 
-    // Store new refresh token
-    let new_token_hash = sha256_hash(&new_refresh_token);
-    let expires_at = Utc::now() + ChronoDuration::from_std(state.config.auth.refresh_token_duration)
-        .map_err(|e| AppError::internal(format!("Invalid duration: {}", e)))?;
-    queries::create_refresh_token(&state.pool, user.id, &new_token_hash, expires_at).await?;
+    queries::touch_session(&state.pool, session_id, client.ip.as_deref(), new_token.expires_at).await?;
 
     let response = AuthResponse {
         access_token,
@@ -241,6 +620,329 @@ pub async fn refresh_token(
     Ok(Json(response))
 }
 
+/// Switch the current organization
+///
+/// Issues an access token scoped to the organization. Its session remembers the
+/// choice, so tokens issued on refresh stay in the organization.
+#[utoipa::path(
+    post,
+    path = "/api/auth/switch-organization",
+    tag = "认证",
+    security(("BearerAuth" = [])),
+    request_body = SwitchOrganizationRequest,
+    responses(
+        (status = 200, description = "切换成功", body = SwitchOrganizationResponse),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "API Key 无法切换组织，或组织要求启用两步验证", body = crate::error::ErrorResponse),
+        (status = 404, description = "组织不存在或不是组织成员", body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn switch_organization(
+    State(state): State<AppState>,
+    auth: AuthExtractor,
+    Json(payload): Json<SwitchOrganizationRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    if auth.0.api_key.is_some() {
+        return Err(AppError::authorization("API keys are bound to their organization"));
+    }
+
+    // Also enforces the organization's two-factor policy
+    auth.0.role_in(&state.pool, payload.organization_id).await?
+        .ok_or_else(|| AppError::not_found("Organization not found"))?;
+
+    let user = queries::find_user_by_id(&state.pool, auth.0.user_id).await?
+        .ok_or_else(|| AppError::auth("User not found"))?;
+    let organization = queries::find_organization_by_id(&state.pool, payload.organization_id).await?
+        .ok_or_else(|| AppError::not_found("Organization not found"))?;
+
+    let (access_token, membership) =
+        issue_access_token(&state, &user, Some(organization.id), auth.0.session_id).await?;
+    let role = membership
+        .filter(|(org_id, _)| *org_id == organization.id)
+        .map(|(_, role)| role)
+        .ok_or_else(|| AppError::not_found("Organization not found"))?;
+
+    Ok(Json(SwitchOrganizationResponse {
+        access_token,
+        organization,
+        role,
+    }))
+}
+
+/// Request a password reset email
+///
+/// Always succeeds, so that the response does not reveal whether an account exists.
+#[utoipa::path(
+    post,
+    path = "/api/auth/forgot-password",
+    tag = "认证",
+    request_body = EmailRequest,
+    responses(
+        (status = 202, description = "若账号存在，已发送重置密码邮件")
+    )
+)]
+pub async fn forgot_password(
+    State(state): State<AppState>,
+    Json(payload): Json<EmailRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    if let Some(user) = queries::find_user_by_email(&state.pool, payload.email.trim()).await? {
+        send_user_token(&state, &user, UserTokenPurpose::PasswordReset).await?;
+    }
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Reset a password with a token from a reset email
+///
+/// Ends every session of the user and invalidates their access tokens.
+#[utoipa::path(
+    post,
+    path = "/api/auth/reset-password",
+    tag = "认证",
+    request_body = ResetPasswordRequest,
+    responses(
+        (status = 204, description = "重置密码成功"),
+        (status = 400, description = "链接无效、已使用或已过期，或新密码不符合要求", body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn reset_password(
+    State(state): State<AppState>,
+    Json(payload): Json<ResetPasswordRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    validate_new_password(&payload.new_password)?;
+
+    let token = queries::consume_user_token(&state.pool, UserTokenPurpose::PasswordReset, &sha256_hash(payload.token.trim()))
+        .await?
+        .ok_or_else(|| AppError::validation("Invalid or expired password reset link"))?;
+
+    let password_hash = hash_password(&payload.new_password, state.config.auth.bcrypt_rounds)?;
+    queries::update_user_password(&state.pool, token.user_id, &password_hash).await?;
+    queries::reset_failed_logins(&state.pool, token.user_id).await?;
+    // Receiving the email proves ownership of the address
+    queries::mark_email_verified(&state.pool, token.user_id).await?;
+    queries::revoke_other_user_sessions(&state.pool, token.user_id, None).await?;
+    state.revocations.invalidate_user(token.user_id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Verify an email address with a token from a verification email
+#[utoipa::path(
+    post,
+    path = "/api/auth/verify-email",
+    tag = "认证",
+    request_body = VerifyEmailRequest,
+    responses(
+        (status = 204, description = "邮箱验证成功"),
+        (status = 400, description = "链接无效、已使用或已过期", body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn verify_email(
+    State(state): State<AppState>,
+    Json(payload): Json<VerifyEmailRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let token = queries::consume_user_token(&state.pool, UserTokenPurpose::EmailVerification, &sha256_hash(payload.token.trim()))
+        .await?
+        .ok_or_else(|| AppError::validation("Invalid or expired verification link"))?;
+
+    queries::mark_email_verified(&state.pool, token.user_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Send a new verification email
+///
+/// Always succeeds, so that the response does not reveal whether an account exists.
+#[utoipa::path(
+    post,
+    path = "/api/auth/resend-verification",
+    tag = "认证",
+    request_body = EmailRequest,
+    responses(
+        (status = 202, description = "若账号存在且未验证，已发送验证邮件")
+    )
+)]
+pub async fn resend_verification(
+    State(state): State<AppState>,
+    Json(payload): Json<EmailRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let user = queries::find_user_by_email(&state.pool, payload.email.trim()).await?;
+    if let Some(user) = user.filter(|u| u.email_verified_at.is_none()) {
+        send_user_token(&state, &user, UserTokenPurpose::EmailVerification).await?;
+    }
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Log out
+#[utoipa::path(
+    post,
+    path = "/api/auth/logout",
+    tag = "认证",
+    security(("BearerAuth" = [])),
+    request_body(content = Option<LogoutRequest>, description = "访问令牌不属于会话时需提供刷新令牌"),
+    responses(
+        (status = 204, description = "退出登录成功"),
+        (status = 400, description = "无可结束的会话", body = crate::error::ErrorResponse),
+        (status = 403, description = "API 密钥不能管理会话", body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn logout(
+    State(state): State<AppState>,
+    auth: AuthExtractor,
+    payload: Option<Json<LogoutRequest>>,
+) -> Result<impl IntoResponse, AppError> {
+    require_user_token(&auth)?;
+
+    let refresh_token = payload.and_then(|Json(p)| p.refresh_token);
+    let session_id = match (auth.0.session_id, refresh_token) {
+        (Some(session_id), _) => Some(session_id),
+        (None, Some(token)) => queries::find_refresh_token(&state.pool, &sha256_hash(&token))
+            .await?
+            .filter(|t| t.user_id == auth.0.user_id)
+            .and_then(|t| t.session_id),
+        (None, None) => None,
+    };
+    let session_id = session_id.ok_or_else(|| AppError::validation("No session to log out of"))?;
+
+    queries::revoke_session(&state.pool, auth.0.user_id, session_id).await?;
+
+    // The access token outlives its session otherwise
+    if let Some(jti) = auth.0.token_id {
+        let expires_at = Utc::now() + state.jwt_service.access_token_duration;
+        queries::revoke_access_token(&state.pool, jti, auth.0.user_id, expires_at).await?;
+    }
+    state.revocations.invalidate_user(auth.0.user_id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Change password
+///
+/// Invalidates all access tokens of the user and ends every other session; the
+/// current session stays signed in and can refresh its access token.
+#[utoipa::path(
+    post,
+    path = "/api/auth/change-password",
+    tag = "认证",
+    security(("BearerAuth" = [])),
+    request_body = ChangePasswordRequest,
+    responses(
+        (status = 204, description = "修改密码成功，需使用刷新令牌获取新的访问令牌"),
+        (status = 400, description = "新密码不符合要求，或账号未设置密码", body = crate::error::ErrorResponse),
+        (status = 401, description = "当前密码错误", body = crate::error::ErrorResponse),
+        (status = 403, description = "API 密钥不能管理会话", body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn change_password(
+    State(state): State<AppState>,
+    auth: AuthExtractor,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    require_user_token(&auth)?;
+    validate_new_password(&payload.new_password)?;
+
+    let user = queries::find_user_by_id(&state.pool, auth.0.user_id).await?
+        .ok_or_else(|| AppError::auth("User not found"))?;
+
+    if user.password_hash.is_none() {
+        return Err(AppError::validation("This account signs in with single sign-on and has no password"));
+    }
+    if !check_password(&user, &payload.current_password)? {
+        return Err(AppError::auth("Current password is incorrect"));
+    }
+
+    let password_hash = hash_password(&payload.new_password, state.config.auth.bcrypt_rounds)?;
+    queries::update_user_password(&state.pool, user.id, &password_hash).await?;
+    queries::revoke_other_user_sessions(&state.pool, user.id, auth.0.session_id).await?;
+    state.revocations.invalidate_user(user.id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// List active sessions
+#[utoipa::path(
+    get,
+    path = "/api/auth/sessions",
+    tag = "认证",
+    security(("BearerAuth" = [])),
+    responses(
+        (status = 200, description = "获取会话列表成功", body = SessionsResponse),
+        (status = 403, description = "API 密钥不能管理会话", body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn list_sessions(
+    State(state): State<AppState>,
+    auth: AuthExtractor,
+) -> Result<impl IntoResponse, AppError> {
+    require_user_token(&auth)?;
+
+    let sessions = queries::list_active_user_sessions(&state.pool, auth.0.user_id).await?;
+    let data = sessions
+        .into_iter()
+        .map(|session| SessionResponse {
+            current: Some(session.id) == auth.0.session_id,
+            session,
+        })
+        .collect();
+
+    Ok(Json(SessionsResponse { data }))
+}
+
+/// Revoke a session
+#[utoipa::path(
+    delete,
+    path = "/api/auth/sessions/{id}",
+    tag = "认证",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "会话ID")
+    ),
+    responses(
+        (status = 204, description = "注销会话成功"),
+        (status = 404, description = "会话不存在", body = crate::error::ErrorResponse),
+        (status = 403, description = "API 密钥不能管理会话", body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn revoke_session(
+    State(state): State<AppState>,
+    Path(session_id): Path<Uuid>,
+    auth: AuthExtractor,
+) -> Result<impl IntoResponse, AppError> {
+    require_user_token(&auth)?;
+
+    if !queries::revoke_session(&state.pool, auth.0.user_id, session_id).await? {
+        return Err(AppError::not_found("Session not found"));
+    }
+    state.revocations.invalidate_user(auth.0.user_id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Revoke all sessions
+#[utoipa::path(
+    delete,
+    path = "/api/auth/sessions",
+    tag = "认证",
+    security(("BearerAuth" = [])),
+    responses(
+        (status = 200, description = "已注销所有会话"),
+        (status = 403, description = "API 密钥不能管理会话", body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn revoke_all_sessions(
+    State(state): State<AppState>,
+    auth: AuthExtractor,
+) -> Result<impl IntoResponse, AppError> {
+    require_user_token(&auth)?;
+
+    let revoked = queries::revoke_all_user_sessions(&state.pool, auth.0.user_id).await?;
+    queries::revoke_all_user_tokens(&state.pool, auth.0.user_id).await?;
+    state.revocations.invalidate_user(auth.0.user_id);
+
+    Ok(Json(json!({ "revoked": revoked })))
+}
+
 /// Health check endpoint
 #[utoipa::path(
     get,
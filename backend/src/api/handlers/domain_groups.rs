@@ -0,0 +1,191 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+    Json as JsonPayload,
+};
+use uuid::Uuid;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::api::routes::AppState;
+use crate::audit::Auditor;
+use crate::auth::{perm, OrgAccess};
+use crate::db::models::{AuditAction, DomainGroup, UpsertDomainGroup};
+use crate::db::queries;
+use crate::error::{AppError, AppResult};
+
+// Response types
+#[derive(serde::Serialize, ToSchema)]
+pub struct DomainGroupResponse {
+    pub data: DomainGroup,
+}
+
+#[derive(serde::Serialize, ToSchema)]
+pub struct DomainGroupsResponse {
+    pub data: Vec<DomainGroup>,
+}
+
+/// Validate a group name and parent, and check that the name is not used by
+/// another group
+async fn check_group_input(
+    state: &AppState,
+    organization_id: Uuid,
+    payload: &UpsertDomainGroup,
+    group_id: Option<Uuid>,
+) -> AppResult<()> {
+    payload.validate()
+        .map_err(|e| AppError::validation(format!("Invalid input: {}", e)))?;
+
+    if queries::domain_group_name_taken(&state.pool, organization_id, &payload.name, group_id).await? {
+        return Err(AppError::validation("A domain group with this name already exists"));
+    }
+
+    if let Some(parent_id) = payload.parent_id {
+        queries::find_domain_group(&state.pool, organization_id, parent_id).await?
+            .ok_or_else(|| AppError::validation("Parent group not found"))?;
+
+        if let Some(group_id) = group_id {
+            if queries::domain_group_in_subtree(&state.pool, group_id, parent_id).await? {
+                return Err(AppError::validation("A group cannot be nested in itself or its subgroups"));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// List domain groups
+///
+/// Members restricted to domain groups only see their own groups and their
+/// subgroups.
+#[utoipa::path(
+    get,
+    path = "/api/organizations/{id}/domain-groups",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID")
+    ),
+    responses(
+        (status = 200, description = "获取成功", body = DomainGroupsResponse),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "不是组织成员")
+    )
+)]
+pub async fn list_domain_groups(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    org: OrgAccess<perm::DomainsRead>,
+) -> AppResult<impl IntoResponse> {
+    let groups = queries::list_domain_groups(&state.pool, id, org.access.group_filter()).await?;
+
+    Ok(Json(DomainGroupsResponse { data: groups }))
+}
+
+/// Create a domain group
+#[utoipa::path(
+    post,
+    path = "/api/organizations/{id}/domain-groups",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID")
+    ),
+    request_body = UpsertDomainGroup,
+    responses(
+        (status = 201, description = "创建成功", body = DomainGroupResponse),
+        (status = 400, description = "请求参数错误、分组名已存在或父分组不存在"),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权限管理域名分组")
+    )
+)]
+pub async fn create_domain_group(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    _org: OrgAccess<perm::OrganizationManage>,
+    audit: Auditor,
+    JsonPayload(payload): JsonPayload<UpsertDomainGroup>,
+) -> AppResult<impl IntoResponse> {
+    check_group_input(&state, id, &payload, None).await?;
+
+    let group = queries::create_domain_group(&state.pool, id, &payload.name, payload.parent_id).await?;
+    audit.created(id, AuditAction::DomainGroupCreated, group.id, &group).await;
+
+    Ok((StatusCode::CREATED, Json(DomainGroupResponse { data: group })))
+}
+
+/// Rename a domain group or move it under another parent
+#[utoipa::path(
+    put,
+    path = "/api/organizations/{id}/domain-groups/{group_id}",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID"),
+        ("group_id" = Uuid, Path, description = "域名分组ID")
+    ),
+    request_body = UpsertDomainGroup,
+    responses(
+        (status = 200, description = "更新成功", body = DomainGroupResponse),
+        (status = 400, description = "请求参数错误、分组名已存在或父分组无效"),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权限管理域名分组"),
+        (status = 404, description = "域名分组不存在")
+    )
+)]
+pub async fn update_domain_group(
+    State(state): State<AppState>,
+    Path((id, group_id)): Path<(Uuid, Uuid)>,
+    _org: OrgAccess<perm::OrganizationManage>,
+    audit: Auditor,
+    JsonPayload(payload): JsonPayload<UpsertDomainGroup>,
+) -> AppResult<impl IntoResponse> {
+    let before = queries::find_domain_group(&state.pool, id, group_id).await?
+        .ok_or_else(|| AppError::not_found("Domain group not found"))?;
+    check_group_input(&state, id, &payload, Some(group_id)).await?;
+
+    let group = queries::update_domain_group(&state.pool, id, group_id, &payload.name, payload.parent_id).await?
+        .ok_or_else(|| AppError::not_found("Domain group not found"))?;
+    audit.updated(id, AuditAction::DomainGroupUpdated, group_id, &before, &group).await;
+
+    Ok(Json(DomainGroupResponse { data: group }))
+}
+
+/// Delete a domain group
+///
+/// Its domains become ungrouped, its subgroups move up to its parent and it is
+/// removed from member restrictions; members left with no groups keep access
+/// to no domains.
+#[utoipa::path(
+    delete,
+    path = "/api/organizations/{id}/domain-groups/{group_id}",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID"),
+        ("group_id" = Uuid, Path, description = "域名分组ID")
+    ),
+    responses(
+        (status = 204, description = "删除成功"),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权限管理域名分组"),
+        (status = 404, description = "域名分组不存在")
+    )
+)]
+pub async fn delete_domain_group(
+    State(state): State<AppState>,
+    Path((id, group_id)): Path<(Uuid, Uuid)>,
+    _org: OrgAccess<perm::OrganizationManage>,
+    audit: Auditor,
+) -> AppResult<impl IntoResponse> {
+    let group = queries::find_domain_group(&state.pool, id, group_id).await?
+        .ok_or_else(|| AppError::not_found("Domain group not found"))?;
+
+    if !queries::delete_domain_group(&state.pool, id, group_id).await? {
+        return Err(AppError::not_found("Domain group not found"));
+    }
+    audit.deleted(id, AuditAction::DomainGroupDeleted, group_id, &group).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,246 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::api::handlers::domains::check_group;
+use crate::api::routes::AppState;
+use crate::audit::Auditor;
+use crate::auth::{perm, OrgAccess};
+use crate::db::models::{
+    AuditAction, DomainFilter, DomainSpec, ImportReport, ImportRowStatus, MonitorSpec,
+};
+use crate::db::queries;
+use crate::error::{AppError, AppResult};
+use crate::inventory;
+use crate::quota;
+use crate::tags::TagSelector;
+
+#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+#[into_params(parameter_in = Query)]
+pub struct ImportQuery {
+    /// Only check the file and report what would be imported
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Skip entries whose URL is already used instead of failing the import
+    #[serde(default)]
+    pub skip_existing: bool,
+    /// Group to put the imported domains in
+    pub group_id: Option<Uuid>,
+}
+
+/// Format of an export file
+#[derive(Debug, Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+#[into_params(parameter_in = Query)]
+pub struct ExportQuery {
+    /// `json` (default) or `csv`
+    #[serde(default)]
+    #[param(value_type = Option<String>)]
+    pub format: ExportFormat,
+}
+
+// Response types
+#[derive(serde::Serialize, ToSchema)]
+pub struct ImportResponse {
+    pub data: ImportReport,
+}
+
+/// Import domains from a CSV or JSON file
+///
+/// The body is a CSV file when sent as `text/csv` and a JSON array of domains
+/// otherwise. Every entry is checked first; if any entry is invalid nothing is
+/// imported and the report lists the errors of each entry. Otherwise all
+/// domains and their monitors are created in one transaction.
+#[utoipa::path(
+    post,
+    path = "/api/domains/import",
+    tag = "域名",
+    security(("BearerAuth" = [])),
+    params(
+        ("org_id" = Option<Uuid>, Query, description = "组织ID（可选，默认使用令牌的当前组织）"),
+        ImportQuery
+    ),
+    request_body(content = Vec<DomainSpec>, description = "域名列表（JSON 数组，或 text/csv 格式的 CSV 文件）"),
+    responses(
+        (status = 200, description = "试运行完成", body = ImportResponse),
+        (status = 201, description = "导入成功", body = ImportResponse),
+        (status = 400, description = "文件格式错误"),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权限创建域名或超出监控器配额"),
+        (status = 422, description = "部分条目无效，未导入任何域名", body = ImportResponse)
+    )
+)]
+pub async fn import_domains(
+    State(state): State<AppState>,
+    org: OrgAccess<perm::DomainsCreate>,
+    Query(query): Query<ImportQuery>,
+    headers: HeaderMap,
+    audit: Auditor,
+    body: String,
+) -> AppResult<Response> {
+    check_group(&state, &org.access, query.group_id).await?;
+
+    let is_csv = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/csv"));
+    let entries = if is_csv {
+        inventory::parse_csv(&body)
+    } else {
+        inventory::parse_json(&body)
+    }
+    .map_err(AppError::validation)?;
+
+    if entries.is_empty() || entries.len() > inventory::MAX_ENTRIES {
+        return Err(AppError::validation(format!(
+            "An import must contain between 1 and {} domains",
+            inventory::MAX_ENTRIES
+        )));
+    }
+
+    let org_id = org.organization_id();
+    let urls: Vec<String> = entries
+        .iter()
+        .filter_map(|(_, entry)| entry.as_ref().ok())
+        .filter_map(|spec| inventory::normalize_url(&spec.url).ok())
+        .collect();
+    let existing: HashSet<String> = queries::existing_domain_urls(&state.pool, org_id, &urls).await?
+        .into_iter()
+        .collect();
+
+    let (mut rows, domains) = inventory::plan(entries, &existing, query.group_id, query.skip_existing);
+    let count = |status| rows.iter().filter(|r| r.status == status).count();
+    let mut report = ImportReport {
+        applied: false,
+        created: count(ImportRowStatus::Create),
+        skipped: count(ImportRowStatus::Skip),
+        errors: count(ImportRowStatus::Error),
+        monitors: domains.iter().map(|d| d.monitors.len()).sum(),
+        rows: Vec::new(),
+    };
+
+    let organization = queries::find_organization_by_id(&state.pool, org_id).await?
+        .ok_or_else(|| AppError::not_found("Organization not found"))?;
+    quota::ensure_monitor_capacity(&state.pool, &organization, report.monitors as i64).await?;
+    quota::check_monitor_intervals(&organization, domains.iter().flat_map(|d| &d.monitors))?;
+
+    if report.errors > 0 {
+        report.rows = rows;
+        let status = if query.dry_run { StatusCode::OK } else { StatusCode::UNPROCESSABLE_ENTITY };
+        return Ok((status, Json(ImportResponse { data: report })).into_response());
+    }
+    if query.dry_run {
+        report.rows = rows;
+        return Ok(Json(ImportResponse { data: report }).into_response());
+    }
+
+    // A domain created since the check makes the whole import fail
+    let created = queries::create_domains(&state.pool, org_id, &domains).await?
+        .ok_or_else(|| AppError::validation("Some of these URLs were added while importing, please retry"))?;
+
+    for domain in &created {
+        audit.created(org_id, AuditAction::DomainCreated, domain.id, domain).await;
+    }
+    for row in rows.iter_mut().filter(|r| r.status == ImportRowStatus::Create) {
+        row.domain_id = created.iter().find(|d| Some(&d.url) == row.url.as_ref()).map(|d| d.id);
+    }
+    report.applied = true;
+    report.rows = rows;
+
+    Ok((StatusCode::CREATED, Json(ImportResponse { data: report })).into_response())
+}
+
+/// Export domains as a CSV or JSON file
+///
+/// The file lists each domain with its tags and monitors and can be imported
+/// again with `POST /api/domains/import`.
+#[utoipa::path(
+    get,
+    path = "/api/domains/export",
+    tag = "域名",
+    security(("BearerAuth" = [])),
+    params(
+        ("org_id" = Option<Uuid>, Query, description = "组织ID（可选，默认使用令牌的当前组织）"),
+        ExportQuery,
+        DomainFilter
+    ),
+    responses(
+        (status = 200, description = "导出成功", body = Vec<DomainSpec>),
+        (status = 400, description = "标签筛选条件无效"),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "不是组织成员")
+    )
+)]
+pub async fn export_domains(
+    State(state): State<AppState>,
+    org: OrgAccess<perm::DomainsRead>,
+    Query(export): Query<ExportQuery>,
+    Query(filter): Query<DomainFilter>,
+) -> AppResult<impl IntoResponse> {
+    let tags = TagSelector::parse(filter.tag.as_deref())?;
+    let org_id = org.organization_id();
+
+    let domains = queries::list_organization_domains(
+        &state.pool,
+        org_id,
+        org.access.group_filter(),
+        filter.group_id,
+        &tags,
+    ).await?;
+
+    let mut monitors: HashMap<Uuid, BTreeMap<String, MonitorSpec>> = HashMap::new();
+    for monitor in queries::list_organization_monitors(&state.pool, org_id).await? {
+        monitors.entry(monitor.domain_id).or_default().insert(
+            monitor.monitor_type.to_string(),
+            MonitorSpec { enabled: monitor.is_enabled, config: monitor.config },
+        );
+    }
+
+    let specs: Vec<DomainSpec> = domains
+        .into_iter()
+        .map(|domain| DomainSpec {
+            monitors: Some(monitors.remove(&domain.id).unwrap_or_default()),
+            display_name: domain.display_name,
+            url: domain.url,
+            tags: domain.tags,
+        })
+        .collect();
+
+    let (content_type, extension, body) = match export.format {
+        ExportFormat::Json => (
+            "application/json",
+            "json",
+            serde_json::to_string_pretty(&specs)
+                .map_err(|e| AppError::internal(format!("Failed to encode domains: {}", e)))?,
+        ),
+        ExportFormat::Csv => (
+            "text/csv; charset=utf-8",
+            "csv",
+            inventory::to_csv(&specs)
+                .map_err(|e| AppError::internal(format!("Failed to encode domains: {}", e)))?,
+        ),
+    };
+
+    let filename = format!("attachment; filename=\"domains-{}.{}\"", org_id, extension);
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, filename),
+        ],
+        body,
+    ))
+}
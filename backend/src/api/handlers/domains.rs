@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Path, State, Query},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::{IntoResponse, Json},
     Json as JsonPayload,
@@ -8,14 +8,18 @@ use serde_json::json;
 use uuid::Uuid;
 use utoipa::ToSchema;
 
+use crate::api::pagination::{Page, PageQuery};
 use crate::api::routes::AppState;
+use crate::audit::Auditor;
 use crate::db::queries;
-use crate::db::models::{Domain, DomainWithStatus, DomainStatistics};
+use crate::db::models::{AuditAction, Domain, DomainListFilter, DomainSpec, DomainStatistics, Tags, UpdateDomain};
 use crate::error::{AppError, AppResult};
-use crate::auth::AuthExtractor;
+use crate::inventory;
+use crate::tags::{self, TagSelector};
+use crate::auth::{perm, Access, DomainAccess, OrgAccess};
 use validator::Validate;
 
-/// Query parameters for domain endpoints
+/// Query parameters for domain endpoints, read by [`OrgAccess`]
 #[derive(serde::Deserialize, ToSchema)]
 pub struct DomainQueryParams {
     pub org_id: Option<Uuid>,
@@ -30,11 +34,59 @@ pub struct CreateDomainRequest {
     /// URL (actual domain address like https://example.com)
     #[validate(length(min = 1, max = 2048))]
     pub url: String,
+    /// Domain group (required for members restricted to domain groups)
+    pub group_id: Option<Uuid>,
+    /// Key/value labels
+    #[serde(default)]
+    pub tags: Tags,
 }
 
-#[derive(serde::Deserialize, ToSchema)]
+#[derive(serde::Deserialize, Validate, ToSchema)]
 pub struct UpdateDomainRequest {
     pub is_active: Option<bool>,
+    /// Display name
+    #[validate(length(min = 1, max = 255))]
+    pub display_name: Option<String>,
+    /// URL; the domain keeps its monitoring history when it changes
+    #[validate(length(min = 1, max = 2048))]
+    pub url: Option<String>,
+    /// Domain group; null removes the domain from its group
+    #[serde(default, deserialize_with = "double_option")]
+    #[schema(value_type = Option<Uuid>)]
+    pub group_id: Option<Option<Uuid>>,
+    /// Replaces all tags of the domain
+    pub tags: Option<Tags>,
+}
+
+#[derive(serde::Deserialize, ToSchema)]
+pub struct BulkTagRequest {
+    pub domain_ids: Vec<Uuid>,
+    /// Tags to add or overwrite
+    #[serde(default)]
+    pub set: Tags,
+    /// Tag keys to remove
+    #[serde(default)]
+    pub remove: Vec<String>,
+}
+
+/// Tell an explicit `null` (`Some(None)`) apart from a missing field (`None`)
+fn double_option<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<Option<Uuid>>, D::Error> {
+    serde::Deserialize::deserialize(deserializer).map(Some)
+}
+
+/// Check that a domain can be put in a group: the group must belong to the
+/// organization, and restricted members can only use their own groups
+pub(crate) async fn check_group(state: &AppState, access: &Access, group_id: Option<Uuid>) -> AppResult<()> {
+    if let Some(group_id) = group_id {
+        queries::find_domain_group(&state.pool, access.organization_id, group_id).await?
+            .ok_or_else(|| AppError::validation("Domain group not found"))?;
+    }
+
+    if !access.can_access_group(group_id) {
+        return Err(AppError::authorization("You can only use your own domain groups"));
+    }
+
+    Ok(())
 }
 
 // Response types
@@ -48,11 +100,6 @@ pub struct DomainsResponse {
     pub data: Vec<Domain>,
 }
 
-#[derive(serde::Serialize, ToSchema)]
-pub struct DomainsWithStatusResponse {
-    pub data: Vec<DomainWithStatus>,
-}
-
 #[derive(serde::Serialize, ToSchema)]
 pub struct DomainStatisticsResponse {
     pub data: DomainStatistics,
@@ -71,44 +118,40 @@ pub struct DomainCreateResponse {
     tag = "域名",
     security(("BearerAuth" = [])),
     params(
-        ("org_id" = Option<Uuid>, Query, description = "组织ID（可选，默认使用用户的默认组织）")
+        ("org_id" = Option<Uuid>, Query, description = "组织ID（可选，默认使用令牌的当前组织）"),
+        DomainListFilter,
+        PageQuery
     ),
     responses(
-        (status = 200, description = "获取成功", body = DomainsWithStatusResponse),
+        (status = 200, description = "获取成功", body = DomainPage),
+        (status = 400, description = "筛选、排序或分页参数无效"),
         (status = 401, description = "未授权"),
         (status = 403, description = "不是组织成员")
     )
 )]
 pub async fn list_domains(
     State(state): State<AppState>,
-    Query(params): Query<DomainQueryParams>,
-    auth: AuthExtractor,
+    org: OrgAccess<perm::DomainsRead>,
+    Query(filter): Query<DomainListFilter>,
+    page: Page,
 ) -> AppResult<impl IntoResponse> {
-    // Use current org from auth context if not provided
-    let org_id = match params.org_id {
-        Some(id) => id,
-        None => {
-            // Try to get user's default organization
-            let orgs = queries::list_user_organizations(&state.pool, auth.0.user_id).await
-                .unwrap_or_default();
-            orgs.first().map(|org| org.id).unwrap_or_else(Uuid::new_v4)
-        }
-    };
-
-    // Check if user is a member
-    let is_member = queries::is_organization_member(&state.pool, org_id, auth.0.user_id).await?;
-    if !is_member {
-        return Err(AppError::authorization("You are not a member of this organization"));
+    let tags = TagSelector::parse(filter.tag.as_deref())?;
+    if filter.q.as_deref().is_some_and(|q| q.trim().is_empty()) {
+        return Err(AppError::validation("Search query cannot be empty"));
     }
 
     // Use enhanced query with monitoring status
-    let domains = queries::list_organization_domains_with_status(&state.pool, org_id).await?;
-
-    let response = json!({
-        "data": domains
-    });
-
-    Ok(Json(response))
+    let (domains, total) = queries::list_organization_domains_with_status(
+        &state.pool,
+        org.organization_id(),
+        org.access.group_filter(),
+        &filter,
+        &tags,
+        page.after,
+        page.fetch_limit(),
+    ).await?;
+
+    Ok(Json(page.paginate(domains, total, |d| d.id)))
 }
 
 /// Create a new domain
@@ -118,74 +161,46 @@ pub async fn list_domains(
     tag = "域名",
     security(("BearerAuth" = [])),
     params(
-        ("org_id" = Option<Uuid>, Query, description = "组织ID（可选，默认使用用户的默认组织）")
+        ("org_id" = Option<Uuid>, Query, description = "组织ID（可选，默认使用令牌的当前组织）")
     ),
     request_body = CreateDomainRequest,
     responses(
         (status = 201, description = "创建成功，自动创建 SSL 和 Uptime 监控器"),
-        (status = 400, description = "请求参数错误"),
+        (status = 400, description = "请求参数错误或 URL 已存在"),
         (status = 401, description = "未授权"),
-        (status = 403, description = "无权限创建域名")
+        (status = 403, description = "无权限创建域名或超出监控器配额")
     )
 )]
 pub async fn create_domain(
     State(state): State<AppState>,
-    Query(params): Query<DomainQueryParams>,
-    auth: AuthExtractor,
+    org: OrgAccess<perm::DomainsCreate>,
+    audit: Auditor,
     JsonPayload(payload): JsonPayload<CreateDomainRequest>,
 ) -> AppResult<impl IntoResponse> {
     // Validate input
     payload.validate()
         .map_err(|e| AppError::validation(format!("Invalid input: {}", e)))?;
+    tags::validate(&payload.tags)?;
+    check_group(&state, &org.access, payload.group_id).await?;
 
-    // Use current org from auth context if not provided
-    let org_id = match params.org_id {
-        Some(id) => id,
-        None => {
-            // Try to get user's default organization
-            let orgs = queries::list_user_organizations(&state.pool, auth.0.user_id).await
-                .unwrap_or_default();
-            orgs.first().map(|org| org.id)
-                .ok_or_else(|| AppError::validation("No organization found. Please create an organization first."))?
-        }
-    };
-
-    // Check if user can write (not just viewer)
-    let role = queries::get_user_role(&state.pool, org_id, auth.0.user_id).await?
-        .ok_or_else(|| AppError::authorization("Organization not found"))?;
-
-    if !role.can_write() {
-        return Err(AppError::authorization("Viewers cannot create domains"));
-    }
-
-    // Normalize URL (remove trailing slashes)
-    let url = payload.url.trim_end_matches('/').to_string();
-    let normalized_name = url.to_lowercase();
+    let url = inventory::normalize_url(&payload.url).map_err(AppError::validation)?;
 
-    // Create domain with display_name and url
-    // Note: We still pass name and normalized_name to the old create_domain function
-    // After migration, the migration script will move these to display_name and url
-    let domain = queries::create_domain(&state.pool, org_id, &payload.display_name, &normalized_name).await?;
+    let org_id = org.organization_id();
 
-    // Auto-create monitors for the new domain
-    let ssl_config = json!({});
-    let uptime_config = json!({});
-
-    let _ = queries::upsert_monitor(
-        &state.pool,
-        domain.id,
-        crate::db::models::MonitorType::SslCert,
-        true,  // is_enabled
-        &ssl_config,
-    ).await;
+    // Every domain gets an SSL and an uptime monitor
+    let spec = DomainSpec {
+        display_name: payload.display_name,
+        url,
+        tags: payload.tags,
+        monitors: None,
+    };
+    let new_domain = inventory::to_new_domain(&spec, payload.group_id)
+        .map_err(|errors| AppError::validation(errors.join("; ")))?;
 
-    let _ = queries::upsert_monitor(
-        &state.pool,
-        domain.id,
-        crate::db::models::MonitorType::Uptime,
-        true,  // is_enabled
-        &uptime_config,
-    ).await;
+    let domain = queries::create_domains(&state.pool, org_id, &[new_domain]).await?
+        .and_then(|mut created| created.pop())
+        .ok_or_else(|| AppError::validation("A domain with this URL already exists"))?;
+    audit.created(org_id, AuditAction::DomainCreated, domain.id, &domain).await;
 
     let response = json!({
         "data": domain,
@@ -212,21 +227,10 @@ pub async fn create_domain(
     )
 )]
 pub async fn get_domain(
-    State(state): State<AppState>,
-    Path(id): Path<Uuid>,
-    auth: AuthExtractor,
+    target: DomainAccess<perm::DomainsRead>,
 ) -> AppResult<impl IntoResponse> {
-    let domain = queries::find_domain_by_id(&state.pool, id).await?
-        .ok_or_else(|| AppError::not_found("Domain not found"))?;
-
-    // Check if user is a member of the domain's organization
-    let is_member = queries::is_organization_member(&state.pool, domain.organization_id, auth.0.user_id).await?;
-    if !is_member {
-        return Err(AppError::authorization("You are not a member of this organization"));
-    }
-
     let response = json!({
-        "data": domain
+        "data": target.domain
     });
 
     Ok(Json(response))
@@ -244,6 +248,7 @@ pub async fn get_domain(
     request_body = UpdateDomainRequest,
     responses(
         (status = 200, description = "更新成功"),
+        (status = 400, description = "请求参数错误或 URL 已存在"),
         (status = 401, description = "未授权"),
         (status = 403, description = "无权限更新域名"),
         (status = 404, description = "域名不存在")
@@ -252,28 +257,38 @@ pub async fn get_domain(
 pub async fn update_domain(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
-    auth: AuthExtractor,
+    target: DomainAccess<perm::DomainsEdit>,
+    audit: Auditor,
     JsonPayload(payload): JsonPayload<UpdateDomainRequest>,
 ) -> AppResult<impl IntoResponse> {
-    let domain = queries::find_domain_by_id(&state.pool, id).await?
-        .ok_or_else(|| AppError::not_found("Domain not found"))?;
-
-    // Check if user can write
-    let role = queries::get_user_role(&state.pool, domain.organization_id, auth.0.user_id).await?
-        .ok_or_else(|| AppError::authorization("Organization not found"))?;
-
-    if !role.can_write() {
-        return Err(AppError::authorization("Viewers cannot update domains"));
+    payload.validate()
+        .map_err(|e| AppError::validation(format!("Invalid input: {}", e)))?;
+    if let Some(group_id) = payload.group_id {
+        check_group(&state, &target.access, group_id).await?;
     }
-
-    // Update domain
-    if let Some(is_active) = payload.is_active {
-        queries::update_domain(&state.pool, id, Some(is_active)).await?;
+    if let Some(tags) = &payload.tags {
+        tags::validate(tags)?;
     }
 
-    // Fetch updated domain
-    let domain = queries::find_domain_by_id(&state.pool, id).await?
-        .ok_or_else(|| AppError::not_found("Domain not found"))?;
+    let url = payload.url.as_deref()
+        .map(inventory::normalize_url)
+        .transpose()
+        .map_err(AppError::validation)?
+        .filter(|url| *url != target.domain.url);
+
+    // Update domain
+    let input = UpdateDomain {
+        is_active: payload.is_active,
+        display_name: payload.display_name,
+        url,
+        group_id: payload.group_id,
+        tags: payload.tags,
+    };
+    let domain = queries::update_domain(&state.pool, id, &input).await?
+        .ok_or_else(|| AppError::validation("A domain with this URL already exists"))?;
+    // A move gets its own action, so that the domain's URL history can be filtered for
+    let action = if input.url.is_some() { AuditAction::DomainUrlChanged } else { AuditAction::DomainUpdated };
+    audit.updated(domain.organization_id, action, id, &target.domain, &domain).await;
 
     let response = json!({
         "data": domain
@@ -282,6 +297,68 @@ pub async fn update_domain(
     Ok(Json(response))
 }
 
+/// Add and remove tags on several domains at once
+///
+/// Tags in `set` are added or overwritten, then keys in `remove` are removed.
+/// Either every domain is updated or none is.
+#[utoipa::path(
+    post,
+    path = "/api/domains/tags",
+    tag = "域名",
+    security(("BearerAuth" = [])),
+    params(
+        ("org_id" = Option<Uuid>, Query, description = "组织ID（可选，默认使用令牌的当前组织）")
+    ),
+    request_body = BulkTagRequest,
+    responses(
+        (status = 200, description = "更新成功", body = DomainsResponse),
+        (status = 400, description = "请求参数错误"),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权限更新域名"),
+        (status = 404, description = "域名不存在")
+    )
+)]
+pub async fn bulk_update_tags(
+    State(state): State<AppState>,
+    org: OrgAccess<perm::DomainsEdit>,
+    audit: Auditor,
+    JsonPayload(payload): JsonPayload<BulkTagRequest>,
+) -> AppResult<impl IntoResponse> {
+    if payload.domain_ids.is_empty() || payload.domain_ids.len() > 1000 {
+        return Err(AppError::validation("domain_ids must contain between 1 and 1000 domains"));
+    }
+    for key in &payload.remove {
+        tags::validate_key(key)?;
+    }
+
+    let org_id = org.organization_id();
+    let domains = queries::find_organization_domains(&state.pool, org_id, &payload.domain_ids).await?;
+    let all_found = payload.domain_ids.iter().all(|id| domains.iter().any(|d| d.id == *id));
+    if !all_found || !domains.iter().all(|d| org.access.can_access_group(d.group_id)) {
+        return Err(AppError::not_found("Domain not found"));
+    }
+
+    let mut updates = Vec::with_capacity(domains.len());
+    for domain in &domains {
+        let mut tags = domain.tags.clone();
+        tags.extend(payload.set.clone());
+        tags.retain(|key, _| !payload.remove.contains(key));
+        tags::validate(&tags)?;
+        updates.push((domain.id, tags));
+    }
+
+    queries::set_domain_tags(&state.pool, &updates).await?;
+
+    let updated = queries::find_organization_domains(&state.pool, org_id, &payload.domain_ids).await?;
+    for after in &updated {
+        if let Some(before) = domains.iter().find(|d| d.id == after.id) {
+            audit.updated(org_id, AuditAction::DomainUpdated, after.id, before, after).await;
+        }
+    }
+
+    Ok(Json(DomainsResponse { data: updated }))
+}
+
 /// Delete domain
 #[utoipa::path(
     delete,
@@ -300,21 +377,11 @@ pub async fn update_domain(
 )]
 pub async fn delete_domain(
     State(state): State<AppState>,
-    Path(id): Path<Uuid>,
-    auth: AuthExtractor,
+    target: DomainAccess<perm::DomainsDelete>,
+    audit: Auditor,
 ) -> AppResult<impl IntoResponse> {
-    let domain = queries::find_domain_by_id(&state.pool, id).await?
-        .ok_or_else(|| AppError::not_found("Domain not found"))?;
-
-    // Check if user is admin or owner
-    let role = queries::get_user_role(&state.pool, domain.organization_id, auth.0.user_id).await?
-        .ok_or_else(|| AppError::authorization("Organization not found"))?;
-
-    if !role.is_admin() {
-        return Err(AppError::authorization("Only admins can delete domains"));
-    }
-
-    queries::delete_domain(&state.pool, id).await?;
+    queries::delete_domain(&state.pool, target.domain.id).await?;
+    audit.deleted(target.domain.organization_id, AuditAction::DomainDeleted, target.domain.id, &target.domain).await;
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -337,20 +404,10 @@ pub async fn delete_domain(
 )]
 pub async fn get_domain_statistics(
     State(state): State<AppState>,
-    Path(id): Path<Uuid>,
-    auth: AuthExtractor,
+    target: DomainAccess<perm::DomainsRead>,
 ) -> AppResult<impl IntoResponse> {
-    let domain = queries::find_domain_by_id(&state.pool, id).await?
-        .ok_or_else(|| AppError::not_found("Domain not found"))?;
-
-    // Check if user is a member of the domain's organization
-    let is_member = queries::is_organization_member(&state.pool, domain.organization_id, auth.0.user_id).await?;
-    if !is_member {
-        return Err(AppError::authorization("You are not a member of this organization"));
-    }
-
     // Get comprehensive statistics
-    let stats = queries::get_domain_statistics(&state.pool, id).await?;
+    let stats = queries::get_domain_statistics(&state.pool, target.domain.id).await?;
 
     let response = json!({
         "data": stats
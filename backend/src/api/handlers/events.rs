@@ -0,0 +1,261 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    response::{
+        sse::{Event as SseEvent, KeepAlive, Sse},
+        IntoResponse, Json,
+    },
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::convert::Infallible;
+use std::time::Duration;
+use tokio::time::{interval_at, Instant, Interval, MissedTickBehavior};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::api::routes::AppState;
+use crate::auth::{perm, AuthContext, OrgAccess, STREAM_TICKET_DURATION_SECONDS};
+use crate::db::models::Permission;
+use crate::events::{Delivery, Subscription};
+use crate::error::{AppError, AppResult};
+
+/// Time between two checks that a stream's caller may still receive events
+const REAUTHORIZE_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EventStreamQuery {
+    /// Comma-separated event types to receive, e.g. `incident_opened,alert_created` (default: all)
+    pub types: Option<String>,
+    /// Only events about this domain
+    pub domain_id: Option<Uuid>,
+    /// Stream ticket, for clients that cannot send the `Authorization` header
+    pub ticket: Option<String>,
+}
+
+/// Ticket opening the event streams of an organization
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StreamTicketResponse {
+    /// Passed as the `ticket` query parameter of the event streams
+    pub ticket: String,
+    /// Seconds during which the ticket can open a stream
+    pub expires_in: i64,
+}
+
+impl EventStreamQuery {
+    fn subscribe(&self, state: AppState, org: OrgAccess<perm::DomainsRead>) -> EventStream {
+        let types = self.types.as_ref().map(|types| {
+            types.split(',').map(str::trim).filter(|t| !t.is_empty()).map(String::from).collect()
+        });
+        let subscription = state.events.subscribe(org.access, types, self.domain_id);
+
+        let mut reauthorize = interval_at(Instant::now() + REAUTHORIZE_INTERVAL, REAUTHORIZE_INTERVAL);
+        reauthorize.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        EventStream { state, auth: org.auth, subscription, reauthorize }
+    }
+}
+
+/// Events of a stream, ending once its caller loses access
+///
+/// The token or API key and the caller's access to the organization are
+/// checked again every [`REAUTHORIZE_INTERVAL`], so that the access token
+/// expiring, logging out, removing the member or deleting the organization
+/// closes its open streams, and
+/// changes to its permissions or groups apply to the following events.
+struct EventStream {
+    state: AppState,
+    auth: AuthContext,
+    subscription: Subscription,
+    reauthorize: Interval,
+}
+
+impl EventStream {
+    /// Wait for the next event, `None` once the bus is gone or the caller
+    /// lost access
+    async fn next(&mut self) -> Option<Delivery> {
+        loop {
+            tokio::select! {
+                delivery = self.subscription.next() => return delivery,
+                _ = self.reauthorize.tick() => {
+                    if !self.is_authorized().await {
+                        return None;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Check the caller's credentials and access to the organization again
+    async fn is_authorized(&mut self) -> bool {
+        let organization_id = self.subscription.access().organization_id;
+        let access = match self.auth.is_still_valid(&self.state).await {
+            Ok(true) => self.auth.access_in(&self.state.pool, organization_id).await,
+            Ok(false) => Ok(None),
+            Err(e) => Err(e),
+        };
+
+        match access {
+            Ok(Some(access)) if access.has(Permission::DomainsRead) => {
+                self.subscription.set_access(access);
+                true
+            }
+            Ok(_) => false,
+            Err(e) => {
+                tracing::debug!("Closing event stream of user {}: {}", self.auth.user_id, e);
+                false
+            }
+        }
+    }
+}
+
+/// Message reporting events missed by a slow client
+fn lagged(missed: u64) -> serde_json::Value {
+    json!({ "type": "lagged", "data": { "missed": missed } })
+}
+
+/// Issue a stream ticket
+///
+/// Browsers can't set headers on `EventSource` and WebSocket requests, so they
+/// open the event streams with this short-lived ticket in the query instead of
+/// their access token. The ticket only opens the streams of this organization,
+/// and is revoked along with the access token it was issued for.
+#[utoipa::path(
+    post,
+    path = "/api/organizations/{id}/events/ticket",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID")
+    ),
+    responses(
+        (status = 200, description = "事件流票据", body = StreamTicketResponse),
+        (status = 400, description = "API 密钥无需票据"),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权访问该组织")
+    )
+)]
+pub async fn create_stream_ticket(
+    State(state): State<AppState>,
+    Path(_id): Path<Uuid>,
+    org: OrgAccess<perm::DomainsRead>,
+) -> AppResult<Json<StreamTicketResponse>> {
+    let (Some(token_id), Some(token_version), Some(expires_at)) =
+        (org.auth.token_id, org.auth.token_version, org.auth.expires_at)
+    else {
+        return Err(AppError::validation("API keys open event streams with the key in a header"));
+    };
+
+    let ticket = state.jwt_service.generate_stream_ticket(
+        org.auth.user_id,
+        org.organization_id(),
+        token_id,
+        org.auth.session_id,
+        token_version,
+        expires_at.timestamp(),
+    )?;
+
+    Ok(Json(StreamTicketResponse { ticket, expires_in: STREAM_TICKET_DURATION_SECONDS }))
+}
+
+/// Stream organization events (Server-Sent Events)
+///
+/// Each event is named after its type and carries it as JSON. Alert events
+/// need the alert read permission, and members restricted to domain groups
+/// only receive events of their groups' domains.
+#[utoipa::path(
+    get,
+    path = "/api/organizations/{id}/events",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID"),
+        EventStreamQuery
+    ),
+    responses(
+        (status = 200, description = "事件流", content_type = "text/event-stream", body = crate::events::Event),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权访问该组织")
+    )
+)]
+pub async fn stream_events(
+    State(state): State<AppState>,
+    Path(_id): Path<Uuid>,
+    org: OrgAccess<perm::DomainsRead>,
+    Query(query): Query<EventStreamQuery>,
+) -> AppResult<impl IntoResponse> {
+    let events = query.subscribe(state, org);
+
+    let stream = futures::stream::unfold(events, |mut events| async move {
+        let event = match events.next().await? {
+            Delivery::Event(event) => SseEvent::default()
+                .event(event.kind.name())
+                .json_data(&event),
+            Delivery::Lagged(missed) => SseEvent::default()
+                .event("lagged")
+                .json_data(lagged(missed)),
+        };
+        // Events always serialize to JSON
+        let event = event.unwrap_or_default();
+        Some((Ok::<_, Infallible>(event), events))
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// Stream organization events over a WebSocket
+///
+/// Same events and filters as the SSE stream, sent as JSON text messages.
+#[utoipa::path(
+    get,
+    path = "/api/organizations/{id}/events/ws",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID"),
+        EventStreamQuery
+    ),
+    responses(
+        (status = 101, description = "切换到 WebSocket 协议", body = crate::events::Event),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权访问该组织")
+    )
+)]
+pub async fn events_websocket(
+    State(state): State<AppState>,
+    Path(_id): Path<Uuid>,
+    org: OrgAccess<perm::DomainsRead>,
+    Query(query): Query<EventStreamQuery>,
+    ws: WebSocketUpgrade,
+) -> AppResult<impl IntoResponse> {
+    let events = query.subscribe(state, org);
+    Ok(ws.on_upgrade(move |socket| forward_events(socket, events)))
+}
+
+/// Send events to the socket until either side goes away or the caller loses access
+async fn forward_events(mut socket: WebSocket, mut events: EventStream) {
+    loop {
+        tokio::select! {
+            delivery = events.next() => {
+                let message = match delivery {
+                    Some(Delivery::Event(event)) => serde_json::to_string(&event),
+                    Some(Delivery::Lagged(missed)) => serde_json::to_string(&lagged(missed)),
+                    None => break,
+                };
+                let Ok(message) = message else { continue };
+                if socket.send(Message::Text(message)).await.is_err() {
+                    break;
+                }
+            }
+            // Clients only send pings, answered by axum, and close frames
+            received = socket.recv() => {
+                if matches!(received, None | Some(Err(_) | Ok(Message::Close(_)))) {
+                    break;
+                }
+            }
+        }
+    }
+}
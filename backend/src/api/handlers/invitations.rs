@@ -0,0 +1,325 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+    Json as JsonPayload,
+};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use uuid::Uuid;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::api::handlers::organizations::OrganizationResponse;
+use crate::api::routes::AppState;
+use crate::audit::Auditor;
+use crate::auth::{generate_user_token, perm, sha256_hash, AuthContext, AuthExtractor, OrgAccess};
+use crate::db::models::{AuditAction, CreateInvitation, Invitation, MemberRole};
+use crate::db::queries;
+use crate::error::{AppError, AppResult};
+use crate::mailer::{self, Email};
+
+// Request types
+#[derive(serde::Deserialize, ToSchema)]
+pub struct InvitationTokenRequest {
+    /// Token from the invitation link
+    pub token: String,
+}
+
+// Response types
+#[derive(serde::Serialize, ToSchema)]
+pub struct InvitationResponse {
+    pub data: Invitation,
+}
+
+#[derive(serde::Serialize, ToSchema)]
+pub struct InvitationsResponse {
+    pub data: Vec<Invitation>,
+}
+
+/// What an invitation link is for, shown before it is accepted
+#[derive(serde::Serialize, ToSchema)]
+pub struct InvitationPreviewResponse {
+    pub organization_name: String,
+    pub email: String,
+    pub role: MemberRole,
+    pub expires_at: DateTime<Utc>,
+    /// An account with the email address exists: sign in to accept, otherwise register
+    pub account_exists: bool,
+}
+
+/// Expiry of an invitation sent now
+fn invitation_expiry(state: &AppState) -> AppResult<DateTime<Utc>> {
+    let ttl = ChronoDuration::from_std(state.config.auth.invitation_ttl)
+        .map_err(|e| AppError::internal(format!("Invalid duration: {}", e)))?;
+    Ok(Utc::now() + ttl)
+}
+
+/// Email the invitation link with a freshly issued token
+async fn send_invitation(state: &AppState, auth: &AuthContext, invitation: &Invitation, token: &str) -> AppResult<()> {
+    let organization = queries::find_organization_by_id(&state.pool, invitation.organization_id).await?
+        .ok_or_else(|| AppError::not_found("Organization not found"))?;
+    let invited_by = queries::find_user_by_id(&state.pool, auth.user_id).await?
+        .map(|user| user.full_name.unwrap_or(user.email))
+        .unwrap_or_else(|| "A WebGuard user".to_string());
+
+    let link = format!(
+        "{}/accept-invitation?token={}",
+        state.config.server.public_url.trim_end_matches('/'),
+        token
+    );
+    let email = Email::invitation(
+        &invitation.email,
+        &organization.name,
+        &invited_by,
+        &link,
+        state.config.auth.invitation_ttl,
+    );
+    mailer::send_in_background(state.mailer.clone(), email);
+
+    Ok(())
+}
+
+/// List open invitations
+#[utoipa::path(
+    get,
+    path = "/api/organizations/{id}/invitations",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID")
+    ),
+    responses(
+        (status = 200, description = "获取成功（含已过期未接受的邀请）", body = InvitationsResponse),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权限管理邀请")
+    )
+)]
+pub async fn list_invitations(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    _org: OrgAccess<perm::MembersManage>,
+) -> AppResult<impl IntoResponse> {
+    let invitations = queries::list_open_invitations(&state.pool, id).await?;
+
+    Ok(Json(InvitationsResponse { data: invitations }))
+}
+
+/// Invite a user by email
+///
+/// The invitee does not need an account yet; accepting the emailed link adds
+/// them to the organization, registering them first if needed.
+#[utoipa::path(
+    post,
+    path = "/api/organizations/{id}/invitations",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID")
+    ),
+    request_body = CreateInvitation,
+    responses(
+        (status = 201, description = "邀请已发送", body = InvitationResponse),
+        (status = 400, description = "请求参数错误，用户已是成员或已有待接受的邀请"),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权限管理邀请")
+    )
+)]
+pub async fn create_invitation(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    org: OrgAccess<perm::MembersManage>,
+    audit: Auditor,
+    JsonPayload(payload): JsonPayload<CreateInvitation>,
+) -> AppResult<impl IntoResponse> {
+    payload.validate()
+        .map_err(|e| AppError::validation(format!("Invalid input: {}", e)))?;
+
+    if payload.role == MemberRole::Owner {
+        return Err(AppError::validation("Invitations cannot grant the owner role"));
+    }
+    org.access.require_grantable(payload.role.permissions())?;
+
+    let email = payload.email.trim().to_lowercase();
+    if queries::is_organization_member_email(&state.pool, id, &email).await? {
+        return Err(AppError::validation("User is already a member"));
+    }
+    let pending = queries::find_open_invitation(&state.pool, id, &email).await?
+        .filter(|invitation| invitation.expires_at > Utc::now());
+    if pending.is_some() {
+        return Err(AppError::validation("User has already been invited; resend the invitation instead"));
+    }
+
+    let token = generate_user_token();
+    let invitation = queries::create_invitation(
+        &state.pool,
+        id,
+        &email,
+        &payload.role,
+        &sha256_hash(&token),
+        org.auth.user_id,
+        invitation_expiry(&state)?,
+    ).await?;
+
+    send_invitation(&state, &org.auth, &invitation, &token).await?;
+    audit.created(id, AuditAction::InvitationCreated, invitation.id, &invitation).await;
+
+    Ok((StatusCode::CREATED, Json(InvitationResponse { data: invitation })))
+}
+
+/// Resend an invitation
+///
+/// Issues a new link, which invalidates the previous one, and restarts the expiry.
+#[utoipa::path(
+    post,
+    path = "/api/organizations/{id}/invitations/{invitation_id}/resend",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID"),
+        ("invitation_id" = Uuid, Path, description = "邀请ID")
+    ),
+    responses(
+        (status = 200, description = "邀请已重新发送", body = InvitationResponse),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权限管理邀请"),
+        (status = 404, description = "邀请不存在、已接受或已撤销")
+    )
+)]
+pub async fn resend_invitation(
+    State(state): State<AppState>,
+    Path((id, invitation_id)): Path<(Uuid, Uuid)>,
+    org: OrgAccess<perm::MembersManage>,
+    audit: Auditor,
+) -> AppResult<impl IntoResponse> {
+    let before = queries::find_invitation(&state.pool, id, invitation_id).await?;
+    let token = generate_user_token();
+    let invitation = queries::renew_invitation(
+        &state.pool,
+        id,
+        invitation_id,
+        &sha256_hash(&token),
+        invitation_expiry(&state)?,
+    ).await?
+    .ok_or_else(|| AppError::not_found("Invitation not found"))?;
+
+    send_invitation(&state, &org.auth, &invitation, &token).await?;
+    audit.updated(id, AuditAction::InvitationResent, invitation.id, &before, &invitation).await;
+
+    Ok(Json(InvitationResponse { data: invitation }))
+}
+
+/// Revoke an invitation
+#[utoipa::path(
+    delete,
+    path = "/api/organizations/{id}/invitations/{invitation_id}",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID"),
+        ("invitation_id" = Uuid, Path, description = "邀请ID")
+    ),
+    responses(
+        (status = 204, description = "撤销成功"),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权限管理邀请"),
+        (status = 404, description = "邀请不存在、已接受或已撤销")
+    )
+)]
+pub async fn revoke_invitation(
+    State(state): State<AppState>,
+    Path((id, invitation_id)): Path<(Uuid, Uuid)>,
+    _org: OrgAccess<perm::MembersManage>,
+    audit: Auditor,
+) -> AppResult<impl IntoResponse> {
+    let before = queries::find_invitation(&state.pool, id, invitation_id).await?;
+    if !queries::revoke_invitation(&state.pool, id, invitation_id).await? {
+        return Err(AppError::not_found("Invitation not found"));
+    }
+    let after = queries::find_invitation(&state.pool, id, invitation_id).await?;
+    audit.updated(id, AuditAction::InvitationRevoked, invitation_id, &before, &after).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Show what an invitation link is for
+///
+/// Tells the client whether to sign in or register before accepting; new users
+/// register with `invitation_token` set.
+#[utoipa::path(
+    post,
+    path = "/api/invitations/preview",
+    tag = "认证",
+    request_body = InvitationTokenRequest,
+    responses(
+        (status = 200, description = "获取邀请信息成功", body = InvitationPreviewResponse),
+        (status = 400, description = "邀请无效、已过期或已撤销", body = crate::error::ErrorResponse),
+        (status = 429, description = "请求过于频繁", body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn preview_invitation(
+    State(state): State<AppState>,
+    JsonPayload(payload): JsonPayload<InvitationTokenRequest>,
+) -> AppResult<impl IntoResponse> {
+    let invitation = queries::find_invitation_by_token(&state.pool, &sha256_hash(payload.token.trim())).await?
+        .ok_or_else(|| AppError::validation("Invalid or expired invitation"))?;
+    let organization = queries::find_organization_by_id(&state.pool, invitation.organization_id).await?
+        .ok_or_else(|| AppError::validation("Invalid or expired invitation"))?;
+    let account_exists = queries::find_user_by_email(&state.pool, &invitation.email).await?.is_some();
+
+    Ok(Json(InvitationPreviewResponse {
+        organization_name: organization.name,
+        email: invitation.email,
+        role: invitation.role,
+        expires_at: invitation.expires_at,
+        account_exists,
+    }))
+}
+
+/// Accept an invitation with the signed-in account
+///
+/// The account's email address must be the invited one. Receiving the
+/// invitation proves ownership of the address, so it is marked verified.
+#[utoipa::path(
+    post,
+    path = "/api/invitations/accept",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    request_body = InvitationTokenRequest,
+    responses(
+        (status = 200, description = "已加入组织", body = OrganizationResponse),
+        (status = 400, description = "邀请无效、已过期或已撤销", body = crate::error::ErrorResponse),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "邀请的邮箱与当前账号不符", body = crate::error::ErrorResponse)
+    )
+)]
+pub async fn accept_invitation(
+    State(state): State<AppState>,
+    auth: AuthExtractor,
+    audit: Auditor,
+    JsonPayload(payload): JsonPayload<InvitationTokenRequest>,
+) -> AppResult<impl IntoResponse> {
+    if auth.0.api_key.is_some() {
+        return Err(AppError::authorization("API keys cannot accept invitations"));
+    }
+
+    let token_hash = sha256_hash(payload.token.trim());
+    let user = queries::find_user_by_id(&state.pool, auth.0.user_id).await?
+        .ok_or_else(|| AppError::auth("User not found"))?;
+    let invitation = queries::find_invitation_by_token(&state.pool, &token_hash).await?
+        .ok_or_else(|| AppError::validation("Invalid or expired invitation"))?;
+
+    if !invitation.email.eq_ignore_ascii_case(&user.email) {
+        return Err(AppError::authorization("This invitation was sent to a different email address"));
+    }
+
+    let before = invitation;
+    let invitation = queries::accept_invitation(&state.pool, &token_hash, &user).await?
+        .ok_or_else(|| AppError::validation("Invalid or expired invitation"))?;
+    queries::mark_email_verified(&state.pool, user.id).await?;
+    audit.updated(invitation.organization_id, AuditAction::InvitationAccepted, invitation.id, &before, &invitation).await;
+
+    let organization = queries::find_organization_by_id(&state.pool, invitation.organization_id).await?
+        .ok_or_else(|| AppError::not_found("Organization not found"))?;
+
+    Ok(Json(OrganizationResponse { data: organization }))
+}
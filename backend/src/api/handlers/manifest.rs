@@ -0,0 +1,181 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Json},
+};
+use serde::Deserialize;
+use serde_json::json;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::api::routes::AppState;
+use crate::audit::Auditor;
+use crate::auth::{perm, OrgAccess};
+use crate::db::models::{AuditAction, ManifestPlan};
+use crate::db::queries;
+use crate::error::{AppError, AppResult};
+use crate::manifest::{self, Current};
+use crate::quota;
+use crate::tags::TagSelector;
+
+/// Format of an exported manifest
+#[derive(Debug, Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ManifestFormat {
+    #[default]
+    Yaml,
+    Json,
+}
+
+#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+#[into_params(parameter_in = Query)]
+pub struct ManifestExportQuery {
+    /// `yaml` (default) or `json`
+    #[serde(default)]
+    #[param(value_type = Option<String>)]
+    pub format: ManifestFormat,
+}
+
+#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+#[into_params(parameter_in = Query)]
+pub struct ManifestApplyQuery {
+    /// Only compute the changes, without making them
+    #[serde(default)]
+    pub plan: bool,
+    /// Delete resources of managed sections that are not in the manifest
+    #[serde(default)]
+    pub prune: bool,
+}
+
+// Response types
+#[derive(serde::Serialize, ToSchema)]
+pub struct ManifestPlanResponse {
+    pub data: ManifestPlan,
+}
+
+/// Load everything a manifest describes
+async fn load_current(state: &AppState, organization_id: Uuid) -> AppResult<Current> {
+    let organization = queries::find_organization_by_id(&state.pool, organization_id).await?
+        .ok_or_else(|| AppError::not_found("Organization not found"))?;
+
+    Ok(Current {
+        webhook_url: organization.webhook_url,
+        groups: queries::list_domain_groups(&state.pool, organization_id, None).await?,
+        domains: queries::list_organization_domains(&state.pool, organization_id, None, None, &TagSelector::default()).await?,
+        monitors: queries::list_organization_monitors(&state.pool, organization_id).await?,
+        routes: queries::list_alert_routes(&state.pool, organization_id).await?,
+    })
+}
+
+/// Members restricted to some domain groups cannot manage the whole organization
+fn require_unrestricted<P>(org: &OrgAccess<P>) -> AppResult<()> {
+    if org.access.group_filter().is_some() {
+        return Err(AppError::authorization("Members restricted to domain groups cannot use manifests"));
+    }
+
+    Ok(())
+}
+
+/// Export the organization as a manifest
+///
+/// The manifest lists every domain group, domain with its monitors, alert route
+/// and the organization webhook; applying it unchanged makes no changes.
+#[utoipa::path(
+    get,
+    path = "/api/organizations/{id}/manifest",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID"),
+        ManifestExportQuery
+    ),
+    responses(
+        (status = 200, description = "导出成功（YAML 或 JSON）", body = Manifest),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权限管理组织")
+    )
+)]
+pub async fn export_manifest(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    org: OrgAccess<perm::OrganizationManage>,
+    Query(query): Query<ManifestExportQuery>,
+) -> AppResult<impl IntoResponse> {
+    require_unrestricted(&org)?;
+
+    let manifest = manifest::export(&load_current(&state, id).await?);
+    let (content_type, body) = match query.format {
+        ManifestFormat::Yaml => (
+            "application/yaml",
+            serde_yaml::to_string(&manifest)
+                .map_err(|e| AppError::internal(format!("Failed to encode manifest: {}", e)))?,
+        ),
+        ManifestFormat::Json => (
+            "application/json",
+            serde_json::to_string_pretty(&manifest)
+                .map_err(|e| AppError::internal(format!("Failed to encode manifest: {}", e)))?,
+        ),
+    };
+
+    Ok(([(header::CONTENT_TYPE, content_type)], body))
+}
+
+/// Sync the organization with a manifest
+///
+/// The body is YAML, or JSON when sent as `application/json`. Sections left out
+/// of the manifest are not touched. The changes are made in one transaction,
+/// or only listed with `plan=true`; resources of managed sections missing from
+/// the manifest are deleted only with `prune=true`.
+#[utoipa::path(
+    put,
+    path = "/api/organizations/{id}/manifest",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID"),
+        ManifestApplyQuery
+    ),
+    request_body(content = Manifest, description = "清单（YAML，或 application/json 格式的 JSON）"),
+    responses(
+        (status = 200, description = "同步成功，或在 plan 模式下返回变更列表", body = ManifestPlanResponse),
+        (status = 400, description = "清单无效"),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权限管理组织或超出监控器配额")
+    )
+)]
+pub async fn apply_manifest(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    org: OrgAccess<perm::OrganizationManage>,
+    Query(query): Query<ManifestApplyQuery>,
+    headers: HeaderMap,
+    audit: Auditor,
+    body: String,
+) -> AppResult<impl IntoResponse> {
+    require_unrestricted(&org)?;
+
+    let is_json = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("json"));
+    let manifest = manifest::parse(&body, is_json).map_err(AppError::validation)?;
+
+    let current = load_current(&state, id).await?;
+    let (changes, ops) = manifest::plan(&manifest, &current, query.prune)
+        .map_err(|errors| AppError::validation(format!("Invalid manifest: {}", errors.join("; "))))?;
+
+    let organization = queries::find_organization_by_id(&state.pool, id).await?
+        .ok_or_else(|| AppError::not_found("Organization not found"))?;
+    if ops.monitor_delta > 0 {
+        quota::ensure_monitor_capacity(&state.pool, &organization, ops.monitor_delta).await?;
+    }
+    quota::check_monitor_intervals(&organization, ops.upsert_domains.iter().flat_map(|op| &op.domain.monitors))?;
+
+    let applied = !query.plan && !ops.is_empty();
+    if applied {
+        queries::apply_manifest(&state.pool, id, &ops).await?;
+        audit.record(id, AuditAction::ManifestApplied, Some(id), json!({ "changes": changes })).await;
+    }
+
+    Ok(Json(ManifestPlanResponse { data: ManifestPlan { applied, changes } }))
+}
@@ -3,5 +3,19 @@ pub mod organizations;
 pub mod domains;
 pub mod monitoring;
 pub mod public;
+pub mod slo;
+pub mod monitors;
+pub mod api_keys;
+pub mod two_factor;
+pub mod sso;
+pub mod invitations;
+pub mod roles;
+pub mod domain_groups;
+pub mod audit;
+pub mod ownership;
+pub mod alert_routes;
+pub mod domain_import;
+pub mod manifest;
+pub mod events;
 
 pub use auth::*;
@@ -8,33 +8,19 @@ use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
 use crate::{
+    api::pagination::{Page, PageQuery},
     api::routes::AppState,
-    auth::AuthExtractor,
+    auth::{perm, DomainAccess},
     db::{models::*, queries},
     error::{AppError, AppResult},
-    monitors::{uptime, ssl},
+    events::{self, EventKind},
+    monitors::{uptime, ssl, HttpOverrides},
 };
 
 // ============================================================================
 // Request/Response Models
 // ============================================================================
 
-#[derive(Debug, Deserialize, IntoParams, ToSchema)]
-pub struct HistoryQuery {
-    #[serde(default = "default_hours")]
-    pub hours: i64,
-    #[serde(default = "default_interval_minutes")]
-    pub interval_minutes: i64,
-}
-
-fn default_hours() -> i64 {
-    24
-}
-
-fn default_interval_minutes() -> i64 {
-    30
-}
-
 #[derive(Debug, Deserialize, IntoParams, ToSchema)]
 pub struct AggregateQuery {
     #[serde(default = "default_period")]
@@ -98,19 +84,8 @@ pub struct SslStatusResponse {
 pub async fn get_latest_uptime(
     State(state): State<AppState>,
     Path(domain_id): Path<Uuid>,
-    auth: AuthExtractor,
+    _target: DomainAccess<perm::DomainsRead>,
 ) -> AppResult<impl IntoResponse> {
-    // Verify domain exists and user has access
-    let domain = queries::find_domain_by_id(&state.pool, domain_id)
-        .await?
-        .ok_or_else(|| AppError::not_found("Domain not found"))?;
-
-    // Check if user is member of the organization
-    let is_member = queries::is_organization_member(&state.pool, domain.organization_id, auth.0.user_id).await?;
-    if !is_member {
-        return Err(AppError::authorization("Not a member of this organization"));
-    }
-
     // Get latest uptime snapshot
     let snapshot = queries::get_latest_uptime_snapshot(&state.pool, domain_id)
         .await?
@@ -149,19 +124,8 @@ pub async fn get_latest_uptime(
 pub async fn get_latest_ssl(
     State(state): State<AppState>,
     Path(domain_id): Path<Uuid>,
-    auth: AuthExtractor,
+    _target: DomainAccess<perm::DomainsRead>,
 ) -> AppResult<impl IntoResponse> {
-    // Verify domain exists and user has access
-    let domain = queries::find_domain_by_id(&state.pool, domain_id)
-        .await?
-        .ok_or_else(|| AppError::not_found("Domain not found"))?;
-
-    // Check if user is member of the organization
-    let is_member = queries::is_organization_member(&state.pool, domain.organization_id, auth.0.user_id).await?;
-    if !is_member {
-        return Err(AppError::authorization("Not a member of this organization"));
-    }
-
     // Get latest SSL snapshot
     let snapshot = queries::get_latest_ssl_snapshot(&state.pool, domain_id)
         .await?
@@ -194,10 +158,12 @@ pub async fn get_latest_ssl(
     security(("BearerAuth" = [])),
     params(
         ("id" = Uuid, Path, description = "域名ID"),
-        HistoryQuery
+        UptimeHistoryFilter,
+        PageQuery
     ),
     responses(
-        (status = 200, description = "获取历史可用性数据成功"),
+        (status = 200, description = "获取历史可用性数据成功", body = UptimeHistoryPage),
+        (status = 400, description = "请求参数错误"),
         (status = 404, description = "域名不存在"),
         (status = 403, description = "无权访问该域名"),
     )
@@ -207,26 +173,21 @@ pub async fn get_latest_ssl(
 pub async fn get_uptime_history(
     State(state): State<AppState>,
     Path(domain_id): Path<Uuid>,
-    Query(query): Query<HistoryQuery>,
-    auth: AuthExtractor,
+    Query(filter): Query<UptimeHistoryFilter>,
+    page: Page,
+    _target: DomainAccess<perm::DomainsRead>,
 ) -> AppResult<impl IntoResponse> {
-    // Verify domain exists and user has access
-    let domain = queries::find_domain_by_id(&state.pool, domain_id)
-        .await?
-        .ok_or_else(|| AppError::not_found("Domain not found"))?;
-
-    // Check if user is member of the organization
-    let is_member = queries::is_organization_member(&state.pool, domain.organization_id, auth.0.user_id).await?;
-    if !is_member {
-        return Err(AppError::authorization("Not a member of this organization"));
+    if filter.interval_minutes < 1 {
+        return Err(AppError::validation("interval_minutes must be at least 1"));
     }
 
     // Get historical data
-    let snapshots = queries::get_uptime_history(
+    let (snapshots, total) = queries::get_uptime_history(
         &state.pool,
         domain_id,
-        query.hours,
-        query.interval_minutes,
+        &filter,
+        page.after,
+        page.fetch_limit(),
     )
     .await?;
 
@@ -244,7 +205,7 @@ pub async fn get_uptime_history(
         })
         .collect();
 
-    Ok(Json(json!({ "data": response })))
+    Ok(Json(page.paginate(response, total, |s| s.id)))
 }
 
 #[utoipa::path(
@@ -268,19 +229,8 @@ pub async fn get_uptime_aggregate(
     State(state): State<AppState>,
     Path(domain_id): Path<Uuid>,
     Query(query): Query<AggregateQuery>,
-    auth: AuthExtractor,
+    _target: DomainAccess<perm::DomainsRead>,
 ) -> AppResult<impl IntoResponse> {
-    // Verify domain exists and user has access
-    let domain = queries::find_domain_by_id(&state.pool, domain_id)
-        .await?
-        .ok_or_else(|| AppError::not_found("Domain not found"))?;
-
-    // Check if user is member of the organization
-    let is_member = queries::is_organization_member(&state.pool, domain.organization_id, auth.0.user_id).await?;
-    if !is_member {
-        return Err(AppError::authorization("Not a member of this organization"));
-    }
-
     // Get the aggregate
     let aggregate = queries::get_latest_uptime_aggregate(&state.pool, domain_id, &query.period).await?;
 
@@ -299,6 +249,7 @@ pub async fn get_uptime_aggregate(
         (status = 200, description = "手动触发监控检查成功"),
         (status = 404, description = "域名不存在"),
         (status = 403, description = "无权访问该域名"),
+        (status = 429, description = "手动检查过于频繁"),
     )
 )]
 /// POST /api/domains/{id}/monitoring/check
@@ -306,22 +257,17 @@ pub async fn get_uptime_aggregate(
 pub async fn trigger_check(
     State(state): State<AppState>,
     Path(domain_id): Path<Uuid>,
-    auth: AuthExtractor,
+    target: DomainAccess<perm::MonitorsCheck>,
 ) -> AppResult<impl IntoResponse> {
-    // Verify domain exists and user has access
-    let domain = queries::find_domain_by_id(&state.pool, domain_id)
-        .await?
-        .ok_or_else(|| AppError::not_found("Domain not found"))?;
+    let domain = target.domain;
 
-    // Check if user is member of the organization
-    let is_member = queries::is_organization_member(&state.pool, domain.organization_id, auth.0.user_id).await?;
-    if !is_member {
-        return Err(AppError::authorization("Not a member of this organization"));
-    }
+    let overrides = HttpOverrides::for_domain(&state.pool, domain_id).await?;
 
     // Trigger uptime check
-    let uptime_result = match uptime::check_uptime(&domain.normalized_name, None).await {
+    let uptime_result = match uptime::check_uptime(&state.http, &domain.normalized_name, None, &overrides).await {
         Ok(result) => {
+            let was_up = queries::get_latest_uptime_snapshot(&state.pool, domain_id).await?.map(|s| s.is_up);
+
             // Save the snapshot
             let snapshot = UptimeSnapshot {
                 id: Uuid::new_v4(),
@@ -334,6 +280,15 @@ pub async fn trigger_check(
                 consecutive_failures: 0,
             };
             queries::save_uptime_snapshot(&state.pool, &snapshot).await?;
+            events::publish_uptime_check(
+                &state.pool,
+                domain_id,
+                was_up,
+                snapshot.is_up,
+                snapshot.status_code,
+                result.response_time_ms as i32,
+                snapshot.error_type,
+            ).await;
             json!({
                 "success": true,
                 "is_up": result.is_up,
@@ -370,6 +325,10 @@ pub async fn trigger_check(
                 hostname_matches: true, // TODO: Implement hostname matching
             };
             queries::save_ssl_snapshot(&state.pool, &snapshot).await?;
+            events::publish(&state.pool, domain_id, EventKind::SslChecked {
+                is_expired: cert_info.is_expired,
+                days_until_expiry: cert_info.days_until_expiry,
+            }).await;
             json!({
                 "success": true,
                 "is_valid": cert_info.is_valid,
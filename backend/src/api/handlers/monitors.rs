@@ -0,0 +1,298 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+    Json as JsonPayload,
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    api::routes::AppState,
+    audit::Auditor,
+    auth::{perm, DomainAccess},
+    db::{models::*, queries},
+    error::{AppError, AppResult},
+    monitors::monitor_config,
+    quota,
+};
+
+// ============================================================================
+// Request/Response Models
+// ============================================================================
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MonitorResponse {
+    pub data: Monitor,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MonitorsResponse {
+    pub data: Vec<Monitor>,
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Load a monitor belonging to the given domain
+async fn find_domain_monitor(state: &AppState, domain_id: Uuid, monitor_id: Uuid) -> AppResult<Monitor> {
+    queries::find_domain_monitor(&state.pool, domain_id, monitor_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Monitor not found"))
+}
+
+/// Check a monitor config against the schema of its type and the
+/// organization's minimum check interval
+async fn check_config(
+    state: &AppState,
+    organization_id: Uuid,
+    monitor_type: &MonitorType,
+    config: &serde_json::Value,
+) -> AppResult<()> {
+    monitor_config::validate(monitor_type, config).map_err(AppError::validation)?;
+
+    let organization = queries::find_organization_by_id(&state.pool, organization_id).await?
+        .ok_or_else(|| AppError::not_found("Organization not found"))?;
+    if let Some(interval) = monitor_config::interval_seconds(config) {
+        quota::check_interval(&organization, interval)?;
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+#[utoipa::path(
+    get,
+    path = "/api/domains/{id}/monitors",
+    tag = "监控",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "域名ID")
+    ),
+    responses(
+        (status = 200, description = "获取监控器列表成功", body = MonitorsResponse),
+        (status = 404, description = "域名不存在"),
+        (status = 403, description = "无权访问该域名"),
+    )
+)]
+/// GET /api/domains/{id}/monitors
+/// List the monitors of a domain
+pub async fn list_monitors(
+    State(state): State<AppState>,
+    Path(domain_id): Path<Uuid>,
+    _target: DomainAccess<perm::DomainsRead>,
+) -> AppResult<impl IntoResponse> {
+    let data = queries::list_domain_monitors(&state.pool, domain_id).await?;
+
+    Ok(Json(MonitorsResponse { data }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/domains/{id}/monitors",
+    tag = "监控",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "域名ID")
+    ),
+    request_body = CreateMonitor,
+    responses(
+        (status = 201, description = "创建监控器成功", body = MonitorResponse),
+        (status = 400, description = "配置无效或该类型的监控器已存在"),
+        (status = 404, description = "域名不存在"),
+        (status = 403, description = "无权管理该域名或超出监控器配额"),
+    )
+)]
+/// POST /api/domains/{id}/monitors
+/// Add a monitor to a domain; a domain has at most one monitor of each type
+pub async fn create_monitor(
+    State(state): State<AppState>,
+    Path(domain_id): Path<Uuid>,
+    target: DomainAccess<perm::MonitorsEdit>,
+    audit: Auditor,
+    JsonPayload(payload): JsonPayload<CreateMonitor>,
+) -> AppResult<impl IntoResponse> {
+    let org_id = target.domain.organization_id;
+    check_config(&state, org_id, &payload.monitor_type, &payload.config).await?;
+
+    let monitor = queries::create_monitor(&state.pool, org_id, domain_id, &payload).await?
+        .ok_or_else(|| AppError::validation(format!(
+            "This domain already has a {} monitor",
+            payload.monitor_type
+        )))?;
+    audit.created(org_id, AuditAction::MonitorCreated, monitor.id, &monitor).await;
+
+    Ok((StatusCode::CREATED, Json(MonitorResponse { data: monitor })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/domains/{id}/monitors/{monitor_id}",
+    tag = "监控",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "域名ID"),
+        ("monitor_id" = Uuid, Path, description = "监控器ID")
+    ),
+    responses(
+        (status = 200, description = "获取监控器成功", body = MonitorResponse),
+        (status = 404, description = "监控器不存在"),
+        (status = 403, description = "无权访问该域名"),
+    )
+)]
+/// GET /api/domains/{id}/monitors/{monitor_id}
+/// Get a monitor
+pub async fn get_monitor(
+    State(state): State<AppState>,
+    Path((domain_id, monitor_id)): Path<(Uuid, Uuid)>,
+    _target: DomainAccess<perm::DomainsRead>,
+) -> AppResult<impl IntoResponse> {
+    let monitor = find_domain_monitor(&state, domain_id, monitor_id).await?;
+
+    Ok(Json(MonitorResponse { data: monitor }))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/domains/{id}/monitors/{monitor_id}",
+    tag = "监控",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "域名ID"),
+        ("monitor_id" = Uuid, Path, description = "监控器ID")
+    ),
+    request_body = UpdateMonitor,
+    responses(
+        (status = 200, description = "更新监控器成功", body = MonitorResponse),
+        (status = 400, description = "配置无效"),
+        (status = 404, description = "监控器不存在"),
+        (status = 403, description = "无权管理该域名或检查间隔低于组织下限"),
+    )
+)]
+/// PUT /api/domains/{id}/monitors/{monitor_id}
+/// Enable or disable a monitor, or replace its config
+pub async fn update_monitor(
+    State(state): State<AppState>,
+    Path((domain_id, monitor_id)): Path<(Uuid, Uuid)>,
+    target: DomainAccess<perm::MonitorsEdit>,
+    audit: Auditor,
+    JsonPayload(payload): JsonPayload<UpdateMonitor>,
+) -> AppResult<impl IntoResponse> {
+    let org_id = target.domain.organization_id;
+    let before = find_domain_monitor(&state, domain_id, monitor_id).await?;
+    if let Some(config) = &payload.config {
+        check_config(&state, org_id, &before.monitor_type, config).await?;
+    }
+
+    let monitor = queries::update_monitor(&state.pool, monitor_id, payload.is_enabled, payload.config.as_ref()).await?
+        .ok_or_else(|| AppError::not_found("Monitor not found"))?;
+    audit.updated(org_id, AuditAction::MonitorUpdated, monitor_id, &before, &monitor).await;
+
+    Ok(Json(MonitorResponse { data: monitor }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/domains/{id}/monitors/{monitor_id}",
+    tag = "监控",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "域名ID"),
+        ("monitor_id" = Uuid, Path, description = "监控器ID")
+    ),
+    responses(
+        (status = 204, description = "删除监控器成功"),
+        (status = 404, description = "监控器不存在"),
+        (status = 403, description = "无权管理该域名"),
+    )
+)]
+/// DELETE /api/domains/{id}/monitors/{monitor_id}
+/// Delete a monitor; the domain's monitoring history is kept
+pub async fn delete_monitor(
+    State(state): State<AppState>,
+    Path((domain_id, monitor_id)): Path<(Uuid, Uuid)>,
+    target: DomainAccess<perm::MonitorsEdit>,
+    audit: Auditor,
+) -> AppResult<impl IntoResponse> {
+    let monitor = find_domain_monitor(&state, domain_id, monitor_id).await?;
+
+    queries::delete_monitor(&state.pool, monitor_id).await?;
+    audit.deleted(target.domain.organization_id, AuditAction::MonitorDeleted, monitor_id, &monitor).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/domains/{id}/monitors/{monitor_id}/pause",
+    tag = "监控",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "域名ID"),
+        ("monitor_id" = Uuid, Path, description = "监控器ID")
+    ),
+    request_body = PauseMonitor,
+    responses(
+        (status = 200, description = "暂停监控器成功", body = MonitorResponse),
+        (status = 400, description = "恢复时间必须晚于当前时间"),
+        (status = 404, description = "监控器不存在"),
+        (status = 403, description = "无权管理该域名"),
+    )
+)]
+/// POST /api/domains/{id}/monitors/{monitor_id}/pause
+/// Stop checking a monitor until the given time, e.g. during maintenance
+pub async fn pause_monitor(
+    State(state): State<AppState>,
+    Path((domain_id, monitor_id)): Path<(Uuid, Uuid)>,
+    target: DomainAccess<perm::MonitorsEdit>,
+    audit: Auditor,
+    JsonPayload(payload): JsonPayload<PauseMonitor>,
+) -> AppResult<impl IntoResponse> {
+    if payload.until <= chrono::Utc::now() {
+        return Err(AppError::validation("A monitor can only be paused until a time in the future"));
+    }
+    let before = find_domain_monitor(&state, domain_id, monitor_id).await?;
+
+    let monitor = queries::set_monitor_paused_until(&state.pool, monitor_id, Some(payload.until)).await?
+        .ok_or_else(|| AppError::not_found("Monitor not found"))?;
+    audit.updated(target.domain.organization_id, AuditAction::MonitorUpdated, monitor_id, &before, &monitor).await;
+
+    Ok(Json(MonitorResponse { data: monitor }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/domains/{id}/monitors/{monitor_id}/resume",
+    tag = "监控",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "域名ID"),
+        ("monitor_id" = Uuid, Path, description = "监控器ID")
+    ),
+    responses(
+        (status = 200, description = "恢复监控器成功", body = MonitorResponse),
+        (status = 404, description = "监控器不存在"),
+        (status = 403, description = "无权管理该域名"),
+    )
+)]
+/// POST /api/domains/{id}/monitors/{monitor_id}/resume
+/// Resume a paused monitor before its pause ends
+pub async fn resume_monitor(
+    State(state): State<AppState>,
+    Path((domain_id, monitor_id)): Path<(Uuid, Uuid)>,
+    target: DomainAccess<perm::MonitorsEdit>,
+    audit: Auditor,
+) -> AppResult<impl IntoResponse> {
+    let before = find_domain_monitor(&state, domain_id, monitor_id).await?;
+
+    let monitor = queries::set_monitor_paused_until(&state.pool, monitor_id, None).await?
+        .ok_or_else(|| AppError::not_found("Monitor not found"))?;
+    audit.updated(target.domain.organization_id, AuditAction::MonitorUpdated, monitor_id, &before, &monitor).await;
+
+    Ok(Json(MonitorResponse { data: monitor }))
+}
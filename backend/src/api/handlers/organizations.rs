@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::{IntoResponse, Json},
     Json as JsonPayload,
@@ -7,11 +7,16 @@ use axum::{
 use uuid::Uuid;
 use utoipa::ToSchema;
 
+use crate::api::pagination::{Page, PageQuery};
 use crate::api::routes::AppState;
-use crate::db::models::{OrganizationMember, MemberRole, Organization, OrganizationStats, Alert};
+use crate::audit::Auditor;
+use crate::db::models::{AlertFilter, AuditAction, DomainFilter, MemberFilter, OrganizationMember, MemberAccess, MemberRole, Organization, OrganizationStats, OrganizationUsage, Alert, RetentionPolicy, RetentionTarget};
 use crate::db::queries;
 use crate::error::{AppError, AppResult};
-use crate::auth::AuthExtractor;
+use crate::auth::{perm, AuthExtractor, OrgAccess};
+use crate::monitors::retention;
+use crate::quota;
+use crate::tags::TagSelector;
 
 // Request types
 #[derive(serde::Deserialize, ToSchema)]
@@ -24,6 +29,10 @@ pub struct CreateOrganizationRequest {
 pub struct UpdateOrganizationRequest {
     pub name: Option<String>,
     pub webhook_url: Option<String>,
+    /// IANA timezone name (e.g. "Asia/Shanghai") used for aggregate periods
+    pub timezone: Option<String>,
+    /// Require all members to enable two-factor authentication
+    pub require_two_factor: Option<bool>,
 }
 
 #[derive(serde::Deserialize, ToSchema)]
@@ -35,6 +44,14 @@ pub struct AddMemberRequest {
 #[derive(serde::Deserialize, ToSchema)]
 pub struct UpdateMemberRoleRequest {
     pub role: MemberRole,
+    /// Custom role whose permissions replace those of `role`
+    pub custom_role_id: Option<Uuid>,
+}
+
+#[derive(serde::Deserialize, ToSchema)]
+pub struct UpdateMemberDomainGroupsRequest {
+    /// Domain groups the member is restricted to; null gives access to all domains
+    pub group_ids: Option<Vec<Uuid>>,
 }
 
 // Response wrapper types
@@ -54,18 +71,42 @@ pub struct MemberResponse {
 }
 
 #[derive(serde::Serialize, ToSchema)]
-pub struct MembersResponse {
-    pub data: Vec<OrganizationMember>,
+pub struct OrganizationStatsResponse {
+    pub data: OrganizationStats,
 }
 
 #[derive(serde::Serialize, ToSchema)]
-pub struct OrganizationStatsResponse {
-    pub data: OrganizationStats,
+pub struct OrganizationUsageResponse {
+    pub data: OrganizationUsage,
 }
 
 #[derive(serde::Serialize, ToSchema)]
-pub struct AlertsResponse {
-    pub data: Vec<Alert>,
+pub struct AlertResponse {
+    pub data: Alert,
+}
+
+#[derive(serde::Serialize, ToSchema)]
+pub struct RetentionPolicyResponse {
+    /// Organization overrides (null uses the default)
+    pub data: RetentionPolicy,
+    /// Global defaults
+    pub defaults: RetentionPolicy,
+}
+
+/// Load a member the caller may manage: the caller must hold every permission
+/// the member has, and not be restricted to domain groups
+async fn manageable_member(
+    state: &AppState,
+    org: &OrgAccess<perm::MembersManage>,
+    user_id: Uuid,
+) -> AppResult<MemberAccess> {
+    let target = queries::get_member_access(&state.pool, org.organization_id(), user_id, None).await?
+        .ok_or_else(|| AppError::not_found("Member not found"))?;
+
+    org.access.require_grantable(&target.permissions())
+        .map_err(|_| AppError::authorization("You cannot manage members with permissions you don't have"))?;
+
+    Ok(target)
 }
 
 /// Create a new organization
@@ -84,8 +125,14 @@ pub struct AlertsResponse {
 pub async fn create_organization(
     State(state): State<AppState>,
     auth: AuthExtractor,
+    audit: Auditor,
     JsonPayload(payload): JsonPayload<CreateOrganizationRequest>,
 ) -> AppResult<impl IntoResponse> {
+    // API keys are scoped to a single organization
+    if auth.0.api_key.is_some() {
+        return Err(AppError::authorization("API keys cannot create organizations"));
+    }
+
     // Generate slug if not provided
     let slug = payload.slug.unwrap_or_else(|| {
         payload.name.to_lowercase()
@@ -97,6 +144,7 @@ pub async fn create_organization(
 
     // Create organization with the user as owner
     let org = queries::create_organization(&state.pool, &payload.name, &slug, auth.0.user_id).await?;
+    audit.created(org.id, AuditAction::OrganizationCreated, org.id, &org).await;
 
     let response = serde_json::json!({
         "data": org
@@ -120,7 +168,7 @@ pub async fn list_organizations(
     State(state): State<AppState>,
     auth: AuthExtractor,
 ) -> AppResult<impl IntoResponse> {
-    let orgs = queries::list_user_organizations(&state.pool, auth.0.user_id).await?;
+    let orgs = auth.0.organizations(&state.pool).await?;
 
     let response = serde_json::json!({
         "data": orgs
@@ -148,14 +196,8 @@ pub async fn list_organizations(
 pub async fn get_organization(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
-    auth: AuthExtractor,
+    _org: OrgAccess,
 ) -> AppResult<impl IntoResponse> {
-    // Check if user is a member of this organization
-    let is_member = queries::is_organization_member(&state.pool, id, auth.0.user_id).await?;
-    if !is_member {
-        return Err(AppError::authorization("You are not a member of this organization"));
-    }
-
     let org = queries::find_organization_by_id(&state.pool, id).await?
         .ok_or_else(|| AppError::not_found("Organization not found"))?;
 
@@ -185,17 +227,13 @@ pub async fn get_organization(
 pub async fn update_organization(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
-    auth: AuthExtractor,
+    org: OrgAccess<perm::OrganizationManage>,
+    audit: Auditor,
     JsonPayload(payload): JsonPayload<UpdateOrganizationRequest>,
 ) -> AppResult<impl IntoResponse> {
-    // Check if user is admin or owner
-    let role = queries::get_user_role(&state.pool, id, auth.0.user_id).await?
+    let before = queries::find_organization_by_id(&state.pool, id).await?
         .ok_or_else(|| AppError::not_found("Organization not found"))?;
 
-    if !role.is_admin() {
-        return Err(AppError::authorization("Only admins can update organization"));
-    }
-
     // Update fields
     if let Some(name) = &payload.name {
         sqlx::query("UPDATE organizations SET name = $1 WHERE id = $2")
@@ -215,9 +253,43 @@ pub async fn update_organization(
             .map_err(|e| AppError::internal(format!("Failed to update organization: {}", e)))?;
     }
 
+    if let Some(timezone) = &payload.timezone {
+        if timezone.parse::<chrono_tz::Tz>().is_err() {
+            return Err(AppError::validation(format!("Invalid timezone: {}", timezone)));
+        }
+
+        sqlx::query("UPDATE organizations SET timezone = $1 WHERE id = $2")
+            .bind(timezone)
+            .bind(id)
+            .execute(&state.pool)
+            .await
+            .map_err(|e| AppError::internal(format!("Failed to update organization: {}", e)))?;
+    }
+
+    if let Some(require_two_factor) = payload.require_two_factor {
+        // Enabling the policy must not lock out the admin enabling it
+        if require_two_factor && org.auth.api_key.is_none() {
+            let user = queries::find_user_by_id(&state.pool, org.auth.user_id).await?
+                .ok_or_else(|| AppError::auth("User not found"))?;
+            if user.totp_enabled_at.is_none() {
+                return Err(AppError::validation(
+                    "Enable two-factor authentication on your account before requiring it",
+                ));
+            }
+        }
+
+        sqlx::query("UPDATE organizations SET require_two_factor = $1 WHERE id = $2")
+            .bind(require_two_factor)
+            .bind(id)
+            .execute(&state.pool)
+            .await
+            .map_err(|e| AppError::internal(format!("Failed to update organization: {}", e)))?;
+    }
+
     // Fetch updated organization
     let org = queries::find_organization_by_id(&state.pool, id).await?
         .ok_or_else(|| AppError::not_found("Organization not found"))?;
+    audit.updated(id, AuditAction::OrganizationUpdated, id, &before, &org).await;
 
     let response = serde_json::json!({
         "data": org
@@ -227,6 +299,10 @@ pub async fn update_organization(
 }
 
 /// Delete organization
+///
+/// The organization is only scheduled for deletion: monitoring pauses, members
+/// lose access and the owner can restore it until `purge_after`, when it is
+/// removed for good.
 #[utoipa::path(
     delete,
     path = "/api/organizations/{id}",
@@ -236,7 +312,7 @@ pub async fn update_organization(
         ("id" = Uuid, Path, description = "组织ID")
     ),
     responses(
-        (status = 204, description = "删除成功"),
+        (status = 200, description = "已计划删除", body = OrganizationResponse),
         (status = 401, description = "未授权"),
         (status = 403, description = "无权限删除组织")
     )
@@ -244,19 +320,65 @@ pub async fn update_organization(
 pub async fn delete_organization(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
-    auth: AuthExtractor,
+    org: OrgAccess,
+    audit: Auditor,
 ) -> AppResult<impl IntoResponse> {
     // Check if user is owner
-    let role = queries::get_user_role(&state.pool, id, auth.0.user_id).await?
+    if org.access.role != MemberRole::Owner {
+        return Err(AppError::authorization("Only owners can delete organization"));
+    }
+
+    let before = queries::find_organization_by_id(&state.pool, id).await?
         .ok_or_else(|| AppError::not_found("Organization not found"))?;
 
-    if role != MemberRole::Owner {
-        return Err(AppError::authorization("Only owners can delete organization"));
+    let grace_days = state.config.retention.deleted_organization_grace_days;
+    let deleted = queries::schedule_organization_deletion(&state.pool, id, grace_days).await?
+        .ok_or_else(|| AppError::validation("Organization is already scheduled for deletion"))?;
+    audit.updated(id, AuditAction::OrganizationDeleted, id, &before, &deleted).await;
+
+    Ok(Json(OrganizationResponse { data: deleted }))
+}
+
+/// Restore an organization scheduled for deletion
+///
+/// Only its owner can restore it; monitoring resumes on the next poll.
+#[utoipa::path(
+    post,
+    path = "/api/organizations/{id}/restore",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID")
+    ),
+    responses(
+        (status = 200, description = "恢复成功", body = OrganizationResponse),
+        (status = 400, description = "组织未计划删除"),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "只有所有者可以恢复组织")
+    )
+)]
+pub async fn restore_organization(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    auth: AuthExtractor,
+    audit: Auditor,
+) -> AppResult<impl IntoResponse> {
+    // Members have no access to a deleted organization, so check the role directly
+    let role = match &auth.0.api_key {
+        Some(_) => None,
+        None => queries::get_member_access(&state.pool, id, auth.0.user_id, None).await?.map(|a| a.role),
+    };
+    if role != Some(MemberRole::Owner) {
+        return Err(AppError::authorization("Only owners can restore organization"));
     }
 
-    queries::delete_organization(&state.pool, id).await?;
+    let before = queries::find_organization_by_id(&state.pool, id).await?
+        .ok_or_else(|| AppError::not_found("Organization not found"))?;
+    let org = queries::restore_organization(&state.pool, id).await?
+        .ok_or_else(|| AppError::validation("Organization is not scheduled for deletion"))?;
+    audit.updated(id, AuditAction::OrganizationRestored, id, &before, &org).await;
 
-    Ok(StatusCode::NO_CONTENT)
+    Ok(Json(OrganizationResponse { data: org }))
 }
 
 /// List organization members
@@ -266,10 +388,13 @@ pub async fn delete_organization(
     tag = "组织",
     security(("BearerAuth" = [])),
     params(
-        ("id" = Uuid, Path, description = "组织ID")
+        ("id" = Uuid, Path, description = "组织ID"),
+        MemberFilter,
+        PageQuery
     ),
     responses(
-        (status = 200, description = "获取成功"),
+        (status = 200, description = "获取成功", body = MemberPage),
+        (status = 400, description = "请求参数错误"),
         (status = 401, description = "未授权"),
         (status = 403, description = "不是组织成员")
     )
@@ -277,33 +402,13 @@ pub async fn delete_organization(
 pub async fn list_members(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
-    auth: AuthExtractor,
+    _org: OrgAccess,
+    Query(filter): Query<MemberFilter>,
+    page: Page,
 ) -> AppResult<impl IntoResponse> {
-    // Check if user is a member
-    let is_member = queries::is_organization_member(&state.pool, id, auth.0.user_id).await?;
-    if !is_member {
-        return Err(AppError::authorization("You are not a member of this organization"));
-    }
+    let (members, total) = queries::list_organization_members(&state.pool, id, &filter, page.after, page.fetch_limit()).await?;
 
-    let members = sqlx::query_as::<_, OrganizationMember>(
-        r#"
-        SELECT om.*, u.email, u.full_name
-        FROM organization_members om
-        INNER JOIN users u ON u.id = om.user_id
-        WHERE om.organization_id = $1
-        ORDER BY om.created_at ASC
-        "#
-    )
-    .bind(id)
-    .fetch_all(&state.pool)
-    .await
-    .map_err(|e| AppError::internal(format!("Failed to fetch members: {}", e)))?;
-
-    let response = serde_json::json!({
-        "data": members
-    });
-
-    Ok(Json(response))
+    Ok(Json(page.paginate(members, total, |m| m.member.id)))
 }
 
 /// Add member to organization
@@ -326,20 +431,15 @@ pub async fn list_members(
 pub async fn add_member(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
-    auth: AuthExtractor,
+    org: OrgAccess<perm::MembersManage>,
+    audit: Auditor,
     JsonPayload(payload): JsonPayload<AddMemberRequest>,
 ) -> AppResult<impl IntoResponse> {
-    // Check if user is admin or owner
-    let role = queries::get_user_role(&state.pool, id, auth.0.user_id).await?
-        .ok_or_else(|| AppError::not_found("Organization not found"))?;
-
-    if !role.is_admin() {
-        return Err(AppError::authorization("Only admins can add members"));
-    }
+    org.access.require_grantable(payload.role.permissions())?;
 
     // Find user by email
     let user = queries::find_user_by_email(&state.pool, &payload.email).await?
-        .ok_or_else(|| AppError::validation("User not found"))?;
+        .ok_or_else(|| AppError::validation("User not found; invite them by email instead"))?;
 
     // Check if user is already a member
     let is_member = queries::is_organization_member(&state.pool, id, user.id).await?;
@@ -372,6 +472,7 @@ pub async fn add_member(
     .fetch_one(&state.pool)
     .await
     .map_err(|e| AppError::internal(format!("Failed to fetch member: {}", e)))?;
+    audit.created(id, AuditAction::MemberAdded, user.id, &member).await;
 
     let response = serde_json::json!({
         "data": member
@@ -400,24 +501,18 @@ pub async fn add_member(
 pub async fn remove_member(
     State(state): State<AppState>,
     Path((id, user_id)): Path<(Uuid, Uuid)>,
-    auth: AuthExtractor,
+    org: OrgAccess<perm::MembersManage>,
+    audit: Auditor,
 ) -> AppResult<impl IntoResponse> {
-    // Check if user is admin or owner
-    let role = queries::get_user_role(&state.pool, id, auth.0.user_id).await?
-        .ok_or_else(|| AppError::not_found("Organization not found"))?;
-
-    if !role.is_admin() {
-        return Err(AppError::authorization("Only admins can remove members"));
-    }
+    let target = manageable_member(&state, &org, user_id).await?;
 
     // Cannot remove the owner
-    let target_role = queries::get_user_role(&state.pool, id, user_id).await?
-        .ok_or_else(|| AppError::not_found("Member not found"))?;
-
-    if target_role == MemberRole::Owner {
+    if target.role == MemberRole::Owner {
         return Err(AppError::validation("Cannot remove organization owner"));
     }
 
+    let before = queries::find_organization_member(&state.pool, id, user_id).await?;
+
     // Remove member
     sqlx::query("DELETE FROM organization_members WHERE organization_id = $1 AND user_id = $2")
         .bind(id)
@@ -425,6 +520,10 @@ pub async fn remove_member(
         .execute(&state.pool)
         .await
         .map_err(|e| AppError::internal(format!("Failed to remove member: {}", e)))?;
+    audit.deleted(id, AuditAction::MemberRemoved, user_id, &before).await;
+
+    // Removal bumps the member's token version; drop cached checks so it applies now
+    state.revocations.invalidate_user(user_id);
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -450,37 +549,99 @@ pub async fn remove_member(
 pub async fn update_member_role(
     State(state): State<AppState>,
     Path((id, user_id)): Path<(Uuid, Uuid)>,
-    auth: AuthExtractor,
+    org: OrgAccess<perm::MembersManage>,
+    audit: Auditor,
     JsonPayload(payload): JsonPayload<UpdateMemberRoleRequest>,
 ) -> AppResult<impl IntoResponse> {
-    // Check if user is owner (only owner can change roles)
-    let role = queries::get_user_role(&state.pool, id, auth.0.user_id).await?
-        .ok_or_else(|| AppError::not_found("Organization not found"))?;
-
-    if role != MemberRole::Owner {
-        return Err(AppError::authorization("Only owners can change member roles"));
+    if user_id == org.auth.user_id {
+        return Err(AppError::validation("You cannot change your own role"));
+    }
+    if payload.role == MemberRole::Owner {
+        return Err(AppError::validation("Cannot grant the owner role"));
     }
 
     // Cannot change owner role
-    let target_role = queries::get_user_role(&state.pool, id, user_id).await?
-        .ok_or_else(|| AppError::not_found("Member not found"))?;
-
-    if target_role == MemberRole::Owner {
+    let target = manageable_member(&state, &org, user_id).await?;
+    if target.role == MemberRole::Owner {
         return Err(AppError::validation("Cannot change owner role"));
     }
 
-    // Update role
-    sqlx::query("UPDATE organization_members SET role = $1 WHERE organization_id = $2 AND user_id = $3")
-        .bind(&payload.role.to_string())
-        .bind(id)
-        .bind(user_id)
-        .execute(&state.pool)
-        .await
-        .map_err(|e| AppError::internal(format!("Failed to update member role: {}", e)))?;
+    // Only permissions the caller holds can be granted
+    let permissions = match payload.custom_role_id {
+        Some(role_id) => queries::find_organization_role(&state.pool, id, role_id).await?
+            .ok_or_else(|| AppError::validation("Role not found"))?
+            .permission_set(),
+        None => payload.role.permissions().to_vec(),
+    };
+    org.access.require_grantable(&permissions)?;
+
+    let before = queries::find_organization_member(&state.pool, id, user_id).await?;
+    queries::update_member_role(&state.pool, id, user_id, &payload.role, payload.custom_role_id).await?;
+    let after = queries::find_organization_member(&state.pool, id, user_id).await?;
+    audit.updated(id, AuditAction::MemberRoleChanged, user_id, &before, &after).await;
+
+    state.revocations.invalidate_user(user_id);
 
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Restrict a member to domain groups
+#[utoipa::path(
+    put,
+    path = "/api/organizations/{id}/members/{user_id}/domain-groups",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID"),
+        ("user_id" = Uuid, Path, description = "用户ID")
+    ),
+    request_body = UpdateMemberDomainGroupsRequest,
+    responses(
+        (status = 200, description = "更新成功", body = MemberResponse),
+        (status = 400, description = "域名分组不存在或无法限制所有者"),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权限管理成员"),
+        (status = 404, description = "成员不存在")
+    )
+)]
+pub async fn update_member_domain_groups(
+    State(state): State<AppState>,
+    Path((id, user_id)): Path<(Uuid, Uuid)>,
+    org: OrgAccess<perm::MembersManage>,
+    audit: Auditor,
+    JsonPayload(payload): JsonPayload<UpdateMemberDomainGroupsRequest>,
+) -> AppResult<impl IntoResponse> {
+    if user_id == org.auth.user_id {
+        return Err(AppError::validation("You cannot change your own domain groups"));
+    }
+
+    let target = manageable_member(&state, &org, user_id).await?;
+    if target.role == MemberRole::Owner {
+        return Err(AppError::validation("Cannot restrict the organization owner"));
+    }
+
+    let group_ids = payload.group_ids.map(|mut ids| {
+        ids.sort();
+        ids.dedup();
+        ids
+    });
+    if let Some(ids) = &group_ids {
+        let found = queries::count_domain_groups(&state.pool, id, ids).await?;
+        if usize::try_from(found).ok() != Some(ids.len()) {
+            return Err(AppError::validation("Domain group not found"));
+        }
+    }
+
+    let before = queries::find_organization_member(&state.pool, id, user_id).await?;
+    queries::set_member_domain_groups(&state.pool, id, user_id, group_ids.as_deref()).await?;
+
+    let member = queries::find_organization_member(&state.pool, id, user_id).await?
+        .ok_or_else(|| AppError::not_found("Member not found"))?;
+    audit.updated(id, AuditAction::MemberDomainGroupsChanged, user_id, &before, &member).await;
+
+    Ok(Json(MemberResponse { data: member }))
+}
+
 /// Get organization statistics
 #[utoipa::path(
     get,
@@ -488,10 +649,12 @@ pub async fn update_member_role(
     tag = "组织",
     security(("BearerAuth" = [])),
     params(
-        ("id" = Uuid, Path, description = "组织ID")
+        ("id" = Uuid, Path, description = "组织ID"),
+        DomainFilter
     ),
     responses(
         (status = 200, description = "获取成功", body = OrganizationStatsResponse),
+        (status = 400, description = "标签筛选条件无效"),
         (status = 401, description = "未授权"),
         (status = 403, description = "不是组织成员")
     )
@@ -499,15 +662,17 @@ pub async fn update_member_role(
 pub async fn get_organization_stats(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
-    auth: AuthExtractor,
+    org: OrgAccess<perm::DomainsRead>,
+    Query(filter): Query<DomainFilter>,
 ) -> AppResult<impl IntoResponse> {
-    // Check if user is a member
-    let is_member = queries::is_organization_member(&state.pool, id, auth.0.user_id).await?;
-    if !is_member {
-        return Err(AppError::authorization("You are not a member of this organization"));
-    }
-
-    let stats = queries::get_organization_stats(&state.pool, id).await?;
+    let tags = TagSelector::parse(filter.tag.as_deref())?;
+    let stats = queries::get_organization_stats(
+        &state.pool,
+        id,
+        org.access.group_filter(),
+        filter.group_id,
+        &tags,
+    ).await?;
 
     let response = serde_json::json!({
         "data": stats
@@ -516,6 +681,33 @@ pub async fn get_organization_stats(
     Ok(Json(response))
 }
 
+/// Get organization usage against its quotas
+#[utoipa::path(
+    get,
+    path = "/api/organizations/{id}/usage",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID")
+    ),
+    responses(
+        (status = 200, description = "获取成功", body = OrganizationUsageResponse),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "不是组织成员")
+    )
+)]
+pub async fn get_organization_usage(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    _org: OrgAccess,
+) -> AppResult<impl IntoResponse> {
+    let organization = queries::find_organization_by_id(&state.pool, id).await?
+        .ok_or_else(|| AppError::not_found("Organization not found"))?;
+    let usage = quota::usage(&state.pool, &organization).await?;
+
+    Ok(Json(OrganizationUsageResponse { data: usage }))
+}
+
 /// List organization alerts
 #[utoipa::path(
     get,
@@ -524,10 +716,12 @@ pub async fn get_organization_stats(
     security(("BearerAuth" = [])),
     params(
         ("id" = Uuid, Path, description = "组织ID"),
-        ("limit" = Option<i32>, Query, description = "返回记录数量限制")
+        AlertFilter,
+        PageQuery
     ),
     responses(
-        (status = 200, description = "获取成功", body = AlertsResponse),
+        (status = 200, description = "获取成功", body = AlertPage),
+        (status = 400, description = "请求参数错误"),
         (status = 401, description = "未授权"),
         (status = 403, description = "不是组织成员")
     )
@@ -535,25 +729,145 @@ pub async fn get_organization_stats(
 pub async fn list_organization_alerts(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
-    auth: AuthExtractor,
-    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
+    org: OrgAccess<perm::AlertsRead>,
+    Query(filter): Query<AlertFilter>,
+    page: Page,
 ) -> AppResult<impl IntoResponse> {
-    // Check if user is a member
-    let is_member = queries::is_organization_member(&state.pool, id, auth.0.user_id).await?;
-    if !is_member {
-        return Err(AppError::authorization("You are not a member of this organization"));
+    let (alerts, total) = queries::list_organization_alerts(
+        &state.pool,
+        id,
+        org.access.group_filter(),
+        &filter,
+        page.after,
+        page.fetch_limit(),
+    ).await?;
+
+    Ok(Json(page.paginate(alerts, total, |a| a.id)))
+}
+
+/// Acknowledge an alert
+#[utoipa::path(
+    post,
+    path = "/api/organizations/{id}/alerts/{alert_id}/acknowledge",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID"),
+        ("alert_id" = Uuid, Path, description = "告警ID")
+    ),
+    responses(
+        (status = 200, description = "确认成功", body = AlertResponse),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权限确认告警"),
+        (status = 404, description = "告警不存在")
+    )
+)]
+pub async fn acknowledge_alert(
+    State(state): State<AppState>,
+    Path((id, alert_id)): Path<(Uuid, Uuid)>,
+    org: OrgAccess<perm::AlertsAck>,
+    audit: Auditor,
+) -> AppResult<impl IntoResponse> {
+    let alert = queries::find_organization_alert(&state.pool, id, alert_id).await?
+        .ok_or_else(|| AppError::not_found("Alert not found"))?;
+
+    if org.access.domain_group_ids.is_some() {
+        let domain = queries::find_domain_by_id(&state.pool, alert.domain_id).await?;
+        if !domain.is_some_and(|d| org.access.can_access_group(d.group_id)) {
+            return Err(AppError::not_found("Alert not found"));
+        }
     }
 
-    // Parse limit parameter (default to 100)
-    let limit = params.get("limit")
-        .and_then(|s| s.parse::<i64>().ok())
-        .unwrap_or(100);
+    let before = alert;
+    let alert = queries::acknowledge_alert(&state.pool, before.id, org.auth.user_id).await?;
+    audit.updated(id, AuditAction::AlertAcknowledged, alert.id, &before, &alert).await;
 
-    let alerts = queries::list_organization_alerts(&state.pool, id, limit).await?;
+    Ok(Json(AlertResponse { data: alert }))
+}
 
-    let response = serde_json::json!({
-        "data": alerts
-    });
+/// Build the retention response with the global defaults alongside the overrides
+fn retention_response(state: &AppState, policy: RetentionPolicy) -> RetentionPolicyResponse {
+    let config = &state.config.retention;
+    let days = |target| Some(retention::default_days(config, target));
+
+    RetentionPolicyResponse {
+        data: policy,
+        defaults: RetentionPolicy {
+            uptime_snapshots_days: days(RetentionTarget::UptimeSnapshots),
+            ssl_cert_snapshots_days: days(RetentionTarget::SslCertSnapshots),
+            domain_dns_snapshots_days: days(RetentionTarget::DomainDnsSnapshots),
+            alerts_days: days(RetentionTarget::Alerts),
+            hourly_aggregates_days: days(RetentionTarget::HourlyAggregates),
+        },
+    }
+}
 
-    Ok(Json(response))
+/// Get organization data retention policy
+#[utoipa::path(
+    get,
+    path = "/api/organizations/{id}/retention",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID")
+    ),
+    responses(
+        (status = 200, description = "获取成功", body = RetentionPolicyResponse),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "不是组织成员")
+    )
+)]
+pub async fn get_retention_policy(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    _org: OrgAccess,
+) -> AppResult<impl IntoResponse> {
+    let policy = queries::get_retention_policy(&state.pool, id).await?.unwrap_or_default();
+
+    Ok(Json(retention_response(&state, policy)))
+}
+
+/// Update organization data retention policy
+#[utoipa::path(
+    put,
+    path = "/api/organizations/{id}/retention",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID")
+    ),
+    request_body = RetentionPolicy,
+    responses(
+        (status = 200, description = "更新成功", body = RetentionPolicyResponse),
+        (status = 400, description = "请求参数错误"),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权限更新组织或超出保留期限配额")
+    )
+)]
+pub async fn update_retention_policy(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    _org: OrgAccess<perm::OrganizationManage>,
+    audit: Auditor,
+    JsonPayload(payload): JsonPayload<RetentionPolicy>,
+) -> AppResult<impl IntoResponse> {
+    let days = [
+        payload.uptime_snapshots_days,
+        payload.ssl_cert_snapshots_days,
+        payload.domain_dns_snapshots_days,
+        payload.alerts_days,
+        payload.hourly_aggregates_days,
+    ];
+    if days.iter().flatten().any(|&d| d <= 0) {
+        return Err(AppError::validation("Retention periods must be at least 1 day"));
+    }
+    let organization = queries::find_organization_by_id(&state.pool, id).await?
+        .ok_or_else(|| AppError::not_found("Organization not found"))?;
+    quota::check_retention(&organization, &payload)?;
+
+    let before = queries::get_retention_policy(&state.pool, id).await?.unwrap_or_default();
+    let policy = queries::upsert_retention_policy(&state.pool, id, &payload).await?;
+    audit.updated(id, AuditAction::RetentionUpdated, id, &before, &policy).await;
+
+    Ok(Json(retention_response(&state, policy)))
 }
@@ -0,0 +1,238 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+    Json as JsonPayload,
+};
+use chrono::{Duration as ChronoDuration, Utc};
+use serde_json::json;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+use crate::api::handlers::organizations::OrganizationResponse;
+use crate::api::routes::AppState;
+use crate::audit::Auditor;
+use crate::auth::OrgAccess;
+use crate::db::models::{AuditAction, MemberRole, OwnershipTransfer};
+use crate::db::queries;
+use crate::error::{AppError, AppResult};
+use crate::mailer::{self, Email};
+
+// Request types
+#[derive(serde::Deserialize, ToSchema)]
+pub struct OwnershipTransferRequest {
+    /// Member who should become the owner
+    pub user_id: Uuid,
+}
+
+// Response types
+#[derive(serde::Serialize, ToSchema)]
+pub struct OwnershipTransferResponse {
+    /// Pending transfer, null if there is none
+    pub data: Option<OwnershipTransfer>,
+}
+
+/// Load the pending, unexpired ownership transfer of an organization
+async fn pending_transfer(state: &AppState, organization_id: Uuid) -> AppResult<Option<OwnershipTransfer>> {
+    Ok(queries::find_ownership_transfer(&state.pool, organization_id).await?
+        .filter(|transfer| transfer.expires_at > Utc::now()))
+}
+
+/// Email the recipient of an ownership transfer
+async fn send_transfer_request(state: &AppState, transfer: &OwnershipTransfer) -> AppResult<()> {
+    let organization = queries::find_organization_by_id(&state.pool, transfer.organization_id).await?
+        .ok_or_else(|| AppError::not_found("Organization not found"))?;
+    let recipient = queries::find_user_by_id(&state.pool, transfer.to_user_id).await?
+        .ok_or_else(|| AppError::not_found("User not found"))?;
+    let owner = queries::find_user_by_id(&state.pool, transfer.from_user_id).await?
+        .map(|user| user.full_name.unwrap_or(user.email))
+        .unwrap_or_else(|| "The owner".to_string());
+
+    let link = format!(
+        "{}/organizations/{}/ownership-transfer",
+        state.config.server.public_url.trim_end_matches('/'),
+        transfer.organization_id
+    );
+    let email = Email::ownership_transfer(
+        &recipient.email,
+        &organization.name,
+        &owner,
+        &link,
+        state.config.auth.ownership_transfer_ttl,
+    );
+    mailer::send_in_background(state.mailer.clone(), email);
+
+    Ok(())
+}
+
+/// Get the pending ownership transfer
+#[utoipa::path(
+    get,
+    path = "/api/organizations/{id}/ownership-transfer",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID")
+    ),
+    responses(
+        (status = 200, description = "获取成功", body = OwnershipTransferResponse),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "不是组织成员")
+    )
+)]
+pub async fn get_ownership_transfer(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    _org: OrgAccess,
+) -> AppResult<impl IntoResponse> {
+    let transfer = pending_transfer(&state, id).await?;
+
+    Ok(Json(OwnershipTransferResponse { data: transfer }))
+}
+
+/// Ask a member to take over the organization
+///
+/// Replaces any pending transfer. Ownership only moves once the member accepts;
+/// the current owner then becomes an admin.
+#[utoipa::path(
+    post,
+    path = "/api/organizations/{id}/ownership-transfer",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID")
+    ),
+    request_body = OwnershipTransferRequest,
+    responses(
+        (status = 201, description = "已发送转让请求", body = OwnershipTransferResponse),
+        (status = 400, description = "目标用户不是组织成员"),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "只有所有者可以转让组织")
+    )
+)]
+pub async fn request_ownership_transfer(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    org: OrgAccess,
+    audit: Auditor,
+    JsonPayload(payload): JsonPayload<OwnershipTransferRequest>,
+) -> AppResult<impl IntoResponse> {
+    if org.access.role != MemberRole::Owner || org.auth.api_key.is_some() {
+        return Err(AppError::authorization("Only owners can transfer organization"));
+    }
+    if payload.user_id == org.auth.user_id {
+        return Err(AppError::validation("You already own this organization"));
+    }
+    if queries::find_organization_member(&state.pool, id, payload.user_id).await?.is_none() {
+        return Err(AppError::validation("Ownership can only be transferred to a member"));
+    }
+
+    let ttl = ChronoDuration::from_std(state.config.auth.ownership_transfer_ttl)
+        .map_err(|e| AppError::internal(format!("Invalid duration: {}", e)))?;
+    let transfer = queries::create_ownership_transfer(
+        &state.pool,
+        id,
+        org.auth.user_id,
+        payload.user_id,
+        Utc::now() + ttl,
+    ).await?;
+
+    send_transfer_request(&state, &transfer).await?;
+    audit.created(id, AuditAction::OwnershipTransferRequested, transfer.id, &transfer).await;
+
+    Ok((StatusCode::CREATED, Json(OwnershipTransferResponse { data: Some(transfer) })))
+}
+
+/// Accept the ownership of the organization
+///
+/// Only the member the transfer was sent to can accept it.
+#[utoipa::path(
+    post,
+    path = "/api/organizations/{id}/ownership-transfer/accept",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID")
+    ),
+    responses(
+        (status = 200, description = "已成为组织所有者", body = OrganizationResponse),
+        (status = 400, description = "转让请求已失效"),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "转让请求不是发给当前用户的"),
+        (status = 404, description = "没有待接受的转让请求")
+    )
+)]
+pub async fn accept_ownership_transfer(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    org: OrgAccess,
+    audit: Auditor,
+) -> AppResult<impl IntoResponse> {
+    let transfer = pending_transfer(&state, id).await?
+        .ok_or_else(|| AppError::not_found("No pending ownership transfer"))?;
+    if transfer.to_user_id != org.auth.user_id || org.auth.api_key.is_some() {
+        return Err(AppError::authorization("This ownership transfer was sent to another member"));
+    }
+
+    if !queries::complete_ownership_transfer(&state.pool, &transfer).await? {
+        return Err(AppError::validation("This ownership transfer is no longer valid"));
+    }
+
+    // The role changes bump both token versions; drop cached checks so they apply now
+    state.revocations.invalidate_user(transfer.from_user_id);
+    state.revocations.invalidate_user(transfer.to_user_id);
+
+    audit.updated(
+        id,
+        AuditAction::OwnershipTransferred,
+        transfer.id,
+        &json!({ "owner": transfer.from_user_id }),
+        &json!({ "owner": transfer.to_user_id }),
+    ).await;
+
+    let organization = queries::find_organization_by_id(&state.pool, id).await?
+        .ok_or_else(|| AppError::not_found("Organization not found"))?;
+
+    Ok(Json(OrganizationResponse { data: organization }))
+}
+
+/// Cancel or decline the pending ownership transfer
+///
+/// The owner can cancel it and its recipient can decline it.
+#[utoipa::path(
+    delete,
+    path = "/api/organizations/{id}/ownership-transfer",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID")
+    ),
+    responses(
+        (status = 204, description = "已取消"),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权取消该转让请求"),
+        (status = 404, description = "没有待接受的转让请求")
+    )
+)]
+pub async fn cancel_ownership_transfer(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    org: OrgAccess,
+    audit: Auditor,
+) -> AppResult<impl IntoResponse> {
+    let transfer = queries::find_ownership_transfer(&state.pool, id).await?
+        .ok_or_else(|| AppError::not_found("No pending ownership transfer"))?;
+
+    let is_party = org.auth.api_key.is_none()
+        && (org.access.role == MemberRole::Owner || transfer.to_user_id == org.auth.user_id);
+    if !is_party {
+        return Err(AppError::authorization("Only the owner or the recipient can cancel an ownership transfer"));
+    }
+
+    if !queries::delete_ownership_transfer(&state.pool, id).await? {
+        return Err(AppError::not_found("No pending ownership transfer"));
+    }
+    audit.deleted(id, AuditAction::OwnershipTransferCancelled, transfer.id, &transfer).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
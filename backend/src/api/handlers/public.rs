@@ -1,43 +1,111 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     response::{IntoResponse, Json},
 };
 use serde_json::json;
-use utoipa::ToSchema;
+use std::collections::{BTreeMap, HashMap};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
 
 use crate::api::routes::AppState;
 use crate::db::queries;
-use crate::db::models::OrganizationWithDomains;
+use crate::db::models::{DomainGroup, OrganizationWithDomains, PublicDomainStatus, StatusPageSection};
 use crate::error::{AppError, AppResult};
 
+#[derive(Debug, serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StatusPageQuery {
+    /// Split the page by the value of this tag instead of by domain group
+    pub section_tag: Option<String>,
+}
+
 // Response types
 #[derive(serde::Serialize, ToSchema)]
 pub struct PublicStatusResponse {
     pub data: OrganizationWithDomains,
 }
 
+/// Full path of every group, e.g. `Customers / Acme`
+fn group_paths(groups: &[DomainGroup]) -> HashMap<Uuid, String> {
+    let by_id: HashMap<Uuid, &DomainGroup> = groups.iter().map(|g| (g.id, g)).collect();
+
+    groups
+        .iter()
+        .map(|group| {
+            let mut names = vec![group.name.as_str()];
+            let mut parent = group.parent_id;
+            // Bounded by the number of groups in case the hierarchy has a cycle
+            while let Some(p) = parent.and_then(|id| by_id.get(&id)).filter(|_| names.len() <= groups.len()) {
+                names.push(&p.name);
+                parent = p.parent_id;
+            }
+            names.reverse();
+            (group.id, names.join(" / "))
+        })
+        .collect()
+}
+
+/// Split domains into named sections, sorted by name, with the remaining
+/// domains in a last unnamed section
+fn build_sections(domains: &[PublicDomainStatus], section_of: impl Fn(&PublicDomainStatus) -> Option<String>) -> Vec<StatusPageSection> {
+    let mut named: BTreeMap<String, Vec<Uuid>> = BTreeMap::new();
+    let mut rest = Vec::new();
+
+    for domain in domains {
+        match section_of(domain) {
+            Some(name) => named.entry(name).or_default().push(domain.id),
+            None => rest.push(domain.id),
+        }
+    }
+
+    let mut sections: Vec<StatusPageSection> = named
+        .into_iter()
+        .map(|(name, domain_ids)| StatusPageSection { name: Some(name), domain_ids })
+        .collect();
+    if !rest.is_empty() {
+        sections.push(StatusPageSection { name: None, domain_ids: rest });
+    }
+
+    sections
+}
+
 /// Get public monitoring status page by organization slug
 /// This endpoint is publicly accessible without authentication
+///
+/// Domains are split into sections by domain group, or by the value of the
+/// `section_tag` tag.
 #[utoipa::path(
     get,
     path = "/api/public/status/{org_slug}",
     tag = "公开",
     params(
-        ("org_slug" = String, Path, description = "组织slug（URL友好标识符）")
+        ("org_slug" = String, Path, description = "组织slug（URL友好标识符）"),
+        StatusPageQuery
     ),
     responses(
         (status = 200, description = "获取成功", body = PublicStatusResponse),
-        (status = 404, description = "组织不存在")
+        (status = 404, description = "组织不存在"),
+        (status = 429, description = "请求过于频繁")
     )
 )]
 pub async fn get_public_status(
     State(state): State<AppState>,
     Path(org_slug): Path<String>,
+    Query(query): Query<StatusPageQuery>,
 ) -> AppResult<impl IntoResponse> {
     // Find organization by slug and get all domains with status
-    let org_with_domains = queries::find_organization_by_slug_with_domains(&state.pool, &org_slug).await?
+    let mut org_with_domains = queries::find_organization_by_slug_with_domains(&state.pool, &org_slug).await?
         .ok_or_else(|| AppError::not_found("Organization not found"))?;
 
+    org_with_domains.sections = match query.section_tag {
+        Some(tag) => build_sections(&org_with_domains.domains, |d| d.tags.get(&tag).cloned()),
+        None => {
+            let groups = queries::list_domain_groups(&state.pool, org_with_domains.organization.id, None).await?;
+            let paths = group_paths(&groups);
+            build_sections(&org_with_domains.domains, |d| d.group_id.and_then(|id| paths.get(&id).cloned()))
+        }
+    };
+
     let response = json!({
         "data": org_with_domains
     });
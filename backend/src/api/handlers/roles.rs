@@ -0,0 +1,213 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+    Json as JsonPayload,
+};
+use uuid::Uuid;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::api::routes::AppState;
+use crate::audit::Auditor;
+use crate::auth::{perm, OrgAccess};
+use crate::db::models::{AuditAction, MemberRole, OrganizationRole, Permission, UpsertOrganizationRole};
+use crate::db::queries;
+use crate::error::{AppError, AppResult};
+
+// Response types
+#[derive(serde::Serialize, ToSchema)]
+pub struct RoleResponse {
+    pub data: OrganizationRole,
+}
+
+/// Permissions of a built-in role
+#[derive(serde::Serialize, ToSchema)]
+pub struct BuiltInRole {
+    pub role: MemberRole,
+    pub permissions: Vec<Permission>,
+}
+
+#[derive(serde::Serialize, ToSchema)]
+pub struct RolesResponse {
+    /// Custom roles of the organization
+    pub data: Vec<OrganizationRole>,
+    pub built_in: Vec<BuiltInRole>,
+}
+
+/// Validate a role definition and check that the caller may grant it
+async fn check_role_input(
+    state: &AppState,
+    org: &OrgAccess<perm::MembersManage>,
+    payload: &UpsertOrganizationRole,
+    role_id: Option<Uuid>,
+) -> AppResult<()> {
+    payload.validate()
+        .map_err(|e| AppError::validation(format!("Invalid input: {}", e)))?;
+    org.access.require_grantable(&payload.permissions)?;
+
+    if queries::organization_role_name_taken(&state.pool, org.organization_id(), &payload.name, role_id).await? {
+        return Err(AppError::validation("A role with this name already exists"));
+    }
+
+    Ok(())
+}
+
+/// Load a custom role the caller may change: it must hold all of its permissions
+async fn find_manageable_role(
+    state: &AppState,
+    org: &OrgAccess<perm::MembersManage>,
+    role_id: Uuid,
+) -> AppResult<OrganizationRole> {
+    let role = queries::find_organization_role(&state.pool, org.organization_id(), role_id).await?
+        .ok_or_else(|| AppError::not_found("Role not found"))?;
+    org.access.require_grantable(&role.permission_set())?;
+
+    Ok(role)
+}
+
+/// List organization roles
+///
+/// Returns the custom roles together with the permissions of the built-in roles.
+#[utoipa::path(
+    get,
+    path = "/api/organizations/{id}/roles",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID")
+    ),
+    responses(
+        (status = 200, description = "获取成功", body = RolesResponse),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "不是组织成员")
+    )
+)]
+pub async fn list_roles(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    _org: OrgAccess,
+) -> AppResult<impl IntoResponse> {
+    let roles = queries::list_organization_roles(&state.pool, id).await?;
+
+    let built_in = [MemberRole::Owner, MemberRole::Admin, MemberRole::Member, MemberRole::Viewer]
+        .into_iter()
+        .map(|role| BuiltInRole { permissions: role.permissions().to_vec(), role })
+        .collect();
+
+    Ok(Json(RolesResponse { data: roles, built_in }))
+}
+
+/// Create a custom role
+#[utoipa::path(
+    post,
+    path = "/api/organizations/{id}/roles",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID")
+    ),
+    request_body = UpsertOrganizationRole,
+    responses(
+        (status = 201, description = "创建成功", body = RoleResponse),
+        (status = 400, description = "请求参数错误或角色名已存在"),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权限管理角色")
+    )
+)]
+pub async fn create_role(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    org: OrgAccess<perm::MembersManage>,
+    audit: Auditor,
+    JsonPayload(payload): JsonPayload<UpsertOrganizationRole>,
+) -> AppResult<impl IntoResponse> {
+    check_role_input(&state, &org, &payload, None).await?;
+
+    let role = queries::create_organization_role(&state.pool, id, &payload).await?;
+    audit.created(id, AuditAction::RoleCreated, role.id, &role).await;
+
+    Ok((StatusCode::CREATED, Json(RoleResponse { data: role })))
+}
+
+/// Update a custom role
+///
+/// Members with the role get the new permissions on their next request.
+#[utoipa::path(
+    put,
+    path = "/api/organizations/{id}/roles/{role_id}",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID"),
+        ("role_id" = Uuid, Path, description = "角色ID")
+    ),
+    request_body = UpsertOrganizationRole,
+    responses(
+        (status = 200, description = "更新成功", body = RoleResponse),
+        (status = 400, description = "请求参数错误或角色名已存在"),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权限管理角色"),
+        (status = 404, description = "角色不存在")
+    )
+)]
+pub async fn update_role(
+    State(state): State<AppState>,
+    Path((id, role_id)): Path<(Uuid, Uuid)>,
+    org: OrgAccess<perm::MembersManage>,
+    audit: Auditor,
+    JsonPayload(payload): JsonPayload<UpsertOrganizationRole>,
+) -> AppResult<impl IntoResponse> {
+    let before = find_manageable_role(&state, &org, role_id).await?;
+    check_role_input(&state, &org, &payload, Some(role_id)).await?;
+
+    let role = queries::update_organization_role(&state.pool, id, role_id, &payload).await?
+        .ok_or_else(|| AppError::not_found("Role not found"))?;
+    audit.updated(id, AuditAction::RoleUpdated, role_id, &before, &role).await;
+
+    Ok(Json(RoleResponse { data: role }))
+}
+
+/// Delete a custom role
+///
+/// Roles still assigned to members cannot be deleted.
+#[utoipa::path(
+    delete,
+    path = "/api/organizations/{id}/roles/{role_id}",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID"),
+        ("role_id" = Uuid, Path, description = "角色ID")
+    ),
+    responses(
+        (status = 204, description = "删除成功"),
+        (status = 400, description = "角色仍分配给成员"),
+        (status = 401, description = "未授权"),
+        (status = 403, description = "无权限管理角色"),
+        (status = 404, description = "角色不存在")
+    )
+)]
+pub async fn delete_role(
+    State(state): State<AppState>,
+    Path((id, role_id)): Path<(Uuid, Uuid)>,
+    org: OrgAccess<perm::MembersManage>,
+    audit: Auditor,
+) -> AppResult<impl IntoResponse> {
+    let role = find_manageable_role(&state, &org, role_id).await?;
+
+    let assigned = queries::count_role_members(&state.pool, role_id).await?;
+    if assigned > 0 {
+        return Err(AppError::validation(format!(
+            "Role is assigned to {} member(s); change their role first",
+            assigned
+        )));
+    }
+
+    if !queries::delete_organization_role(&state.pool, id, role_id).await? {
+        return Err(AppError::not_found("Role not found"));
+    }
+    audit.deleted(id, AuditAction::RoleDeleted, role_id, &role).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,236 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+    Json as JsonPayload,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    api::routes::AppState,
+    audit::Auditor,
+    auth::{perm, DomainAccess},
+    db::{models::*, queries},
+    error::{AppError, AppResult},
+};
+
+// ============================================================================
+// Request/Response Models
+// ============================================================================
+
+#[derive(Debug, Deserialize, IntoParams, ToSchema)]
+pub struct SloHistoryQuery {
+    /// Number of days of history (defaults to the SLO window)
+    pub days: Option<i64>,
+}
+
+/// SLO definition with its latest evaluation
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SloWithStatus {
+    #[serde(flatten)]
+    pub slo: Slo,
+    /// `null` until hourly aggregates exist for the domain
+    pub status: Option<SloSnapshot>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SlosResponse {
+    pub data: Vec<SloWithStatus>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SloHistoryResponse {
+    pub data: Vec<SloSnapshot>,
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Load an SLO belonging to the given domain
+async fn find_domain_slo(state: &AppState, domain_id: Uuid, slo_id: Uuid) -> AppResult<Slo> {
+    queries::find_slo_by_id(&state.pool, slo_id)
+        .await?
+        .filter(|slo| slo.domain_id == domain_id)
+        .ok_or_else(|| AppError::not_found("SLO not found"))
+}
+
+fn validate_input(input: &SloInput) -> AppResult<()> {
+    input
+        .validate()
+        .map_err(|e| AppError::validation(format!("Invalid input: {}", e)))
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+#[utoipa::path(
+    get,
+    path = "/api/domains/{id}/slo",
+    tag = "监控",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "域名ID")
+    ),
+    responses(
+        (status = 200, description = "获取 SLO 及错误预算成功", body = SlosResponse),
+        (status = 404, description = "域名不存在"),
+        (status = 403, description = "无权访问该域名"),
+    )
+)]
+/// GET /api/domains/{id}/slo
+/// List SLOs with remaining error budget and burn rates
+pub async fn list_slos(
+    State(state): State<AppState>,
+    Path(domain_id): Path<Uuid>,
+    _target: DomainAccess<perm::DomainsRead>,
+) -> AppResult<impl IntoResponse> {
+
+    let slos = queries::list_domain_slos(&state.pool, domain_id).await?;
+
+    let mut data = Vec::with_capacity(slos.len());
+    for slo in slos {
+        let status = queries::get_latest_slo_snapshot(&state.pool, slo.id).await?;
+        data.push(SloWithStatus { slo, status });
+    }
+
+    Ok(Json(SlosResponse { data }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/domains/{id}/slo",
+    tag = "监控",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "域名ID")
+    ),
+    request_body = SloInput,
+    responses(
+        (status = 201, description = "创建 SLO 成功"),
+        (status = 400, description = "请求参数错误"),
+        (status = 404, description = "域名不存在"),
+        (status = 403, description = "无权管理该域名"),
+    )
+)]
+/// POST /api/domains/{id}/slo
+/// Define a new SLO for a domain
+pub async fn create_slo(
+    State(state): State<AppState>,
+    Path(domain_id): Path<Uuid>,
+    target: DomainAccess<perm::MonitorsEdit>,
+    audit: Auditor,
+    JsonPayload(payload): JsonPayload<SloInput>,
+) -> AppResult<impl IntoResponse> {
+    validate_input(&payload)?;
+
+    let slo = queries::create_slo(&state.pool, domain_id, &payload).await?;
+    audit.created(target.domain.organization_id, AuditAction::SloCreated, slo.id, &slo).await;
+
+    Ok((StatusCode::CREATED, Json(json!({ "data": slo }))))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/domains/{id}/slo/{slo_id}",
+    tag = "监控",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "域名ID"),
+        ("slo_id" = Uuid, Path, description = "SLO ID")
+    ),
+    request_body = SloInput,
+    responses(
+        (status = 200, description = "更新 SLO 成功"),
+        (status = 400, description = "请求参数错误"),
+        (status = 404, description = "SLO 不存在"),
+        (status = 403, description = "无权管理该域名"),
+    )
+)]
+/// PUT /api/domains/{id}/slo/{slo_id}
+/// Replace an SLO definition
+pub async fn update_slo(
+    State(state): State<AppState>,
+    Path((domain_id, slo_id)): Path<(Uuid, Uuid)>,
+    target: DomainAccess<perm::MonitorsEdit>,
+    audit: Auditor,
+    JsonPayload(payload): JsonPayload<SloInput>,
+) -> AppResult<impl IntoResponse> {
+    validate_input(&payload)?;
+    let before = find_domain_slo(&state, domain_id, slo_id).await?;
+
+    let slo = queries::update_slo(&state.pool, slo_id, &payload).await?;
+    audit.updated(target.domain.organization_id, AuditAction::SloUpdated, slo_id, &before, &slo).await;
+
+    Ok(Json(json!({ "data": slo })))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/domains/{id}/slo/{slo_id}",
+    tag = "监控",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "域名ID"),
+        ("slo_id" = Uuid, Path, description = "SLO ID")
+    ),
+    responses(
+        (status = 204, description = "删除 SLO 成功"),
+        (status = 404, description = "SLO 不存在"),
+        (status = 403, description = "无权管理该域名"),
+    )
+)]
+/// DELETE /api/domains/{id}/slo/{slo_id}
+/// Delete an SLO and its history
+pub async fn delete_slo(
+    State(state): State<AppState>,
+    Path((domain_id, slo_id)): Path<(Uuid, Uuid)>,
+    target: DomainAccess<perm::MonitorsEdit>,
+    audit: Auditor,
+) -> AppResult<impl IntoResponse> {
+    let slo = find_domain_slo(&state, domain_id, slo_id).await?;
+
+    queries::delete_slo(&state.pool, slo_id).await?;
+    audit.deleted(target.domain.organization_id, AuditAction::SloDeleted, slo_id, &slo).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/domains/{id}/slo/{slo_id}/history",
+    tag = "监控",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "域名ID"),
+        ("slo_id" = Uuid, Path, description = "SLO ID"),
+        SloHistoryQuery
+    ),
+    responses(
+        (status = 200, description = "获取 SLO 历史成功", body = SloHistoryResponse),
+        (status = 404, description = "SLO 不存在"),
+        (status = 403, description = "无权访问该域名"),
+    )
+)]
+/// GET /api/domains/{id}/slo/{slo_id}/history
+/// Get the hourly error budget and burn rate history of an SLO
+pub async fn get_slo_history(
+    State(state): State<AppState>,
+    Path((domain_id, slo_id)): Path<(Uuid, Uuid)>,
+    Query(query): Query<SloHistoryQuery>,
+    _target: DomainAccess<perm::DomainsRead>,
+) -> AppResult<impl IntoResponse> {
+    let slo = find_domain_slo(&state, domain_id, slo_id).await?;
+
+    let days = query.days.unwrap_or_else(|| i64::from(slo.window_days)).clamp(1, 365);
+    let since = chrono::Utc::now() - chrono::Duration::days(days);
+
+    let data = queries::get_slo_history(&state.pool, slo_id, since).await?;
+
+    Ok(Json(SloHistoryResponse { data }))
+}
@@ -0,0 +1,446 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::api::handlers::auth::{complete_login, MfaChallengeResponse};
+use crate::api::routes::AppState;
+use crate::audit::Auditor;
+use crate::auth::{
+    generate_user_token, perm, sha256_hash, validate_issuer, ClientInfo, OidcClient, OidcIdentity, OrgAccess,
+};
+use crate::db::models::{AuditAction, MemberRole, SsoConfig, SsoIdentity, UpsertSsoConfig, User};
+use crate::db::queries;
+use crate::error::{AppError, AppResult};
+use crate::mailer::{self, Email};
+use crate::monitors::HttpOverrides;
+
+/// How long a user has to complete a login at the provider
+const SSO_LOGIN_TTL_MINUTES: i64 = 10;
+
+/// How long the owner of an existing account has to confirm linking it
+const SSO_LINK_TTL_MINUTES: i64 = 60;
+
+// ============================================================================
+// Request/Response Models
+// ============================================================================
+
+#[derive(serde::Deserialize, ToSchema)]
+pub struct SsoAuthorizeRequest {
+    /// Organization slug
+    pub organization: String,
+}
+
+#[derive(serde::Serialize, ToSchema)]
+pub struct SsoAuthorizeResponse {
+    /// Provider URL to send the user to
+    pub authorization_url: String,
+}
+
+#[derive(serde::Deserialize, ToSchema)]
+pub struct SsoCallbackRequest {
+    /// `state` parameter of the callback URL
+    pub state: String,
+    /// `code` parameter of the callback URL
+    pub code: String,
+    /// Name of the device, shown in the session list
+    pub device_name: Option<String>,
+}
+
+/// An account already uses the email; the login works once its owner has
+/// confirmed the link sent to them
+#[derive(serde::Serialize, ToSchema)]
+pub struct SsoLinkPendingResponse {
+    /// Address the confirmation link was sent to
+    pub email: String,
+}
+
+#[derive(serde::Deserialize, ToSchema)]
+pub struct SsoLinkConfirmRequest {
+    /// Token of the emailed confirmation link
+    pub token: String,
+}
+
+#[derive(serde::Serialize, ToSchema)]
+pub struct SsoConfigResponse {
+    pub data: SsoConfig,
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// HTTP client for provider requests; redirects are not followed
+fn http_client(state: &AppState) -> AppResult<reqwest::Client> {
+    state.http.client(&HttpOverrides {
+        max_redirects: Some(0),
+        ..HttpOverrides::default()
+    })
+}
+
+/// Frontend page the provider redirects back to
+fn redirect_uri(state: &AppState) -> String {
+    format!("{}/sso/callback", state.config.server.public_url.trim_end_matches('/'))
+}
+
+/// Lowercase, de-duplicated email domains
+fn normalize_email_domains(domains: &[String]) -> AppResult<Vec<String>> {
+    let mut normalized = Vec::with_capacity(domains.len());
+    for domain in domains {
+        let domain = domain.trim().trim_start_matches('@').to_lowercase();
+        if domain.is_empty() || !domain.contains('.') || domain.contains('@') {
+            return Err(AppError::validation(format!("Invalid email domain: {}", domain)));
+        }
+        normalized.push(domain);
+    }
+    normalized.sort();
+    normalized.dedup();
+    Ok(normalized)
+}
+
+/// Result of signing in with a provider identity
+enum SsoLogin {
+    /// The identity belongs to this user; `provisioned` when the provider
+    /// created the account and is therefore trusted with its second factor
+    User { user: Box<User>, provisioned: bool },
+    /// An account already uses the email; its owner was asked to confirm the link
+    LinkPending,
+}
+
+/// Find or provision the user for a provider identity, adding them to the organization
+///
+/// An identity only ever signs in to the organization whose provider asserted
+/// it. It is never linked to an existing account on the provider's word alone:
+/// any organization admin can configure a provider asserting arbitrary emails,
+/// so the account owner has to confirm the link from their inbox first.
+async fn resolve_sso_user(state: &AppState, config: &SsoConfig, identity: &OidcIdentity) -> AppResult<SsoLogin> {
+    let sso_identity = SsoIdentity {
+        organization_id: config.organization_id,
+        issuer: identity.issuer.clone(),
+        subject: identity.subject.clone(),
+        email: identity.email.clone(),
+    };
+
+    let linked = queries::find_user_identity(
+        &state.pool,
+        config.organization_id,
+        &identity.issuer,
+        &identity.subject,
+    ).await?;
+    if let Some(linked) = linked {
+        let user = queries::find_user_by_id(&state.pool, linked.user_id).await?
+            .filter(|u| u.is_active)
+            .ok_or_else(|| AppError::auth("This account has been deactivated"))?;
+        queries::link_sso_identity(&state.pool, user.id, &sso_identity, &config.default_role).await?;
+        return Ok(SsoLogin::User { user: Box::new(user), provisioned: linked.provisioned });
+    }
+
+    if let Some(user) = queries::find_user_by_email(&state.pool, &identity.email).await? {
+        if !identity.email_verified {
+            return Err(AppError::authorization(
+                "The identity provider has not verified this email address",
+            ));
+        }
+        send_link_request(state, &user, &sso_identity).await?;
+        return Ok(SsoLogin::LinkPending);
+    }
+
+    let user = queries::provision_sso_user(
+        &state.pool,
+        &sso_identity,
+        identity.name.as_deref(),
+        &config.default_role,
+    ).await?;
+    Ok(SsoLogin::User { user: Box::new(user), provisioned: true })
+}
+
+/// Store a pending link of an identity to an existing account and email its
+/// confirmation link to the account owner
+async fn send_link_request(state: &AppState, user: &User, identity: &SsoIdentity) -> AppResult<()> {
+    let organization = queries::find_organization_by_id(&state.pool, identity.organization_id).await?
+        .ok_or_else(|| AppError::not_found("Organization not found"))?;
+
+    let token = generate_user_token();
+    let valid_for = Duration::minutes(SSO_LINK_TTL_MINUTES);
+    queries::create_sso_link_request(
+        &state.pool,
+        &sha256_hash(&token),
+        user.id,
+        identity,
+        Utc::now() + valid_for,
+    ).await?;
+
+    let link = format!("{}/sso/link?token={}", state.config.server.public_url.trim_end_matches('/'), token);
+    let valid_for = valid_for.to_std()
+        .map_err(|e| AppError::internal(format!("Invalid duration: {}", e)))?;
+    mailer::send_in_background(
+        state.mailer.clone(),
+        Email::sso_link(&user.email, &organization.name, &link, valid_for),
+    );
+
+    Ok(())
+}
+
+// ============================================================================
+// Login Handlers
+// ============================================================================
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/sso/authorize",
+    tag = "认证",
+    request_body = SsoAuthorizeRequest,
+    responses(
+        (status = 200, description = "返回身份提供方授权地址", body = SsoAuthorizeResponse),
+        (status = 404, description = "组织不存在或未启用单点登录", body = crate::error::ErrorResponse),
+        (status = 502, description = "身份提供方不可用", body = crate::error::ErrorResponse)
+    )
+)]
+/// POST /api/auth/sso/authorize
+/// Start a single sign-on login for an organization
+pub async fn sso_authorize(
+    State(state): State<AppState>,
+    Json(payload): Json<SsoAuthorizeRequest>,
+) -> AppResult<impl IntoResponse> {
+    let org = queries::find_organization_by_slug(&state.pool, payload.organization.trim()).await?
+        .ok_or_else(|| AppError::not_found("Organization not found"))?;
+    let config = queries::find_sso_config(&state.pool, org.id).await?
+        .filter(|c| c.enabled)
+        .ok_or_else(|| AppError::not_found("Single sign-on is not enabled for this organization"))?;
+
+    let client = OidcClient::discover(http_client(&state)?, &config, &redirect_uri(&state)).await?;
+    let request = client.authorization_request();
+
+    queries::create_sso_login_state(
+        &state.pool,
+        &sha256_hash(&request.state),
+        org.id,
+        &request.nonce,
+        &request.pkce_verifier,
+        Utc::now() + Duration::minutes(SSO_LOGIN_TTL_MINUTES),
+    ).await?;
+
+    Ok(Json(SsoAuthorizeResponse {
+        authorization_url: request.url,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/sso/callback",
+    tag = "认证",
+    request_body = SsoCallbackRequest,
+    responses(
+        (status = 200, description = "登录成功", body = crate::api::handlers::auth::AuthResponse),
+        (status = 202, description = "需要两步验证（返回 mfa_token），或已向现有账号发送关联确认邮件（返回 email）", body = SsoLinkPendingResponse),
+        (status = 400, description = "登录请求无效或已过期", body = crate::error::ErrorResponse),
+        (status = 401, description = "授权码或 ID 令牌无效", body = crate::error::ErrorResponse),
+        (status = 403, description = "邮箱域名不允许，或邮箱未经身份提供方验证", body = crate::error::ErrorResponse)
+    )
+)]
+/// POST /api/auth/sso/callback
+/// Complete a single sign-on login with the code returned by the provider
+///
+/// Users are provisioned on their first login and added to the organization
+/// with its default role. The provider is responsible for the second factor of
+/// the accounts it provisioned; other accounts keep their own.
+pub async fn sso_callback(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    Json(payload): Json<SsoCallbackRequest>,
+) -> AppResult<impl IntoResponse> {
+    let login = queries::consume_sso_login_state(&state.pool, &sha256_hash(payload.state.trim())).await?
+        .ok_or_else(|| AppError::validation("Invalid or expired single sign-on request"))?;
+    let config = queries::find_sso_config(&state.pool, login.organization_id).await?
+        .filter(|c| c.enabled)
+        .ok_or_else(|| AppError::validation("Single sign-on is not enabled for this organization"))?;
+
+    let oidc = OidcClient::discover(http_client(&state)?, &config, &redirect_uri(&state)).await?;
+    let identity = oidc.exchange_code(payload.code.trim(), &login.nonce, &login.pkce_verifier).await?;
+
+    if !config.allows_email(&identity.email) {
+        return Err(AppError::authorization(
+            "Your email domain is not allowed to sign in to this organization",
+        ));
+    }
+
+    let user = match resolve_sso_user(&state, &config, &identity).await? {
+        SsoLogin::User { user, provisioned } => {
+            if !provisioned && user.totp_enabled_at.is_some() {
+                let mfa_token = state.jwt_service.generate_mfa_token(
+                    user.id,
+                    Some(config.organization_id),
+                    user.token_version,
+                )?;
+                return Ok((StatusCode::ACCEPTED, Json(MfaChallengeResponse { mfa_token })).into_response());
+            }
+            *user
+        }
+        SsoLogin::LinkPending => {
+            let response = SsoLinkPendingResponse { email: identity.email };
+            return Ok((StatusCode::ACCEPTED, Json(response)).into_response());
+        }
+    };
+
+    let response = complete_login(
+        &state,
+        user,
+        Some(config.organization_id),
+        Some(config.organization_id),
+        payload.device_name.as_deref(),
+        &client,
+    ).await?;
+
+    Ok(Json(response).into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/sso/link",
+    tag = "认证",
+    request_body = SsoLinkConfirmRequest,
+    responses(
+        (status = 204, description = "已将身份关联到现有账号，可重新通过单点登录登录"),
+        (status = 400, description = "确认链接无效或已过期，或组织未启用单点登录", body = crate::error::ErrorResponse)
+    )
+)]
+/// POST /api/auth/sso/link
+/// Confirm linking a provider identity to an existing account
+///
+/// The identity then signs in to the account, with its own second factor.
+pub async fn confirm_sso_link(
+    State(state): State<AppState>,
+    Json(payload): Json<SsoLinkConfirmRequest>,
+) -> AppResult<impl IntoResponse> {
+    let request = queries::consume_sso_link_request(&state.pool, &sha256_hash(payload.token.trim())).await?
+        .ok_or_else(|| AppError::validation("Invalid or expired link"))?;
+
+    // The provider may have been replaced since the login
+    let config = queries::find_sso_config(&state.pool, request.identity.organization_id).await?
+        .filter(|c| c.enabled && c.issuer == request.identity.issuer)
+        .ok_or_else(|| AppError::validation("Single sign-on is not enabled for this organization"))?;
+
+    queries::link_sso_identity(&state.pool, request.user_id, &request.identity, &config.default_role).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ============================================================================
+// Configuration Handlers
+// ============================================================================
+
+#[utoipa::path(
+    get,
+    path = "/api/organizations/{id}/sso",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID")
+    ),
+    responses(
+        (status = 200, description = "获取单点登录配置成功", body = SsoConfigResponse),
+        (status = 403, description = "无权限管理单点登录"),
+        (status = 404, description = "组织不存在或未配置单点登录")
+    )
+)]
+/// GET /api/organizations/:id/sso
+/// Get the SSO configuration; the client secret is never returned
+pub async fn get_sso_config(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    _org: OrgAccess<perm::OrganizationManage>,
+) -> AppResult<impl IntoResponse> {
+    let config = queries::find_sso_config(&state.pool, id).await?
+        .ok_or_else(|| AppError::not_found("Single sign-on is not configured"))?;
+
+    Ok(Json(SsoConfigResponse { data: config }))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/organizations/{id}/sso",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID")
+    ),
+    request_body = UpsertSsoConfig,
+    responses(
+        (status = 200, description = "保存单点登录配置成功", body = SsoConfigResponse),
+        (status = 400, description = "请求参数错误，或无法发现身份提供方"),
+        (status = 403, description = "无权限管理单点登录"),
+        (status = 404, description = "组织不存在")
+    )
+)]
+/// PUT /api/organizations/:id/sso
+/// Create or replace the SSO configuration
+pub async fn update_sso_config(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    _org: OrgAccess<perm::OrganizationManage>,
+    audit: Auditor,
+    Json(mut payload): Json<UpsertSsoConfig>,
+) -> AppResult<impl IntoResponse> {
+    payload.validate()
+        .map_err(|e| AppError::validation(format!("Invalid input: {}", e)))?;
+
+    if payload.default_role == MemberRole::Owner {
+        return Err(AppError::validation("The default role cannot be owner"));
+    }
+    payload.issuer = payload.issuer.trim().trim_end_matches('/').to_string();
+    payload.allowed_email_domains = normalize_email_domains(&payload.allowed_email_domains)?;
+
+    let before = queries::find_sso_config(&state.pool, id).await?;
+    let client_secret = match payload.client_secret.clone() {
+        Some(secret) => secret,
+        None => before.as_ref()
+            .map(|c| c.client_secret.clone())
+            .ok_or_else(|| AppError::validation("client_secret is required"))?,
+    };
+
+    validate_issuer(http_client(&state)?, &payload.issuer).await?;
+
+    let config = queries::upsert_sso_config(&state.pool, id, &payload, &client_secret).await?;
+    audit.updated(id, AuditAction::SsoUpdated, id, &before, &config).await;
+
+    Ok(Json(SsoConfigResponse { data: config }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/organizations/{id}/sso",
+    tag = "组织",
+    security(("BearerAuth" = [])),
+    params(
+        ("id" = Uuid, Path, description = "组织ID")
+    ),
+    responses(
+        (status = 204, description = "删除单点登录配置成功"),
+        (status = 403, description = "无权限管理单点登录"),
+        (status = 404, description = "组织不存在或未配置单点登录")
+    )
+)]
+/// DELETE /api/organizations/:id/sso
+/// Remove the SSO configuration; users provisioned through SSO keep their accounts
+pub async fn delete_sso_config(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    _org: OrgAccess<perm::OrganizationManage>,
+    audit: Auditor,
+) -> AppResult<impl IntoResponse> {
+    let before = queries::find_sso_config(&state.pool, id).await?
+        .ok_or_else(|| AppError::not_found("Single sign-on is not configured"))?;
+
+    if !queries::delete_sso_config(&state.pool, id).await? {
+        return Err(AppError::not_found("Single sign-on is not configured"));
+    }
+    audit.deleted(id, AuditAction::SsoDeleted, id, &before).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
@@ -0,0 +1,279 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use utoipa::ToSchema;
+
+use crate::api::handlers::auth::{check_password, require_user_token};
+use crate::api::routes::AppState;
+use crate::auth::{
+    generate_recovery_codes, generate_totp_secret, normalize_recovery_code, provisioning_uri,
+    sha256_hash, verify_totp_code, AuthExtractor,
+};
+use crate::db::{models::User, queries};
+use crate::error::{AppError, AppResult};
+
+// ============================================================================
+// Request/Response Models
+// ============================================================================
+
+#[derive(serde::Deserialize, ToSchema)]
+pub struct TwoFactorCodeRequest {
+    /// Current TOTP code, or a recovery code
+    pub code: String,
+}
+
+#[derive(serde::Deserialize, ToSchema)]
+pub struct DisableTwoFactorRequest {
+    pub password: String,
+    /// Current TOTP code, or a recovery code
+    pub code: String,
+}
+
+#[derive(serde::Serialize, ToSchema)]
+pub struct TwoFactorStatusResponse {
+    pub enabled: bool,
+    pub recovery_codes_remaining: i64,
+    /// One of the user's organizations requires two-factor authentication
+    pub required: bool,
+}
+
+#[derive(serde::Serialize, ToSchema)]
+pub struct TwoFactorSetupResponse {
+    /// Base32 secret, for manual entry
+    pub secret: String,
+    /// `otpauth://` URI to render as a QR code
+    pub provisioning_uri: String,
+}
+
+/// Recovery codes, only shown once
+#[derive(serde::Serialize, ToSchema)]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Check a second factor: a TOTP code, or else an unused recovery code
+///
+/// Accepted TOTP codes and recovery codes cannot be used again.
+///
+/// # Errors
+///
+/// Returns an error if the lookup fails
+pub async fn verify_second_factor(state: &AppState, user: &User, code: &str) -> AppResult<bool> {
+    let Some(secret) = user.totp_secret.as_deref() else {
+        return Ok(false);
+    };
+
+    if let Some(step) = verify_totp_code(secret, code, user.totp_last_used_step)? {
+        return queries::record_totp_step(&state.pool, user.id, step).await;
+    }
+
+    if user.totp_enabled_at.is_none() {
+        return Ok(false);
+    }
+    let code_hash = sha256_hash(&normalize_recovery_code(code));
+    queries::consume_recovery_code(&state.pool, user.id, &code_hash).await
+}
+
+/// Load the calling user, rejecting API keys
+async fn current_user(state: &AppState, auth: &AuthExtractor) -> AppResult<User> {
+    require_user_token(auth)?;
+
+    queries::find_user_by_id(&state.pool, auth.0.user_id)
+        .await?
+        .ok_or_else(|| AppError::auth("User not found"))
+}
+
+/// Generate recovery codes, returning them with their hashes
+fn new_recovery_codes() -> (Vec<String>, Vec<String>) {
+    let codes = generate_recovery_codes();
+    let hashes = codes.iter().map(|c| sha256_hash(&normalize_recovery_code(c))).collect();
+    (codes, hashes)
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/2fa",
+    tag = "认证",
+    security(("BearerAuth" = [])),
+    responses(
+        (status = 200, description = "获取两步验证状态成功", body = TwoFactorStatusResponse),
+        (status = 403, description = "API 密钥不能管理会话")
+    )
+)]
+/// GET /api/auth/2fa
+/// Two-factor authentication status of the current user
+pub async fn get_two_factor_status(
+    State(state): State<AppState>,
+    auth: AuthExtractor,
+) -> AppResult<impl IntoResponse> {
+    let user = current_user(&state, &auth).await?;
+
+    let enabled = user.totp_enabled_at.is_some();
+    let recovery_codes_remaining = if enabled {
+        queries::count_unused_recovery_codes(&state.pool, user.id).await?
+    } else {
+        0
+    };
+
+    Ok(Json(TwoFactorStatusResponse {
+        enabled,
+        recovery_codes_remaining,
+        required: queries::user_requires_two_factor(&state.pool, user.id).await?,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/2fa/setup",
+    tag = "认证",
+    security(("BearerAuth" = [])),
+    responses(
+        (status = 200, description = "生成 TOTP 密钥成功，需确认验证码后启用", body = TwoFactorSetupResponse),
+        (status = 400, description = "两步验证已启用"),
+        (status = 403, description = "API 密钥不能管理会话")
+    )
+)]
+/// POST /api/auth/2fa/setup
+/// Start enrollment: generate a TOTP secret and its provisioning URI
+pub async fn setup_two_factor(
+    State(state): State<AppState>,
+    auth: AuthExtractor,
+) -> AppResult<impl IntoResponse> {
+    let user = current_user(&state, &auth).await?;
+
+    if user.totp_enabled_at.is_some() {
+        return Err(AppError::validation("Two-factor authentication is already enabled"));
+    }
+
+    let secret = generate_totp_secret();
+    queries::set_pending_totp_secret(&state.pool, user.id, &secret).await?;
+
+    Ok(Json(TwoFactorSetupResponse {
+        provisioning_uri: provisioning_uri(&secret, &user.email)?,
+        secret,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/2fa/enable",
+    tag = "认证",
+    security(("BearerAuth" = [])),
+    request_body = TwoFactorCodeRequest,
+    responses(
+        (status = 200, description = "启用两步验证成功，返回恢复码（仅显示一次）", body = RecoveryCodesResponse),
+        (status = 400, description = "未开始设置或验证码错误"),
+        (status = 403, description = "API 密钥不能管理会话")
+    )
+)]
+/// POST /api/auth/2fa/enable
+/// Confirm enrollment with a first TOTP code
+pub async fn enable_two_factor(
+    State(state): State<AppState>,
+    auth: AuthExtractor,
+    Json(payload): Json<TwoFactorCodeRequest>,
+) -> AppResult<impl IntoResponse> {
+    let user = current_user(&state, &auth).await?;
+
+    if user.totp_enabled_at.is_some() {
+        return Err(AppError::validation("Two-factor authentication is already enabled"));
+    }
+    if user.totp_secret.is_none() {
+        return Err(AppError::validation("Start two-factor setup first"));
+    }
+    if !verify_second_factor(&state, &user, &payload.code).await? {
+        return Err(AppError::validation("Invalid two-factor code"));
+    }
+
+    let (recovery_codes, hashes) = new_recovery_codes();
+    queries::enable_totp(&state.pool, user.id, &hashes).await?;
+
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/2fa/disable",
+    tag = "认证",
+    security(("BearerAuth" = [])),
+    request_body = DisableTwoFactorRequest,
+    responses(
+        (status = 204, description = "关闭两步验证成功"),
+        (status = 400, description = "未启用两步验证，或所在组织要求两步验证"),
+        (status = 401, description = "密码或验证码错误"),
+        (status = 403, description = "API 密钥不能管理会话")
+    )
+)]
+/// POST /api/auth/2fa/disable
+/// Turn off two-factor authentication
+pub async fn disable_two_factor(
+    State(state): State<AppState>,
+    auth: AuthExtractor,
+    Json(payload): Json<DisableTwoFactorRequest>,
+) -> AppResult<impl IntoResponse> {
+    let user = current_user(&state, &auth).await?;
+
+    if user.totp_enabled_at.is_none() {
+        return Err(AppError::validation("Two-factor authentication is not enabled"));
+    }
+    if !check_password(&user, &payload.password)? {
+        return Err(AppError::auth("Invalid password"));
+    }
+    if !verify_second_factor(&state, &user, &payload.code).await? {
+        return Err(AppError::auth("Invalid two-factor code"));
+    }
+    if queries::user_requires_two_factor(&state.pool, user.id).await? {
+        return Err(AppError::validation(
+            "Two-factor authentication is required by one of your organizations",
+        ));
+    }
+
+    queries::disable_totp(&state.pool, user.id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/2fa/recovery-codes",
+    tag = "认证",
+    security(("BearerAuth" = [])),
+    request_body = TwoFactorCodeRequest,
+    responses(
+        (status = 200, description = "重新生成恢复码成功（仅显示一次）", body = RecoveryCodesResponse),
+        (status = 400, description = "未启用两步验证"),
+        (status = 401, description = "验证码错误"),
+        (status = 403, description = "API 密钥不能管理会话")
+    )
+)]
+/// POST /api/auth/2fa/recovery-codes
+/// Replace all recovery codes
+pub async fn regenerate_recovery_codes(
+    State(state): State<AppState>,
+    auth: AuthExtractor,
+    Json(payload): Json<TwoFactorCodeRequest>,
+) -> AppResult<impl IntoResponse> {
+    let user = current_user(&state, &auth).await?;
+
+    if user.totp_enabled_at.is_none() {
+        return Err(AppError::validation("Two-factor authentication is not enabled"));
+    }
+    if !verify_second_factor(&state, &user, &payload.code).await? {
+        return Err(AppError::auth("Invalid two-factor code"));
+    }
+
+    let (recovery_codes, hashes) = new_recovery_codes();
+    queries::replace_recovery_codes(&state.pool, user.id, &hashes).await?;
+
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
@@ -1,5 +1,7 @@
 pub mod handlers;
 pub mod openapi;
+pub mod pagination;
+pub mod rate_limit;
 pub mod routes;
 
 pub use routes::create_router;
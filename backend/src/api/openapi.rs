@@ -1,6 +1,6 @@
 //! OpenAPI/Swagger 文档配置
 
-use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
 use utoipa::OpenApi;
 
 /// WebGuard API 文档
@@ -19,25 +19,90 @@ use utoipa::OpenApi;
         crate::api::handlers::auth::health_check,
         crate::api::handlers::auth::register,
         crate::api::handlers::auth::login,
+        crate::api::handlers::auth::login_two_factor,
         crate::api::handlers::auth::refresh_token,
+        crate::api::handlers::auth::forgot_password,
+        crate::api::handlers::auth::reset_password,
+        crate::api::handlers::auth::verify_email,
+        crate::api::handlers::auth::resend_verification,
+        crate::api::handlers::auth::logout,
+        crate::api::handlers::auth::change_password,
+        crate::api::handlers::auth::list_sessions,
+        crate::api::handlers::auth::revoke_session,
+        crate::api::handlers::auth::revoke_all_sessions,
+        crate::api::handlers::auth::switch_organization,
+        crate::api::handlers::two_factor::get_two_factor_status,
+        crate::api::handlers::two_factor::setup_two_factor,
+        crate::api::handlers::two_factor::enable_two_factor,
+        crate::api::handlers::two_factor::disable_two_factor,
+        crate::api::handlers::two_factor::regenerate_recovery_codes,
+        crate::api::handlers::sso::sso_authorize,
+        crate::api::handlers::sso::sso_callback,
+        crate::api::handlers::sso::confirm_sso_link,
+        crate::api::handlers::invitations::preview_invitation,
         // 组织相关
         crate::api::handlers::organizations::create_organization,
         crate::api::handlers::organizations::list_organizations,
         crate::api::handlers::organizations::get_organization,
         crate::api::handlers::organizations::update_organization,
         crate::api::handlers::organizations::delete_organization,
+        crate::api::handlers::organizations::restore_organization,
+        crate::api::handlers::ownership::get_ownership_transfer,
+        crate::api::handlers::ownership::request_ownership_transfer,
+        crate::api::handlers::ownership::accept_ownership_transfer,
+        crate::api::handlers::ownership::cancel_ownership_transfer,
         crate::api::handlers::organizations::list_members,
         crate::api::handlers::organizations::add_member,
         crate::api::handlers::organizations::remove_member,
         crate::api::handlers::organizations::update_member_role,
+        crate::api::handlers::organizations::update_member_domain_groups,
+        crate::api::handlers::roles::list_roles,
+        crate::api::handlers::roles::create_role,
+        crate::api::handlers::roles::update_role,
+        crate::api::handlers::roles::delete_role,
+        crate::api::handlers::invitations::list_invitations,
+        crate::api::handlers::invitations::create_invitation,
+        crate::api::handlers::invitations::resend_invitation,
+        crate::api::handlers::invitations::revoke_invitation,
+        crate::api::handlers::invitations::accept_invitation,
         crate::api::handlers::organizations::get_organization_stats,
+        crate::api::handlers::organizations::get_organization_usage,
         crate::api::handlers::organizations::list_organization_alerts,
+        crate::api::handlers::organizations::acknowledge_alert,
+        crate::api::handlers::organizations::get_retention_policy,
+        crate::api::handlers::organizations::update_retention_policy,
+        crate::api::handlers::api_keys::list_api_keys,
+        crate::api::handlers::api_keys::create_api_key,
+        crate::api::handlers::api_keys::get_api_key,
+        crate::api::handlers::api_keys::update_api_key,
+        crate::api::handlers::api_keys::revoke_api_key,
+        crate::api::handlers::sso::get_sso_config,
+        crate::api::handlers::sso::update_sso_config,
+        crate::api::handlers::sso::delete_sso_config,
+        crate::api::handlers::audit::list_audit_log,
+        crate::api::handlers::audit::export_audit_log,
+        crate::api::handlers::events::stream_events,
+        crate::api::handlers::events::events_websocket,
+        crate::api::handlers::events::create_stream_ticket,
         // 域名相关
+        crate::api::handlers::domain_groups::list_domain_groups,
+        crate::api::handlers::domain_groups::create_domain_group,
+        crate::api::handlers::domain_groups::update_domain_group,
+        crate::api::handlers::domain_groups::delete_domain_group,
+        crate::api::handlers::alert_routes::list_alert_routes,
+        crate::api::handlers::alert_routes::create_alert_route,
+        crate::api::handlers::alert_routes::update_alert_route,
+        crate::api::handlers::alert_routes::delete_alert_route,
+        crate::api::handlers::manifest::export_manifest,
+        crate::api::handlers::manifest::apply_manifest,
         crate::api::handlers::domains::list_domains,
         crate::api::handlers::domains::create_domain,
         crate::api::handlers::domains::get_domain,
         crate::api::handlers::domains::update_domain,
         crate::api::handlers::domains::delete_domain,
+        crate::api::handlers::domains::bulk_update_tags,
+        crate::api::handlers::domain_import::import_domains,
+        crate::api::handlers::domain_import::export_domains,
         crate::api::handlers::domains::get_domain_statistics,
         // 监控相关
         crate::api::handlers::monitoring::get_latest_uptime,
@@ -45,6 +110,18 @@ use utoipa::OpenApi;
         crate::api::handlers::monitoring::get_uptime_history,
         crate::api::handlers::monitoring::get_uptime_aggregate,
         crate::api::handlers::monitoring::trigger_check,
+        crate::api::handlers::monitors::list_monitors,
+        crate::api::handlers::monitors::create_monitor,
+        crate::api::handlers::monitors::get_monitor,
+        crate::api::handlers::monitors::update_monitor,
+        crate::api::handlers::monitors::delete_monitor,
+        crate::api::handlers::monitors::pause_monitor,
+        crate::api::handlers::monitors::resume_monitor,
+        crate::api::handlers::slo::list_slos,
+        crate::api::handlers::slo::create_slo,
+        crate::api::handlers::slo::update_slo,
+        crate::api::handlers::slo::delete_slo,
+        crate::api::handlers::slo::get_slo_history,
         // 公开接口
         crate::api::handlers::public::get_public_status,
     ),
@@ -55,7 +132,30 @@ use utoipa::OpenApi;
             // 认证
             crate::api::handlers::auth::RegisterRequestJson,
             crate::api::handlers::auth::LoginRequestJson,
+            crate::api::handlers::auth::LoginTwoFactorRequest,
+            crate::api::handlers::auth::MfaChallengeResponse,
+            crate::api::handlers::two_factor::TwoFactorCodeRequest,
+            crate::api::handlers::two_factor::DisableTwoFactorRequest,
+            crate::api::handlers::two_factor::TwoFactorStatusResponse,
+            crate::api::handlers::two_factor::TwoFactorSetupResponse,
+            crate::api::handlers::two_factor::RecoveryCodesResponse,
+            crate::api::handlers::sso::SsoAuthorizeRequest,
+            crate::api::handlers::sso::SsoAuthorizeResponse,
+            crate::api::handlers::sso::SsoCallbackRequest,
+            crate::api::handlers::sso::SsoLinkPendingResponse,
+            crate::api::handlers::sso::SsoLinkConfirmRequest,
             crate::api::handlers::auth::RefreshTokenRequest,
+            crate::api::handlers::auth::LogoutRequest,
+            crate::api::handlers::auth::EmailRequest,
+            crate::api::handlers::auth::ResetPasswordRequest,
+            crate::api::handlers::auth::VerifyEmailRequest,
+            crate::api::handlers::auth::VerificationPendingResponse,
+            crate::api::handlers::auth::ChangePasswordRequest,
+            crate::api::handlers::auth::SessionResponse,
+            crate::api::handlers::auth::SessionsResponse,
+            crate::api::handlers::auth::SwitchOrganizationRequest,
+            crate::api::handlers::auth::SwitchOrganizationResponse,
+            crate::db::models::Session,
             crate::api::handlers::auth::AuthResponse,
             crate::api::handlers::auth::UserResponse,
             // 组织
@@ -63,39 +163,136 @@ use utoipa::OpenApi;
             crate::api::handlers::organizations::UpdateOrganizationRequest,
             crate::api::handlers::organizations::AddMemberRequest,
             crate::api::handlers::organizations::UpdateMemberRoleRequest,
+            crate::api::handlers::organizations::UpdateMemberDomainGroupsRequest,
+            crate::db::models::Permission,
+            crate::db::models::OrganizationRole,
+            crate::db::models::UpsertOrganizationRole,
+            crate::api::handlers::roles::RoleResponse,
+            crate::api::handlers::roles::RolesResponse,
+            crate::api::handlers::roles::BuiltInRole,
+            crate::db::models::CreateInvitation,
+            crate::db::models::Invitation,
+            crate::api::handlers::invitations::InvitationTokenRequest,
+            crate::api::handlers::invitations::InvitationResponse,
+            crate::api::handlers::invitations::InvitationsResponse,
+            crate::api::handlers::invitations::InvitationPreviewResponse,
             crate::api::handlers::organizations::OrganizationResponse,
             crate::api::handlers::organizations::OrganizationsResponse,
+            crate::db::models::OwnershipTransfer,
+            crate::api::handlers::ownership::OwnershipTransferRequest,
+            crate::api::handlers::ownership::OwnershipTransferResponse,
             crate::api::handlers::organizations::MemberResponse,
-            crate::api::handlers::organizations::MembersResponse,
             crate::api::handlers::organizations::OrganizationStatsResponse,
-            crate::api::handlers::organizations::AlertsResponse,
+            crate::api::handlers::organizations::OrganizationUsageResponse,
+            crate::api::handlers::organizations::AlertResponse,
+            crate::api::handlers::organizations::RetentionPolicyResponse,
             crate::db::models::Organization,
             crate::db::models::OrganizationMember,
             crate::db::models::MemberRole,
             crate::db::models::OrganizationStats,
             crate::db::models::Alert,
             crate::db::models::AlertSeverity,
+            crate::db::models::RetentionPolicy,
+            crate::db::models::QuotaUsage,
+            crate::db::models::OrganizationUsage,
+            crate::db::models::CreateApiKey,
+            crate::api::handlers::api_keys::UpdateApiKeyRequest,
+            crate::api::handlers::api_keys::ApiKeyResponse,
+            crate::api::handlers::api_keys::ApiKeysResponse,
+            crate::api::handlers::api_keys::ApiKeyCreatedResponse,
+            crate::db::models::ApiKey,
+            crate::db::models::ApiKeyScope,
+            crate::db::models::UpsertSsoConfig,
+            crate::db::models::SsoConfig,
+            crate::api::handlers::sso::SsoConfigResponse,
+            crate::db::models::AuditEvent,
+            crate::db::models::AuditEventFilter,
+            crate::api::handlers::audit::AuditPageQuery,
+            crate::api::handlers::audit::AuditLogResponse,
+            crate::events::Event,
+            crate::events::EventKind,
+            crate::api::handlers::events::StreamTicketResponse,
             // 域名
             crate::api::handlers::domains::CreateDomainRequest,
             crate::api::handlers::domains::UpdateDomainRequest,
+            crate::api::handlers::domains::BulkTagRequest,
             crate::api::handlers::domains::DomainQueryParams,
             crate::api::handlers::domains::DomainResponse,
             crate::api::handlers::domains::DomainsResponse,
-            crate::api::handlers::domains::DomainsWithStatusResponse,
+            crate::api::pagination::DomainPage,
+            crate::api::pagination::AlertPage,
+            crate::api::pagination::MemberPage,
+            crate::api::pagination::UptimeHistoryPage,
             crate::api::handlers::domains::DomainStatisticsResponse,
             crate::api::handlers::domains::DomainCreateResponse,
+            crate::api::handlers::domain_import::ImportQuery,
+            crate::api::handlers::domain_import::ExportQuery,
+            crate::api::handlers::domain_import::ImportResponse,
             crate::db::models::Domain,
+            crate::db::models::DomainGroup,
+            crate::db::models::UpsertDomainGroup,
+            crate::db::models::DomainFilter,
+            crate::db::models::DomainListFilter,
+            crate::db::models::DomainSort,
+            crate::db::models::UptimeStatus,
+            crate::db::models::AlertFilter,
+            crate::db::models::TimeSort,
+            crate::db::models::MemberFilter,
+            crate::db::models::MemberSort,
+            crate::db::models::MemberWithUser,
+            crate::db::models::DomainSpec,
+            crate::db::models::MonitorSpec,
+            crate::db::models::ImportRow,
+            crate::db::models::ImportRowStatus,
+            crate::db::models::ImportReport,
+            crate::db::models::AlertRoute,
+            crate::db::models::UpsertAlertRoute,
+            crate::api::handlers::alert_routes::AlertRouteResponse,
+            crate::api::handlers::alert_routes::AlertRoutesResponse,
+            crate::api::handlers::manifest::ManifestExportQuery,
+            crate::api::handlers::manifest::ManifestApplyQuery,
+            crate::api::handlers::manifest::ManifestPlanResponse,
+            crate::db::models::Manifest,
+            crate::db::models::ManifestGroup,
+            crate::db::models::ManifestDomain,
+            crate::db::models::ManifestAlerting,
+            crate::db::models::ManifestAlertRoute,
+            crate::db::models::ManifestResource,
+            crate::db::models::ManifestAction,
+            crate::db::models::ManifestChange,
+            crate::db::models::ManifestPlan,
+            crate::api::handlers::domain_groups::DomainGroupResponse,
+            crate::api::handlers::domain_groups::DomainGroupsResponse,
             crate::db::models::DomainWithStatus,
             crate::db::models::DomainStatistics,
             // 监控
-            crate::api::handlers::monitoring::HistoryQuery,
+            crate::db::models::UptimeHistoryFilter,
             crate::api::handlers::monitoring::AggregateQuery,
             crate::api::handlers::monitoring::UptimeStatusResponse,
             crate::api::handlers::monitoring::SslStatusResponse,
+            crate::api::handlers::monitors::MonitorResponse,
+            crate::api::handlers::monitors::MonitorsResponse,
+            crate::db::models::Monitor,
+            crate::db::models::MonitorType,
+            crate::db::models::CreateMonitor,
+            crate::db::models::UpdateMonitor,
+            crate::db::models::PauseMonitor,
+            crate::monitors::monitor_config::UptimeConfig,
+            crate::monitors::monitor_config::CheckConfig,
+            crate::api::handlers::slo::SloHistoryQuery,
+            crate::api::handlers::slo::SloWithStatus,
+            crate::api::handlers::slo::SlosResponse,
+            crate::api::handlers::slo::SloHistoryResponse,
+            crate::db::models::Slo,
+            crate::db::models::SloInput,
+            crate::db::models::SloSnapshot,
+            crate::db::models::LatencyPercentile,
+            crate::db::models::BurnState,
             // 公开接口
             crate::api::handlers::public::PublicStatusResponse,
             crate::db::models::PublicDomainStatus,
             crate::db::models::OrganizationWithDomains,
+            crate::db::models::StatusPageSection,
         )
     ),
     tags(
@@ -124,7 +321,14 @@ impl utoipa::Modify for SecurityAddon {
                         .description(Some("JWT 访问令牌认证"))
                         .build(),
                 ),
-            )
+            );
+            components.add_security_scheme(
+                "ApiKeyAuth",
+                SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::with_description(
+                    "X-Api-Key",
+                    "组织 API Key 认证（也可使用 Authorization: ApiKey <key>）",
+                ))),
+            );
         }
     }
 }
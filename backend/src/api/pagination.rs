@@ -0,0 +1,113 @@
+//! Cursor pagination for list endpoints
+//!
+//! Handlers take a [`Page`] from the `limit` and `cursor` query parameters and
+//! return a [`Paginated`] envelope. The cursor is the ID of the last item of the
+//! previous page: queries fetch the rows sorting after it, so pages stay stable
+//! while new rows are inserted.
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Query},
+    http::request::Parts,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::api::handlers::monitoring::UptimeStatusResponse;
+use crate::db::models::{Alert, DomainWithStatus, OrganizationMember};
+use crate::error::AppError;
+
+/// Items per page when `limit` is not given
+const DEFAULT_LIMIT: i64 = 50;
+/// Largest accepted `limit`
+const MAX_LIMIT: i64 = 500;
+
+/// Pagination query parameters
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PageQuery {
+    /// Items per page (1-500, default 50)
+    pub limit: Option<i64>,
+    /// `next_cursor` of the previous page
+    pub cursor: Option<Uuid>,
+}
+
+/// Requested page of a list endpoint
+#[derive(Debug, Clone, Copy)]
+pub struct Page {
+    pub limit: i64,
+    /// Only items after this one
+    pub after: Option<Uuid>,
+}
+
+impl Page {
+    /// Number of rows to fetch: one more than the page, to know whether
+    /// another page follows
+    #[must_use]
+    pub const fn fetch_limit(&self) -> i64 {
+        self.limit + 1
+    }
+
+    /// Build the response from rows fetched with [`Self::fetch_limit`]
+    pub fn paginate<T>(&self, mut rows: Vec<T>, total: i64, id: impl Fn(&T) -> Uuid) -> Paginated<T> {
+        let has_more = rows.len() as i64 > self.limit;
+        rows.truncate(usize::try_from(self.limit).unwrap_or(0));
+        let next_cursor = if has_more { rows.last().map(id) } else { None };
+
+        Paginated { data: rows, next_cursor, total }
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Page {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Query(query) = Query::<PageQuery>::try_from_uri(&parts.uri)
+            .map_err(|e| AppError::validation(format!("Invalid query: {}", e)))?;
+
+        let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+        if !(1..=MAX_LIMIT).contains(&limit) {
+            return Err(AppError::validation(format!("limit must be between 1 and {}", MAX_LIMIT)));
+        }
+
+        Ok(Self { limit, after: query.cursor })
+    }
+}
+
+/// One page of a list
+#[derive(Debug, Serialize, ToSchema)]
+#[aliases(
+    DomainPage = Paginated<DomainWithStatus>,
+    AlertPage = Paginated<Alert>,
+    MemberPage = Paginated<OrganizationMember>,
+    UptimeHistoryPage = Paginated<UptimeStatusResponse>,
+)]
+pub struct Paginated<T> {
+    pub data: Vec<T>,
+    /// Cursor of the next page, absent on the last page
+    pub next_cursor: Option<Uuid>,
+    /// Number of items matching the filters, across all pages
+    pub total: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paginate() {
+        let ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        let page = Page { limit: 2, after: None };
+
+        let first = page.paginate(ids.clone(), 3, |id| *id);
+        assert_eq!(first.data, ids[..2]);
+        assert_eq!(first.next_cursor, Some(ids[1]));
+        assert_eq!(first.total, 3);
+
+        let last = page.paginate(ids[2..].to_vec(), 3, |id| *id);
+        assert_eq!(last.data, ids[2..]);
+        assert_eq!(last.next_cursor, None);
+    }
+}
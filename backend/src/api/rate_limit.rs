@@ -0,0 +1,188 @@
+//! Request rate limiting
+//!
+//! Every [`RateLimitPolicy`] has its own GCRA limiter, keyed by the calling user
+//! or API key on authenticated routes and by client IP otherwise. State is kept
+//! in memory, so limits apply per server instance.
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use governor::{
+    clock::{Clock, DefaultClock},
+    state::keyed::DefaultKeyedStateStore,
+    Quota, RateLimiter,
+};
+use std::net::SocketAddr;
+use std::num::NonZeroU32;
+use uuid::Uuid;
+
+use crate::api::routes::AppState;
+use crate::auth::{AuthContext, ClientInfo};
+use crate::config::RateLimitConfig;
+use crate::error::{AppError, AppResult};
+
+/// Number of tracked keys above which idle keys are dropped
+const MAX_TRACKED_KEYS: usize = 10_000;
+
+/// Group of routes sharing a limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitPolicy {
+    /// Password, second factor, SSO and token refresh logins, and password changes
+    Login,
+    Register,
+    /// Password reset and email verification
+    AccountRecovery,
+    PublicStatus,
+    TriggerCheck,
+}
+
+/// Who a request is counted against
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum RateLimitKey {
+    Ip(String),
+    User(Uuid),
+    ApiKey(Uuid),
+}
+
+type KeyedLimiter = RateLimiter<RateLimitKey, DefaultKeyedStateStore<RateLimitKey>, DefaultClock>;
+
+/// Limiters of all policies
+pub struct RateLimiters {
+    clock: DefaultClock,
+    login: Option<KeyedLimiter>,
+    register: Option<KeyedLimiter>,
+    account_recovery: Option<KeyedLimiter>,
+    public_status: Option<KeyedLimiter>,
+    trigger_check: Option<KeyedLimiter>,
+}
+
+impl RateLimiters {
+    /// Build the limiters; disabled or zero limits are not enforced
+    #[must_use]
+    pub fn from_config(config: &RateLimitConfig) -> Self {
+        let clock = DefaultClock::default();
+        let limiter = |per_period: u32, quota: fn(NonZeroU32) -> Quota| {
+            NonZeroU32::new(per_period)
+                .filter(|_| config.enabled)
+                .map(|n| RateLimiter::new(quota(n), DefaultKeyedStateStore::default(), &clock))
+        };
+
+        Self {
+            login: limiter(config.login_per_minute, Quota::per_minute),
+            register: limiter(config.register_per_hour, Quota::per_hour),
+            account_recovery: limiter(config.account_recovery_per_hour, Quota::per_hour),
+            public_status: limiter(config.public_per_minute, Quota::per_minute),
+            trigger_check: limiter(config.trigger_check_per_minute, Quota::per_minute),
+            clock,
+        }
+    }
+
+    fn limiter(&self, policy: RateLimitPolicy) -> Option<&KeyedLimiter> {
+        match policy {
+            RateLimitPolicy::Login => self.login.as_ref(),
+            RateLimitPolicy::Register => self.register.as_ref(),
+            RateLimitPolicy::AccountRecovery => self.account_recovery.as_ref(),
+            RateLimitPolicy::PublicStatus => self.public_status.as_ref(),
+            RateLimitPolicy::TriggerCheck => self.trigger_check.as_ref(),
+        }
+    }
+
+    /// Count a request against a policy
+    fn check(&self, policy: RateLimitPolicy, key: &RateLimitKey) -> AppResult<()> {
+        let Some(limiter) = self.limiter(policy) else {
+            return Ok(());
+        };
+
+        if limiter.len() > MAX_TRACKED_KEYS {
+            limiter.retain_recent();
+        }
+
+        limiter.check_key(key).map_err(|not_until| {
+            AppError::rate_limited(
+                "Too many requests, please try again later",
+                not_until.wait_time_from(self.clock.now()),
+            )
+        })
+    }
+}
+
+/// Middleware enforcing a policy; attach with
+/// `from_fn_with_state((state, policy), rate_limit)`
+///
+/// Authenticated routes must add this layer inside the auth layer, so that the
+/// caller is known.
+///
+/// # Errors
+///
+/// Returns a rate limit error once the caller has exceeded the policy's limit
+pub async fn rate_limit(
+    State((state, policy)): State<(AppState, RateLimitPolicy)>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let key = match request.extensions().get::<AuthContext>() {
+        Some(AuthContext { api_key: Some(key), .. }) => RateLimitKey::ApiKey(key.id),
+        Some(auth) => RateLimitKey::User(auth.user_id),
+        None => {
+            let connect_info = request.extensions().get::<ConnectInfo<SocketAddr>>().map(|c| c.0);
+            let client = ClientInfo::from_parts(request.headers(), connect_info, state.config.server.trust_proxy_headers);
+            RateLimitKey::Ip(client.ip.unwrap_or_default())
+        }
+    };
+
+    state.rate_limiters.check(policy, &key)?;
+
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn config() -> RateLimitConfig {
+        RateLimitConfig {
+            enabled: true,
+            login_per_minute: 2,
+            register_per_hour: 0,
+            account_recovery_per_hour: 1,
+            public_per_minute: 1,
+            trigger_check_per_minute: 1,
+            lockout_threshold: 5,
+            lockout_base: Duration::from_secs(60),
+            lockout_max: Duration::from_secs(3600),
+        }
+    }
+
+    #[test]
+    fn test_rate_limiters() {
+        let limiters = RateLimiters::from_config(&config());
+        let ip = RateLimitKey::Ip("203.0.113.1".to_string());
+
+        assert!(limiters.check(RateLimitPolicy::Login, &ip).is_ok());
+        assert!(limiters.check(RateLimitPolicy::Login, &ip).is_ok());
+        match limiters.check(RateLimitPolicy::Login, &ip) {
+            Err(AppError::RateLimited { retry_after, .. }) => assert!(retry_after > Duration::ZERO),
+            other => panic!("expected rate limit, got {:?}", other),
+        }
+
+        // Keys and policies are counted separately; a zero limit is not enforced
+        assert!(limiters.check(RateLimitPolicy::Login, &RateLimitKey::User(Uuid::new_v4())).is_ok());
+        assert!(limiters.check(RateLimitPolicy::AccountRecovery, &ip).is_ok());
+        for _ in 0..10 {
+            assert!(limiters.check(RateLimitPolicy::Register, &ip).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_rate_limiters_disabled() {
+        let limiters = RateLimiters::from_config(&RateLimitConfig { enabled: false, ..config() });
+        let ip = RateLimitKey::Ip("203.0.113.1".to_string());
+
+        for _ in 0..10 {
+            assert!(limiters.check(RateLimitPolicy::PublicStatus, &ip).is_ok());
+        }
+    }
+}
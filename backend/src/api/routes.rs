@@ -4,12 +4,17 @@ use axum::{
     Json,
 };
 use sqlx::PgPool;
+use std::sync::Arc;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
-use crate::auth::{JwtService, auth_middleware};
+use crate::auth::{JwtService, RevocationCache, auth_middleware};
 use crate::api::handlers;
 use crate::api::openapi::ApiDoc;
+use crate::api::rate_limit::{rate_limit, RateLimitPolicy, RateLimiters};
+use crate::events::EventBus;
+use crate::mailer::Mailer;
+use crate::monitors::HttpClientFactory;
 use crate::Config;
 
 /// Application state shared across all handlers
@@ -18,6 +23,13 @@ pub struct AppState {
     pub pool: PgPool,
     pub jwt_service: JwtService,
     pub config: Config,
+    pub http: Arc<HttpClientFactory>,
+    /// Cached access token revocation checks
+    pub revocations: Arc<RevocationCache>,
+    pub mailer: Arc<dyn Mailer>,
+    pub rate_limiters: Arc<RateLimiters>,
+    /// Events received by this instance, for the event streams
+    pub events: Arc<EventBus>,
 }
 
 /// Handler to serve OpenAPI JSON
@@ -30,13 +42,24 @@ pub fn create_router(
     pool: PgPool,
     jwt_service: JwtService,
     config: Config,
+    http: Arc<HttpClientFactory>,
+    mailer: Arc<dyn Mailer>,
+    events: Arc<EventBus>,
 ) -> Router {
     let state = AppState {
         pool,
         jwt_service,
+        revocations: Arc::new(RevocationCache::new(config.auth.revocation_cache_ttl)),
+        rate_limiters: Arc::new(RateLimiters::from_config(&config.rate_limit)),
         config,
+        http,
+        mailer,
+        events,
     };
 
+    // Layer enforcing a rate limit policy on a group of routes
+    let limit = |policy| axum::middleware::from_fn_with_state((state.clone(), policy), rate_limit);
+
     // Public routes (no auth required)
     let public_routes = Router::new()
         // Health check
@@ -44,41 +67,148 @@ pub fn create_router(
         // OpenAPI JSON
         .route("/api-docs/openapi.json", get(openapi_json))
         // Auth routes
-        .route("/api/auth/register", post(handlers::auth::register))
-        .route("/api/auth/login", post(handlers::auth::login))
-        .route("/api/auth/refresh", post(handlers::auth::refresh_token))
+        .merge(
+            Router::new()
+                .route("/api/auth/login", post(handlers::auth::login))
+                .route("/api/auth/login/2fa", post(handlers::auth::login_two_factor))
+                .route("/api/auth/refresh", post(handlers::auth::refresh_token))
+                .route("/api/auth/sso/authorize", post(handlers::sso::sso_authorize))
+                .route("/api/auth/sso/callback", post(handlers::sso::sso_callback))
+                .route("/api/auth/sso/link", post(handlers::sso::confirm_sso_link))
+                .route_layer(limit(RateLimitPolicy::Login)),
+        )
+        .merge(
+            Router::new()
+                .route("/api/auth/register", post(handlers::auth::register))
+                .route_layer(limit(RateLimitPolicy::Register)),
+        )
+        .merge(
+            Router::new()
+                .route("/api/auth/forgot-password", post(handlers::auth::forgot_password))
+                .route("/api/auth/reset-password", post(handlers::auth::reset_password))
+                .route("/api/auth/verify-email", post(handlers::auth::verify_email))
+                .route("/api/auth/resend-verification", post(handlers::auth::resend_verification))
+                .route("/api/invitations/preview", post(handlers::invitations::preview_invitation))
+                .route_layer(limit(RateLimitPolicy::AccountRecovery)),
+        )
         // Public status page
-        .route("/api/public/status/:org_slug", get(handlers::public::get_public_status));
+        .merge(
+            Router::new()
+                .route("/api/public/status/:org_slug", get(handlers::public::get_public_status))
+                .route_layer(limit(RateLimitPolicy::PublicStatus)),
+        );
 
     // Protected routes (auth required)
     let protected_routes = Router::new()
+        // Session routes
+        .route("/api/auth/logout", post(handlers::auth::logout))
+        .route("/api/auth/sessions", get(handlers::auth::list_sessions))
+        .route("/api/auth/sessions", delete(handlers::auth::revoke_all_sessions))
+        .route("/api/auth/sessions/:id", delete(handlers::auth::revoke_session))
+        .route("/api/auth/switch-organization", post(handlers::auth::switch_organization))
+        // Two-factor authentication routes
+        .route("/api/auth/2fa", get(handlers::two_factor::get_two_factor_status))
+        .route("/api/auth/2fa/setup", post(handlers::two_factor::setup_two_factor))
+        // Credential checks, limited per user
+        .merge(
+            Router::new()
+                .route("/api/auth/change-password", post(handlers::auth::change_password))
+                .route("/api/auth/2fa/enable", post(handlers::two_factor::enable_two_factor))
+                .route("/api/auth/2fa/disable", post(handlers::two_factor::disable_two_factor))
+                .route("/api/auth/2fa/recovery-codes", post(handlers::two_factor::regenerate_recovery_codes))
+                .route_layer(limit(RateLimitPolicy::Login)),
+        )
         // Organization routes
         .route("/api/organizations", post(handlers::organizations::create_organization))
         .route("/api/organizations", get(handlers::organizations::list_organizations))
         .route("/api/organizations/:id", get(handlers::organizations::get_organization))
         .route("/api/organizations/:id", put(handlers::organizations::update_organization))
         .route("/api/organizations/:id", delete(handlers::organizations::delete_organization))
+        .route("/api/organizations/:id/restore", post(handlers::organizations::restore_organization))
+        .route("/api/organizations/:id/ownership-transfer", get(handlers::ownership::get_ownership_transfer))
+        .route("/api/organizations/:id/ownership-transfer", post(handlers::ownership::request_ownership_transfer))
+        .route("/api/organizations/:id/ownership-transfer", delete(handlers::ownership::cancel_ownership_transfer))
+        .route("/api/organizations/:id/ownership-transfer/accept", post(handlers::ownership::accept_ownership_transfer))
         .route("/api/organizations/:id/members", get(handlers::organizations::list_members))
         .route("/api/organizations/:id/members", post(handlers::organizations::add_member))
         .route("/api/organizations/:id/members/:user_id", delete(handlers::organizations::remove_member))
         .route("/api/organizations/:id/members/:user_id/role", put(handlers::organizations::update_member_role))
+        .route("/api/organizations/:id/members/:user_id/domain-groups", put(handlers::organizations::update_member_domain_groups))
+        .route("/api/organizations/:id/roles", get(handlers::roles::list_roles))
+        .route("/api/organizations/:id/roles", post(handlers::roles::create_role))
+        .route("/api/organizations/:id/roles/:role_id", put(handlers::roles::update_role))
+        .route("/api/organizations/:id/roles/:role_id", delete(handlers::roles::delete_role))
+        .route("/api/organizations/:id/invitations", get(handlers::invitations::list_invitations))
+        .route("/api/organizations/:id/invitations", post(handlers::invitations::create_invitation))
+        .route("/api/organizations/:id/invitations/:invitation_id", delete(handlers::invitations::revoke_invitation))
+        .route("/api/organizations/:id/invitations/:invitation_id/resend", post(handlers::invitations::resend_invitation))
+        .route("/api/invitations/accept", post(handlers::invitations::accept_invitation))
         // Organization statistics and alerts
         .route("/api/organizations/:id/stats", get(handlers::organizations::get_organization_stats))
+        .route("/api/organizations/:id/usage", get(handlers::organizations::get_organization_usage))
         .route("/api/organizations/:id/alerts", get(handlers::organizations::list_organization_alerts))
+        .route("/api/organizations/:id/alerts/:alert_id/acknowledge", post(handlers::organizations::acknowledge_alert))
+        .route("/api/organizations/:id/alert-routes", get(handlers::alert_routes::list_alert_routes))
+        .route("/api/organizations/:id/alert-routes", post(handlers::alert_routes::create_alert_route))
+        .route("/api/organizations/:id/alert-routes/:route_id", put(handlers::alert_routes::update_alert_route))
+        .route("/api/organizations/:id/alert-routes/:route_id", delete(handlers::alert_routes::delete_alert_route))
+        .route("/api/organizations/:id/manifest", get(handlers::manifest::export_manifest))
+        .route("/api/organizations/:id/manifest", put(handlers::manifest::apply_manifest))
+        .route("/api/organizations/:id/retention", get(handlers::organizations::get_retention_policy))
+        .route("/api/organizations/:id/retention", put(handlers::organizations::update_retention_policy))
+        .route("/api/organizations/:id/api-keys", get(handlers::api_keys::list_api_keys))
+        .route("/api/organizations/:id/api-keys", post(handlers::api_keys::create_api_key))
+        .route("/api/organizations/:id/api-keys/:key_id", get(handlers::api_keys::get_api_key))
+        .route("/api/organizations/:id/api-keys/:key_id", put(handlers::api_keys::update_api_key))
+        .route("/api/organizations/:id/api-keys/:key_id", delete(handlers::api_keys::revoke_api_key))
+        .route("/api/organizations/:id/sso", get(handlers::sso::get_sso_config))
+        .route("/api/organizations/:id/sso", put(handlers::sso::update_sso_config))
+        .route("/api/organizations/:id/sso", delete(handlers::sso::delete_sso_config))
+        .route("/api/organizations/:id/audit-log", get(handlers::audit::list_audit_log))
+        .route("/api/organizations/:id/audit-log/export", get(handlers::audit::export_audit_log))
+        .route("/api/organizations/:id/events", get(handlers::events::stream_events))
+        .route("/api/organizations/:id/events/ws", get(handlers::events::events_websocket))
+        .route("/api/organizations/:id/events/ticket", post(handlers::events::create_stream_ticket))
+        // Domain group routes
+        .route("/api/organizations/:id/domain-groups", get(handlers::domain_groups::list_domain_groups))
+        .route("/api/organizations/:id/domain-groups", post(handlers::domain_groups::create_domain_group))
+        .route("/api/organizations/:id/domain-groups/:group_id", put(handlers::domain_groups::update_domain_group))
+        .route("/api/organizations/:id/domain-groups/:group_id", delete(handlers::domain_groups::delete_domain_group))
         // Domain routes
         .route("/api/domains", get(handlers::domains::list_domains))
         .route("/api/domains", post(handlers::domains::create_domain))
+        .route("/api/domains/tags", post(handlers::domains::bulk_update_tags))
+        .route("/api/domains/import", post(handlers::domain_import::import_domains))
+        .route("/api/domains/export", get(handlers::domain_import::export_domains))
         .route("/api/domains/:id", get(handlers::domains::get_domain))
         .route("/api/domains/:id", put(handlers::domains::update_domain))
         .route("/api/domains/:id", delete(handlers::domains::delete_domain))
         // Domain statistics
         .route("/api/domains/:id/statistics", get(handlers::domains::get_domain_statistics))
+        // Monitor routes
+        .route("/api/domains/:id/monitors", get(handlers::monitors::list_monitors))
+        .route("/api/domains/:id/monitors", post(handlers::monitors::create_monitor))
+        .route("/api/domains/:id/monitors/:monitor_id", get(handlers::monitors::get_monitor))
+        .route("/api/domains/:id/monitors/:monitor_id", put(handlers::monitors::update_monitor))
+        .route("/api/domains/:id/monitors/:monitor_id", delete(handlers::monitors::delete_monitor))
+        .route("/api/domains/:id/monitors/:monitor_id/pause", post(handlers::monitors::pause_monitor))
+        .route("/api/domains/:id/monitors/:monitor_id/resume", post(handlers::monitors::resume_monitor))
         // Monitoring routes
         .route("/api/domains/:id/monitoring/uptime/latest", get(handlers::monitoring::get_latest_uptime))
         .route("/api/domains/:id/monitoring/ssl/latest", get(handlers::monitoring::get_latest_ssl))
         .route("/api/domains/:id/monitoring/uptime/history", get(handlers::monitoring::get_uptime_history))
         .route("/api/domains/:id/monitoring/uptime/aggregate", get(handlers::monitoring::get_uptime_aggregate))
-        .route("/api/domains/:id/monitoring/check", post(handlers::monitoring::trigger_check))
+        .merge(
+            Router::new()
+                .route("/api/domains/:id/monitoring/check", post(handlers::monitoring::trigger_check))
+                .route_layer(limit(RateLimitPolicy::TriggerCheck)),
+        )
+        // SLO routes
+        .route("/api/domains/:id/slo", get(handlers::slo::list_slos))
+        .route("/api/domains/:id/slo", post(handlers::slo::create_slo))
+        .route("/api/domains/:id/slo/:slo_id", put(handlers::slo::update_slo))
+        .route("/api/domains/:id/slo/:slo_id", delete(handlers::slo::delete_slo))
+        .route("/api/domains/:id/slo/:slo_id/history", get(handlers::slo::get_slo_history))
         .route_layer(axum::middleware::from_fn_with_state(state.clone(), auth_middleware));
 
     // Combine all routes with state
@@ -0,0 +1,156 @@
+//! Audit log of administrative actions
+//!
+//! Mutating handlers take an [`Auditor`] and call [`Auditor::created`],
+//! [`Auditor::updated`] or [`Auditor::deleted`] once the change is committed. Recording is best-effort: a failure is logged and
+//! never fails the request that made the change.
+
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sqlx::PgPool;
+use std::convert::Infallible;
+use uuid::Uuid;
+
+use crate::api::routes::AppState;
+use crate::auth::{AuthContext, ClientInfo};
+use crate::db::models::{AuditAction, NewAuditEvent};
+use crate::db::queries;
+
+/// Fields left out of recorded changes
+const IGNORED_FIELDS: &[&str] = &["id", "organization_id", "created_at", "updated_at"];
+
+/// Records audit events on behalf of the caller of a request
+#[derive(Clone)]
+pub struct Auditor {
+    pool: PgPool,
+    actor_user_id: Option<Uuid>,
+    actor_api_key_id: Option<Uuid>,
+    client: ClientInfo,
+}
+
+impl Auditor {
+    /// Auditor for a request without an authentication context, e.g. registration
+    #[must_use]
+    pub fn new(pool: PgPool, actor_user_id: Option<Uuid>, client: ClientInfo) -> Self {
+        Self { pool, actor_user_id, actor_api_key_id: None, client }
+    }
+
+    /// Record an action on a target with the given changes
+    pub async fn record(&self, organization_id: Uuid, action: AuditAction, target_id: Option<Uuid>, changes: Value) {
+        let event = NewAuditEvent {
+            organization_id,
+            actor_user_id: self.actor_user_id,
+            actor_api_key_id: self.actor_api_key_id,
+            action,
+            target_id,
+            changes,
+            ip_address: self.client.ip.clone(),
+            user_agent: self.client.user_agent.clone(),
+        };
+
+        if let Err(e) = queries::insert_audit_event(&self.pool, &event).await {
+            tracing::warn!("Failed to record audit event {}: {}", action, e);
+        }
+    }
+
+    /// Record the creation of an object, with all of its fields as `after`
+    pub async fn created<T: Serialize>(&self, organization_id: Uuid, action: AuditAction, target_id: Uuid, after: &T) {
+        self.record(organization_id, action, Some(target_id), diff(&Value::Null, &snapshot(after))).await;
+    }
+
+    /// Record a change to an object, with the fields that differ
+    pub async fn updated<B: Serialize, A: Serialize>(
+        &self,
+        organization_id: Uuid,
+        action: AuditAction,
+        target_id: Uuid,
+        before: &B,
+        after: &A,
+    ) {
+        self.record(organization_id, action, Some(target_id), diff(&snapshot(before), &snapshot(after))).await;
+    }
+
+    /// Record the deletion of an object, with all of its fields as `before`
+    pub async fn deleted<T: Serialize>(&self, organization_id: Uuid, action: AuditAction, target_id: Uuid, before: &T) {
+        self.record(organization_id, action, Some(target_id), diff(&snapshot(before), &Value::Null)).await;
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for Auditor {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let client = ClientInfo::from_request_parts(parts, state).await?;
+        let auth = parts.extensions.get::<AuthContext>();
+
+        Ok(Self {
+            pool: state.pool.clone(),
+            // API keys act on their own; the user ID of their context is the key's creator
+            actor_user_id: auth.filter(|a| a.api_key.is_none()).map(|a| a.user_id),
+            actor_api_key_id: auth.and_then(|a| a.api_key.as_ref()).map(|k| k.id),
+            client,
+        })
+    }
+}
+
+/// JSON value of an object for [`diff`]
+#[must_use]
+pub fn snapshot<T: Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+/// Top-level fields that differ between two JSON objects, as
+/// `{"field": {"before": ..., "after": ...}}`
+///
+/// A missing object (`null`) counts as an object without fields, so a
+/// creation or deletion records every field.
+#[must_use]
+pub fn diff(before: &Value, after: &Value) -> Value {
+    let empty = Map::new();
+    let before = before.as_object().unwrap_or(&empty);
+    let after = after.as_object().unwrap_or(&empty);
+
+    let mut changes = Map::new();
+    for key in before.keys().chain(after.keys().filter(|k| !before.contains_key(*k))) {
+        if IGNORED_FIELDS.contains(&key.as_str()) {
+            continue;
+        }
+        let old = before.get(key).unwrap_or(&Value::Null);
+        let new = after.get(key).unwrap_or(&Value::Null);
+        if old != new {
+            changes.insert(key.clone(), serde_json::json!({ "before": old, "after": new }));
+        }
+    }
+
+    Value::Object(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_diff_keeps_changed_fields_only() {
+        let before = json!({ "name": "Acme", "plan": "free", "updated_at": "2024-01-01" });
+        let after = json!({ "name": "Acme Inc", "plan": "free", "updated_at": "2024-02-01", "slug": "acme" });
+
+        assert_eq!(
+            diff(&before, &after),
+            json!({
+                "name": { "before": "Acme", "after": "Acme Inc" },
+                "slug": { "before": null, "after": "acme" }
+            })
+        );
+        assert_eq!(diff(&before, &before), json!({}));
+    }
+
+    #[test]
+    fn test_diff_of_creation_and_deletion() {
+        let object = json!({ "name": "prod", "created_at": "2024-01-01" });
+
+        assert_eq!(diff(&Value::Null, &object), json!({ "name": { "before": null, "after": "prod" } }));
+        assert_eq!(diff(&object, &Value::Null), json!({ "name": { "before": "prod", "after": null } }));
+    }
+}
@@ -0,0 +1,36 @@
+use crate::auth::random_token;
+
+/// Prefix identifying Web-Guard API keys
+pub const API_KEY_PREFIX: &str = "wg_";
+
+/// Number of random characters in a key
+const API_KEY_LENGTH: usize = 40;
+
+/// Number of characters of the key stored in clear text for display
+const DISPLAY_PREFIX_LENGTH: usize = 11;
+
+/// Generate a new random API key
+#[must_use]
+pub fn generate_api_key() -> String {
+    format!("{}{}", API_KEY_PREFIX, random_token(API_KEY_LENGTH))
+}
+
+/// Part of the key shown to users to identify it
+#[must_use]
+pub fn display_prefix(key: &str) -> String {
+    key.chars().take(DISPLAY_PREFIX_LENGTH).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_api_key() {
+        let key = generate_api_key();
+        assert!(key.starts_with(API_KEY_PREFIX));
+        assert_eq!(key.len(), API_KEY_PREFIX.len() + API_KEY_LENGTH);
+        assert_ne!(key, generate_api_key());
+        assert_eq!(display_prefix(&key).len(), DISPLAY_PREFIX_LENGTH);
+    }
+}
@@ -6,19 +6,60 @@ use chrono::{Utc, Duration as ChronoDuration};
 
 use crate::error::{AppError, AppResult};
 
+/// Role of access tokens that are not scoped to an organization; scoped tokens
+/// carry the user's member role in it
+pub const USER_ROLE: &str = "user";
+
+/// Role of refresh tokens
+pub const REFRESH_TOKEN_ROLE: &str = "refresh";
+
+/// Role of the short-lived tokens issued between the password and second factor steps of a login
+pub const MFA_CHALLENGE_ROLE: &str = "mfa";
+
+/// Lifetime of MFA challenge tokens
+const MFA_CHALLENGE_DURATION_MINUTES: i64 = 5;
+
+/// Role of the tickets that open the event streams of an organization
+pub const STREAM_TICKET_ROLE: &str = "stream";
+
+/// Time a stream ticket can be used to open a stream
+pub const STREAM_TICKET_DURATION_SECONDS: i64 = 60;
+
 /// JWT claims structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
     /// Subject (user ID)
     pub sub: Uuid,
-    /// Current organization ID
+    /// Current organization ID, or the organization of the identity provider for
+    /// MFA tokens of SSO logins
     pub org: Option<Uuid>,
-    /// User role
+    /// Role in the current organization, or the token type for refresh and MFA tokens
     pub role: String,
     /// Issued at
     pub iat: i64,
     /// Expiration time
     pub exp: i64,
+    /// Unique token ID
+    #[serde(default)]
+    pub jti: Uuid,
+    /// Session the token was issued for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<Uuid>,
+    /// Token version of the user when the token was issued
+    #[serde(default)]
+    pub ver: i32,
+    /// Expiration time of the access token a stream ticket was issued for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pex: Option<i64>,
+}
+
+impl Claims {
+    /// Expiration time of the access the token grants: the access token a
+    /// stream ticket was issued for, or else the token itself
+    #[must_use]
+    pub fn access_expires_at(&self) -> i64 {
+        self.pex.unwrap_or(self.exp)
+    }
 }
 
 /// JWT service for token generation and validation
@@ -66,6 +107,8 @@ impl JwtService {
         user_id: Uuid,
         org_id: Option<Uuid>,
         role: &str,
+        session_id: Option<Uuid>,
+        token_version: i32,
     ) -> AppResult<String> {
         let now = Utc::now();
         let exp = now + self.access_token_duration;
@@ -76,6 +119,10 @@ impl JwtService {
             role: role.to_string(),
             iat: now.timestamp(),
             exp: exp.timestamp(),
+            jti: Uuid::new_v4(),
+            sid: session_id,
+            ver: token_version,
+            pex: None,
         };
 
         encode(&Header::default(), &claims, &self.encoding_key())
@@ -87,22 +134,93 @@ impl JwtService {
     /// # Errors
     ///
     /// Returns an error if token generation fails
-    pub fn generate_refresh_token(&self, user_id: Uuid) -> AppResult<String> {
+    pub fn generate_refresh_token(&self, user_id: Uuid, session_id: Uuid) -> AppResult<String> {
         let now = Utc::now();
         let exp = now + self.refresh_token_duration;
 
         let claims = Claims {
             sub: user_id,
             org: None,
-            role: "refresh".to_string(),
+            role: REFRESH_TOKEN_ROLE.to_string(),
             iat: now.timestamp(),
             exp: exp.timestamp(),
+            jti: Uuid::new_v4(),
+            sid: Some(session_id),
+            ver: 0,
+            pex: None,
         };
 
         encode(&Header::default(), &claims, &self.encoding_key())
             .map_err(|e| AppError::internal(format!("Failed to generate refresh token: {}", e)))
     }
 
+    /// Generate an MFA challenge token, exchanged for real tokens with a second factor
+    ///
+    /// `sso_org_id` is the organization whose identity provider started the
+    /// login, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if token generation fails
+    pub fn generate_mfa_token(&self, user_id: Uuid, sso_org_id: Option<Uuid>, token_version: i32) -> AppResult<String> {
+        let now = Utc::now();
+        let exp = now + ChronoDuration::minutes(MFA_CHALLENGE_DURATION_MINUTES);
+
+        let claims = Claims {
+            sub: user_id,
+            org: sso_org_id,
+            role: MFA_CHALLENGE_ROLE.to_string(),
+            iat: now.timestamp(),
+            exp: exp.timestamp(),
+            jti: Uuid::new_v4(),
+            sid: None,
+            ver: token_version,
+            pex: None,
+        };
+
+        encode(&Header::default(), &claims, &self.encoding_key())
+            .map_err(|e| AppError::internal(format!("Failed to generate MFA token: {}", e)))
+    }
+
+    /// Generate a stream ticket, accepted in the query of one organization's
+    /// event streams only
+    ///
+    /// The ticket carries the `jti`, session, token version and expiry of the
+    /// access token it is issued for, so revoking that token also revokes the
+    /// ticket, and the streams it opened close once either is revoked or the
+    /// token expires.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if token generation fails
+    pub fn generate_stream_ticket(
+        &self,
+        user_id: Uuid,
+        org_id: Uuid,
+        token_id: Uuid,
+        session_id: Option<Uuid>,
+        token_version: i32,
+        token_expires_at: i64,
+    ) -> AppResult<String> {
+        let now = Utc::now();
+        let exp = (now + ChronoDuration::seconds(STREAM_TICKET_DURATION_SECONDS)).timestamp().min(token_expires_at);
+
+        let claims = Claims {
+            sub: user_id,
+            org: Some(org_id),
+            role: STREAM_TICKET_ROLE.to_string(),
+            iat: now.timestamp(),
+            exp,
+            jti: token_id,
+            sid: session_id,
+            ver: token_version,
+            pex: Some(token_expires_at),
+        };
+
+        encode(&Header::default(), &claims, &self.encoding_key())
+            .map_err(|e| AppError::internal(format!("Failed to generate stream ticket: {}", e)))
+    }
+
     /// Validate and decode a token
     ///
     /// # Errors
@@ -140,12 +258,38 @@ mod tests {
 
         let user_id = Uuid::new_v4();
         let token = service
-            .generate_access_token(user_id, None, "user")
+            .generate_access_token(user_id, None, USER_ROLE, None, 0)
             .expect("Failed to generate token");
 
         let claims = service.validate_token(&token).expect("Failed to validate token");
         assert_eq!(claims.sub, user_id);
-        assert_eq!(claims.role, "user");
+        assert_eq!(claims.role, USER_ROLE);
+    }
+
+    #[test]
+    fn test_stream_ticket() {
+        let service = JwtService::new("test_secret", Duration::from_secs(900), Duration::from_secs(86400));
+
+        let (user_id, org_id, token_id) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let token_expires_at = Utc::now().timestamp() + 600;
+        let ticket = service
+            .generate_stream_ticket(user_id, org_id, token_id, None, 3, token_expires_at)
+            .expect("Failed to generate ticket");
+
+        let claims = service.validate_token(&ticket).expect("Failed to validate ticket");
+        assert_eq!(claims.role, STREAM_TICKET_ROLE);
+        assert_eq!(claims.org, Some(org_id));
+        assert_eq!((claims.jti, claims.ver), (token_id, 3));
+        assert!(claims.exp - claims.iat <= STREAM_TICKET_DURATION_SECONDS);
+        // Streams opened with the ticket last as long as the access token
+        assert_eq!(claims.access_expires_at(), token_expires_at);
+
+        // A ticket never outlives the access token it was issued for
+        let token_expires_at = Utc::now().timestamp() + 10;
+        let ticket = service
+            .generate_stream_ticket(user_id, org_id, token_id, None, 3, token_expires_at)
+            .expect("Failed to generate ticket");
+        assert_eq!(service.validate_token(&ticket).unwrap().exp, token_expires_at);
     }
 
     #[test]
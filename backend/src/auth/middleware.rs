@@ -1,12 +1,21 @@
 use axum::{
-    extract::{Request, State, Extension},
-    http::StatusCode,
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts, Request, State, Extension},
+    http::{request::Parts, HeaderMap, Method, StatusCode, Uri},
     middleware::Next,
     response::Response,
 };
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::convert::Infallible;
+use std::net::SocketAddr;
 use uuid::Uuid;
 
 use crate::api::routes::AppState;
+use crate::auth::{sha256_hash, Access, Claims, MFA_CHALLENGE_ROLE, REFRESH_TOKEN_ROLE, STREAM_TICKET_ROLE};
+use crate::db::models::{ApiKeyScope, MemberRole, Organization};
+use crate::db::queries;
+use crate::error::{AppError, AppResult};
 
 /// Context containing authenticated user information
 #[derive(Debug, Clone)]
@@ -14,46 +23,404 @@ pub struct AuthContext {
     pub user_id: Uuid,
     pub org_id: Option<Uuid>,
     pub role: String,
+    /// Login session of the access token, if any
+    pub session_id: Option<Uuid>,
+    /// `jti` of the access token, `None` for API keys
+    pub token_id: Option<Uuid>,
+    /// User's token version when the access token was issued, `None` for API keys
+    pub token_version: Option<i32>,
+    /// Expiry of the access token, or of the one a stream ticket was issued
+    /// for; `None` for API keys
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Set when the request is authenticated with an API key
+    pub api_key: Option<ApiKeyContext>,
+}
+
+/// API key used to authenticate a request
+#[derive(Debug, Clone)]
+pub struct ApiKeyContext {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    pub scopes: Vec<ApiKeyScope>,
+}
+
+impl AuthContext {
+    /// Role of the caller in an organization, `None` if it has no access
+    ///
+    /// API keys only have access to their own organization, with a role derived
+    /// from their scopes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the membership lookup fails, if the organization is
+    /// scheduled for deletion, if it requires two-factor authentication and
+    /// the user has not enabled it, or if it enforces single sign-on and the
+    /// session was not signed in by its identity provider
+    pub async fn role_in(&self, pool: &PgPool, organization_id: Uuid) -> AppResult<Option<MemberRole>> {
+        Ok(self.access_in(pool, organization_id).await?.map(|a| a.role))
+    }
+
+    /// Effective permissions of the caller in an organization, `None` if it has
+    /// no access
+    ///
+    /// API keys get the permissions of the role derived from their scopes.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Self::role_in`]
+    pub async fn access_in(&self, pool: &PgPool, organization_id: Uuid) -> AppResult<Option<Access>> {
+        match &self.api_key {
+            Some(key) if key.organization_id == organization_id => {
+                let role = ApiKeyScope::role_for(&key.scopes);
+                Ok(Some(Access {
+                    organization_id,
+                    permissions: role.permissions().to_vec(),
+                    role,
+                    domain_group_ids: None,
+                }))
+            }
+            Some(_) => Ok(None),
+            None => match queries::get_member_access(pool, organization_id, self.user_id, self.session_id).await? {
+                Some(access) if access.organization_deleted => Err(AppError::authorization(
+                    "This organization is scheduled for deletion; its owner can restore it",
+                )),
+                Some(access) if access.two_factor_missing => Err(AppError::authorization(
+                    "This organization requires two-factor authentication; enable it to continue",
+                )),
+                Some(access) if access.requires_sso() => Err(AppError::authorization(
+                    "This organization requires signing in with single sign-on",
+                )),
+                access => Ok(access.map(|a| Access {
+                    organization_id,
+                    permissions: a.permissions(),
+                    role: a.role,
+                    domain_group_ids: a.domain_group_ids,
+                })),
+            },
+        }
+    }
+
+    /// Organization a request applies to: the requested one, or else the one
+    /// the token is scoped to (an API key's own organization)
+    ///
+    /// Membership is not checked here.
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error if neither is set
+    pub fn current_org(&self, requested: Option<Uuid>) -> AppResult<Uuid> {
+        requested.or(self.org_id).ok_or_else(|| {
+            AppError::validation("No organization selected; pass org_id or switch to an organization first")
+        })
+    }
+
+    /// Check if the caller has access to an organization
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the membership lookup fails
+    pub async fn is_member_of(&self, pool: &PgPool, organization_id: Uuid) -> AppResult<bool> {
+        Ok(self.role_in(pool, organization_id).await?.is_some())
+    }
+
+    /// Check if the access token has expired by `now`
+    #[must_use]
+    pub fn has_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// Check that the request's credentials are still valid: the access token
+    /// neither expired nor revoked, or the API key still usable
+    ///
+    /// Requests are checked once by [`auth_middleware`]; long-lived connections
+    /// call this again while they stay open.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lookup fails
+    pub async fn is_still_valid(&self, state: &AppState) -> AppResult<bool> {
+        if self.has_expired(Utc::now()) {
+            return Ok(false);
+        }
+        if let Some(key) = &self.api_key {
+            let api_key = queries::find_api_key_credential(&state.pool, key.id).await?;
+            return Ok(api_key.is_some_and(|k| k.is_usable()));
+        }
+
+        match (self.token_id, self.token_version) {
+            (Some(jti), Some(ver)) => Ok(!is_revoked(state, self.user_id, jti, self.session_id, ver).await?),
+            _ => Ok(false),
+        }
+    }
+
+    /// Organizations the caller has access to
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lookup fails
+    pub async fn organizations(&self, pool: &PgPool) -> AppResult<Vec<Organization>> {
+        match &self.api_key {
+            Some(key) => Ok(queries::find_organization_by_id(pool, key.organization_id)
+                .await?
+                .into_iter()
+                .collect()),
+            None => queries::list_user_organizations(pool, self.user_id).await,
+        }
+    }
+}
+
+/// Client address and user agent of a request
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl ClientInfo {
+    /// Read client information from request headers and connection info
+    #[must_use]
+    pub fn from_parts(headers: &HeaderMap, connect_info: Option<SocketAddr>, trust_proxy_headers: bool) -> Self {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|h| h.to_str().ok())
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+        };
+
+        let forwarded_ip = trust_proxy_headers
+            .then(|| {
+                header("X-Forwarded-For")
+                    .and_then(|v| v.split(',').next())
+                    .map(str::trim)
+                    .or_else(|| header("X-Real-IP"))
+            })
+            .flatten();
+
+        Self {
+            ip: forwarded_ip
+                .map(str::to_string)
+                .or_else(|| connect_info.map(|addr| addr.ip().to_string())),
+            user_agent: header("User-Agent").map(str::to_string),
+        }
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for ClientInfo {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let connect_info = parts.extensions.get::<ConnectInfo<SocketAddr>>().map(|c| c.0);
+        Ok(Self::from_parts(&parts.headers, connect_info, state.config.server.trust_proxy_headers))
+    }
+}
+
+/// Credentials presented with a request
+enum Credentials<'a> {
+    Bearer(&'a str),
+    ApiKey(&'a str),
+}
+
+/// Extract credentials from `Authorization: Bearer`, `Authorization: ApiKey` or `X-Api-Key`
+fn credentials(headers: &HeaderMap) -> Option<Credentials<'_>> {
+    if let Some(header) = headers.get("Authorization").and_then(|h| h.to_str().ok()) {
+        if let Some(token) = header.strip_prefix("Bearer ") {
+            return Some(Credentials::Bearer(token));
+        }
+        if let Some(key) = header.strip_prefix("ApiKey ") {
+            return Some(Credentials::ApiKey(key));
+        }
+        return None;
+    }
+
+    headers
+        .get("X-Api-Key")
+        .and_then(|h| h.to_str().ok())
+        .map(Credentials::ApiKey)
+}
+
+/// Stream ticket from the `ticket` query parameter, with the organization of
+/// the event stream requested
+///
+/// Only accepted on the event stream routes: browsers can't set headers on
+/// `EventSource` and WebSocket requests.
+fn stream_ticket<'a>(method: &Method, uri: &'a Uri) -> Option<(Uuid, &'a str)> {
+    if method != Method::GET {
+        return None;
+    }
+
+    let path = uri.path().strip_prefix("/api/organizations/")?;
+    let id = path.strip_suffix("/events").or_else(|| path.strip_suffix("/events/ws"))?;
+    let organization_id = id.parse().ok()?;
+
+    let ticket = uri.query()?
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("ticket="))?;
+    Some((organization_id, ticket))
+}
+
+/// Scope an API key needs for a request
+///
+/// Changes need the admin scope unless the route is one of those open to write
+/// keys, so that a new route is never open to more keys than its handler expects.
+fn required_scope(method: &Method, path: &str) -> ApiKeyScope {
+    if path.contains("/api-keys") || path.contains("/members") {
+        ApiKeyScope::Admin
+    } else if method == Method::GET || method == Method::HEAD {
+        ApiKeyScope::Read
+    } else if method == Method::POST && path.ends_with("/monitoring/check") {
+        ApiKeyScope::TriggerCheck
+    } else if is_write_route(path) {
+        ApiKeyScope::Write
+    } else {
+        ApiKeyScope::Admin
+    }
+}
+
+/// Routes whose changes are open to write keys: domains with their monitors and
+/// SLOs, and acknowledging alerts
+fn is_write_route(path: &str) -> bool {
+    let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+    matches!(
+        segments.as_slice(),
+        ["api", "domains", ..] | ["api", "organizations", _, "alerts", _, "acknowledge"]
+    )
+}
+
+/// Authenticate a request with an API key
+async fn authenticate_api_key(
+    state: &AppState,
+    key: &str,
+    required: ApiKeyScope,
+    client: &ClientInfo,
+) -> Result<AuthContext, StatusCode> {
+    let api_key = queries::find_api_key_by_hash(&state.pool, &sha256_hash(key.trim()))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .filter(|k| k.is_usable())
+        .ok_or(StatusCode::UNAUTHORIZED)?
+        .key;
+
+    let scopes = api_key.scope_set();
+    if !scopes.iter().any(|scope| scope.grants(required)) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    if let Err(e) = queries::touch_api_key(&state.pool, api_key.id, client.ip.as_deref()).await {
+        tracing::warn!("Failed to record API key usage: {}", e);
+    }
+
+    Ok(AuthContext {
+        user_id: api_key.created_by,
+        org_id: Some(api_key.organization_id),
+        role: ApiKeyScope::role_for(&scopes).to_string(),
+        session_id: None,
+        token_id: None,
+        token_version: None,
+        expires_at: None,
+        api_key: Some(ApiKeyContext {
+            id: api_key.id,
+            organization_id: api_key.organization_id,
+            scopes,
+        }),
+    })
+}
+
+/// Check an access token against its user's status and token version, the `jti`
+/// denylist and its session
+async fn is_revoked(
+    state: &AppState,
+    user_id: Uuid,
+    jti: Uuid,
+    session_id: Option<Uuid>,
+    token_version: i32,
+) -> AppResult<bool> {
+    if let Some(revoked) = state.revocations.get(jti) {
+        return Ok(revoked);
+    }
+
+    let status = queries::get_access_token_status(&state.pool, user_id, jti, session_id).await?;
+
+    let revoked = status.is_none_or(|s| {
+        !s.is_active || s.token_version != token_version || s.is_denied || s.session_revoked
+    });
+    state.revocations.insert(jti, user_id, revoked);
+
+    Ok(revoked)
+}
+
+/// Authenticate a request with a validated access token or stream ticket
+async fn authenticate_token(state: &AppState, claims: Claims) -> Result<AuthContext, StatusCode> {
+    // Tokens issued before revocation support have no jti and must be refreshed
+    if claims.jti.is_nil() {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    let revoked = is_revoked(state, claims.sub, claims.jti, claims.sid, claims.ver)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if revoked {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let expires_at = DateTime::from_timestamp(claims.access_expires_at(), 0);
+    Ok(AuthContext {
+        user_id: claims.sub,
+        org_id: claims.org,
+        role: claims.role,
+        session_id: claims.sid,
+        token_id: Some(claims.jti),
+        token_version: Some(claims.ver),
+        expires_at,
+        api_key: None,
+    })
 }
 
 /// Authentication middleware
 ///
-/// Validates JWT token from Authorization header and adds auth context to request extensions
+/// Validates the JWT or API key from the request headers, or the stream ticket
+/// of an event stream, and adds auth context to request extensions
 pub async fn auth_middleware(
     State(state): State<AppState>,
     mut request: Request,
     next: Next,
 ) -> Result<Response, StatusCode> {
-    // Get Authorization header
-    let auth_header = request
-        .headers()
-        .get("Authorization")
-        .and_then(|h| h.to_str().ok());
-
-    let token = match auth_header {
-        Some(header) if header.starts_with("Bearer ") => {
-            header.trim_start_matches("Bearer ")
-        }
-        _ => {
-            return Err(StatusCode::UNAUTHORIZED);
+    let auth_context = match credentials(request.headers()) {
+        Some(Credentials::Bearer(token)) => {
+            // Validate token
+            let claims = state.jwt_service
+                .validate_token(token)
+                .map_err(|_| StatusCode::UNAUTHORIZED)?;
+
+            // Refresh, MFA challenge and stream tokens shouldn't access API endpoints
+            if [REFRESH_TOKEN_ROLE, MFA_CHALLENGE_ROLE, STREAM_TICKET_ROLE].contains(&claims.role.as_str()) {
+                return Err(StatusCode::UNAUTHORIZED);
+            }
+
+            authenticate_token(&state, claims).await?
         }
-    };
+        Some(Credentials::ApiKey(key)) => {
+            let key = key.to_string();
+            let required = required_scope(request.method(), request.uri().path());
+            let connect_info = request.extensions().get::<ConnectInfo<SocketAddr>>().map(|c| c.0);
+            let client = ClientInfo::from_parts(request.headers(), connect_info, state.config.server.trust_proxy_headers);
 
-    // Validate token
-    let claims = state.jwt_service
-        .validate_token(token)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+            authenticate_api_key(&state, &key, required, &client).await?
+        }
+        None => {
+            let (organization_id, ticket) = stream_ticket(request.method(), request.uri())
+                .ok_or(StatusCode::UNAUTHORIZED)?;
+            let claims = state.jwt_service
+                .validate_token(ticket)
+                .map_err(|_| StatusCode::UNAUTHORIZED)?;
 
-    // Check if it's a refresh token (refresh tokens shouldn't access API endpoints)
-    if claims.role == "refresh" {
-        return Err(StatusCode::UNAUTHORIZED);
-    }
+            // Tickets only open the streams of the organization they were issued for
+            if claims.role != STREAM_TICKET_ROLE || claims.org != Some(organization_id) {
+                return Err(StatusCode::UNAUTHORIZED);
+            }
 
-    // Add auth context to request extensions
-    let auth_context = AuthContext {
-        user_id: claims.sub,
-        org_id: claims.org,
-        role: claims.role,
+            authenticate_token(&state, claims).await?
+        }
     };
 
     request.extensions_mut().insert(auth_context);
@@ -63,3 +430,92 @@ pub async fn auth_middleware(
 
 /// Type alias for AuthContext Extension extractor
 pub type AuthExtractor = Extension<AuthContext>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_required_scope() {
+        assert_eq!(required_scope(&Method::GET, "/api/domains"), ApiKeyScope::Read);
+        assert_eq!(required_scope(&Method::POST, "/api/domains"), ApiKeyScope::Write);
+        assert_eq!(
+            required_scope(&Method::POST, "/api/domains/1/monitoring/check"),
+            ApiKeyScope::TriggerCheck
+        );
+        assert_eq!(required_scope(&Method::GET, "/api/organizations/1/members"), ApiKeyScope::Admin);
+        assert_eq!(required_scope(&Method::GET, "/api/organizations/1/api-keys"), ApiKeyScope::Admin);
+        assert_eq!(required_scope(&Method::GET, "/api/organizations/1/roles"), ApiKeyScope::Read);
+        assert_eq!(required_scope(&Method::PUT, "/api/domains/1/monitors/2"), ApiKeyScope::Write);
+        assert_eq!(
+            required_scope(&Method::POST, "/api/organizations/1/alerts/2/acknowledge"),
+            ApiKeyScope::Write
+        );
+
+        // Any other change defaults to the admin scope
+        assert_eq!(required_scope(&Method::POST, "/api/organizations/1/roles"), ApiKeyScope::Admin);
+        assert_eq!(required_scope(&Method::POST, "/api/organizations/1/invitations"), ApiKeyScope::Admin);
+        assert_eq!(required_scope(&Method::PUT, "/api/organizations/1/sso"), ApiKeyScope::Admin);
+        assert_eq!(
+            required_scope(&Method::POST, "/api/organizations/1/ownership-transfer"),
+            ApiKeyScope::Admin
+        );
+        assert_eq!(required_scope(&Method::DELETE, "/api/organizations/1"), ApiKeyScope::Admin);
+        assert_eq!(required_scope(&Method::POST, "/api/domainsx"), ApiKeyScope::Admin);
+    }
+
+    #[test]
+    fn test_stream_ticket() {
+        let id = Uuid::new_v4();
+        let ticket = |method, uri: String| {
+            let uri: Uri = uri.parse().unwrap();
+            stream_ticket(&method, &uri) == Some((id, "abc.def"))
+        };
+
+        assert!(ticket(Method::GET, format!("/api/organizations/{id}/events?types=alert_created&ticket=abc.def")));
+        assert!(ticket(Method::GET, format!("/api/organizations/{id}/events/ws?ticket=abc.def")));
+        assert!(!ticket(Method::GET, format!("/api/organizations/{id}/alerts?ticket=abc.def")));
+        assert!(!ticket(Method::GET, format!("/api/organizations/{id}/events?access_token=abc.def")));
+        assert!(!ticket(Method::GET, format!("/api/domains/{id}/events?ticket=abc.def")));
+        assert!(!ticket(Method::GET, format!("/api/organizations/{id}/x/events?ticket=abc.def")));
+        assert!(!ticket(Method::POST, format!("/api/organizations/{id}/events?ticket=abc.def")));
+    }
+
+    #[test]
+    fn test_stream_ticket_expiry() {
+        use chrono::Duration;
+        use crate::auth::JwtService;
+
+        let jwt = JwtService::new("test_secret", std::time::Duration::from_secs(900), std::time::Duration::from_secs(86400));
+        let token_expires_at = DateTime::from_timestamp(Utc::now().timestamp() + 600, 0).unwrap();
+        let ticket = jwt
+            .generate_stream_ticket(Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4(), None, 0, token_expires_at.timestamp())
+            .unwrap();
+        let claims = jwt.validate_token(&ticket).unwrap();
+
+        // The stream outlives the ticket, but not the access token it was issued for
+        let auth = AuthContext {
+            user_id: claims.sub,
+            org_id: claims.org,
+            role: claims.role.clone(),
+            session_id: None,
+            token_id: Some(claims.jti),
+            token_version: Some(claims.ver),
+            expires_at: DateTime::from_timestamp(claims.access_expires_at(), 0),
+            api_key: None,
+        };
+        assert_eq!(auth.expires_at, Some(token_expires_at));
+        assert!(!auth.has_expired(Utc::now() + Duration::minutes(5)));
+        assert!(auth.has_expired(token_expires_at));
+    }
+
+    #[test]
+    fn test_client_info_proxy_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Forwarded-For", "203.0.113.7, 10.0.0.1".parse().unwrap());
+        let peer = Some("10.0.0.1:4000".parse().unwrap());
+
+        assert_eq!(ClientInfo::from_parts(&headers, peer, true).ip.as_deref(), Some("203.0.113.7"));
+        assert_eq!(ClientInfo::from_parts(&headers, peer, false).ip.as_deref(), Some("10.0.0.1"));
+    }
+}
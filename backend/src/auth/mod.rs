@@ -1,7 +1,17 @@
 pub mod jwt;
 pub mod password;
 pub mod middleware;
+pub mod api_key;
+pub mod revocation;
+pub mod totp;
+pub mod oidc;
+pub mod permissions;
 
 pub use jwt::*;
 pub use password::*;
 pub use middleware::*;
+pub use api_key::*;
+pub use revocation::*;
+pub use totp::*;
+pub use oidc::*;
+pub use permissions::*;
@@ -0,0 +1,202 @@
+//! OpenID Connect client for organization single sign-on
+//!
+//! Logins use the authorization code flow with PKCE. The provider is discovered
+//! from its issuer URL on every login, so configuration changes at the provider
+//! are picked up without restarting.
+
+use openidconnect::core::{
+    CoreAuthenticationFlow, CoreClient, CoreProviderMetadata, CoreUserInfoClaims,
+};
+use openidconnect::{
+    AuthorizationCode, ClientId, ClientSecret, CsrfToken, EndUserName, HttpRequest, HttpResponse,
+    IssuerUrl, LocalizedClaim, Nonce, OAuth2TokenResponse, PkceCodeChallenge, PkceCodeVerifier,
+    RedirectUrl, Scope, TokenResponse,
+};
+use reqwest::Client;
+
+use crate::db::models::SsoConfig;
+use crate::error::{AppError, AppResult};
+
+/// Authorization request to send the user to
+pub struct AuthorizationRequest {
+    pub url: String,
+    /// CSRF state, echoed back to the callback
+    pub state: String,
+    pub nonce: String,
+    pub pkce_verifier: String,
+}
+
+/// Identity asserted by the provider after a successful login
+#[derive(Debug, Clone)]
+pub struct OidcIdentity {
+    pub issuer: String,
+    pub subject: String,
+    pub email: String,
+    /// The provider verified that the user owns the email address
+    pub email_verified: bool,
+    pub name: Option<String>,
+}
+
+/// Client for the provider of one organization
+pub struct OidcClient {
+    client: CoreClient,
+    http: Client,
+}
+
+impl OidcClient {
+    /// Discover the provider and build a client for it
+    ///
+    /// `http` must not follow redirects, so that the provider cannot redirect
+    /// token requests to other hosts.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if discovery fails
+    pub async fn discover(http: Client, config: &SsoConfig, redirect_uri: &str) -> AppResult<Self> {
+        let redirect_uri = RedirectUrl::new(redirect_uri.to_string())
+            .map_err(|e| AppError::internal(format!("Invalid SSO redirect URL: {}", e)))?;
+        let metadata = discover_metadata(http.clone(), &config.issuer)
+            .await
+            .map_err(AppError::external)?;
+
+        let client = CoreClient::from_provider_metadata(
+            metadata,
+            ClientId::new(config.client_id.clone()),
+            Some(ClientSecret::new(config.client_secret.clone())),
+        )
+        .set_redirect_uri(redirect_uri);
+
+        Ok(Self { client, http })
+    }
+
+    /// Start a login, with a fresh state, nonce and PKCE challenge
+    #[must_use]
+    pub fn authorization_request(&self) -> AuthorizationRequest {
+        let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
+
+        let (url, state, nonce) = self
+            .client
+            .authorize_url(CoreAuthenticationFlow::AuthorizationCode, CsrfToken::new_random, Nonce::new_random)
+            .add_scope(Scope::new("email".to_string()))
+            .add_scope(Scope::new("profile".to_string()))
+            .set_pkce_challenge(pkce_challenge)
+            .url();
+
+        AuthorizationRequest {
+            url: url.to_string(),
+            state: state.secret().clone(),
+            nonce: nonce.secret().clone(),
+            pkce_verifier: pkce_verifier.secret().clone(),
+        }
+    }
+
+    /// Exchange an authorization code and verify the returned ID token
+    ///
+    /// The email address is read from the user info endpoint when the ID token
+    /// does not contain it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an authentication error if the code is rejected or the ID token is invalid
+    pub async fn exchange_code(&self, code: &str, nonce: &str, pkce_verifier: &str) -> AppResult<OidcIdentity> {
+        let token_response = self
+            .client
+            .exchange_code(AuthorizationCode::new(code.to_string()))
+            .set_pkce_verifier(PkceCodeVerifier::new(pkce_verifier.to_string()))
+            .request_async(|request| send(self.http.clone(), request))
+            .await
+            .map_err(|e| AppError::auth(format!("Authorization code exchange failed: {}", error_chain(&e))))?;
+
+        let id_token = token_response
+            .id_token()
+            .ok_or_else(|| AppError::auth("Identity provider did not return an ID token"))?;
+        let claims = id_token
+            .claims(&self.client.id_token_verifier(), &Nonce::new(nonce.to_string()))
+            .map_err(|e| AppError::auth(format!("Invalid ID token: {}", e)))?;
+
+        let mut email = claims.email().map(|e| e.as_str().to_string());
+        let mut email_verified = claims.email_verified();
+        let mut name = localized_name(claims.name());
+
+        if email.is_none() {
+            let user_info: CoreUserInfoClaims = self
+                .client
+                .user_info(token_response.access_token().clone(), Some(claims.subject().clone()))
+                .map_err(|e| AppError::auth(format!("Identity provider has no user info endpoint: {}", e)))?
+                .request_async(|request| send(self.http.clone(), request))
+                .await
+                .map_err(|e| AppError::auth(format!("User info request failed: {}", error_chain(&e))))?;
+
+            email = user_info.email().map(|e| e.as_str().to_string());
+            email_verified = user_info.email_verified();
+            name = name.or_else(|| localized_name(user_info.name()));
+        }
+
+        Ok(OidcIdentity {
+            issuer: claims.issuer().as_str().to_string(),
+            subject: claims.subject().as_str().to_string(),
+            email: email
+                .ok_or_else(|| AppError::auth("Identity provider did not return an email address"))?
+                .trim()
+                .to_lowercase(),
+            email_verified: email_verified.unwrap_or(false),
+            name,
+        })
+    }
+}
+
+/// Check that an issuer URL points to a working OpenID provider
+///
+/// # Errors
+///
+/// Returns a validation error if discovery fails
+pub async fn validate_issuer(http: Client, issuer: &str) -> AppResult<()> {
+    discover_metadata(http, issuer)
+        .await
+        .map(|_| ())
+        .map_err(AppError::validation)
+}
+
+async fn discover_metadata(http: Client, issuer: &str) -> Result<CoreProviderMetadata, String> {
+    let issuer = IssuerUrl::new(issuer.to_string()).map_err(|e| format!("Invalid issuer URL: {}", e))?;
+
+    CoreProviderMetadata::discover_async(issuer, |request| send(http.clone(), request))
+        .await
+        .map_err(|e| format!("OpenID provider discovery failed: {}", error_chain(&e)))
+}
+
+fn localized_name(name: Option<&LocalizedClaim<EndUserName>>) -> Option<String> {
+    name.and_then(|n| n.get(None)).map(|n| n.as_str().to_string())
+}
+
+/// Send a request for the OpenID Connect client through the shared HTTP client
+async fn send(http: Client, request: HttpRequest) -> Result<HttpResponse, reqwest::Error> {
+    let response = http
+        .request(request.method, request.url.as_str())
+        .headers(request.headers)
+        .body(request.body)
+        .send()
+        .await?;
+
+    Ok(HttpResponse {
+        status_code: response.status(),
+        headers: response.headers().clone(),
+        body: response.bytes().await?.to_vec(),
+    })
+}
+
+/// Error message including its sources, which carry the useful details
+fn error_chain(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(e) = source {
+        let detail = e.to_string();
+        // Some errors already include their source in their own message
+        if !message.contains(&detail) {
+            message.push_str(": ");
+            message.push_str(&detail);
+        }
+        source = e.source();
+    }
+    message
+}
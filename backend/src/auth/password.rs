@@ -1,4 +1,6 @@
 use bcrypt::{hash, verify, DEFAULT_COST};
+use rand::{distributions::Alphanumeric, Rng};
+use sha2::{Digest, Sha256};
 use crate::error::{AppError, AppResult};
 
 /// Hash a password using bcrypt
@@ -25,6 +27,33 @@ pub fn verify_password(password: &str, hash: &str) -> AppResult<bool> {
     verify(password, hash).map_err(|e| AppError::internal(format!("Failed to verify password: {}", e)))
 }
 
+/// Compute the SHA-256 hex digest used to store tokens and API keys
+#[must_use]
+pub fn sha256_hash(input: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(input.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Number of random characters in tokens sent by email
+const USER_TOKEN_LENGTH: usize = 43;
+
+/// Generate a random alphanumeric secret
+#[must_use]
+pub fn random_token(length: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(length)
+        .map(char::from)
+        .collect()
+}
+
+/// Generate a single-use token for password reset and email verification links
+#[must_use]
+pub fn generate_user_token() -> String {
+    random_token(USER_TOKEN_LENGTH)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
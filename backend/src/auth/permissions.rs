@@ -0,0 +1,291 @@
+//! Permission checks for organization resources
+//!
+//! Handlers declare the permission they need in their signature with
+//! [`OrgAccess`] or [`DomainAccess`], e.g. `OrgAccess<perm::MembersManage>`;
+//! the extractor resolves the organization, loads the caller's effective
+//! permissions and rejects the request before the handler runs.
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Query, RawPathParams},
+    http::request::Parts,
+};
+use std::marker::PhantomData;
+use uuid::Uuid;
+
+use crate::api::routes::AppState;
+use crate::auth::AuthContext;
+use crate::db::models::{Domain, MemberRole, Permission};
+use crate::db::queries;
+use crate::error::{AppError, AppResult};
+
+/// Caller's effective access to an organization
+#[derive(Debug, Clone)]
+pub struct Access {
+    pub organization_id: Uuid,
+    /// Built-in role; a custom role replaces its permissions but not the role itself
+    pub role: MemberRole,
+    pub permissions: Vec<Permission>,
+    /// Domain groups the caller is restricted to (`None` for all domains)
+    pub domain_group_ids: Option<Vec<Uuid>>,
+}
+
+impl Access {
+    /// Check if the caller has a permission
+    #[must_use]
+    pub fn has(&self, permission: Permission) -> bool {
+        self.permissions.contains(&permission)
+    }
+
+    /// Check if the caller has every one of the given permissions, so that it
+    /// may grant them to someone else
+    #[must_use]
+    pub fn grants_all(&self, permissions: &[Permission]) -> bool {
+        permissions.iter().all(|p| self.has(*p))
+    }
+
+    /// Require a permission
+    ///
+    /// # Errors
+    ///
+    /// Returns an authorization error if the caller lacks it
+    pub fn require(&self, permission: Permission) -> AppResult<()> {
+        if self.has(permission) {
+            Ok(())
+        } else {
+            Err(AppError::authorization(format!("Missing permission: {}", permission)))
+        }
+    }
+
+    /// Require that the caller may grant a set of permissions to someone else:
+    /// it must hold all of them and not be restricted to domain groups itself
+    ///
+    /// # Errors
+    ///
+    /// Returns an authorization error otherwise
+    pub fn require_grantable(&self, permissions: &[Permission]) -> AppResult<()> {
+        if self.domain_group_ids.is_some() {
+            return Err(AppError::authorization("Members restricted to domain groups cannot grant access"));
+        }
+        if !self.grants_all(permissions) {
+            return Err(AppError::authorization("You cannot grant permissions you don't have"));
+        }
+
+        Ok(())
+    }
+
+    /// Check if the caller can access a domain in the given group
+    #[must_use]
+    pub fn can_access_group(&self, group_id: Option<Uuid>) -> bool {
+        match &self.domain_group_ids {
+            None => true,
+            Some(allowed) => group_id.is_some_and(|id| allowed.contains(&id)),
+        }
+    }
+
+    /// Groups to filter domain listings by, `None` when unrestricted
+    #[must_use]
+    pub fn group_filter(&self) -> Option<&[Uuid]> {
+        self.domain_group_ids.as_deref()
+    }
+}
+
+/// Permission an extractor requires, as a type parameter
+pub trait RequiredPermission: Send + Sync + 'static {
+    /// `None` only requires access to the organization
+    const PERMISSION: Option<Permission>;
+}
+
+/// Marker types naming the permission an extractor requires
+pub mod perm {
+    use super::{Permission, RequiredPermission};
+
+    /// Access to the organization, without any particular permission
+    pub struct Member;
+
+    impl RequiredPermission for Member {
+        const PERMISSION: Option<Permission> = None;
+    }
+
+    macro_rules! permission_markers {
+        ($($name:ident),* $(,)?) => {
+            $(
+                #[doc = concat!("Requires [`Permission::", stringify!($name), "`]")]
+                pub struct $name;
+
+                impl RequiredPermission for $name {
+                    const PERMISSION: Option<Permission> = Some(Permission::$name);
+                }
+            )*
+        };
+    }
+
+    permission_markers!(
+        DomainsRead,
+        DomainsCreate,
+        DomainsEdit,
+        DomainsDelete,
+        MonitorsEdit,
+        MonitorsCheck,
+        AlertsRead,
+        AlertsAck,
+        MembersManage,
+        OrganizationManage,
+        ApiKeysManage,
+        AuditRead,
+        Billing,
+    );
+}
+
+#[derive(serde::Deserialize)]
+struct OrgQuery {
+    org_id: Option<Uuid>,
+}
+
+fn auth_context(parts: &Parts) -> AppResult<AuthContext> {
+    parts
+        .extensions
+        .get::<AuthContext>()
+        .cloned()
+        .ok_or_else(|| AppError::auth("Missing authentication"))
+}
+
+/// The `:id` path parameter, if the route has one
+async fn path_id(parts: &mut Parts, state: &AppState, what: &str) -> AppResult<Option<Uuid>> {
+    let params = RawPathParams::from_request_parts(parts, state)
+        .await
+        .map_err(|_| AppError::internal("Path parameters unavailable"))?;
+
+    params
+        .iter()
+        .find(|(name, _)| *name == "id")
+        .map(|(_, value)| value.parse().map_err(|_| AppError::validation(format!("Invalid {} ID", what))))
+        .transpose()
+}
+
+async fn require_access<P: RequiredPermission>(
+    state: &AppState,
+    auth: &AuthContext,
+    organization_id: Uuid,
+) -> AppResult<Access> {
+    let access = auth.access_in(&state.pool, organization_id).await?
+        .ok_or_else(|| AppError::authorization("You are not a member of this organization"))?;
+
+    if let Some(permission) = P::PERMISSION {
+        access.require(permission)?;
+    }
+
+    Ok(access)
+}
+
+/// Access to an organization, requiring permission `P`
+///
+/// The organization is the `:id` path parameter, or else the `org_id` query
+/// parameter or the token's current organization.
+pub struct OrgAccess<P = perm::Member> {
+    pub auth: AuthContext,
+    pub access: Access,
+    _permission: PhantomData<P>,
+}
+
+impl<P> OrgAccess<P> {
+    #[must_use]
+    pub const fn organization_id(&self) -> Uuid {
+        self.access.organization_id
+    }
+}
+
+#[async_trait]
+impl<P: RequiredPermission> FromRequestParts<AppState> for OrgAccess<P> {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let auth = auth_context(parts)?;
+
+        let organization_id = match path_id(parts, state, "organization").await? {
+            Some(id) => id,
+            None => {
+                let Query(query) = Query::<OrgQuery>::try_from_uri(&parts.uri)
+                    .map_err(|e| AppError::validation(format!("Invalid query: {}", e)))?;
+                auth.current_org(query.org_id)?
+            }
+        };
+
+        let access = require_access::<P>(state, &auth, organization_id).await?;
+
+        Ok(Self { auth, access, _permission: PhantomData })
+    }
+}
+
+/// Access to the domain of the `:id` path parameter, requiring permission `P`
+/// in its organization and access to its group
+pub struct DomainAccess<P = perm::DomainsRead> {
+    pub auth: AuthContext,
+    pub access: Access,
+    pub domain: Domain,
+    _permission: PhantomData<P>,
+}
+
+#[async_trait]
+impl<P: RequiredPermission> FromRequestParts<AppState> for DomainAccess<P> {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let auth = auth_context(parts)?;
+
+        let domain_id = path_id(parts, state, "domain").await?
+            .ok_or_else(|| AppError::internal("Route has no domain ID"))?;
+        let domain = queries::find_domain_by_id(&state.pool, domain_id).await?
+            .ok_or_else(|| AppError::not_found("Domain not found"))?;
+
+        let access = require_access::<P>(state, &auth, domain.organization_id).await?;
+        if !access.can_access_group(domain.group_id) {
+            return Err(AppError::authorization("You don't have access to this domain"));
+        }
+
+        Ok(Self { auth, access, domain, _permission: PhantomData })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn access(role: MemberRole, domain_group_ids: Option<Vec<Uuid>>) -> Access {
+        Access { organization_id: Uuid::nil(), permissions: role.permissions().to_vec(), role, domain_group_ids }
+    }
+
+    #[test]
+    fn test_permission_names_round_trip() {
+        for permission in Permission::ALL {
+            assert_eq!(permission.as_str().parse::<Permission>(), Ok(*permission));
+            assert_eq!(serde_json::to_value(permission).unwrap(), permission.as_str());
+        }
+        assert!("domains.write".parse::<Permission>().is_err());
+    }
+
+    #[test]
+    fn test_built_in_roles_are_nested() {
+        let roles = [MemberRole::Viewer, MemberRole::Member, MemberRole::Admin, MemberRole::Owner];
+        for pair in roles.windows(2) {
+            assert!(access(pair[1].clone(), None).grants_all(pair[0].permissions()));
+        }
+        assert!(!MemberRole::Admin.permissions().contains(&Permission::Billing));
+        assert!(MemberRole::Viewer.permissions().contains(&Permission::MonitorsCheck));
+    }
+
+    #[test]
+    fn test_grants_and_group_restrictions() {
+        let group = Uuid::new_v4();
+        let admin = access(MemberRole::Admin, None);
+        assert!(admin.require_grantable(MemberRole::Admin.permissions()).is_ok());
+        assert!(admin.require_grantable(MemberRole::Owner.permissions()).is_err());
+        assert!(admin.can_access_group(None));
+
+        let restricted = access(MemberRole::Admin, Some(vec![group]));
+        assert!(restricted.require_grantable(MemberRole::Viewer.permissions()).is_err());
+        assert!(restricted.can_access_group(Some(group)));
+        assert!(!restricted.can_access_group(Some(Uuid::new_v4())));
+        assert!(!restricted.can_access_group(None));
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Upper bound on cached entries; expired ones are evicted first when it is reached
+const MAX_ENTRIES: usize = 10_000;
+
+struct Entry {
+    user_id: Uuid,
+    revoked: bool,
+    checked_at: Instant,
+}
+
+/// In-process cache of access token revocation checks, keyed by `jti`
+///
+/// Changes made by this process invalidate the affected entries right away;
+/// changes made elsewhere (other instances, direct SQL) apply once the TTL expires.
+pub struct RevocationCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<Uuid, Entry>>,
+}
+
+impl RevocationCache {
+    /// Create an empty cache
+    #[must_use]
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Cached revocation state of a token, if still fresh
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cache lock is poisoned
+    #[must_use]
+    pub fn get(&self, jti: Uuid) -> Option<bool> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(&jti)
+            .filter(|entry| entry.checked_at.elapsed() < self.ttl)
+            .map(|entry| entry.revoked)
+    }
+
+    /// Record the revocation state of a token
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cache lock is poisoned
+    pub fn insert(&self, jti: Uuid, user_id: Uuid, revoked: bool) {
+        let mut entries = self.entries.lock().unwrap();
+
+        if entries.len() >= MAX_ENTRIES {
+            entries.retain(|_, entry| entry.checked_at.elapsed() < self.ttl);
+            if entries.len() >= MAX_ENTRIES {
+                entries.clear();
+            }
+        }
+
+        entries.insert(jti, Entry { user_id, revoked, checked_at: Instant::now() });
+    }
+
+    /// Forget every cached token of a user, so that the next request re-checks the database
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cache lock is poisoned
+    pub fn invalidate_user(&self, user_id: Uuid) {
+        self.entries.lock().unwrap().retain(|_, entry| entry.user_id != user_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_revocation_cache() {
+        let cache = RevocationCache::new(Duration::from_secs(60));
+        let (jti, user_id) = (Uuid::new_v4(), Uuid::new_v4());
+
+        assert_eq!(cache.get(jti), None);
+        cache.insert(jti, user_id, false);
+        assert_eq!(cache.get(jti), Some(false));
+
+        cache.invalidate_user(Uuid::new_v4());
+        assert_eq!(cache.get(jti), Some(false));
+        cache.invalidate_user(user_id);
+        assert_eq!(cache.get(jti), None);
+
+        let expired = RevocationCache::new(Duration::ZERO);
+        expired.insert(jti, user_id, true);
+        assert_eq!(expired.get(jti), None);
+    }
+}
@@ -0,0 +1,138 @@
+use chrono::Utc;
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use crate::auth::random_token;
+use crate::error::{AppError, AppResult};
+
+/// Issuer shown by authenticator apps
+const TOTP_ISSUER: &str = "WebGuard";
+
+/// RFC 6238 defaults, supported by all common authenticator apps
+const TOTP_DIGITS: usize = 6;
+const TOTP_STEP_SECONDS: u64 = 30;
+
+/// Number of time steps accepted before and after the current one, for clock drift
+const TOTP_SKEW_STEPS: i64 = 1;
+
+/// Number of recovery codes issued at once
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+/// Number of random characters in a recovery code
+const RECOVERY_CODE_LENGTH: usize = 10;
+
+/// Generate a new base32-encoded TOTP secret
+#[must_use]
+pub fn generate_totp_secret() -> String {
+    match Secret::generate_secret().to_encoded() {
+        Secret::Encoded(secret) => secret,
+        Secret::Raw(_) => unreachable!("to_encoded always returns an encoded secret"),
+    }
+}
+
+fn totp(secret: &str, account_name: &str) -> AppResult<TOTP> {
+    let bytes = Secret::Encoded(secret.to_string())
+        .to_bytes()
+        .map_err(|e| AppError::internal(format!("Invalid TOTP secret: {:?}", e)))?;
+
+    Ok(TOTP::new_unchecked(
+        Algorithm::SHA1,
+        TOTP_DIGITS,
+        0,
+        TOTP_STEP_SECONDS,
+        bytes,
+        Some(TOTP_ISSUER.to_string()),
+        account_name.to_string(),
+    ))
+}
+
+/// `otpauth://` URI to show as a QR code during enrollment
+///
+/// # Errors
+///
+/// Returns an error if the secret is not valid base32
+pub fn provisioning_uri(secret: &str, account_name: &str) -> AppResult<String> {
+    Ok(totp(secret, account_name)?.get_url())
+}
+
+/// Check a TOTP code, returning the matched time step
+///
+/// Steps up to `last_used_step` are rejected so that a code cannot be used twice.
+///
+/// # Errors
+///
+/// Returns an error if the secret is not valid base32
+pub fn verify_totp_code(secret: &str, code: &str, last_used_step: Option<i64>) -> AppResult<Option<i64>> {
+    let code = code.trim();
+    if code.len() != TOTP_DIGITS || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return Ok(None);
+    }
+
+    let totp = totp(secret, "")?;
+    let current_step = Utc::now().timestamp() / TOTP_STEP_SECONDS as i64;
+
+    for step in (current_step - TOTP_SKEW_STEPS)..=(current_step + TOTP_SKEW_STEPS) {
+        if last_used_step.is_some_and(|last| step <= last) {
+            continue;
+        }
+        let expected = totp.generate(step as u64 * TOTP_STEP_SECONDS);
+        if constant_time_eq(expected.as_bytes(), code.as_bytes()) {
+            return Ok(Some(step));
+        }
+    }
+
+    Ok(None)
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Generate a set of recovery codes, formatted as `xxxxx-xxxxx`
+#[must_use]
+pub fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let code = random_token(RECOVERY_CODE_LENGTH).to_lowercase();
+            format!("{}-{}", &code[..RECOVERY_CODE_LENGTH / 2], &code[RECOVERY_CODE_LENGTH / 2..])
+        })
+        .collect()
+}
+
+/// Canonical form of a recovery code, as hashed for storage
+#[must_use]
+pub fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_totp_code() {
+        let secret = generate_totp_secret();
+        let step = Utc::now().timestamp() / TOTP_STEP_SECONDS as i64;
+        let code = totp(&secret, "").unwrap().generate(step as u64 * TOTP_STEP_SECONDS);
+
+        assert_eq!(verify_totp_code(&secret, &code, None).unwrap(), Some(step));
+        assert_eq!(verify_totp_code(&secret, &code, Some(step)).unwrap(), None);
+        assert_eq!(verify_totp_code(&secret, "12345", None).unwrap(), None);
+    }
+
+    #[test]
+    fn test_provisioning_uri() {
+        let uri = provisioning_uri(&generate_totp_secret(), "user@example.com").unwrap();
+        assert!(uri.starts_with("otpauth://totp/WebGuard:user%40example.com?secret="));
+        assert!(uri.contains("issuer=WebGuard"));
+    }
+
+    #[test]
+    fn test_recovery_codes() {
+        let codes = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        assert_eq!(normalize_recovery_code(&codes[0].to_uppercase()), codes[0].replace('-', ""));
+    }
+}
@@ -13,6 +13,9 @@ pub struct Config {
     pub http: HttpConfig,
     pub telemetry: TelemetryConfig,
     pub webhook: WebhookConfig,
+    pub retention: RetentionConfig,
+    pub mail: MailConfig,
+    pub rate_limit: RateLimitConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,6 +30,10 @@ pub struct ServerConfig {
     pub host: String,
     pub port: u16,
     pub frontend_dist_path: String,
+    /// Take the client IP from X-Forwarded-For / X-Real-IP (only behind a trusted proxy)
+    pub trust_proxy_headers: bool,
+    /// Base URL of the web app, used for links sent by email
+    pub public_url: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,6 +44,24 @@ pub struct AuthConfig {
     #[serde(with = "duration_serde")]
     pub refresh_token_duration: Duration,
     pub bcrypt_rounds: u32,
+    /// How long a token revocation check is cached, bounding how late
+    /// changes made outside this process take effect (in seconds)
+    #[serde(with = "duration_serde")]
+    pub revocation_cache_ttl: Duration,
+    /// Refuse to log in users whose email address is not verified
+    pub require_email_verification: bool,
+    /// Validity of password reset links (in seconds)
+    #[serde(with = "duration_serde")]
+    pub password_reset_ttl: Duration,
+    /// Validity of email verification links (in seconds)
+    #[serde(with = "duration_serde")]
+    pub email_verification_ttl: Duration,
+    /// Validity of organization invitations (in seconds)
+    #[serde(with = "duration_serde")]
+    pub invitation_ttl: Duration,
+    /// Time a member has to accept an organization ownership transfer (in seconds)
+    #[serde(with = "duration_serde")]
+    pub ownership_transfer_ttl: Duration,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -63,6 +88,9 @@ pub struct MonitoringConfig {
     pub poll_interval: Duration,
     /// Maximum number of concurrent checks
     pub max_concurrent_checks: u32,
+    /// How often to catch up on missing uptime aggregates (in seconds)
+    #[serde(with = "duration_serde")]
+    pub aggregate_interval: Duration,
     /// Threshold for slow response time (in milliseconds)
     pub slow_threshold_ms: u64,
     /// Available frequency presets in seconds
@@ -79,6 +107,29 @@ pub struct HttpConfig {
     pub timeout: Duration,
     pub max_redirects: u32,
     pub user_agent: String,
+    /// Outbound proxy URL (http://, https:// or socks5://)
+    pub proxy: Option<String>,
+    /// Path to a PEM bundle of additional trusted root certificates
+    pub ca_bundle_path: Option<String>,
+    /// IP family used for outbound connections
+    pub ip_preference: IpPreference,
+    /// TCP connect timeout (in seconds)
+    #[serde(with = "duration_serde")]
+    pub connect_timeout: Duration,
+    /// Maximum idle keep-alive connections kept per host
+    pub pool_max_idle_per_host: usize,
+    /// How long an idle connection is kept in the pool (in seconds)
+    #[serde(with = "duration_serde")]
+    pub pool_idle_timeout: Duration,
+}
+
+/// IP family preference for outbound HTTP checks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IpPreference {
+    Any,
+    Ipv4,
+    Ipv6,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -93,6 +144,83 @@ pub struct WebhookConfig {
     #[serde(with = "duration_serde")]
     pub timeout: Duration,
     pub retry_attempts: u32,
+    /// How often new alerts are delivered (in seconds)
+    #[serde(with = "duration_serde")]
+    pub dispatch_interval: Duration,
+}
+
+/// Global data retention defaults (in days); organizations may override them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionConfig {
+    /// How often the pruning job runs (in seconds)
+    #[serde(with = "duration_serde")]
+    pub interval: Duration,
+    /// Maximum number of rows deleted per statement
+    pub batch_size: i64,
+    pub uptime_snapshots_days: i32,
+    pub ssl_cert_snapshots_days: i32,
+    pub domain_dns_snapshots_days: i32,
+    pub alerts_days: i32,
+    /// Hourly aggregates are downsampled into daily/weekly/monthly rows, which are kept
+    pub hourly_aggregates_days: i32,
+    /// Convert uptime_snapshots to monthly range partitions and drop expired ones
+    pub partition_uptime_snapshots: bool,
+    /// Grace period during which a deleted organization can be restored before it is purged
+    pub deleted_organization_grace_days: i32,
+}
+
+/// Outgoing email settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MailConfig {
+    pub transport: MailTransport,
+    /// Sender address, e.g. `WebGuard <noreply@example.com>`
+    pub from: String,
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    /// Upgrade the SMTP connection with STARTTLS (disable only for local relays)
+    pub smtp_starttls: bool,
+    /// File the `file` transport appends emails to, one JSON object per line
+    pub file_path: String,
+}
+
+/// Request rate limits and login lockout
+///
+/// Limits are per client IP, or per user/API key on authenticated routes. A
+/// limit of 0 disables it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    /// Login, second factor, SSO and token refresh attempts per minute
+    pub login_per_minute: u32,
+    /// Registrations per hour
+    pub register_per_hour: u32,
+    /// Password reset and email verification requests per hour
+    pub account_recovery_per_hour: u32,
+    /// Public status page requests per minute
+    pub public_per_minute: u32,
+    /// Manual checks per minute
+    pub trigger_check_per_minute: u32,
+    /// Consecutive failed logins before an account is locked
+    pub lockout_threshold: u32,
+    /// First lockout duration, doubled for every further failure (in seconds)
+    #[serde(with = "duration_serde")]
+    pub lockout_base: Duration,
+    /// Longest lockout duration (in seconds)
+    #[serde(with = "duration_serde")]
+    pub lockout_max: Duration,
+}
+
+/// How outgoing emails are delivered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MailTransport {
+    Smtp,
+    /// Append to a file (for tests and development)
+    File,
+    /// Only log emails
+    Log,
 }
 
 /// Duration serialization helper
@@ -137,14 +265,22 @@ impl Config {
         cfg = cfg
             .set_default("server.host", "0.0.0.0")?
             .set_default("server.port", 9002)?
-            .set_default("server.frontend_dist_path", "../frontend/dist")?;
+            .set_default("server.frontend_dist_path", "../frontend/dist")?
+            .set_default("server.trust_proxy_headers", false)?
+            .set_default("server.public_url", "http://localhost:9002")?;
 
         // Auth
         cfg = cfg
             .set_default("auth.jwt_secret", "change-this-secret-in-production")?
             .set_default("auth.access_token_duration", 900)?  // 15 minutes
             .set_default("auth.refresh_token_duration", 604800)?  // 7 days
-            .set_default("auth.bcrypt_rounds", 12)?;
+            .set_default("auth.bcrypt_rounds", 12)?
+            .set_default("auth.revocation_cache_ttl", 10)?
+            .set_default("auth.require_email_verification", false)?
+            .set_default("auth.password_reset_ttl", 3600)?  // 1 hour
+            .set_default("auth.email_verification_ttl", 172800)?  // 2 days
+            .set_default("auth.invitation_ttl", 604800)?  // 7 days
+            .set_default("auth.ownership_transfer_ttl", 604800)?;  // 7 days
 
         // Scheduler
         cfg = cfg
@@ -162,6 +298,7 @@ impl Config {
         cfg = cfg
             .set_default("monitoring.poll_interval", 60)?  // Check every minute
             .set_default("monitoring.max_concurrent_checks", 50)?
+            .set_default("monitoring.aggregate_interval", 300)?  // 5 minutes
             .set_default("monitoring.slow_threshold_ms", 3000)?  // 3 seconds
             .set_default("monitoring.uptime_frequency_presets", vec![60, 300, 600, 1800])?  // 1min, 5min, 10min, 30min
             .set_default("monitoring.dns_frequency_presets", vec![60, 360, 720, 1440])?  // 1h, 6h, 12h, 24h (in minutes)
@@ -172,7 +309,11 @@ impl Config {
         cfg = cfg
             .set_default("http.timeout", 10)?
             .set_default("http.max_redirects", 3)?
-            .set_default("http.user_agent", "WebGuard/1.0 (+https://webguard.io)")?;
+            .set_default("http.user_agent", "WebGuard/1.0 (+https://webguard.io)")?
+            .set_default("http.ip_preference", "any")?
+            .set_default("http.connect_timeout", 5)?
+            .set_default("http.pool_max_idle_per_host", 32)?
+            .set_default("http.pool_idle_timeout", 90)?;
 
         // Telemetry
         cfg = cfg
@@ -183,7 +324,41 @@ impl Config {
         // Webhook
         cfg = cfg
             .set_default("webhook.timeout", 5)?
-            .set_default("webhook.retry_attempts", 3)?;
+            .set_default("webhook.retry_attempts", 3)?
+            .set_default("webhook.dispatch_interval", 30)?;
+
+        // Retention
+        cfg = cfg
+            .set_default("retention.interval", 3600)?  // 1 hour
+            .set_default("retention.batch_size", 5000)?
+            .set_default("retention.uptime_snapshots_days", 30)?
+            .set_default("retention.ssl_cert_snapshots_days", 90)?
+            .set_default("retention.domain_dns_snapshots_days", 90)?
+            .set_default("retention.alerts_days", 180)?
+            .set_default("retention.hourly_aggregates_days", 90)?
+            .set_default("retention.partition_uptime_snapshots", false)?
+            .set_default("retention.deleted_organization_grace_days", 30)?;
+
+        // Mail
+        cfg = cfg
+            .set_default("mail.transport", "log")?
+            .set_default("mail.from", "WebGuard <noreply@localhost>")?
+            .set_default("mail.smtp_host", "localhost")?
+            .set_default("mail.smtp_port", 587)?
+            .set_default("mail.smtp_starttls", true)?
+            .set_default("mail.file_path", "mail.jsonl")?;
+
+        // Rate limiting
+        cfg = cfg
+            .set_default("rate_limit.enabled", true)?
+            .set_default("rate_limit.login_per_minute", 10)?
+            .set_default("rate_limit.register_per_hour", 10)?
+            .set_default("rate_limit.account_recovery_per_hour", 10)?
+            .set_default("rate_limit.public_per_minute", 120)?
+            .set_default("rate_limit.trigger_check_per_minute", 10)?
+            .set_default("rate_limit.lockout_threshold", 5)?
+            .set_default("rate_limit.lockout_base", 60)?  // 1 minute
+            .set_default("rate_limit.lockout_max", 3600)?;  // 1 hour
 
         // Override with environment variables
         cfg = cfg.add_source(
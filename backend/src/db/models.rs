@@ -1,7 +1,8 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 
 // ============================================================================
 // User & Organization Models
@@ -12,13 +13,31 @@ use utoipa::ToSchema;
 pub struct User {
     pub id: Uuid,
     pub email: String,
+    /// Unset for users provisioned through single sign-on
     #[serde(skip_serializing)]
-    pub password_hash: String,
+    pub password_hash: Option<String>,
     pub full_name: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub last_login_at: Option<DateTime<Utc>>,
     pub is_active: bool,
+    /// Bumped to invalidate all access tokens of the user
+    #[serde(skip_serializing)]
+    pub token_version: i32,
+    pub email_verified_at: Option<DateTime<Utc>>,
+    /// Base32 TOTP secret, set once enrollment starts
+    #[serde(skip_serializing)]
+    pub totp_secret: Option<String>,
+    /// Set once enrollment is confirmed; two-factor login is required from then on
+    pub totp_enabled_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing)]
+    pub totp_last_used_step: Option<i64>,
+    /// Consecutive failed login attempts
+    #[serde(skip_serializing)]
+    pub failed_login_count: i32,
+    /// Logins are refused until then
+    #[serde(skip_serializing)]
+    pub locked_until: Option<DateTime<Utc>>,
 }
 
 /// Organization (team)
@@ -33,6 +52,59 @@ pub struct Organization {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub max_monitors: i32,
+    /// Shortest interval a monitor may be checked at
+    pub min_check_interval_seconds: i32,
+    /// Longest retention period the organization may configure
+    pub max_retention_days: i32,
+    /// Maximum number of usable API keys
+    pub max_api_keys: i32,
+    /// IANA timezone used for daily/weekly/monthly aggregate boundaries
+    pub timezone: String,
+    /// Members must have two-factor authentication enabled to access the organization
+    pub require_two_factor: bool,
+    /// Set while the organization is scheduled for deletion
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// When a deleted organization is purged; it can be restored until then
+    pub purge_after: Option<DateTime<Utc>>,
+}
+
+/// Role of a user in an organization, with the organization's 2FA policy applied
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct MemberAccess {
+    pub role: MemberRole,
+    /// Permissions of the member's custom role, if one is assigned
+    pub custom_permissions: Option<Vec<String>>,
+    /// Domain groups the member is restricted to (`None` for all domains)
+    pub domain_group_ids: Option<Vec<Uuid>>,
+    /// The organization requires 2FA and the user has not enabled it
+    pub two_factor_missing: bool,
+    /// The organization is scheduled for deletion
+    pub organization_deleted: bool,
+    /// The organization enforces signing in through its identity provider
+    pub sso_enforced: bool,
+    /// The caller's session was signed in by the organization's identity provider
+    pub sso_session: bool,
+}
+
+impl MemberAccess {
+    /// Whether the caller must sign in through the organization's identity
+    /// provider to access it
+    ///
+    /// Owners are exempt, so that an organization cannot be locked out by a
+    /// broken provider.
+    #[must_use]
+    pub fn requires_sso(&self) -> bool {
+        self.sso_enforced && !self.sso_session && self.role != MemberRole::Owner
+    }
+
+    /// Effective permissions: the custom role's if assigned, else the built-in role's
+    #[must_use]
+    pub fn permissions(&self) -> Vec<Permission> {
+        match &self.custom_permissions {
+            Some(names) => Permission::parse_all(names),
+            None => self.role.permissions().to_vec(),
+        }
+    }
 }
 
 /// Organization member with role
@@ -42,9 +114,60 @@ pub struct OrganizationMember {
     pub organization_id: Uuid,
     pub user_id: Uuid,
     pub role: MemberRole,
+    /// Custom role replacing the built-in role's permissions
+    pub custom_role_id: Option<Uuid>,
+    /// Domain groups the member is restricted to (null for all domains)
+    pub domain_group_ids: Option<Vec<Uuid>>,
     pub created_at: DateTime<Utc>,
 }
 
+/// Organization member with the user's email and name
+#[derive(Debug, Clone, Serialize, sqlx::FromRow, ToSchema)]
+pub struct MemberWithUser {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub member: OrganizationMember,
+    pub email: String,
+    pub full_name: Option<String>,
+}
+
+/// Filters and order of member listings
+#[derive(Debug, Clone, Default, Deserialize, IntoParams, ToSchema)]
+#[into_params(parameter_in = Query)]
+pub struct MemberFilter {
+    pub role: Option<MemberRole>,
+    /// Part of the email or name of the member
+    pub q: Option<String>,
+    /// `created_at` (default), `email` or `full_name`; prefix with `-` for descending
+    pub sort: Option<MemberSort>,
+}
+
+/// Order of member listings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+pub enum MemberSort {
+    #[default]
+    #[serde(rename = "created_at")]
+    CreatedAt,
+    #[serde(rename = "-created_at")]
+    CreatedAtDesc,
+    #[serde(rename = "email")]
+    Email,
+    #[serde(rename = "-email")]
+    EmailDesc,
+}
+
+impl MemberSort {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::CreatedAt => "created_at",
+            Self::CreatedAtDesc => "-created_at",
+            Self::Email => "email",
+            Self::EmailDesc => "-email",
+        }
+    }
+}
+
 /// Member role in an organization
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "varchar", rename_all = "lowercase")]
@@ -67,6 +190,24 @@ impl MemberRole {
     pub const fn can_write(&self) -> bool {
         matches!(self, Self::Owner | Self::Admin | Self::Member)
     }
+
+    /// Permissions granted by this built-in role
+    #[must_use]
+    pub const fn permissions(&self) -> &'static [Permission] {
+        use Permission::*;
+
+        match self {
+            Self::Owner => Permission::ALL,
+            Self::Admin => &[
+                DomainsRead, DomainsCreate, DomainsEdit, DomainsDelete, MonitorsEdit, MonitorsCheck,
+                AlertsRead, AlertsAck, MembersManage, OrganizationManage, ApiKeysManage, AuditRead,
+            ],
+            Self::Member => &[
+                DomainsRead, DomainsCreate, DomainsEdit, MonitorsEdit, MonitorsCheck, AlertsRead, AlertsAck,
+            ],
+            Self::Viewer => &[DomainsRead, MonitorsCheck, AlertsRead],
+        }
+    }
 }
 
 impl std::fmt::Display for MemberRole {
@@ -94,6 +235,170 @@ impl std::str::FromStr for MemberRole {
     }
 }
 
+/// Action a member may be allowed to perform in an organization
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+pub enum Permission {
+    /// View domains, monitoring data, SLOs and statistics
+    #[serde(rename = "domains.read")]
+    DomainsRead,
+    #[serde(rename = "domains.create")]
+    DomainsCreate,
+    #[serde(rename = "domains.edit")]
+    DomainsEdit,
+    #[serde(rename = "domains.delete")]
+    DomainsDelete,
+    /// Configure monitors and SLOs
+    #[serde(rename = "monitors.edit")]
+    MonitorsEdit,
+    /// Trigger manual checks
+    #[serde(rename = "monitors.check")]
+    MonitorsCheck,
+    #[serde(rename = "alerts.read")]
+    AlertsRead,
+    /// Acknowledge alerts
+    #[serde(rename = "alerts.ack")]
+    AlertsAck,
+    /// Add, remove and invite members, change their roles and manage custom roles
+    #[serde(rename = "members.manage")]
+    MembersManage,
+    /// Change organization settings, SSO, retention and domain groups
+    #[serde(rename = "organization.manage")]
+    OrganizationManage,
+    #[serde(rename = "api_keys.manage")]
+    ApiKeysManage,
+    /// View and export the audit log
+    #[serde(rename = "audit.read")]
+    AuditRead,
+    /// Manage the organization's plan and billing
+    #[serde(rename = "billing")]
+    Billing,
+}
+
+impl Permission {
+    /// Every permission, as granted to owners
+    pub const ALL: &'static [Self] = &[
+        Self::DomainsRead,
+        Self::DomainsCreate,
+        Self::DomainsEdit,
+        Self::DomainsDelete,
+        Self::MonitorsEdit,
+        Self::MonitorsCheck,
+        Self::AlertsRead,
+        Self::AlertsAck,
+        Self::MembersManage,
+        Self::OrganizationManage,
+        Self::ApiKeysManage,
+        Self::AuditRead,
+        Self::Billing,
+    ];
+
+    /// Name of the permission as stored and serialized
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::DomainsRead => "domains.read",
+            Self::DomainsCreate => "domains.create",
+            Self::DomainsEdit => "domains.edit",
+            Self::DomainsDelete => "domains.delete",
+            Self::MonitorsEdit => "monitors.edit",
+            Self::MonitorsCheck => "monitors.check",
+            Self::AlertsRead => "alerts.read",
+            Self::AlertsAck => "alerts.ack",
+            Self::MembersManage => "members.manage",
+            Self::OrganizationManage => "organization.manage",
+            Self::ApiKeysManage => "api_keys.manage",
+            Self::AuditRead => "audit.read",
+            Self::Billing => "billing",
+        }
+    }
+
+    /// Parse stored permission names, ignoring unknown values
+    #[must_use]
+    pub fn parse_all(names: &[String]) -> Vec<Self> {
+        names.iter().filter_map(|n| n.parse().ok()).collect()
+    }
+
+    /// Sorted, de-duplicated permission names as stored in the database
+    #[must_use]
+    pub fn names(permissions: &[Self]) -> Vec<String> {
+        let mut permissions = permissions.to_vec();
+        permissions.sort();
+        permissions.dedup();
+        permissions.into_iter().map(|p| p.as_str().to_string()).collect()
+    }
+}
+
+impl std::fmt::Display for Permission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Permission {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|p| p.as_str() == s)
+            .ok_or_else(|| format!("Invalid permission: {}", s))
+    }
+}
+
+/// Custom role defined by an organization as a named set of permissions
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct OrganizationRole {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub permissions: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl OrganizationRole {
+    /// Parsed permissions, ignoring unknown values
+    #[must_use]
+    pub fn permission_set(&self) -> Vec<Permission> {
+        Permission::parse_all(&self.permissions)
+    }
+}
+
+/// Invitation to join an organization, sent by email
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct Invitation {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    pub email: String,
+    /// Role given to the invitee once they accept
+    pub role: MemberRole,
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    pub invited_by: Option<Uuid>,
+    pub expires_at: DateTime<Utc>,
+    /// When the invitation email was last sent
+    pub sent_at: DateTime<Utc>,
+    pub accepted_at: Option<DateTime<Utc>>,
+    pub accepted_by: Option<Uuid>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Pending transfer of an organization's ownership to another member
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct OwnershipTransfer {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    /// Current owner, who becomes an admin once the transfer is accepted
+    pub from_user_id: Uuid,
+    /// Member who becomes the owner
+    pub to_user_id: Uuid,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
 // ============================================================================
 // Domain & Monitor Models
 // ============================================================================
@@ -105,13 +410,127 @@ pub struct Domain {
     pub organization_id: Uuid,
     pub name: String,
     pub normalized_name: String,
+    /// User-friendly name
+    pub display_name: String,
+    /// Address being monitored, such as `https://example.com`
+    pub url: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub is_active: bool,
+    pub group_id: Option<Uuid>,
+    #[sqlx(json)]
+    pub tags: Tags,
+}
+
+/// Key/value labels of a domain
+pub type Tags = BTreeMap<String, String>;
+
+/// Named group of domains, used to organize domains and to restrict members to
+/// part of an organization
+///
+/// Groups can be nested; access to a group includes its subgroups.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct DomainGroup {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub parent_id: Option<Uuid>,
+}
+
+/// Filters for domain listings and statistics
+#[derive(Debug, Clone, Default, Deserialize, IntoParams, ToSchema)]
+#[into_params(parameter_in = Query)]
+pub struct DomainFilter {
+    /// Only domains in this group or its subgroups
+    pub group_id: Option<Uuid>,
+    /// Comma-separated tags the domains must have, as `key` or `key=value`
+    pub tag: Option<String>,
+}
+
+/// Filters and order of the domain list
+#[derive(Debug, Clone, Default, Deserialize, IntoParams, ToSchema)]
+#[into_params(parameter_in = Query)]
+pub struct DomainListFilter {
+    /// Only domains in this group or its subgroups
+    pub group_id: Option<Uuid>,
+    /// Comma-separated tags the domains must have, as `key` or `key=value`
+    pub tag: Option<String>,
+    /// Result of the latest uptime check
+    pub status: Option<UptimeStatus>,
+    /// Only domains whose certificate is (`true`) or is not (`false`) expiring or expired
+    pub ssl_expiring: Option<bool>,
+    /// Fuzzy search on display name and URL
+    pub q: Option<String>,
+    /// `created_at`, `name`, `url` or `relevance`; prefix with `-` for descending.
+    /// Defaults to `relevance` when searching and `-created_at` otherwise
+    pub sort: Option<DomainSort>,
+}
+
+impl DomainListFilter {
+    /// Requested order, or the default one
+    #[must_use]
+    pub fn sort(&self) -> DomainSort {
+        match (self.sort, &self.q) {
+            (Some(sort), _) => sort,
+            (None, Some(_)) => DomainSort::Relevance,
+            (None, None) => DomainSort::CreatedAtDesc,
+        }
+    }
+}
+
+/// Result of an uptime check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UptimeStatus {
+    Up,
+    Down,
+}
+
+impl UptimeStatus {
+    #[must_use]
+    pub const fn is_up(self) -> bool {
+        matches!(self, Self::Up)
+    }
+}
+
+/// Order of the domain list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+pub enum DomainSort {
+    #[serde(rename = "created_at")]
+    CreatedAt,
+    #[serde(rename = "-created_at")]
+    CreatedAtDesc,
+    #[serde(rename = "name")]
+    Name,
+    #[serde(rename = "-name")]
+    NameDesc,
+    #[serde(rename = "url")]
+    Url,
+    #[serde(rename = "-url")]
+    UrlDesc,
+    /// Best matches of the search first
+    #[serde(rename = "relevance")]
+    Relevance,
+}
+
+impl DomainSort {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::CreatedAt => "created_at",
+            Self::CreatedAtDesc => "-created_at",
+            Self::Name => "name",
+            Self::NameDesc => "-name",
+            Self::Url => "url",
+            Self::UrlDesc => "-url",
+            Self::Relevance => "relevance",
+        }
+    }
 }
 
 /// Monitor configuration for a domain
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
 pub struct Monitor {
     pub id: Uuid,
     pub domain_id: Uuid,
@@ -119,13 +538,27 @@ pub struct Monitor {
     #[sqlx(rename = "type")]
     pub monitor_type: MonitorType,
     pub is_enabled: bool,
+    /// Settings of the monitor type (see [`crate::monitors::monitor_config`])
     pub config: serde_json::Value,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// The monitor is not checked until then
+    pub paused_until: Option<DateTime<Utc>>,
+}
+
+/// Active monitor with what the scheduler needs to run it
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ScheduledMonitor {
+    #[sqlx(flatten)]
+    pub monitor: Monitor,
+    /// Address of the domain being checked
+    pub url: String,
+    /// Shortest check interval allowed for the domain's organization
+    pub min_check_interval_seconds: i32,
 }
 
 /// Type of monitor
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "varchar", rename_all = "snake_case")]
 pub enum MonitorType {
     DomainDns,
@@ -159,6 +592,247 @@ impl std::str::FromStr for MonitorType {
     }
 }
 
+// ============================================================================
+// Inventory Models
+// ============================================================================
+
+/// Domain to create along with its monitors
+#[derive(Debug, Clone)]
+pub struct NewDomain {
+    pub display_name: String,
+    /// Normalized URL (see [`crate::inventory::normalize_url`])
+    pub url: String,
+    pub group_id: Option<Uuid>,
+    pub tags: Tags,
+    pub monitors: Vec<NewMonitor>,
+}
+
+/// Monitor to create for a new domain
+#[derive(Debug, Clone)]
+pub struct NewMonitor {
+    pub monitor_type: MonitorType,
+    pub is_enabled: bool,
+    pub config: serde_json::Value,
+}
+
+/// Domain entry of an import or export file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct DomainSpec {
+    pub display_name: String,
+    pub url: String,
+    #[serde(default)]
+    pub tags: Tags,
+    /// Monitors by type (`ssl_cert`, `uptime`, `domain_dns`, `security_headers`);
+    /// when missing the domain gets SSL and uptime monitors with default settings
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monitors: Option<BTreeMap<String, MonitorSpec>>,
+}
+
+/// Monitor entry of an import or export file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct MonitorSpec {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "empty_object")]
+    pub config: serde_json::Value,
+}
+
+fn empty_object() -> serde_json::Value {
+    serde_json::Value::Object(serde_json::Map::new())
+}
+
+/// Outcome of one entry of an import
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportRowStatus {
+    /// The domain is (or would be) created
+    Create,
+    /// A domain with this URL already exists and `skip_existing` is set
+    Skip,
+    /// The entry is invalid; nothing is imported
+    Error,
+}
+
+/// Result of one entry of an import
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ImportRow {
+    /// Entry number: line number for CSV files, 1-based index for JSON
+    pub row: usize,
+    pub display_name: Option<String>,
+    /// Normalized URL
+    pub url: Option<String>,
+    pub status: ImportRowStatus,
+    pub errors: Vec<String>,
+    /// Domain created for this entry
+    pub domain_id: Option<Uuid>,
+}
+
+/// Result of an import, per entry
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ImportReport {
+    /// Whether the domains were created; false for dry runs and failed imports
+    pub applied: bool,
+    pub created: usize,
+    pub skipped: usize,
+    pub errors: usize,
+    /// Monitors created, or that would be created
+    pub monitors: usize,
+    pub rows: Vec<ImportRow>,
+}
+
+// ============================================================================
+// Manifest Models
+// ============================================================================
+
+/// Desired configuration of an organization, kept in version control
+///
+/// Each section is optional; sections that are left out are not managed and
+/// stay as they are.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    /// Domain groups, identified by name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub groups: Option<Vec<ManifestGroup>>,
+    /// Domains, identified by URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domains: Option<Vec<ManifestDomain>>,
+    /// Organization webhook and alert routes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alerting: Option<ManifestAlerting>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestGroup {
+    pub name: String,
+    /// Name of the parent group
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ManifestDomain {
+    #[serde(flatten)]
+    pub spec: DomainSpec,
+    /// Name of the domain's group
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    #[serde(default = "default_true")]
+    pub active: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestAlerting {
+    /// Webhook receiving alerts no route matches
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    /// Alert routes, identified by name
+    #[serde(default)]
+    pub routes: Vec<ManifestAlertRoute>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestAlertRoute {
+    pub name: String,
+    /// Name of the group whose domains (subgroups included) the route applies to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    #[serde(default)]
+    pub tags: Tags,
+    #[serde(default = "default_min_severity")]
+    pub min_severity: AlertSeverity,
+    pub webhook_url: String,
+}
+
+/// Kind of resource managed by a manifest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ManifestResource {
+    Group,
+    Domain,
+    AlertRoute,
+    Webhook,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ManifestAction {
+    Create,
+    Update,
+    Delete,
+    /// Exists but is not in the manifest; deleted only with `prune`
+    Unmanaged,
+}
+
+/// Difference between a manifest and the organization
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ManifestChange {
+    pub resource: ManifestResource,
+    /// Name, or URL for domains
+    pub name: String,
+    pub action: ManifestAction,
+    /// Fields that change, for updates
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<String>,
+}
+
+/// Changes needed to bring an organization in line with a manifest
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ManifestPlan {
+    /// Whether the changes were made; false in plan mode
+    pub applied: bool,
+    pub changes: Vec<ManifestChange>,
+}
+
+/// Domain to create or update from a manifest
+#[derive(Debug, Clone)]
+pub struct ManifestDomainOp {
+    /// Domain to update, `None` to create one
+    pub id: Option<Uuid>,
+    /// Desired domain; its `group_id` is resolved from `group` when applying
+    pub domain: NewDomain,
+    pub group: Option<String>,
+    pub is_active: bool,
+    /// Replace the domain's monitors with `domain.monitors`
+    pub replace_monitors: bool,
+}
+
+/// Operations applying a manifest, in the order they are applied
+#[derive(Debug, Clone, Default)]
+pub struct ManifestOps {
+    /// Groups to create, by name
+    pub create_groups: Vec<String>,
+    /// New parent of groups, by name
+    pub group_parents: Vec<(String, Option<String>)>,
+    pub delete_domains: Vec<Uuid>,
+    pub upsert_domains: Vec<ManifestDomainOp>,
+    pub delete_routes: Vec<Uuid>,
+    /// Routes to create (`None`) or replace; group names are resolved when applying
+    pub upsert_routes: Vec<(Option<Uuid>, ManifestAlertRoute)>,
+    pub delete_groups: Vec<Uuid>,
+    /// New organization webhook (`Some(None)` removes it)
+    pub webhook_url: Option<Option<String>>,
+    /// Change in the number of monitors of the organization
+    pub monitor_delta: i64,
+}
+
+impl ManifestOps {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.create_groups.is_empty()
+            && self.group_parents.is_empty()
+            && self.delete_domains.is_empty()
+            && self.upsert_domains.is_empty()
+            && self.delete_routes.is_empty()
+            && self.upsert_routes.is_empty()
+            && self.delete_groups.is_empty()
+            && self.webhook_url.is_none()
+    }
+}
+
 // ============================================================================
 // Task Models
 // ============================================================================
@@ -277,7 +951,7 @@ pub struct UptimeAggregate {
 }
 
 /// Time period for aggregates
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "varchar", rename_all = "lowercase")]
 pub enum AggregatePeriod {
     Hour,
@@ -297,6 +971,28 @@ impl std::fmt::Display for AggregatePeriod {
     }
 }
 
+impl std::str::FromStr for AggregatePeriod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hour" => Ok(Self::Hour),
+            "day" => Ok(Self::Day),
+            "week" => Ok(Self::Week),
+            "month" => Ok(Self::Month),
+            _ => Err(format!("Invalid aggregate period: {}", s)),
+        }
+    }
+}
+
+/// Domain to compute aggregates for, with its organization's timezone
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct AggregationTarget {
+    pub domain_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub timezone: String,
+}
+
 /// Security header monitoring snapshot
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct SecurityHeaderSnapshot {
@@ -314,51 +1010,603 @@ pub struct SecurityHeaderSnapshot {
 }
 
 // ============================================================================
-// Alert Models
+// Retention Models
 // ============================================================================
 
-/// Alert event
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
-pub struct Alert {
-    pub id: Uuid,
-    pub organization_id: Uuid,
-    pub domain_id: Uuid,
-    #[serde(rename = "type")]
-    pub alert_type: String,
-    pub severity: AlertSeverity,
-    pub title: String,
-    pub description: Option<String>,
-    pub metadata: serde_json::Value,
-    pub webhook_sent_at: Option<DateTime<Utc>>,
-    pub webhook_status_code: Option<i32>,
-    pub webhook_success: Option<bool>,
-    pub created_at: DateTime<Utc>,
+/// Per-organization retention overrides (in days, `None` uses the global default)
+#[derive(Debug, Clone, Default, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct RetentionPolicy {
+    pub uptime_snapshots_days: Option<i32>,
+    pub ssl_cert_snapshots_days: Option<i32>,
+    pub domain_dns_snapshots_days: Option<i32>,
+    pub alerts_days: Option<i32>,
+    pub hourly_aggregates_days: Option<i32>,
 }
 
-/// Alert severity level
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, ToSchema)]
-#[sqlx(type_name = "varchar", rename_all = "lowercase")]
-pub enum AlertSeverity {
-    Info,
-    Warning,
-    Critical,
+/// Data subject to retention pruning
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetentionTarget {
+    UptimeSnapshots,
+    SslCertSnapshots,
+    DomainDnsSnapshots,
+    Alerts,
+    HourlyAggregates,
 }
 
-impl std::fmt::Display for AlertSeverity {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl RetentionTarget {
+    pub const ALL: [Self; 5] = [
+        Self::UptimeSnapshots,
+        Self::SslCertSnapshots,
+        Self::DomainDnsSnapshots,
+        Self::Alerts,
+        Self::HourlyAggregates,
+    ];
+
+    /// Table holding the rows
+    #[must_use]
+    pub const fn table(self) -> &'static str {
         match self {
-            Self::Info => write!(f, "info"),
-            Self::Warning => write!(f, "warning"),
-            Self::Critical => write!(f, "critical"),
+            Self::UptimeSnapshots => "uptime_snapshots",
+            Self::SslCertSnapshots => "ssl_cert_snapshots",
+            Self::DomainDnsSnapshots => "domain_dns_snapshots",
+            Self::Alerts => "alerts",
+            Self::HourlyAggregates => "uptime_aggregates",
+        }
+    }
+
+    /// Timestamp column compared against the retention cutoff
+    #[must_use]
+    pub const fn time_column(self) -> &'static str {
+        match self {
+            Self::UptimeSnapshots | Self::SslCertSnapshots | Self::DomainDnsSnapshots => "check_time",
+            Self::Alerts => "created_at",
+            Self::HourlyAggregates => "period_start",
         }
     }
+
+    /// Column of `retention_policies` holding the per-organization override
+    #[must_use]
+    pub const fn policy_column(self) -> &'static str {
+        match self {
+            Self::UptimeSnapshots => "uptime_snapshots_days",
+            Self::SslCertSnapshots => "ssl_cert_snapshots_days",
+            Self::DomainDnsSnapshots => "domain_dns_snapshots_days",
+            Self::Alerts => "alerts_days",
+            Self::HourlyAggregates => "hourly_aggregates_days",
+        }
+    }
+
+    /// Aggregate period that must be computed before rows can be pruned
+    #[must_use]
+    pub const fn required_aggregate(self) -> Option<AggregatePeriod> {
+        match self {
+            Self::UptimeSnapshots => Some(AggregatePeriod::Hour),
+            Self::HourlyAggregates => Some(AggregatePeriod::Day),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for RetentionTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.table())
+    }
 }
 
 // ============================================================================
-// Authentication Models
+// Quota Models
 // ============================================================================
 
-/// Refresh token for JWT
+/// Current use of a counted quota
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+pub struct QuotaUsage {
+    pub used: i64,
+    pub limit: i64,
+}
+
+impl QuotaUsage {
+    /// Whether `additional` more items still fit in the quota
+    #[must_use]
+    pub const fn allows(&self, additional: i64) -> bool {
+        self.used + additional <= self.limit
+    }
+}
+
+/// Usage of an organization against its limits
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OrganizationUsage {
+    pub monitors: QuotaUsage,
+    /// Number of monitors per monitor type
+    pub monitors_by_type: std::collections::BTreeMap<String, i64>,
+    pub api_keys: QuotaUsage,
+    /// Shortest interval a monitor may be checked at
+    pub min_check_interval_seconds: i32,
+    /// Longest retention period the organization may configure
+    pub max_retention_days: i32,
+}
+
+// ============================================================================
+// SLO Models
+// ============================================================================
+
+/// Service level objective for a domain
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct Slo {
+    pub id: Uuid,
+    pub domain_id: Uuid,
+    pub name: String,
+    /// Target percentage of good checks (e.g. 99.9)
+    pub availability_target: f64,
+    /// Hours whose latency percentile exceeds this threshold count as bad
+    pub latency_threshold_ms: Option<i32>,
+    pub latency_percentile: LatencyPercentile,
+    pub window_days: i32,
+    pub fast_burn_threshold: f64,
+    pub slow_burn_threshold: f64,
+    pub is_enabled: bool,
+    pub burn_state: BurnState,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Response time percentile used by latency objectives
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum LatencyPercentile {
+    #[default]
+    P95,
+    P99,
+}
+
+impl std::fmt::Display for LatencyPercentile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::P95 => write!(f, "p95"),
+            Self::P99 => write!(f, "p99"),
+        }
+    }
+}
+
+/// Error budget burn state of an SLO
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum BurnState {
+    Ok,
+    SlowBurn,
+    FastBurn,
+}
+
+impl std::fmt::Display for BurnState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ok => write!(f, "ok"),
+            Self::SlowBurn => write!(f, "slow_burn"),
+            Self::FastBurn => write!(f, "fast_burn"),
+        }
+    }
+}
+
+/// Evaluated state of an SLO at the end of an hourly aggregate
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct SloSnapshot {
+    pub as_of: DateTime<Utc>,
+    pub total_checks: i64,
+    pub good_checks: i64,
+    pub sli_percentage: Option<f64>,
+    /// Percentage of the error budget left (negative when exhausted)
+    pub error_budget_remaining: f64,
+    pub burn_rate_1h: f64,
+    pub burn_rate_6h: f64,
+    pub burn_state: BurnState,
+}
+
+/// Good/total check counts over an SLO's window and its burn rate windows
+#[derive(Debug, Clone, Default, sqlx::FromRow)]
+pub struct SloCounts {
+    /// End of the latest hourly aggregate, `None` when there is no data yet
+    pub as_of: Option<DateTime<Utc>>,
+    pub total_checks: i64,
+    pub good_checks: i64,
+    pub total_checks_1h: i64,
+    pub good_checks_1h: i64,
+    pub total_checks_6h: i64,
+    pub good_checks_6h: i64,
+}
+
+/// Create or update an SLO
+#[derive(Debug, Clone, Deserialize, validator::Validate, ToSchema)]
+pub struct SloInput {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    #[validate(range(min = 0.001, max = 99.999))]
+    pub availability_target: f64,
+    #[validate(range(min = 1))]
+    pub latency_threshold_ms: Option<i32>,
+    #[serde(default)]
+    pub latency_percentile: LatencyPercentile,
+    #[serde(default = "default_slo_window_days")]
+    #[validate(range(min = 1, max = 365))]
+    pub window_days: i32,
+    #[serde(default = "default_fast_burn_threshold")]
+    #[validate(range(min = 0.1))]
+    pub fast_burn_threshold: f64,
+    #[serde(default = "default_slow_burn_threshold")]
+    #[validate(range(min = 0.1))]
+    pub slow_burn_threshold: f64,
+    #[serde(default = "default_true")]
+    pub is_enabled: bool,
+}
+
+fn default_slo_window_days() -> i32 {
+    30
+}
+
+/// Budget consumed 2% in one hour of a 30 day window
+fn default_fast_burn_threshold() -> f64 {
+    14.4
+}
+
+/// Budget consumed 5% in six hours of a 30 day window
+fn default_slow_burn_threshold() -> f64 {
+    6.0
+}
+
+fn default_true() -> bool {
+    true
+}
+
+// ============================================================================
+// Alert Models
+// ============================================================================
+
+/// Alert event
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct Alert {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    pub domain_id: Uuid,
+    #[serde(rename = "type")]
+    pub alert_type: String,
+    pub severity: AlertSeverity,
+    pub title: String,
+    pub description: Option<String>,
+    pub metadata: serde_json::Value,
+    pub webhook_sent_at: Option<DateTime<Utc>>,
+    pub webhook_status_code: Option<i32>,
+    pub webhook_success: Option<bool>,
+    pub created_at: DateTime<Utc>,
+    pub acknowledged_at: Option<DateTime<Utc>>,
+    pub acknowledged_by: Option<Uuid>,
+}
+
+/// Time range, bucketing and filters of the uptime history
+#[derive(Debug, Clone, Deserialize, IntoParams, ToSchema)]
+#[into_params(parameter_in = Query)]
+pub struct UptimeHistoryFilter {
+    /// Hours of history, ignored when `from` is given
+    #[serde(default = "default_history_hours")]
+    pub hours: i64,
+    /// Length of a bucket
+    #[serde(default = "default_history_interval_minutes")]
+    pub interval_minutes: i64,
+    /// Checks at or after this time
+    pub from: Option<DateTime<Utc>>,
+    /// Checks before this time
+    pub to: Option<DateTime<Utc>>,
+    /// Only buckets where every check was up, or where at least one was down
+    pub status: Option<UptimeStatus>,
+    /// `-check_time` (default) or `check_time`
+    pub sort: Option<TimeSort>,
+}
+
+fn default_history_hours() -> i64 {
+    24
+}
+
+fn default_history_interval_minutes() -> i64 {
+    30
+}
+
+/// Filters and order of alert listings
+#[derive(Debug, Clone, Default, Deserialize, IntoParams, ToSchema)]
+#[into_params(parameter_in = Query)]
+pub struct AlertFilter {
+    pub severity: Option<AlertSeverity>,
+    pub domain_id: Option<Uuid>,
+    /// Only acknowledged (`true`) or unacknowledged (`false`) alerts
+    pub acknowledged: Option<bool>,
+    /// Alerts at or after this time
+    pub from: Option<DateTime<Utc>>,
+    /// Alerts before this time
+    pub to: Option<DateTime<Utc>>,
+    /// `-created_at` (default) or `created_at`
+    pub sort: Option<TimeSort>,
+}
+
+/// Chronological order of a listing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+pub enum TimeSort {
+    /// Oldest first
+    #[serde(rename = "created_at", alias = "check_time")]
+    Oldest,
+    /// Newest first
+    #[default]
+    #[serde(rename = "-created_at", alias = "-check_time")]
+    Newest,
+}
+
+impl TimeSort {
+    #[must_use]
+    pub const fn is_newest_first(self) -> bool {
+        matches!(self, Self::Newest)
+    }
+}
+
+/// Alert not delivered yet, with the webhooks it should be posted to
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct PendingAlertDelivery {
+    #[sqlx(flatten)]
+    pub alert: Alert,
+    pub destinations: Vec<String>,
+}
+
+/// Rule sending the alerts of matching domains to a webhook
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct AlertRoute {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    pub name: String,
+    /// Only domains in this group or its subgroups
+    pub group_id: Option<Uuid>,
+    /// Only domains having all of these tags
+    #[sqlx(json)]
+    pub tags: Tags,
+    pub min_severity: AlertSeverity,
+    pub webhook_url: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Create or replace an alert route
+#[derive(Debug, Clone, Deserialize, validator::Validate, ToSchema)]
+pub struct UpsertAlertRoute {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    pub group_id: Option<Uuid>,
+    #[serde(default)]
+    pub tags: Tags,
+    #[serde(default = "default_min_severity")]
+    pub min_severity: AlertSeverity,
+    #[validate(url, length(max = 2048))]
+    pub webhook_url: String,
+}
+
+fn default_min_severity() -> AlertSeverity {
+    AlertSeverity::Info
+}
+
+/// Alert severity level
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+pub enum AlertSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl std::fmt::Display for AlertSeverity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Info => write!(f, "info"),
+            Self::Warning => write!(f, "warning"),
+            Self::Critical => write!(f, "critical"),
+        }
+    }
+}
+
+// ============================================================================
+// Audit Models
+// ============================================================================
+
+/// Recorded administrative action
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct AuditEvent {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    pub actor_user_id: Option<Uuid>,
+    /// Email of the acting user, if it still exists
+    pub actor_email: Option<String>,
+    /// Set when the action was performed with an API key
+    pub actor_api_key_id: Option<Uuid>,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: Option<Uuid>,
+    /// Changed fields as `{"field": {"before": ..., "after": ...}}`
+    pub changes: serde_json::Value,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Administrative action recorded in the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    OrganizationCreated,
+    OrganizationUpdated,
+    OrganizationDeleted,
+    OrganizationRestored,
+    ManifestApplied,
+    OwnershipTransferRequested,
+    OwnershipTransferCancelled,
+    OwnershipTransferred,
+    RetentionUpdated,
+    SsoUpdated,
+    SsoDeleted,
+    MemberAdded,
+    MemberRemoved,
+    MemberRoleChanged,
+    MemberDomainGroupsChanged,
+    InvitationCreated,
+    InvitationResent,
+    InvitationRevoked,
+    InvitationAccepted,
+    RoleCreated,
+    RoleUpdated,
+    RoleDeleted,
+    ApiKeyCreated,
+    ApiKeyUpdated,
+    ApiKeyRevoked,
+    DomainGroupCreated,
+    DomainGroupUpdated,
+    DomainGroupDeleted,
+    DomainCreated,
+    DomainUpdated,
+    /// A domain update that moved it to another URL
+    DomainUrlChanged,
+    DomainDeleted,
+    MonitorCreated,
+    MonitorUpdated,
+    MonitorDeleted,
+    SloCreated,
+    SloUpdated,
+    SloDeleted,
+    AlertAcknowledged,
+    AlertRouteCreated,
+    AlertRouteUpdated,
+    AlertRouteDeleted,
+}
+
+impl AuditAction {
+    /// Name of the action as stored, e.g. `domain.created`
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::OrganizationCreated => "organization.created",
+            Self::OrganizationUpdated => "organization.updated",
+            Self::OrganizationDeleted => "organization.deleted",
+            Self::OrganizationRestored => "organization.restored",
+            Self::ManifestApplied => "organization.manifest_applied",
+            Self::OwnershipTransferRequested => "ownership.transfer_requested",
+            Self::OwnershipTransferCancelled => "ownership.transfer_cancelled",
+            Self::OwnershipTransferred => "ownership.transferred",
+            Self::RetentionUpdated => "retention.updated",
+            Self::SsoUpdated => "sso.updated",
+            Self::SsoDeleted => "sso.deleted",
+            Self::MemberAdded => "member.added",
+            Self::MemberRemoved => "member.removed",
+            Self::MemberRoleChanged => "member.role_changed",
+            Self::MemberDomainGroupsChanged => "member.domain_groups_changed",
+            Self::InvitationCreated => "invitation.created",
+            Self::InvitationResent => "invitation.resent",
+            Self::InvitationRevoked => "invitation.revoked",
+            Self::InvitationAccepted => "invitation.accepted",
+            Self::RoleCreated => "role.created",
+            Self::RoleUpdated => "role.updated",
+            Self::RoleDeleted => "role.deleted",
+            Self::ApiKeyCreated => "api_key.created",
+            Self::ApiKeyUpdated => "api_key.updated",
+            Self::ApiKeyRevoked => "api_key.revoked",
+            Self::DomainGroupCreated => "domain_group.created",
+            Self::DomainGroupUpdated => "domain_group.updated",
+            Self::DomainGroupDeleted => "domain_group.deleted",
+            Self::DomainCreated => "domain.created",
+            Self::DomainUpdated => "domain.updated",
+            Self::DomainUrlChanged => "domain.url_changed",
+            Self::DomainDeleted => "domain.deleted",
+            Self::MonitorCreated => "monitor.created",
+            Self::MonitorUpdated => "monitor.updated",
+            Self::MonitorDeleted => "monitor.deleted",
+            Self::SloCreated => "slo.created",
+            Self::SloUpdated => "slo.updated",
+            Self::SloDeleted => "slo.deleted",
+            Self::AlertAcknowledged => "alert.acknowledged",
+            Self::AlertRouteCreated => "alert_route.created",
+            Self::AlertRouteUpdated => "alert_route.updated",
+            Self::AlertRouteDeleted => "alert_route.deleted",
+        }
+    }
+
+    /// Kind of object the action applies to
+    #[must_use]
+    pub const fn target_type(self) -> &'static str {
+        match self {
+            Self::OrganizationCreated
+            | Self::OrganizationUpdated
+            | Self::OrganizationDeleted
+            | Self::OrganizationRestored
+            | Self::ManifestApplied
+            | Self::OwnershipTransferRequested
+            | Self::OwnershipTransferCancelled
+            | Self::OwnershipTransferred
+            | Self::RetentionUpdated
+            | Self::SsoUpdated
+            | Self::SsoDeleted => "organization",
+            Self::MemberAdded
+            | Self::MemberRemoved
+            | Self::MemberRoleChanged
+            | Self::MemberDomainGroupsChanged => "member",
+            Self::InvitationCreated
+            | Self::InvitationResent
+            | Self::InvitationRevoked
+            | Self::InvitationAccepted => "invitation",
+            Self::RoleCreated | Self::RoleUpdated | Self::RoleDeleted => "role",
+            Self::ApiKeyCreated | Self::ApiKeyUpdated | Self::ApiKeyRevoked => "api_key",
+            Self::DomainGroupCreated | Self::DomainGroupUpdated | Self::DomainGroupDeleted => "domain_group",
+            Self::DomainCreated
+            | Self::DomainUpdated
+            | Self::DomainUrlChanged
+            | Self::DomainDeleted => "domain",
+            Self::MonitorCreated | Self::MonitorUpdated | Self::MonitorDeleted => "monitor",
+            Self::SloCreated | Self::SloUpdated | Self::SloDeleted => "slo",
+            Self::AlertAcknowledged => "alert",
+            Self::AlertRouteCreated | Self::AlertRouteUpdated | Self::AlertRouteDeleted => "alert_route",
+        }
+    }
+}
+
+impl std::fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Filters for audit log queries
+#[derive(Debug, Clone, Default, Deserialize, IntoParams, ToSchema)]
+#[into_params(parameter_in = Query)]
+pub struct AuditEventFilter {
+    /// Action, e.g. `member.role_changed`
+    pub action: Option<String>,
+    /// Target type, e.g. `domain`
+    pub target_type: Option<String>,
+    pub target_id: Option<Uuid>,
+    /// Acting user
+    pub actor_id: Option<Uuid>,
+    /// Events at or after this time
+    pub from: Option<DateTime<Utc>>,
+    /// Events before this time
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// Audit event to record
+#[derive(Debug, Clone)]
+pub struct NewAuditEvent {
+    pub organization_id: Uuid,
+    pub actor_user_id: Option<Uuid>,
+    pub actor_api_key_id: Option<Uuid>,
+    pub action: AuditAction,
+    pub target_id: Option<Uuid>,
+    pub changes: serde_json::Value,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+// ============================================================================
+// Authentication Models
+// ============================================================================
+
+/// Refresh token for JWT
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct RefreshToken {
     pub id: Uuid,
@@ -369,6 +1617,249 @@ pub struct RefreshToken {
     pub created_at: DateTime<Utc>,
     pub revoked_at: Option<DateTime<Utc>>,
     pub is_revoked: bool,
+    /// Session (token family) the token belongs to
+    pub session_id: Option<Uuid>,
+    /// Token issued when this one was rotated
+    pub replaced_by: Option<Uuid>,
+}
+
+/// What a single-use token sent by email is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum UserTokenPurpose {
+    PasswordReset,
+    EmailVerification,
+}
+
+/// Single-use token sent by email (password reset, email verification)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct UserToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub purpose: UserTokenPurpose,
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Revocation state of an access token
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct AccessTokenStatus {
+    pub is_active: bool,
+    pub token_version: i32,
+    /// The token's jti is on the denylist
+    pub is_denied: bool,
+    /// The token's session has been revoked
+    pub session_revoked: bool,
+}
+
+/// Login session, shared by the chain of refresh tokens issued from one login
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct Session {
+    pub id: Uuid,
+    #[serde(skip_serializing)]
+    pub user_id: Uuid,
+    pub device_name: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    /// Organization the session's access tokens are scoped to
+    pub organization_id: Option<Uuid>,
+    /// Organization whose identity provider signed the session in, if any
+    pub sso_organization_id: Option<Uuid>,
+}
+
+/// OpenID Connect provider configuration of an organization
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct SsoConfig {
+    pub organization_id: Uuid,
+    /// Issuer URL, used for discovery
+    pub issuer: String,
+    pub client_id: String,
+    #[serde(skip_serializing)]
+    pub client_secret: String,
+    /// Email domains the provider may sign in
+    pub allowed_email_domains: Vec<String>,
+    /// Role given to users joining the organization through SSO
+    pub default_role: MemberRole,
+    pub enabled: bool,
+    /// Members other than the owner must log in through SSO
+    pub enforce_sso: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SsoConfig {
+    /// Check if the provider may sign in the given email address
+    #[must_use]
+    pub fn allows_email(&self, email: &str) -> bool {
+        email.rsplit_once('@').is_some_and(|(_, domain)| {
+            self.allowed_email_domains.iter().any(|d| d.eq_ignore_ascii_case(domain))
+        })
+    }
+}
+
+/// Identity at an OpenID Connect provider, linked to a user for one organization
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct UserIdentity {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub organization_id: Uuid,
+    pub issuer: String,
+    pub subject: String,
+    pub email: String,
+    /// The account was created by the provider, which is trusted with its second factor
+    pub provisioned: bool,
+    pub created_at: DateTime<Utc>,
+    pub last_login_at: Option<DateTime<Utc>>,
+}
+
+/// Provider identity asserted through an organization's SSO config
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct SsoIdentity {
+    pub organization_id: Uuid,
+    pub issuer: String,
+    pub subject: String,
+    pub email: String,
+}
+
+/// Identity waiting for the owner of an existing account to confirm the link
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct SsoLinkRequest {
+    pub user_id: Uuid,
+    #[sqlx(flatten)]
+    pub identity: SsoIdentity,
+}
+
+/// Pending SSO authorization request
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct SsoLoginState {
+    pub organization_id: Uuid,
+    pub nonce: String,
+    pub pkce_verifier: String,
+}
+
+/// Organization-scoped API key
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    pub created_by: Uuid,
+    pub name: String,
+    /// First characters of the key, for identification
+    pub key_prefix: String,
+    #[serde(skip_serializing)]
+    pub key_hash: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub last_used_ip: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKey {
+    /// Parsed scopes, ignoring unknown values
+    #[must_use]
+    pub fn scope_set(&self) -> Vec<ApiKeyScope> {
+        self.scopes.iter().filter_map(|s| s.parse().ok()).collect()
+    }
+
+    /// Check if the key can currently be used
+    #[must_use]
+    pub fn is_usable(&self) -> bool {
+        self.revoked_at.is_none() && self.expires_at.is_none_or(|exp| exp > Utc::now())
+    }
+}
+
+/// API key presented by a request, with the status of the member it acts as
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ApiKeyCredential {
+    #[sqlx(flatten)]
+    pub key: ApiKey,
+    /// The key's creator is an active user and still a member of its organization
+    pub creator_active: bool,
+}
+
+impl ApiKeyCredential {
+    /// Check if the key can currently be used: a key stops working once its
+    /// creator leaves the organization or is deactivated
+    #[must_use]
+    pub fn is_usable(&self) -> bool {
+        self.creator_active && self.key.is_usable()
+    }
+}
+
+/// Permission granted to an API key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum ApiKeyScope {
+    /// Read-only access
+    Read,
+    /// Create and modify domains, monitors and SLOs
+    Write,
+    /// Trigger manual checks
+    TriggerCheck,
+    /// Full access, including members and API keys
+    Admin,
+}
+
+impl ApiKeyScope {
+    /// Check if this scope grants the required one
+    #[must_use]
+    pub const fn grants(self, required: Self) -> bool {
+        matches!(
+            (self, required),
+            (Self::Admin, _)
+                | (Self::Write, Self::Write | Self::Read | Self::TriggerCheck)
+                | (Self::Read, Self::Read)
+                | (Self::TriggerCheck, Self::TriggerCheck)
+        )
+    }
+
+    /// Organization role equivalent to a set of scopes
+    #[must_use]
+    pub fn role_for(scopes: &[Self]) -> MemberRole {
+        if scopes.contains(&Self::Admin) {
+            MemberRole::Admin
+        } else if scopes.contains(&Self::Write) {
+            MemberRole::Member
+        } else {
+            MemberRole::Viewer
+        }
+    }
+}
+
+impl std::fmt::Display for ApiKeyScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Read => write!(f, "read"),
+            Self::Write => write!(f, "write"),
+            Self::TriggerCheck => write!(f, "trigger-check"),
+            Self::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+impl std::str::FromStr for ApiKeyScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read" => Ok(Self::Read),
+            "write" => Ok(Self::Write),
+            "trigger-check" => Ok(Self::TriggerCheck),
+            "admin" => Ok(Self::Admin),
+            _ => Err(format!("Invalid scope: {}", s)),
+        }
+    }
 }
 
 // ============================================================================
@@ -399,6 +1890,7 @@ pub struct CreateOrganization {
 pub struct UpdateOrganization {
     pub name: Option<String>,
     pub webhook_url: Option<String>,
+    pub timezone: Option<String>,
 }
 
 /// Create a new domain
@@ -409,27 +1901,99 @@ pub struct CreateDomain {
 }
 
 /// Update a domain
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 pub struct UpdateDomain {
     pub is_active: Option<bool>,
+    pub display_name: Option<String>,
+    /// Normalized URL
+    pub url: Option<String>,
+    /// New group, `Some(None)` to move the domain out of any group
+    pub group_id: Option<Option<Uuid>>,
+    pub tags: Option<Tags>,
 }
 
 /// Create a new monitor
-#[derive(Debug, Clone, Deserialize, validator::Validate)]
+#[derive(Debug, Clone, Deserialize, validator::Validate, ToSchema)]
 pub struct CreateMonitor {
     #[serde(rename = "type")]
     pub monitor_type: MonitorType,
     pub is_enabled: Option<bool>,
+    /// Settings of the monitor type; defaults to `{}`
+    #[serde(default = "empty_object")]
     pub config: serde_json::Value,
 }
 
 /// Update a monitor
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, ToSchema)]
 pub struct UpdateMonitor {
     pub is_enabled: Option<bool>,
+    /// Replaces all settings of the monitor
     pub config: Option<serde_json::Value>,
 }
 
+/// Pause a monitor
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct PauseMonitor {
+    /// When checks resume
+    pub until: DateTime<Utc>,
+}
+
+/// Create an organization API key
+#[derive(Debug, Clone, Deserialize, validator::Validate, ToSchema)]
+pub struct CreateApiKey {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    #[validate(length(min = 1))]
+    pub scopes: Vec<ApiKeyScope>,
+    /// Expiry time (never expires when omitted)
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl CreateApiKey {
+    /// Sorted, de-duplicated scope names as stored in the database
+    #[must_use]
+    pub fn scope_names(scopes: &[ApiKeyScope]) -> Vec<String> {
+        let mut names: Vec<String> = scopes.iter().map(ToString::to_string).collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+}
+
+/// Create or replace an organization's SSO configuration
+#[derive(Debug, Clone, Deserialize, validator::Validate, ToSchema)]
+pub struct UpsertSsoConfig {
+    /// Issuer URL; `/.well-known/openid-configuration` must be served below it
+    #[validate(url, length(max = 500))]
+    pub issuer: String,
+    #[validate(length(min = 1, max = 255))]
+    pub client_id: String,
+    /// Client secret (the stored secret is kept when omitted)
+    #[validate(length(min = 1, max = 500))]
+    pub client_secret: Option<String>,
+    /// Email domains the provider may sign in
+    #[validate(length(min = 1))]
+    pub allowed_email_domains: Vec<String>,
+    /// Role given to users joining through SSO (defaults to member)
+    #[serde(default = "UpsertSsoConfig::default_role")]
+    pub default_role: MemberRole,
+    #[serde(default = "UpsertSsoConfig::default_enabled")]
+    pub enabled: bool,
+    /// Members other than the owner must log in through SSO
+    #[serde(default)]
+    pub enforce_sso: bool,
+}
+
+impl UpsertSsoConfig {
+    const fn default_role() -> MemberRole {
+        MemberRole::Member
+    }
+
+    const fn default_enabled() -> bool {
+        true
+    }
+}
+
 /// Login request
 #[derive(Debug, Clone, Deserialize, validator::Validate)]
 pub struct LoginRequest {
@@ -447,6 +2011,35 @@ pub struct AddMember {
     pub role: MemberRole,
 }
 
+/// Invite a user to an organization by email
+#[derive(Debug, Clone, Deserialize, validator::Validate, ToSchema)]
+pub struct CreateInvitation {
+    #[validate(email, length(max = 255))]
+    pub email: String,
+    /// Role given once the invitation is accepted (cannot be owner)
+    pub role: MemberRole,
+}
+
+/// Create or update a custom organization role
+#[derive(Debug, Clone, Deserialize, validator::Validate, ToSchema)]
+pub struct UpsertOrganizationRole {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    #[validate(length(max = 1000))]
+    pub description: Option<String>,
+    pub permissions: Vec<Permission>,
+}
+
+/// Create or replace a domain group
+#[derive(Debug, Clone, Deserialize, validator::Validate, ToSchema)]
+pub struct UpsertDomainGroup {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    /// Group to nest this group in, null for a top-level group
+    #[serde(default)]
+    pub parent_id: Option<Uuid>,
+}
+
 /// Refresh token request
 #[derive(Debug, Clone, Deserialize)]
 pub struct RefreshTokenRequest {
@@ -477,6 +2070,9 @@ pub struct DomainWithStatus {
     pub url: String, // This will be the actual URL after migration
     pub normalized_name: String,
     pub is_active: bool,
+    pub group_id: Option<Uuid>,
+    #[sqlx(json)]
+    pub tags: Tags,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     // Uptime status
@@ -523,6 +2119,20 @@ pub struct PublicDomainStatus {
     pub last_check_time: Option<DateTime<Utc>>,
     pub uptime_7d: Option<rust_decimal::Decimal>,
     pub uptime_30d: Option<rust_decimal::Decimal>,
+    /// Used to build sections, not published
+    #[serde(skip)]
+    pub group_id: Option<Uuid>,
+    #[serde(skip)]
+    #[sqlx(json)]
+    pub tags: Tags,
+}
+
+/// Section of the public status page
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct StatusPageSection {
+    /// Group path or tag value, null for the remaining domains
+    pub name: Option<String>,
+    pub domain_ids: Vec<Uuid>,
 }
 
 /// Organization with domains for public status page
@@ -530,4 +2140,62 @@ pub struct PublicDomainStatus {
 pub struct OrganizationWithDomains {
     pub organization: Organization,
     pub domains: Vec<PublicDomainStatus>,
+    /// Domains split by group, or by tag value with `section_tag`
+    pub sections: Vec<StatusPageSection>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member_access(role: MemberRole, sso_enforced: bool, sso_session: bool) -> MemberAccess {
+        MemberAccess {
+            role,
+            custom_permissions: None,
+            domain_group_ids: None,
+            two_factor_missing: false,
+            organization_deleted: false,
+            sso_enforced,
+            sso_session,
+        }
+    }
+
+    #[test]
+    fn test_requires_sso() {
+        // A password session of a member of an enforcing and a non-enforcing organization
+        let enforcing = member_access(MemberRole::Member, true, false);
+        let other = member_access(MemberRole::Admin, false, false);
+        assert!(enforcing.requires_sso());
+        assert!(!other.requires_sso());
+
+        // Signed in through the provider, or owner of the organization
+        assert!(!member_access(MemberRole::Member, true, true).requires_sso());
+        assert!(!member_access(MemberRole::Owner, true, false).requires_sso());
+    }
+
+    #[test]
+    fn test_api_key_usable_by_active_member_only() {
+        let now = Utc::now();
+        let key = ApiKey {
+            id: Uuid::new_v4(),
+            organization_id: Uuid::new_v4(),
+            created_by: Uuid::new_v4(),
+            name: "ci".to_string(),
+            key_prefix: "wg_abcd".to_string(),
+            key_hash: String::new(),
+            scopes: vec!["admin".to_string()],
+            expires_at: None,
+            last_used_at: None,
+            last_used_ip: None,
+            created_at: now,
+            updated_at: now,
+            revoked_at: None,
+        };
+
+        assert!(ApiKeyCredential { key: key.clone(), creator_active: true }.is_usable());
+        // Creator removed from the organization or deactivated
+        assert!(!ApiKeyCredential { key: key.clone(), creator_active: false }.is_usable());
+        let revoked = ApiKey { revoked_at: Some(now), ..key };
+        assert!(!ApiKeyCredential { key: revoked, creator_active: true }.is_usable());
+    }
 }
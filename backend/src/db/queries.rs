@@ -1,7 +1,29 @@
-use sqlx::PgPool;
+use sqlx::postgres::PgRow;
+use sqlx::{FromRow, PgConnection, PgPool, Row};
+use std::collections::HashMap;
 use uuid::Uuid;
 use crate::db::models::*;
 use crate::error::{AppError, AppResult};
+use crate::tags::TagSelector;
+
+/// Row of a paginated listing, with the number of rows matching its filters
+/// across all pages in a `total` column
+struct Counted<T> {
+    item: T,
+    total: i64,
+}
+
+impl<'r, T: FromRow<'r, PgRow>> FromRow<'r, PgRow> for Counted<T> {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        Ok(Self { item: T::from_row(row)?, total: row.try_get("total")? })
+    }
+}
+
+/// Split counted rows into the items and their total
+fn split_total<T>(rows: Vec<Counted<T>>) -> (Vec<T>, i64) {
+    let total = rows.first().map_or(0, |row| row.total);
+    (rows.into_iter().map(|row| row.item).collect(), total)
+}
 
 // ============================================================================
 // User Queries
@@ -66,311 +88,3368 @@ pub async fn update_last_login(pool: &PgPool, user_id: Uuid) -> AppResult<()> {
     Ok(())
 }
 
-// ============================================================================
-// Organization Queries
-// ============================================================================
+/// Update a user's password
+///
+/// Bumps the user's token version, which invalidates their outstanding access tokens.
+pub async fn update_user_password(pool: &PgPool, user_id: Uuid, password_hash: &str) -> AppResult<()> {
+    sqlx::query("UPDATE users SET password_hash = $2 WHERE id = $1")
+        .bind(user_id)
+        .bind(password_hash)
+        .execute(pool)
+        .await
+        .map_err(AppError::from)?;
 
-/// Find an organization by ID
-pub async fn find_organization_by_id(pool: &PgPool, id: Uuid) -> AppResult<Option<Organization>> {
-    sqlx::query_as::<_, Organization>(
-        "SELECT * FROM organizations WHERE id = $1"
+    Ok(())
+}
+
+/// Record a failed login attempt, locking the account once the threshold is reached
+///
+/// Returns the end of the lockout, if the account is now locked.
+pub async fn record_failed_login(
+    pool: &PgPool,
+    user_id: Uuid,
+    threshold: i32,
+    lockout_base_secs: i64,
+    lockout_max_secs: i64,
+) -> AppResult<Option<chrono::DateTime<chrono::Utc>>> {
+    sqlx::query_scalar::<_, Option<chrono::DateTime<chrono::Utc>>>(
+        r#"
+        UPDATE users
+        SET failed_login_count = failed_login_count + 1,
+            locked_until = CASE
+                WHEN failed_login_count + 1 >= $2 THEN NOW() + make_interval(secs => LEAST(
+                    $3 * POWER(2, LEAST(failed_login_count + 1 - $2, 30)),
+                    $4
+                ))
+                ELSE locked_until
+            END
+        WHERE id = $1
+        RETURNING locked_until
+        "#
     )
-    .bind(id)
-    .fetch_optional(pool)
+    .bind(user_id)
+    .bind(threshold)
+    .bind(lockout_base_secs)
+    .bind(lockout_max_secs)
+    .fetch_one(pool)
     .await
     .map_err(AppError::from)
 }
 
-/// Find an organization by slug
-pub async fn find_organization_by_slug(pool: &PgPool, slug: &str) -> AppResult<Option<Organization>> {
-    sqlx::query_as::<_, Organization>(
-        "SELECT * FROM organizations WHERE slug = $1"
+/// Clear failed login attempts and any lockout after a successful login
+pub async fn reset_failed_logins(pool: &PgPool, user_id: Uuid) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        UPDATE users SET failed_login_count = 0, locked_until = NULL
+        WHERE id = $1 AND (failed_login_count > 0 OR locked_until IS NOT NULL)
+        "#
     )
-    .bind(slug)
-    .fetch_optional(pool)
+    .bind(user_id)
+    .execute(pool)
     .await
-    .map_err(AppError::from)
+    .map_err(AppError::from)?;
+
+    Ok(())
 }
 
-/// Create a new organization
-pub async fn create_organization(
-    pool: &PgPool,
-    name: &str,
-    slug: &str,
-    owner_id: Uuid,
-) -> AppResult<Organization> {
-    let mut tx = pool.begin().await.map_err(AppError::from)?;
+/// Mark a user's email address as verified
+pub async fn mark_email_verified(pool: &PgPool, user_id: Uuid) -> AppResult<()> {
+    sqlx::query("UPDATE users SET email_verified_at = COALESCE(email_verified_at, NOW()) WHERE id = $1")
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .map_err(AppError::from)?;
 
-    let org = sqlx::query_as::<_, Organization>(
+    Ok(())
+}
+
+// ============================================================================
+// Two-Factor Authentication Queries
+// ============================================================================
+
+/// Start TOTP enrollment with a new secret, not enforced until confirmed
+pub async fn set_pending_totp_secret(pool: &PgPool, user_id: Uuid, secret: &str) -> AppResult<()> {
+    sqlx::query(
         r#"
-        INSERT INTO organizations (name, slug)
-        VALUES ($1, $2)
-        RETURNING *
+        UPDATE users
+        SET totp_secret = $2, totp_enabled_at = NULL, totp_last_used_step = NULL
+        WHERE id = $1
         "#
     )
-    .bind(name)
-    .bind(slug)
-    .fetch_one(&mut *tx)
+    .bind(user_id)
+    .bind(secret)
+    .execute(pool)
     .await
     .map_err(AppError::from)?;
 
-    // Add the owner as a member
-    sqlx::query(
+    Ok(())
+}
+
+/// Record a used TOTP time step
+///
+/// Returns `false` if the step (or a later one) was already used, i.e. the code is replayed.
+pub async fn record_totp_step(pool: &PgPool, user_id: Uuid, step: i64) -> AppResult<bool> {
+    let result = sqlx::query(
         r#"
-        INSERT INTO organization_members (organization_id, user_id, role)
-        VALUES ($1, $2, 'owner')
+        UPDATE users
+        SET totp_last_used_step = $2
+        WHERE id = $1 AND (totp_last_used_step IS NULL OR totp_last_used_step < $2)
         "#
     )
-    .bind(org.id)
-    .bind(owner_id)
-    .execute(&mut *tx)
+    .bind(user_id)
+    .bind(step)
+    .execute(pool)
     .await
     .map_err(AppError::from)?;
 
+    Ok(result.rows_affected() > 0)
+}
+
+/// Confirm TOTP enrollment and store the user's recovery codes
+pub async fn enable_totp(pool: &PgPool, user_id: Uuid, recovery_code_hashes: &[String]) -> AppResult<()> {
+    let mut tx = pool.begin().await.map_err(AppError::from)?;
+
+    sqlx::query("UPDATE users SET totp_enabled_at = NOW() WHERE id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+
+    insert_recovery_codes(&mut tx, user_id, recovery_code_hashes).await?;
+
     tx.commit().await.map_err(AppError::from)?;
 
-    Ok(org)
+    Ok(())
 }
 
-/// List organizations for a user
-pub async fn list_user_organizations(pool: &PgPool, user_id: Uuid) -> AppResult<Vec<Organization>> {
-    sqlx::query_as::<_, Organization>(
+/// Turn off two-factor authentication and delete the recovery codes
+pub async fn disable_totp(pool: &PgPool, user_id: Uuid) -> AppResult<()> {
+    let mut tx = pool.begin().await.map_err(AppError::from)?;
+
+    sqlx::query(
         r#"
-        SELECT o.* FROM organizations o
-        INNER JOIN organization_members om ON o.id = om.organization_id
-        WHERE om.user_id = $1
-        ORDER BY o.created_at DESC
+        UPDATE users
+        SET totp_secret = NULL, totp_enabled_at = NULL, totp_last_used_step = NULL
+        WHERE id = $1
         "#
     )
     .bind(user_id)
-    .fetch_all(pool)
+    .execute(&mut *tx)
     .await
-    .map_err(AppError::from)
+    .map_err(AppError::from)?;
+
+    sqlx::query("DELETE FROM recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+
+    tx.commit().await.map_err(AppError::from)?;
+
+    Ok(())
 }
 
-/// Get user's role in an organization
-pub async fn get_user_role(
-    pool: &PgPool,
-    organization_id: Uuid,
+/// Replace all recovery codes of a user
+pub async fn replace_recovery_codes(pool: &PgPool, user_id: Uuid, code_hashes: &[String]) -> AppResult<()> {
+    let mut tx = pool.begin().await.map_err(AppError::from)?;
+    insert_recovery_codes(&mut tx, user_id, code_hashes).await?;
+    tx.commit().await.map_err(AppError::from)?;
+
+    Ok(())
+}
+
+async fn insert_recovery_codes(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     user_id: Uuid,
-) -> AppResult<Option<MemberRole>> {
-    let role = sqlx::query_scalar::<_, MemberRole>(
+    code_hashes: &[String],
+) -> AppResult<()> {
+    sqlx::query("DELETE FROM recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(AppError::from)?;
+
+    sqlx::query(
         r#"
-        SELECT role FROM organization_members
-        WHERE organization_id = $1 AND user_id = $2
+        INSERT INTO recovery_codes (user_id, code_hash)
+        SELECT $1, UNNEST($2::varchar[])
         "#
     )
-    .bind(organization_id)
     .bind(user_id)
-    .fetch_optional(pool)
+    .bind(code_hashes)
+    .execute(&mut **tx)
     .await
     .map_err(AppError::from)?;
 
-    Ok(role)
+    Ok(())
 }
 
-/// Check if user is member of organization
-pub async fn is_organization_member(
-    pool: &PgPool,
-    organization_id: Uuid,
-    user_id: Uuid,
-) -> AppResult<bool> {
-    let exists = sqlx::query_scalar::<_, bool>(
+/// Mark an unused recovery code as used, returning whether it was valid
+pub async fn consume_recovery_code(pool: &PgPool, user_id: Uuid, code_hash: &str) -> AppResult<bool> {
+    let result = sqlx::query(
         r#"
-        SELECT EXISTS(
-            SELECT 1 FROM organization_members
-            WHERE organization_id = $1 AND user_id = $2
-        )
+        UPDATE recovery_codes
+        SET used_at = NOW()
+        WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
         "#
     )
-    .bind(organization_id)
     .bind(user_id)
-    .fetch_one(pool)
+    .bind(code_hash)
+    .execute(pool)
     .await
     .map_err(AppError::from)?;
 
-    Ok(exists)
+    Ok(result.rows_affected() > 0)
 }
 
-/// Delete an organization
-pub async fn delete_organization(pool: &PgPool, organization_id: Uuid) -> AppResult<()> {
+/// Count the unused recovery codes of a user
+pub async fn count_unused_recovery_codes(pool: &PgPool, user_id: Uuid) -> AppResult<i64> {
+    sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM recovery_codes WHERE user_id = $1 AND used_at IS NULL")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .map_err(AppError::from)
+}
+
+// ============================================================================
+// User Token Queries
+// ============================================================================
+
+/// Store a single-use token, replacing the user's unused tokens for the same purpose
+pub async fn create_user_token(
+    pool: &PgPool,
+    user_id: Uuid,
+    purpose: UserTokenPurpose,
+    token_hash: &str,
+    expires_at: chrono::DateTime<chrono::Utc>,
+) -> AppResult<UserToken> {
     let mut tx = pool.begin().await.map_err(AppError::from)?;
 
-    // Delete all members
-    sqlx::query("DELETE FROM organization_members WHERE organization_id = $1")
-        .bind(organization_id)
+    sqlx::query("DELETE FROM user_tokens WHERE user_id = $1 AND purpose = $2 AND used_at IS NULL")
+        .bind(user_id)
+        .bind(purpose)
         .execute(&mut *tx)
         .await
         .map_err(AppError::from)?;
 
-    // Delete the organization
-    sqlx::query("DELETE FROM organizations WHERE id = $1")
-        .bind(organization_id)
-        .execute(&mut *tx)
+    let token = sqlx::query_as::<_, UserToken>(
+        r#"
+        INSERT INTO user_tokens (user_id, purpose, token_hash, expires_at)
+        VALUES ($1, $2, $3, $4)
+        RETURNING *
+        "#
+    )
+    .bind(user_id)
+    .bind(purpose)
+    .bind(token_hash)
+    .bind(expires_at)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+
+    tx.commit().await.map_err(AppError::from)?;
+
+    Ok(token)
+}
+
+/// Mark a token as used, if it is valid for the purpose and neither used nor expired
+pub async fn consume_user_token(
+    pool: &PgPool,
+    purpose: UserTokenPurpose,
+    token_hash: &str,
+) -> AppResult<Option<UserToken>> {
+    sqlx::query_as::<_, UserToken>(
+        r#"
+        UPDATE user_tokens
+        SET used_at = NOW()
+        WHERE token_hash = $1 AND purpose = $2 AND used_at IS NULL AND expires_at > NOW()
+        RETURNING *
+        "#
+    )
+    .bind(token_hash)
+    .bind(purpose)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Delete expired single-use tokens
+pub async fn delete_expired_user_tokens(pool: &PgPool) -> AppResult<u64> {
+    let result = sqlx::query("DELETE FROM user_tokens WHERE expires_at <= NOW()")
+        .execute(pool)
         .await
         .map_err(AppError::from)?;
 
-    tx.commit().await.map_err(AppError::from)?;
-    Ok(())
+    Ok(result.rows_affected())
 }
 
 // ============================================================================
-// Domain Queries
+// SSO Queries
 // ============================================================================
 
-/// List domains for an organization
-pub async fn list_organization_domains(
+/// Get the SSO configuration of an organization
+pub async fn find_sso_config(pool: &PgPool, organization_id: Uuid) -> AppResult<Option<SsoConfig>> {
+    sqlx::query_as::<_, SsoConfig>("SELECT * FROM sso_configs WHERE organization_id = $1")
+        .bind(organization_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Create or replace the SSO configuration of an organization
+pub async fn upsert_sso_config(
     pool: &PgPool,
     organization_id: Uuid,
-) -> AppResult<Vec<Domain>> {
-    sqlx::query_as::<_, Domain>(
-        "SELECT * FROM domains WHERE organization_id = $1 ORDER BY created_at DESC"
+    config: &UpsertSsoConfig,
+    client_secret: &str,
+) -> AppResult<SsoConfig> {
+    sqlx::query_as::<_, SsoConfig>(
+        r#"
+        INSERT INTO sso_configs
+            (organization_id, issuer, client_id, client_secret, allowed_email_domains, default_role, enabled, enforce_sso)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (organization_id) DO UPDATE SET
+            issuer = EXCLUDED.issuer,
+            client_id = EXCLUDED.client_id,
+            client_secret = EXCLUDED.client_secret,
+            allowed_email_domains = EXCLUDED.allowed_email_domains,
+            default_role = EXCLUDED.default_role,
+            enabled = EXCLUDED.enabled,
+            enforce_sso = EXCLUDED.enforce_sso
+        RETURNING *
+        "#
     )
     .bind(organization_id)
-    .fetch_all(pool)
+    .bind(&config.issuer)
+    .bind(&config.client_id)
+    .bind(client_secret)
+    .bind(&config.allowed_email_domains)
+    .bind(&config.default_role)
+    .bind(config.enabled)
+    .bind(config.enforce_sso)
+    .fetch_one(pool)
     .await
     .map_err(AppError::from)
 }
 
-/// Find a domain by ID
-pub async fn find_domain_by_id(pool: &PgPool, id: Uuid) -> AppResult<Option<Domain>> {
-    sqlx::query_as::<_, Domain>(
-        "SELECT * FROM domains WHERE id = $1"
-    )
-    .bind(id)
-    .fetch_optional(pool)
-    .await
-    .map_err(AppError::from)
+/// Delete the SSO configuration of an organization
+pub async fn delete_sso_config(pool: &PgPool, organization_id: Uuid) -> AppResult<bool> {
+    let result = sqlx::query("DELETE FROM sso_configs WHERE organization_id = $1")
+        .bind(organization_id)
+        .execute(pool)
+        .await
+        .map_err(AppError::from)?;
+
+    Ok(result.rows_affected() > 0)
 }
 
-/// Create a new domain
-pub async fn create_domain(
+/// Store a pending SSO authorization request
+pub async fn create_sso_login_state(
     pool: &PgPool,
+    state_hash: &str,
     organization_id: Uuid,
-    name: &str,
-    normalized_name: &str,
-) -> AppResult<Domain> {
-    let domain = sqlx::query_as::<_, Domain>(
+    nonce: &str,
+    pkce_verifier: &str,
+    expires_at: chrono::DateTime<chrono::Utc>,
+) -> AppResult<()> {
+    sqlx::query(
         r#"
-        INSERT INTO domains (organization_id, name, normalized_name)
-        VALUES ($1, $2, $3)
-        RETURNING *
+        INSERT INTO sso_login_states (state_hash, organization_id, nonce, pkce_verifier, expires_at)
+        VALUES ($1, $2, $3, $4, $5)
         "#
     )
+    .bind(state_hash)
     .bind(organization_id)
-    .bind(name)
-    .bind(normalized_name)
-    .fetch_one(pool)
+    .bind(nonce)
+    .bind(pkce_verifier)
+    .bind(expires_at)
+    .execute(pool)
     .await
     .map_err(AppError::from)?;
 
-    Ok(domain)
+    Ok(())
+}
+
+/// Remove a pending SSO authorization request, if it has not expired
+pub async fn consume_sso_login_state(pool: &PgPool, state_hash: &str) -> AppResult<Option<SsoLoginState>> {
+    sqlx::query_as::<_, SsoLoginState>(
+        r#"
+        DELETE FROM sso_login_states
+        WHERE state_hash = $1 AND expires_at > NOW()
+        RETURNING organization_id, nonce, pkce_verifier
+        "#
+    )
+    .bind(state_hash)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
 }
 
-/// Update a domain
-pub async fn update_domain(
-    pool: &PgPool,
-    domain_id: Uuid,
-    is_active: Option<bool>,
-) -> AppResult<()> {
-    if is_active.is_some() {
-        sqlx::query(
-            "UPDATE domains SET is_active = $1 WHERE id = $2"
-        )
-        .bind(is_active.unwrap())
-        .bind(domain_id)
+/// Delete expired SSO authorization requests
+pub async fn delete_expired_sso_login_states(pool: &PgPool) -> AppResult<u64> {
+    let result = sqlx::query("DELETE FROM sso_login_states WHERE expires_at <= NOW()")
         .execute(pool)
         .await
         .map_err(AppError::from)?;
-    }
+
+    Ok(result.rows_affected())
+}
+
+/// Find the identity linked for an organization to a provider subject
+pub async fn find_user_identity(
+    pool: &PgPool,
+    organization_id: Uuid,
+    issuer: &str,
+    subject: &str,
+) -> AppResult<Option<UserIdentity>> {
+    sqlx::query_as::<_, UserIdentity>(
+        r#"
+        SELECT * FROM user_identities
+        WHERE organization_id = $1 AND issuer = $2 AND subject = $3
+        "#
+    )
+    .bind(organization_id)
+    .bind(issuer)
+    .bind(subject)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Link a provider identity to a user for an organization and add them to it if needed
+///
+/// Existing members keep their role, and a linked identity keeps whether it
+/// provisioned the account.
+pub async fn link_sso_identity(
+    pool: &PgPool,
+    user_id: Uuid,
+    identity: &SsoIdentity,
+    default_role: &MemberRole,
+) -> AppResult<()> {
+    let mut tx = pool.begin().await.map_err(AppError::from)?;
+    link_sso_identity_tx(&mut tx, user_id, identity, default_role, false).await?;
+    tx.commit().await.map_err(AppError::from)?;
+
+    Ok(())
+}
+
+/// Create a password-less user for a provider identity and add them to the organization
+pub async fn provision_sso_user(
+    pool: &PgPool,
+    identity: &SsoIdentity,
+    full_name: Option<&str>,
+    default_role: &MemberRole,
+) -> AppResult<User> {
+    let mut tx = pool.begin().await.map_err(AppError::from)?;
+
+    // The provider has vouched for the email address
+    let user = sqlx::query_as::<_, User>(
+        r#"
+        INSERT INTO users (email, full_name, email_verified_at)
+        VALUES ($1, $2, NOW())
+        RETURNING *
+        "#
+    )
+    .bind(&identity.email)
+    .bind(full_name)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+
+    link_sso_identity_tx(&mut tx, user.id, identity, default_role, true).await?;
+    tx.commit().await.map_err(AppError::from)?;
+
+    Ok(user)
+}
+
+async fn link_sso_identity_tx(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: Uuid,
+    identity: &SsoIdentity,
+    default_role: &MemberRole,
+    provisioned: bool,
+) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO user_identities (user_id, organization_id, issuer, subject, email, provisioned, last_login_at)
+        VALUES ($1, $2, $3, $4, $5, $6, NOW())
+        ON CONFLICT (organization_id, issuer, subject)
+        DO UPDATE SET email = EXCLUDED.email, last_login_at = NOW()
+        "#
+    )
+    .bind(user_id)
+    .bind(identity.organization_id)
+    .bind(&identity.issuer)
+    .bind(&identity.subject)
+    .bind(&identity.email)
+    .bind(provisioned)
+    .execute(&mut **tx)
+    .await
+    .map_err(AppError::from)?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO organization_members (organization_id, user_id, role)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (organization_id, user_id) DO NOTHING
+        "#
+    )
+    .bind(identity.organization_id)
+    .bind(user_id)
+    .bind(default_role)
+    .execute(&mut **tx)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(())
+}
+
+/// Store an identity waiting for the account owner to confirm the link,
+/// replacing their pending request for the same organization
+pub async fn create_sso_link_request(
+    pool: &PgPool,
+    token_hash: &str,
+    user_id: Uuid,
+    identity: &SsoIdentity,
+    expires_at: chrono::DateTime<chrono::Utc>,
+) -> AppResult<()> {
+    let mut tx = pool.begin().await.map_err(AppError::from)?;
+
+    sqlx::query("DELETE FROM sso_link_requests WHERE user_id = $1 AND organization_id = $2")
+        .bind(user_id)
+        .bind(identity.organization_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO sso_link_requests (token_hash, user_id, organization_id, issuer, subject, email, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#
+    )
+    .bind(token_hash)
+    .bind(user_id)
+    .bind(identity.organization_id)
+    .bind(&identity.issuer)
+    .bind(&identity.subject)
+    .bind(&identity.email)
+    .bind(expires_at)
+    .execute(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+
+    tx.commit().await.map_err(AppError::from)?;
+
+    Ok(())
+}
+
+/// Remove a pending identity link, if it has not expired
+pub async fn consume_sso_link_request(pool: &PgPool, token_hash: &str) -> AppResult<Option<SsoLinkRequest>> {
+    sqlx::query_as::<_, SsoLinkRequest>(
+        r#"
+        DELETE FROM sso_link_requests
+        WHERE token_hash = $1 AND expires_at > NOW()
+        RETURNING user_id, organization_id, issuer, subject, email
+        "#
+    )
+    .bind(token_hash)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Delete expired identity links
+pub async fn delete_expired_sso_link_requests(pool: &PgPool) -> AppResult<u64> {
+    let result = sqlx::query("DELETE FROM sso_link_requests WHERE expires_at <= NOW()")
+        .execute(pool)
+        .await
+        .map_err(AppError::from)?;
+
+    Ok(result.rows_affected())
+}
+
+// ============================================================================
+// Organization Queries
+// ============================================================================
+
+/// Find an organization by ID
+pub async fn find_organization_by_id(pool: &PgPool, id: Uuid) -> AppResult<Option<Organization>> {
+    sqlx::query_as::<_, Organization>(
+        "SELECT * FROM organizations WHERE id = $1"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Find an organization by slug
+pub async fn find_organization_by_slug(pool: &PgPool, slug: &str) -> AppResult<Option<Organization>> {
+    sqlx::query_as::<_, Organization>(
+        "SELECT * FROM organizations WHERE slug = $1"
+    )
+    .bind(slug)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Create a new organization
+pub async fn create_organization(
+    pool: &PgPool,
+    name: &str,
+    slug: &str,
+    owner_id: Uuid,
+) -> AppResult<Organization> {
+    let mut tx = pool.begin().await.map_err(AppError::from)?;
+
+    let org = sqlx::query_as::<_, Organization>(
+        r#"
+        INSERT INTO organizations (name, slug)
+        VALUES ($1, $2)
+        RETURNING *
+        "#
+    )
+    .bind(name)
+    .bind(slug)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+
+    // Add the owner as a member
+    sqlx::query(
+        r#"
+        INSERT INTO organization_members (organization_id, user_id, role)
+        VALUES ($1, $2, 'owner')
+        "#
+    )
+    .bind(org.id)
+    .bind(owner_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+
+    tx.commit().await.map_err(AppError::from)?;
+
+    Ok(org)
+}
+
+/// List organizations for a user
+pub async fn list_user_organizations(pool: &PgPool, user_id: Uuid) -> AppResult<Vec<Organization>> {
+    sqlx::query_as::<_, Organization>(
+        r#"
+        SELECT o.* FROM organizations o
+        INNER JOIN organization_members om ON o.id = om.organization_id
+        WHERE om.user_id = $1
+        ORDER BY o.created_at DESC
+        "#
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Get user's role in an organization
+pub async fn get_user_role(
+    pool: &PgPool,
+    organization_id: Uuid,
+    user_id: Uuid,
+) -> AppResult<Option<MemberRole>> {
+    let role = sqlx::query_scalar::<_, MemberRole>(
+        r#"
+        SELECT role FROM organization_members
+        WHERE organization_id = $1 AND user_id = $2
+        "#
+    )
+    .bind(organization_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(role)
+}
+
+/// Get a user's role in an organization together with the organization's 2FA policy
+pub async fn get_member_access(
+    pool: &PgPool,
+    organization_id: Uuid,
+    user_id: Uuid,
+    session_id: Option<Uuid>,
+) -> AppResult<Option<MemberAccess>> {
+    sqlx::query_as::<_, MemberAccess>(
+        r#"
+        SELECT
+            m.role,
+            r.permissions AS custom_permissions,
+            domain_group_subtree(m.domain_group_ids) AS domain_group_ids,
+            (o.require_two_factor AND u.totp_enabled_at IS NULL) AS two_factor_missing,
+            o.deleted_at IS NOT NULL AS organization_deleted,
+            COALESCE(c.enabled AND c.enforce_sso, false) AS sso_enforced,
+            EXISTS(
+                SELECT 1 FROM sessions s
+                WHERE s.id = $3 AND s.user_id = m.user_id AND s.sso_organization_id = o.id
+            ) AS sso_session
+        FROM organization_members m
+        JOIN organizations o ON o.id = m.organization_id
+        JOIN users u ON u.id = m.user_id
+        LEFT JOIN organization_roles r ON r.id = m.custom_role_id
+        LEFT JOIN sso_configs c ON c.organization_id = o.id
+        WHERE m.organization_id = $1 AND m.user_id = $2
+        "#
+    )
+    .bind(organization_id)
+    .bind(user_id)
+    .bind(session_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Check if any organization of the user requires two-factor authentication
+pub async fn user_requires_two_factor(pool: &PgPool, user_id: Uuid) -> AppResult<bool> {
+    sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT EXISTS(
+            SELECT 1 FROM organization_members m
+            JOIN organizations o ON o.id = m.organization_id
+            WHERE m.user_id = $1 AND o.require_two_factor
+        )
+        "#
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Check if user is member of organization
+pub async fn is_organization_member(
+    pool: &PgPool,
+    organization_id: Uuid,
+    user_id: Uuid,
+) -> AppResult<bool> {
+    let exists = sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT EXISTS(
+            SELECT 1 FROM organization_members
+            WHERE organization_id = $1 AND user_id = $2
+        )
+        "#
+    )
+    .bind(organization_id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(exists)
+}
+
+/// Schedule an organization for deletion after a grace period, cancelling any
+/// pending ownership transfer
+///
+/// Returns `None` if it is already scheduled.
+pub async fn schedule_organization_deletion(
+    pool: &PgPool,
+    organization_id: Uuid,
+    grace_days: i32,
+) -> AppResult<Option<Organization>> {
+    let mut tx = pool.begin().await.map_err(AppError::from)?;
+
+    let org = sqlx::query_as::<_, Organization>(
+        r#"
+        UPDATE organizations
+        SET deleted_at = NOW(), purge_after = NOW() + make_interval(days => $2)
+        WHERE id = $1 AND deleted_at IS NULL
+        RETURNING *
+        "#
+    )
+    .bind(organization_id)
+    .bind(grace_days)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+
+    sqlx::query("DELETE FROM ownership_transfers WHERE organization_id = $1")
+        .bind(organization_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(org)
+}
+
+/// Restore an organization scheduled for deletion
+///
+/// Returns `None` if it is not scheduled for deletion.
+pub async fn restore_organization(pool: &PgPool, organization_id: Uuid) -> AppResult<Option<Organization>> {
+    sqlx::query_as::<_, Organization>(
+        r#"
+        UPDATE organizations SET deleted_at = NULL, purge_after = NULL
+        WHERE id = $1 AND deleted_at IS NOT NULL
+        RETURNING *
+        "#
+    )
+    .bind(organization_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Permanently delete organizations whose deletion grace period is over,
+/// returning their number
+///
+/// Their domains, monitoring data and members are removed with them; audit
+/// events are kept.
+pub async fn purge_deleted_organizations(pool: &PgPool) -> AppResult<u64> {
+    let mut tx = pool.begin().await.map_err(AppError::from)?;
+
+    // Members go first: their custom role assignments would block deleting the roles
+    sqlx::query(
+        r#"
+        DELETE FROM organization_members
+        WHERE organization_id IN (
+            SELECT id FROM organizations WHERE deleted_at IS NOT NULL AND purge_after <= NOW()
+        )
+        "#
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+
+    let result = sqlx::query("DELETE FROM organizations WHERE deleted_at IS NOT NULL AND purge_after <= NOW()")
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(result.rows_affected())
+}
+
+// ============================================================================
+// Ownership Transfer Queries
+// ============================================================================
+
+/// Find the pending ownership transfer of an organization
+pub async fn find_ownership_transfer(pool: &PgPool, organization_id: Uuid) -> AppResult<Option<OwnershipTransfer>> {
+    sqlx::query_as::<_, OwnershipTransfer>("SELECT * FROM ownership_transfers WHERE organization_id = $1")
+        .bind(organization_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Start an ownership transfer, replacing any pending one
+pub async fn create_ownership_transfer(
+    pool: &PgPool,
+    organization_id: Uuid,
+    from_user_id: Uuid,
+    to_user_id: Uuid,
+    expires_at: chrono::DateTime<chrono::Utc>,
+) -> AppResult<OwnershipTransfer> {
+    sqlx::query_as::<_, OwnershipTransfer>(
+        r#"
+        INSERT INTO ownership_transfers (organization_id, from_user_id, to_user_id, expires_at)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (organization_id) DO UPDATE
+        SET from_user_id = $2, to_user_id = $3, expires_at = $4, created_at = NOW()
+        RETURNING *
+        "#
+    )
+    .bind(organization_id)
+    .bind(from_user_id)
+    .bind(to_user_id)
+    .bind(expires_at)
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Delete the pending ownership transfer of an organization
+pub async fn delete_ownership_transfer(pool: &PgPool, organization_id: Uuid) -> AppResult<bool> {
+    let result = sqlx::query("DELETE FROM ownership_transfers WHERE organization_id = $1")
+        .bind(organization_id)
+        .execute(pool)
+        .await
+        .map_err(AppError::from)?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Complete an ownership transfer: the previous owner becomes an admin and the
+/// new owner loses any custom role or domain group restriction
+///
+/// Returns `false`, changing nothing, if the transfer is gone, its sender is no
+/// longer the owner or its recipient no longer a member.
+pub async fn complete_ownership_transfer(pool: &PgPool, transfer: &OwnershipTransfer) -> AppResult<bool> {
+    let mut tx = pool.begin().await.map_err(AppError::from)?;
+
+    let deleted = sqlx::query("DELETE FROM ownership_transfers WHERE id = $1")
+        .bind(transfer.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+
+    let demoted = sqlx::query(
+        r#"
+        UPDATE organization_members
+        SET role = 'admin', custom_role_id = NULL, domain_group_ids = NULL
+        WHERE organization_id = $1 AND user_id = $2 AND role = 'owner'
+        "#
+    )
+    .bind(transfer.organization_id)
+    .bind(transfer.from_user_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+
+    let promoted = sqlx::query(
+        r#"
+        UPDATE organization_members
+        SET role = 'owner', custom_role_id = NULL, domain_group_ids = NULL
+        WHERE organization_id = $1 AND user_id = $2
+        "#
+    )
+    .bind(transfer.organization_id)
+    .bind(transfer.to_user_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+
+    if deleted.rows_affected() == 0 || demoted.rows_affected() == 0 || promoted.rows_affected() == 0 {
+        tx.rollback().await.map_err(AppError::from)?;
+        return Ok(false);
+    }
+
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(true)
+}
+
+// ============================================================================
+// Role & Permission Queries
+// ============================================================================
+
+/// List the custom roles of an organization
+pub async fn list_organization_roles(pool: &PgPool, organization_id: Uuid) -> AppResult<Vec<OrganizationRole>> {
+    sqlx::query_as::<_, OrganizationRole>(
+        "SELECT * FROM organization_roles WHERE organization_id = $1 ORDER BY name"
+    )
+    .bind(organization_id)
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Find a custom role of an organization
+pub async fn find_organization_role(
+    pool: &PgPool,
+    organization_id: Uuid,
+    role_id: Uuid,
+) -> AppResult<Option<OrganizationRole>> {
+    sqlx::query_as::<_, OrganizationRole>(
+        "SELECT * FROM organization_roles WHERE id = $1 AND organization_id = $2"
+    )
+    .bind(role_id)
+    .bind(organization_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Check if another custom role of the organization already uses a name
+pub async fn organization_role_name_taken(
+    pool: &PgPool,
+    organization_id: Uuid,
+    name: &str,
+    except_id: Option<Uuid>,
+) -> AppResult<bool> {
+    sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT EXISTS(
+            SELECT 1 FROM organization_roles
+            WHERE organization_id = $1 AND name = $2 AND id IS DISTINCT FROM $3
+        )
+        "#
+    )
+    .bind(organization_id)
+    .bind(name)
+    .bind(except_id)
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Create a custom role
+pub async fn create_organization_role(
+    pool: &PgPool,
+    organization_id: Uuid,
+    input: &UpsertOrganizationRole,
+) -> AppResult<OrganizationRole> {
+    sqlx::query_as::<_, OrganizationRole>(
+        r#"
+        INSERT INTO organization_roles (organization_id, name, description, permissions)
+        VALUES ($1, $2, $3, $4)
+        RETURNING *
+        "#
+    )
+    .bind(organization_id)
+    .bind(&input.name)
+    .bind(&input.description)
+    .bind(Permission::names(&input.permissions))
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Replace a custom role's name, description and permissions
+pub async fn update_organization_role(
+    pool: &PgPool,
+    organization_id: Uuid,
+    role_id: Uuid,
+    input: &UpsertOrganizationRole,
+) -> AppResult<Option<OrganizationRole>> {
+    sqlx::query_as::<_, OrganizationRole>(
+        r#"
+        UPDATE organization_roles
+        SET name = $3, description = $4, permissions = $5
+        WHERE id = $1 AND organization_id = $2
+        RETURNING *
+        "#
+    )
+    .bind(role_id)
+    .bind(organization_id)
+    .bind(&input.name)
+    .bind(&input.description)
+    .bind(Permission::names(&input.permissions))
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Number of members a custom role is assigned to
+pub async fn count_role_members(pool: &PgPool, role_id: Uuid) -> AppResult<i64> {
+    sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM organization_members WHERE custom_role_id = $1"
+    )
+    .bind(role_id)
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Delete a custom role, returning whether it existed
+pub async fn delete_organization_role(pool: &PgPool, organization_id: Uuid, role_id: Uuid) -> AppResult<bool> {
+    let result = sqlx::query("DELETE FROM organization_roles WHERE id = $1 AND organization_id = $2")
+        .bind(role_id)
+        .bind(organization_id)
+        .execute(pool)
+        .await
+        .map_err(AppError::from)?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// List the members of an organization with their email and name
+///
+/// Returns up to `limit` members after the member `after` in the requested
+/// order, and the number of members matching the filters.
+pub async fn list_organization_members(
+    pool: &PgPool,
+    organization_id: Uuid,
+    filter: &MemberFilter,
+    after: Option<Uuid>,
+    limit: i64,
+) -> AppResult<(Vec<MemberWithUser>, i64)> {
+    let rows = sqlx::query_as::<_, Counted<MemberWithUser>>(
+        r#"
+        WITH matching AS (
+            SELECT om.*, u.email, u.full_name
+            FROM organization_members om
+            INNER JOIN users u ON u.id = om.user_id
+            WHERE om.organization_id = $1
+              AND ($2::varchar IS NULL OR om.role = $2)
+              AND ($3::text IS NULL OR u.email ILIKE '%' || $3 || '%' OR u.full_name ILIKE '%' || $3 || '%')
+        ),
+        after AS (SELECT * FROM matching WHERE id = $4)
+        SELECT m.*, (SELECT COUNT(*) FROM matching) AS total
+        FROM matching m
+        WHERE $4::uuid IS NULL OR CASE $5::text
+            WHEN '-created_at' THEN (m.created_at, m.id) < (SELECT created_at, id FROM after)
+            WHEN 'email' THEN (m.email, m.id) > (SELECT email, id FROM after)
+            WHEN '-email' THEN (m.email, m.id) < (SELECT email, id FROM after)
+            ELSE (m.created_at, m.id) > (SELECT created_at, id FROM after)
+        END
+        ORDER BY
+            CASE WHEN $5 = '-created_at' THEN m.created_at END DESC,
+            CASE WHEN $5 = 'email' THEN m.email END,
+            CASE WHEN $5 = '-email' THEN m.email END DESC,
+            CASE WHEN $5 IN ('-created_at', '-email') THEN m.id END DESC,
+            CASE WHEN $5 = 'email' THEN m.id END,
+            m.created_at,
+            m.id
+        LIMIT $6
+        "#
+    )
+    .bind(organization_id)
+    .bind(filter.role.as_ref())
+    .bind(&filter.q)
+    .bind(after)
+    .bind(filter.sort.unwrap_or_default().as_str())
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(split_total(rows))
+}
+
+/// Find a member of an organization
+pub async fn find_organization_member(
+    pool: &PgPool,
+    organization_id: Uuid,
+    user_id: Uuid,
+) -> AppResult<Option<OrganizationMember>> {
+    sqlx::query_as::<_, OrganizationMember>(
+        "SELECT * FROM organization_members WHERE organization_id = $1 AND user_id = $2"
+    )
+    .bind(organization_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Set a member's built-in role and custom role
+pub async fn update_member_role(
+    pool: &PgPool,
+    organization_id: Uuid,
+    user_id: Uuid,
+    role: &MemberRole,
+    custom_role_id: Option<Uuid>,
+) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        UPDATE organization_members SET role = $3, custom_role_id = $4
+        WHERE organization_id = $1 AND user_id = $2
+        "#
+    )
+    .bind(organization_id)
+    .bind(user_id)
+    .bind(role)
+    .bind(custom_role_id)
+    .execute(pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(())
+}
+
+/// Restrict a member to domain groups, `None` lifting the restriction
+pub async fn set_member_domain_groups(
+    pool: &PgPool,
+    organization_id: Uuid,
+    user_id: Uuid,
+    group_ids: Option<&[Uuid]>,
+) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        UPDATE organization_members SET domain_group_ids = $3
+        WHERE organization_id = $1 AND user_id = $2
+        "#
+    )
+    .bind(organization_id)
+    .bind(user_id)
+    .bind(group_ids)
+    .execute(pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(())
+}
+
+// ============================================================================
+// Invitation Queries
+// ============================================================================
+
+/// Check if a user with the given email address is a member of an organization
+pub async fn is_organization_member_email(
+    pool: &PgPool,
+    organization_id: Uuid,
+    email: &str,
+) -> AppResult<bool> {
+    sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT EXISTS(
+            SELECT 1 FROM organization_members om
+            INNER JOIN users u ON u.id = om.user_id
+            WHERE om.organization_id = $1 AND LOWER(u.email) = LOWER($2)
+        )
+        "#
+    )
+    .bind(organization_id)
+    .bind(email)
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Find an invitation of an organization
+pub async fn find_invitation(
+    pool: &PgPool,
+    organization_id: Uuid,
+    invitation_id: Uuid,
+) -> AppResult<Option<Invitation>> {
+    sqlx::query_as::<_, Invitation>(
+        "SELECT * FROM organization_invitations WHERE organization_id = $1 AND id = $2"
+    )
+    .bind(organization_id)
+    .bind(invitation_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Find the open (neither accepted nor revoked) invitation of an email address
+pub async fn find_open_invitation(
+    pool: &PgPool,
+    organization_id: Uuid,
+    email: &str,
+) -> AppResult<Option<Invitation>> {
+    sqlx::query_as::<_, Invitation>(
+        r#"
+        SELECT * FROM organization_invitations
+        WHERE organization_id = $1 AND LOWER(email) = LOWER($2)
+          AND accepted_at IS NULL AND revoked_at IS NULL
+        "#
+    )
+    .bind(organization_id)
+    .bind(email)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Create an invitation, revoking an expired open invitation of the same email address
+pub async fn create_invitation(
+    pool: &PgPool,
+    organization_id: Uuid,
+    email: &str,
+    role: &MemberRole,
+    token_hash: &str,
+    invited_by: Uuid,
+    expires_at: chrono::DateTime<chrono::Utc>,
+) -> AppResult<Invitation> {
+    let mut tx = pool.begin().await.map_err(AppError::from)?;
+
+    sqlx::query(
+        r#"
+        UPDATE organization_invitations SET revoked_at = NOW()
+        WHERE organization_id = $1 AND LOWER(email) = LOWER($2) AND expires_at <= NOW()
+          AND accepted_at IS NULL AND revoked_at IS NULL
+        "#
+    )
+    .bind(organization_id)
+    .bind(email)
+    .execute(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+
+    let invitation = sqlx::query_as::<_, Invitation>(
+        r#"
+        INSERT INTO organization_invitations (organization_id, email, role, token_hash, invited_by, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING *
+        "#
+    )
+    .bind(organization_id)
+    .bind(email)
+    .bind(role)
+    .bind(token_hash)
+    .bind(invited_by)
+    .bind(expires_at)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+
+    tx.commit().await.map_err(AppError::from)?;
+
+    Ok(invitation)
+}
+
+/// List open invitations of an organization, including expired ones
+pub async fn list_open_invitations(pool: &PgPool, organization_id: Uuid) -> AppResult<Vec<Invitation>> {
+    sqlx::query_as::<_, Invitation>(
+        r#"
+        SELECT * FROM organization_invitations
+        WHERE organization_id = $1 AND accepted_at IS NULL AND revoked_at IS NULL
+        ORDER BY created_at DESC
+        "#
+    )
+    .bind(organization_id)
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Replace the token of an open invitation and extend its expiry
+pub async fn renew_invitation(
+    pool: &PgPool,
+    organization_id: Uuid,
+    invitation_id: Uuid,
+    token_hash: &str,
+    expires_at: chrono::DateTime<chrono::Utc>,
+) -> AppResult<Option<Invitation>> {
+    sqlx::query_as::<_, Invitation>(
+        r#"
+        UPDATE organization_invitations
+        SET token_hash = $3, expires_at = $4, sent_at = NOW()
+        WHERE organization_id = $1 AND id = $2 AND accepted_at IS NULL AND revoked_at IS NULL
+        RETURNING *
+        "#
+    )
+    .bind(organization_id)
+    .bind(invitation_id)
+    .bind(token_hash)
+    .bind(expires_at)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Revoke an open invitation
+pub async fn revoke_invitation(pool: &PgPool, organization_id: Uuid, invitation_id: Uuid) -> AppResult<bool> {
+    let result = sqlx::query(
+        r#"
+        UPDATE organization_invitations SET revoked_at = NOW()
+        WHERE organization_id = $1 AND id = $2 AND accepted_at IS NULL AND revoked_at IS NULL
+        "#
+    )
+    .bind(organization_id)
+    .bind(invitation_id)
+    .execute(pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Find the open, unexpired invitation with the given token
+pub async fn find_invitation_by_token(pool: &PgPool, token_hash: &str) -> AppResult<Option<Invitation>> {
+    sqlx::query_as::<_, Invitation>(
+        r#"
+        SELECT * FROM organization_invitations
+        WHERE token_hash = $1 AND expires_at > NOW() AND accepted_at IS NULL AND revoked_at IS NULL
+        "#
+    )
+    .bind(token_hash)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Accept an invitation on behalf of an existing user, adding them to the organization
+///
+/// Returns `None` unless the invitation is open, unexpired and addressed to the user's email.
+pub async fn accept_invitation(pool: &PgPool, token_hash: &str, user: &User) -> AppResult<Option<Invitation>> {
+    let mut tx = pool.begin().await.map_err(AppError::from)?;
+
+    let invitation = accept_invitation_tx(&mut tx, token_hash, user).await?;
+    tx.commit().await.map_err(AppError::from)?;
+
+    Ok(invitation)
+}
+
+/// Register a user through an invitation and add them to the organization
+///
+/// The invitation proves ownership of the email address, so it is marked verified.
+/// Returns `None`, without creating the user, unless the invitation is open,
+/// unexpired and addressed to `email`.
+pub async fn register_invited_user(
+    pool: &PgPool,
+    token_hash: &str,
+    email: &str,
+    password_hash: &str,
+    full_name: Option<&str>,
+) -> AppResult<Option<(User, Invitation)>> {
+    let mut tx = pool.begin().await.map_err(AppError::from)?;
+
+    let user = sqlx::query_as::<_, User>(
+        r#"
+        INSERT INTO users (email, password_hash, full_name, email_verified_at)
+        VALUES ($1, $2, $3, NOW())
+        RETURNING *
+        "#
+    )
+    .bind(email)
+    .bind(password_hash)
+    .bind(full_name)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+
+    // Dropping the transaction rolls the user back
+    let Some(invitation) = accept_invitation_tx(&mut tx, token_hash, &user).await? else {
+        return Ok(None);
+    };
+    tx.commit().await.map_err(AppError::from)?;
+
+    Ok(Some((user, invitation)))
+}
+
+async fn accept_invitation_tx(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    token_hash: &str,
+    user: &User,
+) -> AppResult<Option<Invitation>> {
+    let invitation = sqlx::query_as::<_, Invitation>(
+        r#"
+        UPDATE organization_invitations SET accepted_at = NOW(), accepted_by = $2
+        WHERE token_hash = $1 AND LOWER(email) = LOWER($3) AND expires_at > NOW()
+          AND accepted_at IS NULL AND revoked_at IS NULL
+        RETURNING *
+        "#
+    )
+    .bind(token_hash)
+    .bind(user.id)
+    .bind(&user.email)
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(AppError::from)?;
+
+    let Some(invitation) = invitation else {
+        return Ok(None);
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO organization_members (organization_id, user_id, role)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (organization_id, user_id) DO NOTHING
+        "#
+    )
+    .bind(invitation.organization_id)
+    .bind(user.id)
+    .bind(&invitation.role)
+    .execute(&mut **tx)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(Some(invitation))
+}
+
+/// Delete invitations that expired more than 30 days ago without being accepted
+pub async fn delete_expired_invitations(pool: &PgPool) -> AppResult<u64> {
+    let result = sqlx::query(
+        r#"
+        DELETE FROM organization_invitations
+        WHERE accepted_at IS NULL AND expires_at <= NOW() - INTERVAL '30 days'
+        "#
+    )
+    .execute(pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(result.rows_affected())
+}
+
+// ============================================================================
+// Domain Queries
+// ============================================================================
+
+/// List domains for an organization, optionally restricted to some groups
+/// (`group_ids`, subgroups already included), one group subtree and tags
+pub async fn list_organization_domains(
+    pool: &PgPool,
+    organization_id: Uuid,
+    group_ids: Option<&[Uuid]>,
+    group_id: Option<Uuid>,
+    tags: &TagSelector,
+) -> AppResult<Vec<Domain>> {
+    sqlx::query_as::<_, Domain>(
+        r#"
+        SELECT * FROM domains d
+        WHERE d.organization_id = $1
+          AND ($2::uuid[] IS NULL OR d.group_id = ANY($2))
+          AND ($3::uuid IS NULL OR d.group_id = ANY(domain_group_subtree(ARRAY[$3]::uuid[])))
+          AND ($4::jsonb IS NULL OR d.tags @> $4)
+          AND ($5::text[] IS NULL OR d.tags ?& $5)
+        ORDER BY d.created_at DESC
+        "#
+    )
+    .bind(organization_id)
+    .bind(group_ids)
+    .bind(group_id)
+    .bind(tags.values_json())
+    .bind(tags.keys())
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// URLs among `urls` already used by a domain of the organization
+pub async fn existing_domain_urls(pool: &PgPool, organization_id: Uuid, urls: &[String]) -> AppResult<Vec<String>> {
+    sqlx::query_scalar::<_, String>(
+        "SELECT url FROM domains WHERE organization_id = $1 AND url = ANY($2)"
+    )
+    .bind(organization_id)
+    .bind(urls)
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Find a domain by ID
+pub async fn find_domain_by_id(pool: &PgPool, id: Uuid) -> AppResult<Option<Domain>> {
+    sqlx::query_as::<_, Domain>(
+        "SELECT * FROM domains WHERE id = $1"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Create domains along with their monitors
+///
+/// Either every domain is created or none is. Returns `None` when a domain of
+/// the organization already uses one of the URLs, and fails when the monitors
+/// don't fit in the organization's quota.
+pub async fn create_domains(
+    pool: &PgPool,
+    organization_id: Uuid,
+    domains: &[NewDomain],
+) -> AppResult<Option<Vec<Domain>>> {
+    let mut tx = pool.begin().await.map_err(AppError::from)?;
+    let quota = lock_monitor_quota(&mut tx, organization_id).await?;
+    let monitors = domains.iter().map(|d| d.monitors.len() as i64).sum();
+    crate::quota::check_monitor_quota(&quota, monitors)?;
+
+    let mut created = Vec::with_capacity(domains.len());
+
+    for new in domains {
+        // name and normalized_name predate display_name and url and are kept in sync
+        let domain = sqlx::query_as::<_, Domain>(
+            r#"
+            INSERT INTO domains (organization_id, name, normalized_name, display_name, url, group_id, tags)
+            VALUES ($1, $2, $3, $2, $3, $4, $5)
+            ON CONFLICT (organization_id, url) DO NOTHING
+            RETURNING *
+            "#
+        )
+        .bind(organization_id)
+        .bind(&new.display_name)
+        .bind(&new.url)
+        .bind(new.group_id)
+        .bind(sqlx::types::Json(&new.tags))
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+
+        let Some(domain) = domain else {
+            return Ok(None);
+        };
+
+        for monitor in &new.monitors {
+            sqlx::query("INSERT INTO monitors (domain_id, type, is_enabled, config) VALUES ($1, $2, $3, $4)")
+                .bind(domain.id)
+                .bind(&monitor.monitor_type)
+                .bind(monitor.is_enabled)
+                .bind(&monitor.config)
+                .execute(&mut *tx)
+                .await
+                .map_err(AppError::from)?;
+        }
+        created.push(domain);
+    }
+
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(Some(created))
+}
+
+/// Update a domain, all in one transaction
+///
+/// Changing the URL bumps the `updated_at` of the domain's monitors so that the
+/// scheduler checks the new address right away. Returns `None` when another
+/// domain of the organization already uses the URL.
+pub async fn update_domain(
+    pool: &PgPool,
+    domain_id: Uuid,
+    input: &UpdateDomain,
+) -> AppResult<Option<Domain>> {
+    let mut tx = pool.begin().await.map_err(AppError::from)?;
+
+    // name and normalized_name predate display_name and url and are kept in sync
+    let domain = sqlx::query_as::<_, Domain>(
+        r#"
+        UPDATE domains
+        SET is_active = COALESCE($2, is_active),
+            display_name = COALESCE($3, display_name),
+            name = COALESCE($3, name),
+            url = COALESCE($4, url),
+            normalized_name = COALESCE($4, normalized_name),
+            group_id = CASE WHEN $5 THEN $6 ELSE group_id END,
+            tags = COALESCE($7, tags)
+        WHERE id = $1
+          AND NOT EXISTS (
+              SELECT 1 FROM domains other
+              WHERE other.organization_id = domains.organization_id
+                AND other.url = $4 AND other.id <> domains.id
+          )
+        RETURNING *
+        "#
+    )
+    .bind(domain_id)
+    .bind(input.is_active)
+    .bind(&input.display_name)
+    .bind(&input.url)
+    .bind(input.group_id.is_some())
+    .bind(input.group_id.flatten())
+    .bind(input.tags.as_ref().map(sqlx::types::Json))
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+
+    let Some(domain) = domain else {
+        return Ok(None);
+    };
+
+    if input.url.is_some() {
+        sqlx::query("UPDATE monitors SET updated_at = NOW() WHERE domain_id = $1")
+            .bind(domain_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::from)?;
+    }
+
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(Some(domain))
+}
+
+/// Delete a domain
+pub async fn delete_domain(pool: &PgPool, domain_id: Uuid) -> AppResult<()> {
+    sqlx::query("DELETE FROM domains WHERE id = $1")
+        .bind(domain_id)
+        .execute(pool)
+        .await
+        .map_err(AppError::from)?;
+
+    Ok(())
+}
+
+/// Find domains of an organization by ID
+pub async fn find_organization_domains(
+    pool: &PgPool,
+    organization_id: Uuid,
+    domain_ids: &[Uuid],
+) -> AppResult<Vec<Domain>> {
+    sqlx::query_as::<_, Domain>(
+        "SELECT * FROM domains WHERE organization_id = $1 AND id = ANY($2) ORDER BY created_at DESC"
+    )
+    .bind(organization_id)
+    .bind(domain_ids)
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Replace the tags of domains, all in one transaction
+pub async fn set_domain_tags(pool: &PgPool, updates: &[(Uuid, Tags)]) -> AppResult<()> {
+    let mut tx = pool.begin().await.map_err(AppError::from)?;
+
+    for (domain_id, tags) in updates {
+        sqlx::query("UPDATE domains SET tags = $1 WHERE id = $2")
+            .bind(sqlx::types::Json(tags))
+            .bind(domain_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::from)?;
+    }
+
+    tx.commit().await.map_err(AppError::from)
+}
+
+// ============================================================================
+// Domain Group Queries
+// ============================================================================
+
+/// List the domain groups of an organization, optionally only the given ones
+pub async fn list_domain_groups(
+    pool: &PgPool,
+    organization_id: Uuid,
+    group_ids: Option<&[Uuid]>,
+) -> AppResult<Vec<DomainGroup>> {
+    sqlx::query_as::<_, DomainGroup>(
+        r#"
+        SELECT * FROM domain_groups
+        WHERE organization_id = $1 AND ($2::uuid[] IS NULL OR id = ANY($2))
+        ORDER BY name
+        "#
+    )
+    .bind(organization_id)
+    .bind(group_ids)
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Find a domain group of an organization
+pub async fn find_domain_group(
+    pool: &PgPool,
+    organization_id: Uuid,
+    group_id: Uuid,
+) -> AppResult<Option<DomainGroup>> {
+    sqlx::query_as::<_, DomainGroup>(
+        "SELECT * FROM domain_groups WHERE id = $1 AND organization_id = $2"
+    )
+    .bind(group_id)
+    .bind(organization_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Count how many of the given groups belong to an organization
+pub async fn count_domain_groups(pool: &PgPool, organization_id: Uuid, group_ids: &[Uuid]) -> AppResult<i64> {
+    sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM domain_groups WHERE organization_id = $1 AND id = ANY($2)"
+    )
+    .bind(organization_id)
+    .bind(group_ids)
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Check if another group of the organization already uses a name
+pub async fn domain_group_name_taken(
+    pool: &PgPool,
+    organization_id: Uuid,
+    name: &str,
+    except_id: Option<Uuid>,
+) -> AppResult<bool> {
+    sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT EXISTS(
+            SELECT 1 FROM domain_groups
+            WHERE organization_id = $1 AND name = $2 AND id IS DISTINCT FROM $3
+        )
+        "#
+    )
+    .bind(organization_id)
+    .bind(name)
+    .bind(except_id)
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Check if a group is one of the groups under `root_id`, or `root_id` itself
+pub async fn domain_group_in_subtree(pool: &PgPool, root_id: Uuid, group_id: Uuid) -> AppResult<bool> {
+    sqlx::query_scalar::<_, bool>("SELECT $2 = ANY(domain_group_subtree(ARRAY[$1]::uuid[]))")
+        .bind(root_id)
+        .bind(group_id)
+        .fetch_one(pool)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Create a domain group
+pub async fn create_domain_group(
+    pool: &PgPool,
+    organization_id: Uuid,
+    name: &str,
+    parent_id: Option<Uuid>,
+) -> AppResult<DomainGroup> {
+    sqlx::query_as::<_, DomainGroup>(
+        "INSERT INTO domain_groups (organization_id, name, parent_id) VALUES ($1, $2, $3) RETURNING *"
+    )
+    .bind(organization_id)
+    .bind(name)
+    .bind(parent_id)
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Rename a domain group and move it under another parent
+pub async fn update_domain_group(
+    pool: &PgPool,
+    organization_id: Uuid,
+    group_id: Uuid,
+    name: &str,
+    parent_id: Option<Uuid>,
+) -> AppResult<Option<DomainGroup>> {
+    sqlx::query_as::<_, DomainGroup>(
+        r#"
+        UPDATE domain_groups SET name = $3, parent_id = $4
+        WHERE id = $1 AND organization_id = $2
+        RETURNING *
+        "#
+    )
+    .bind(group_id)
+    .bind(organization_id)
+    .bind(name)
+    .bind(parent_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Delete a domain group, returning whether it existed
+///
+/// Its domains are left ungrouped, its subgroups move up to its parent and the
+/// group is removed from member restrictions, in the same transaction.
+pub async fn delete_domain_group(pool: &PgPool, organization_id: Uuid, group_id: Uuid) -> AppResult<bool> {
+    let mut tx = pool.begin().await.map_err(AppError::from)?;
+
+    if !remove_domain_group(&mut tx, organization_id, group_id).await? {
+        return Ok(false);
+    }
+
+    tx.commit().await.map_err(AppError::from)?;
+
+    Ok(true)
+}
+
+/// Delete a domain group as part of a transaction (see [`delete_domain_group`])
+async fn remove_domain_group(conn: &mut PgConnection, organization_id: Uuid, group_id: Uuid) -> AppResult<bool> {
+    sqlx::query(
+        r#"
+        UPDATE domain_groups SET parent_id = (SELECT parent_id FROM domain_groups WHERE id = $1)
+        WHERE parent_id = $1
+        "#
+    )
+    .bind(group_id)
+    .execute(&mut *conn)
+    .await
+    .map_err(AppError::from)?;
+
+    let result = sqlx::query("DELETE FROM domain_groups WHERE id = $1 AND organization_id = $2")
+        .bind(group_id)
+        .bind(organization_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::from)?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE organization_members SET domain_group_ids = array_remove(domain_group_ids, $2)
+        WHERE organization_id = $1 AND $2 = ANY(domain_group_ids)
+        "#
+    )
+    .bind(organization_id)
+    .bind(group_id)
+    .execute(&mut *conn)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(true)
+}
+
+// ============================================================================
+// Monitor Queries
+// ============================================================================
+
+/// List monitors for a domain
+pub async fn list_domain_monitors(pool: &PgPool, domain_id: Uuid) -> AppResult<Vec<Monitor>> {
+    sqlx::query_as::<_, Monitor>(
+        "SELECT * FROM monitors WHERE domain_id = $1"
+    )
+    .bind(domain_id)
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Find a monitor by ID
+pub async fn find_monitor_by_id(pool: &PgPool, id: Uuid) -> AppResult<Option<Monitor>> {
+    sqlx::query_as::<_, Monitor>(
+        "SELECT * FROM monitors WHERE id = $1"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Create or update a monitor
+pub async fn upsert_monitor(
+    pool: &PgPool,
+    domain_id: Uuid,
+    monitor_type: MonitorType,
+    is_enabled: bool,
+    config: &serde_json::Value,
+) -> AppResult<Monitor> {
+    let monitor = sqlx::query_as::<_, Monitor>(
+        r#"
+        INSERT INTO monitors (domain_id, type, is_enabled, config)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (domain_id, type)
+        DO UPDATE SET is_enabled = $3, config = $4, updated_at = NOW()
+        RETURNING *
+        "#
+    )
+    .bind(domain_id)
+    .bind(monitor_type)
+    .bind(is_enabled)
+    .bind(config)
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(monitor)
+}
+
+/// Find a monitor of a domain
+pub async fn find_domain_monitor(pool: &PgPool, domain_id: Uuid, monitor_id: Uuid) -> AppResult<Option<Monitor>> {
+    sqlx::query_as::<_, Monitor>(
+        "SELECT * FROM monitors WHERE id = $1 AND domain_id = $2"
+    )
+    .bind(monitor_id)
+    .bind(domain_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Create a monitor, returning `None` if the domain already has one of this type
+///
+/// Fails when the organization's monitor quota is used up.
+pub async fn create_monitor(
+    pool: &PgPool,
+    organization_id: Uuid,
+    domain_id: Uuid,
+    input: &CreateMonitor,
+) -> AppResult<Option<Monitor>> {
+    let mut tx = pool.begin().await.map_err(AppError::from)?;
+    let quota = lock_monitor_quota(&mut tx, organization_id).await?;
+    crate::quota::check_monitor_quota(&quota, 1)?;
+
+    let monitor = sqlx::query_as::<_, Monitor>(
+        r#"
+        INSERT INTO monitors (domain_id, type, is_enabled, config)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (domain_id, type) DO NOTHING
+        RETURNING *
+        "#
+    )
+    .bind(domain_id)
+    .bind(&input.monitor_type)
+    .bind(input.is_enabled.unwrap_or(true))
+    .bind(&input.config)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(monitor)
+}
+
+/// Update monitor
+pub async fn update_monitor(
+    pool: &PgPool,
+    monitor_id: Uuid,
+    is_enabled: Option<bool>,
+    config: Option<&serde_json::Value>,
+) -> AppResult<Option<Monitor>> {
+    sqlx::query_as::<_, Monitor>(
+        r#"
+        UPDATE monitors
+        SET is_enabled = COALESCE($1, is_enabled),
+            config = COALESCE($2, config),
+            updated_at = NOW()
+        WHERE id = $3
+        RETURNING *
+        "#
+    )
+    .bind(is_enabled)
+    .bind(config)
+    .bind(monitor_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Pause a monitor until the given time, or resume it with `None`
+pub async fn set_monitor_paused_until(
+    pool: &PgPool,
+    monitor_id: Uuid,
+    paused_until: Option<chrono::DateTime<chrono::Utc>>,
+) -> AppResult<Option<Monitor>> {
+    sqlx::query_as::<_, Monitor>(
+        "UPDATE monitors SET paused_until = $2, updated_at = NOW() WHERE id = $1 RETURNING *"
+    )
+    .bind(monitor_id)
+    .bind(paused_until)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Delete a monitor
+pub async fn delete_monitor(pool: &PgPool, monitor_id: Uuid) -> AppResult<()> {
+    sqlx::query("DELETE FROM monitors WHERE id = $1")
+        .bind(monitor_id)
+        .execute(pool)
+        .await
+        .map_err(AppError::from)?;
+
+    Ok(())
+}
+
+/// Get all active monitors with their domain information
+pub async fn get_all_active_monitors(pool: &PgPool) -> AppResult<Vec<Monitor>> {
+    sqlx::query_as::<_, Monitor>(
+        r#"
+        SELECT m.*, d.name as "domain_name?"
+        FROM monitors m
+        INNER JOIN domains d ON d.id = m.domain_id
+        INNER JOIN organizations o ON o.id = d.organization_id
+        WHERE m.is_enabled = true AND d.is_active = true
+          AND o.deleted_at IS NULL
+        ORDER BY m.created_at ASC
+        "#
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// List all monitors due to be scheduled: enabled, not paused, on an active
+/// domain of an organization that is not deleted
+pub async fn list_all_active_monitors(pool: &PgPool) -> AppResult<Vec<ScheduledMonitor>> {
+    sqlx::query_as::<_, ScheduledMonitor>(
+        r#"
+        SELECT m.*, d.url, o.min_check_interval_seconds
+        FROM monitors m
+        INNER JOIN domains d ON d.id = m.domain_id
+        INNER JOIN organizations o ON o.id = d.organization_id
+        WHERE m.is_enabled = true AND d.is_active = true
+          AND (m.paused_until IS NULL OR m.paused_until <= NOW())
+          AND o.deleted_at IS NULL
+        ORDER BY m.created_at ASC
+        "#
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// List the monitors of all domains of an organization
+pub async fn list_organization_monitors(pool: &PgPool, organization_id: Uuid) -> AppResult<Vec<Monitor>> {
+    sqlx::query_as::<_, Monitor>(
+        r#"
+        SELECT m.* FROM monitors m
+        JOIN domains d ON d.id = m.domain_id
+        WHERE d.organization_id = $1
+        ORDER BY m.type
+        "#
+    )
+    .bind(organization_id)
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Count the monitors of an organization's domains per type
+pub async fn count_monitors_by_type(pool: &PgPool, organization_id: Uuid) -> AppResult<Vec<(MonitorType, i64)>> {
+    sqlx::query_as::<_, (MonitorType, i64)>(
+        r#"
+        SELECT m.type, COUNT(*)
+        FROM monitors m
+        INNER JOIN domains d ON d.id = m.domain_id
+        WHERE d.organization_id = $1
+        GROUP BY m.type
+        ORDER BY m.type
+        "#
+    )
+    .bind(organization_id)
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Lock an organization's row until the end of the transaction and count its monitors
+///
+/// Every transaction creating monitors takes this lock first, so the count
+/// includes the monitors of any concurrent creation that committed meanwhile.
+async fn lock_monitor_quota(conn: &mut PgConnection, organization_id: Uuid) -> AppResult<QuotaUsage> {
+    let limit = lock_organization_quotas(conn, organization_id).await?.max_monitors;
+    // A separate statement, so that it sees what was committed while waiting for the lock
+    let used = count_monitors(conn, organization_id).await?;
+
+    Ok(QuotaUsage { used, limit: i64::from(limit) })
+}
+
+/// Lock an organization's row until the end of the transaction, returning its limits
+async fn lock_organization_quotas(conn: &mut PgConnection, organization_id: Uuid) -> AppResult<Organization> {
+    sqlx::query_as::<_, Organization>("SELECT * FROM organizations WHERE id = $1 FOR UPDATE")
+        .bind(organization_id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(AppError::from)?
+        .ok_or_else(|| AppError::not_found("Organization not found"))
+}
+
+async fn count_monitors(conn: &mut PgConnection, organization_id: Uuid) -> AppResult<i64> {
+    sqlx::query_scalar::<_, i64>(
+        r#"
+        SELECT COUNT(*)
+        FROM monitors m
+        INNER JOIN domains d ON d.id = m.domain_id
+        WHERE d.organization_id = $1
+        "#
+    )
+    .bind(organization_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(AppError::from)
+}
+
+// ============================================================================
+// Manifest Queries
+// ============================================================================
+
+/// Apply the operations planned from a manifest, all in one transaction
+///
+/// Fails when the organization ends up with more monitors than before and than
+/// its quota allows, whatever changed since the operations were planned.
+pub async fn apply_manifest(pool: &PgPool, organization_id: Uuid, ops: &ManifestOps) -> AppResult<()> {
+    let mut tx = pool.begin().await.map_err(AppError::from)?;
+    let quota = lock_monitor_quota(&mut tx, organization_id).await?;
+
+    for name in &ops.create_groups {
+        sqlx::query("INSERT INTO domain_groups (organization_id, name) VALUES ($1, $2)")
+            .bind(organization_id)
+            .bind(name)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::from)?;
+    }
+
+    let groups: HashMap<String, Uuid> = sqlx::query_as::<_, (String, Uuid)>(
+        "SELECT name, id FROM domain_groups WHERE organization_id = $1"
+    )
+    .bind(organization_id)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(AppError::from)?
+    .into_iter()
+    .collect();
+    let group_id = |name: &Option<String>| name.as_ref().and_then(|n| groups.get(n).copied());
+
+    for (name, parent) in &ops.group_parents {
+        sqlx::query("UPDATE domain_groups SET parent_id = $3 WHERE organization_id = $1 AND name = $2")
+            .bind(organization_id)
+            .bind(name)
+            .bind(group_id(parent))
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::from)?;
+    }
+
+    for domain_id in &ops.delete_domains {
+        sqlx::query("DELETE FROM domains WHERE id = $1 AND organization_id = $2")
+            .bind(domain_id)
+            .bind(organization_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::from)?;
+    }
+
+    for op in &ops.upsert_domains {
+        let new = &op.domain;
+        let domain_id = match op.id {
+            Some(domain_id) => {
+                sqlx::query(
+                    r#"
+                    UPDATE domains SET name = $3, display_name = $3, group_id = $4, is_active = $5, tags = $6
+                    WHERE id = $1 AND organization_id = $2
+                    "#
+                )
+                .bind(domain_id)
+                .bind(organization_id)
+                .bind(&new.display_name)
+                .bind(group_id(&op.group))
+                .bind(op.is_active)
+                .bind(sqlx::types::Json(&new.tags))
+                .execute(&mut *tx)
+                .await
+                .map_err(AppError::from)?;
+                domain_id
+            }
+            None => sqlx::query_scalar::<_, Uuid>(
+                r#"
+                INSERT INTO domains (organization_id, name, normalized_name, display_name, url, group_id, is_active, tags)
+                VALUES ($1, $2, $3, $2, $3, $4, $5, $6)
+                RETURNING id
+                "#
+            )
+            .bind(organization_id)
+            .bind(&new.display_name)
+            .bind(&new.url)
+            .bind(group_id(&op.group))
+            .bind(op.is_active)
+            .bind(sqlx::types::Json(&new.tags))
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(AppError::from)?,
+        };
+
+        if !op.replace_monitors {
+            continue;
+        }
+        // Monitors are updated in place so that their history is kept
+        let types: Vec<String> = new.monitors.iter().map(|m| m.monitor_type.to_string()).collect();
+        sqlx::query("DELETE FROM monitors WHERE domain_id = $1 AND NOT (type = ANY($2))")
+            .bind(domain_id)
+            .bind(&types)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::from)?;
+
+        for monitor in &new.monitors {
+            sqlx::query(
+                r#"
+                INSERT INTO monitors (domain_id, type, is_enabled, config)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (domain_id, type)
+                DO UPDATE SET is_enabled = $3, config = $4, updated_at = NOW()
+                "#
+            )
+            .bind(domain_id)
+            .bind(&monitor.monitor_type)
+            .bind(monitor.is_enabled)
+            .bind(&monitor.config)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::from)?;
+        }
+    }
+
+    for route_id in &ops.delete_routes {
+        sqlx::query("DELETE FROM alert_routes WHERE id = $1 AND organization_id = $2")
+            .bind(route_id)
+            .bind(organization_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::from)?;
+    }
+
+    for (route_id, route) in &ops.upsert_routes {
+        sqlx::query(
+            r#"
+            INSERT INTO alert_routes (id, organization_id, name, group_id, tags, min_severity, webhook_url)
+            VALUES (COALESCE($1, gen_random_uuid()), $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (id) DO UPDATE
+            SET name = $3, group_id = $4, tags = $5, min_severity = $6, webhook_url = $7
+            "#
+        )
+        .bind(route_id)
+        .bind(organization_id)
+        .bind(&route.name)
+        .bind(group_id(&route.group))
+        .bind(sqlx::types::Json(&route.tags))
+        .bind(&route.min_severity)
+        .bind(&route.webhook_url)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+    }
+
+    for group_id in &ops.delete_groups {
+        remove_domain_group(&mut tx, organization_id, *group_id).await?;
+    }
+
+    if let Some(webhook_url) = &ops.webhook_url {
+        sqlx::query("UPDATE organizations SET webhook_url = $2 WHERE id = $1")
+            .bind(organization_id)
+            .bind(webhook_url)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::from)?;
+    }
+
+    let added = count_monitors(&mut tx, organization_id).await? - quota.used;
+    if added > 0 {
+        crate::quota::check_monitor_quota(&quota, added)?;
+    }
+
+    tx.commit().await.map_err(AppError::from)
+}
+
+// ============================================================================
+// Task Queries
+// ============================================================================
+
+/// Fetch pending tasks for execution
+pub async fn fetch_pending_tasks(
+    pool: &PgPool,
+    limit: i64,
+) -> AppResult<Vec<Task>> {
+    sqlx::query_as::<_, Task>(
+        r#"
+        SELECT * FROM tasks
+        WHERE status = 'pending' AND scheduled_at <= NOW()
+        ORDER BY priority DESC, scheduled_at ASC
+        LIMIT $1
+        FOR UPDATE SKIP LOCKED
+        "#
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Update task status to running
+pub async fn update_task_status(
+    pool: &PgPool,
+    task_id: Uuid,
+    status: TaskStatus,
+) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        UPDATE tasks
+        SET status = $1,
+            started_at = CASE WHEN $1 = 'running' THEN NOW() ELSE started_at END,
+            completed_at = CASE WHEN $1 IN ('completed', 'failed', 'cancelled') THEN NOW() ELSE completed_at END
+        WHERE id = $2
+        "#
+    )
+    .bind(status)
+    .bind(task_id)
+    .execute(pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(())
+}
+
+/// Complete a task with result
+pub async fn complete_task(
+    pool: &PgPool,
+    task_id: Uuid,
+    result: &serde_json::Value,
+) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        UPDATE tasks
+        SET status = 'completed',
+            completed_at = NOW(),
+            result = $1
+        WHERE id = $2
+        "#
+    )
+    .bind(result)
+    .bind(task_id)
+    .execute(pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(())
+}
+
+/// Fail a task with error
+pub async fn fail_task(
+    pool: &PgPool,
+    task_id: Uuid,
+    error_message: &str,
+) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        UPDATE tasks
+        SET status = 'failed',
+            completed_at = NOW(),
+            error_message = $1,
+            attempt_count = attempt_count + 1
+        WHERE id = $2
+        "#
+    )
+    .bind(error_message)
+    .bind(task_id)
+    .execute(pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(())
+}
+
+/// Increment task attempt count and retry
+pub async fn retry_task(
+    pool: &PgPool,
+    task_id: Uuid,
+    retry_after_seconds: i64,
+) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        UPDATE tasks
+        SET status = 'pending',
+            attempt_count = attempt_count + 1,
+            scheduled_at = NOW() + INTERVAL '1 second' * $1
+        WHERE id = $2
+        "#
+    )
+    .bind(retry_after_seconds)
+    .bind(task_id)
+    .execute(pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(())
+}
+
+/// Create a new task
+pub async fn create_task(
+    pool: &PgPool,
+    monitor_id: Uuid,
+    task_type: &str,
+    scheduled_at: chrono::DateTime<chrono::Utc>,
+) -> AppResult<Task> {
+    let task = sqlx::query_as::<_, Task>(
+        r#"
+        INSERT INTO tasks (monitor_id, type, status, scheduled_at)
+        VALUES ($1, $2, 'pending', $3)
+        RETURNING *
+        "#
+    )
+    .bind(monitor_id)
+    .bind(task_type)
+    .bind(scheduled_at)
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(task)
+}
+
+// ============================================================================
+// Snapshot Queries
+// ============================================================================
+
+/// Get latest DNS snapshot for a domain
+pub async fn get_latest_dns_snapshot(
+    pool: &PgPool,
+    domain_id: Uuid,
+) -> AppResult<Option<DomainDnsSnapshot>> {
+    sqlx::query_as::<_, DomainDnsSnapshot>(
+        r#"
+        SELECT * FROM domain_dns_snapshots
+        WHERE domain_id = $1
+        ORDER BY check_time DESC
+        LIMIT 1
+        "#
+    )
+    .bind(domain_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Save DNS snapshot
+pub async fn save_dns_snapshot(
+    pool: &PgPool,
+    snapshot: &DomainDnsSnapshot,
+) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO domain_dns_snapshots (
+            domain_id, check_time, is_resolvable, a_records, aaaa_records,
+            cname_records, nameservers, registrar, registry_expires_at,
+            registry_status, has_changed_since_last, changes
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+        "#
+    )
+    .bind(snapshot.domain_id)
+    .bind(snapshot.check_time)
+    .bind(snapshot.is_resolvable)
+    .bind(&snapshot.a_records)
+    .bind(&snapshot.aaaa_records)
+    .bind(&snapshot.cname_records)
+    .bind(&snapshot.nameservers)
+    .bind(&snapshot.registrar)
+    .bind(snapshot.registry_expires_at)
+    .bind(&snapshot.registry_status)
+    .bind(snapshot.has_changed_since_last)
+    .bind(&snapshot.changes)
+    .execute(pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(())
+}
+
+/// Get latest SSL snapshot for a domain
+pub async fn get_latest_ssl_snapshot(
+    pool: &PgPool,
+    domain_id: Uuid,
+) -> AppResult<Option<SslCertSnapshot>> {
+    sqlx::query_as::<_, SslCertSnapshot>(
+        r#"
+        SELECT * FROM ssl_cert_snapshots
+        WHERE domain_id = $1
+        ORDER BY check_time DESC
+        LIMIT 1
+        "#
+    )
+    .bind(domain_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Save SSL snapshot
+pub async fn save_ssl_snapshot(
+    pool: &PgPool,
+    snapshot: &SslCertSnapshot,
+) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO ssl_cert_snapshots (
+            domain_id, check_time, is_valid, issuer, subject, sans,
+            valid_from, valid_until, days_until_expiry, is_expiring_soon,
+            is_expired, chain_is_valid, hostname_matches
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+        "#
+    )
+    .bind(snapshot.domain_id)
+    .bind(snapshot.check_time)
+    .bind(snapshot.is_valid)
+    .bind(&snapshot.issuer)
+    .bind(&snapshot.subject)
+    .bind(&snapshot.sans)
+    .bind(snapshot.valid_from)
+    .bind(snapshot.valid_until)
+    .bind(snapshot.days_until_expiry)
+    .bind(snapshot.is_expiring_soon)
+    .bind(snapshot.is_expired)
+    .bind(snapshot.chain_is_valid)
+    .bind(snapshot.hostname_matches)
+    .execute(pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(())
+}
+
+/// Create SSL certificate snapshot (helper for scheduler)
+pub async fn create_ssl_cert_snapshot(
+    pool: &PgPool,
+    domain_id: Uuid,
+    issuer: &str,
+    subject: &str,
+    valid_from: chrono::DateTime<chrono::Utc>,
+    valid_until: chrono::DateTime<chrono::Utc>,
+    is_expired: bool,
+    days_until_expiry: i64,
+    is_self_signed: bool,
+) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO ssl_cert_snapshots (
+            domain_id, check_time, is_valid, issuer, subject,
+            valid_from, valid_until, days_until_expiry, is_expired
+        )
+        VALUES ($1, NOW(), $2, $3, $4, $5, $6, $7, $8)
+        "#
+    )
+    .bind(domain_id)
+    .bind(!is_expired)
+    .bind(issuer)
+    .bind(subject)
+    .bind(valid_from)
+    .bind(valid_until)
+    .bind(days_until_expiry)
+    .bind(is_expired)
+    .execute(pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(())
+}
+
+/// Get uptime snapshots for a domain
+pub async fn get_uptime_snapshots(
+    pool: &PgPool,
+    domain_id: Uuid,
+    limit: i64,
+) -> AppResult<Vec<UptimeSnapshot>> {
+    sqlx::query_as::<_, UptimeSnapshot>(
+        r#"
+        SELECT * FROM uptime_snapshots
+        WHERE domain_id = $1
+        ORDER BY check_time DESC
+        LIMIT $2
+        "#
+    )
+    .bind(domain_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Save uptime snapshot
+pub async fn save_uptime_snapshot(
+    pool: &PgPool,
+    snapshot: &UptimeSnapshot,
+) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO uptime_snapshots (
+            domain_id, check_time, status_code, response_time_ms,
+            is_up, error_type, consecutive_failures
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#
+    )
+    .bind(snapshot.domain_id)
+    .bind(snapshot.check_time)
+    .bind(snapshot.status_code)
+    .bind(snapshot.response_time_ms)
+    .bind(snapshot.is_up)
+    .bind(&snapshot.error_type)
+    .bind(snapshot.consecutive_failures)
+    .execute(pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(())
+}
+
+/// Create uptime snapshot (helper for scheduler)
+pub async fn create_uptime_snapshot(
+    pool: &PgPool,
+    domain_id: Uuid,
+    is_up: bool,
+    status_code: Option<i32>,
+    response_time_ms: i32,
+    error_message: Option<&str>,
+) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO uptime_snapshots (
+            domain_id, check_time, is_up, status_code, response_time_ms, error_type
+        )
+        VALUES ($1, NOW(), $2, $3, $4, $5)
+        "#
+    )
+    .bind(domain_id)
+    .bind(is_up)
+    .bind(status_code)
+    .bind(response_time_ms)
+    .bind(error_message)
+    .execute(pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(())
+}
+
+/// Get security header snapshot for a domain
+pub async fn get_latest_security_snapshot(
+    pool: &PgPool,
+    domain_id: Uuid,
+) -> AppResult<Option<SecurityHeaderSnapshot>> {
+    sqlx::query_as::<_, SecurityHeaderSnapshot>(
+        r#"
+        SELECT * FROM security_header_snapshots
+        WHERE domain_id = $1
+        ORDER BY check_time DESC
+        LIMIT 1
+        "#
+    )
+    .bind(domain_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Save security header snapshot
+pub async fn save_security_snapshot(
+    pool: &PgPool,
+    snapshot: &SecurityHeaderSnapshot,
+) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO security_header_snapshots (
+            domain_id, check_time, has_https_redirect, has_csp, has_hsts,
+            has_x_frame_options, has_x_content_type_options, csp_header, hsts_header, score
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        "#
+    )
+    .bind(snapshot.domain_id)
+    .bind(snapshot.check_time)
+    .bind(snapshot.has_https_redirect)
+    .bind(snapshot.has_csp)
+    .bind(snapshot.has_hsts)
+    .bind(snapshot.has_x_frame_options)
+    .bind(snapshot.has_x_content_type_options)
+    .bind(&snapshot.csp_header)
+    .bind(&snapshot.hsts_header)
+    .bind(snapshot.score)
+    .execute(pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(())
+}
+
+// ============================================================================
+// Alert Queries
+// ============================================================================
+
+/// List alerts for an organization, optionally only for domains in the given groups
+///
+/// Returns up to `limit` alerts after the alert `after` in the requested
+/// order, and the number of alerts matching the filters.
+pub async fn list_organization_alerts(
+    pool: &PgPool,
+    organization_id: Uuid,
+    group_ids: Option<&[Uuid]>,
+    filter: &AlertFilter,
+    after: Option<Uuid>,
+    limit: i64,
+) -> AppResult<(Vec<Alert>, i64)> {
+    let rows = sqlx::query_as::<_, Counted<Alert>>(
+        r#"
+        WITH matching AS (
+            SELECT a.* FROM alerts a
+            JOIN domains d ON d.id = a.domain_id
+            WHERE a.organization_id = $1
+              AND ($2::uuid[] IS NULL OR d.group_id = ANY($2))
+              AND ($3::varchar IS NULL OR a.severity = $3)
+              AND ($4::uuid IS NULL OR a.domain_id = $4)
+              AND ($5::boolean IS NULL OR (a.acknowledged_at IS NOT NULL) = $5)
+              AND ($6::timestamptz IS NULL OR a.created_at >= $6)
+              AND ($7::timestamptz IS NULL OR a.created_at < $7)
+        ),
+        after AS (SELECT * FROM matching WHERE id = $8)
+        SELECT m.*, (SELECT COUNT(*) FROM matching) AS total
+        FROM matching m
+        WHERE $8::uuid IS NULL OR CASE WHEN $9
+            THEN (m.created_at, m.id) < (SELECT created_at, id FROM after)
+            ELSE (m.created_at, m.id) > (SELECT created_at, id FROM after)
+        END
+        ORDER BY
+            CASE WHEN $9 THEN m.created_at END DESC,
+            CASE WHEN $9 THEN m.id END DESC,
+            m.created_at,
+            m.id
+        LIMIT $10
+        "#
+    )
+    .bind(organization_id)
+    .bind(group_ids)
+    .bind(filter.severity.as_ref())
+    .bind(filter.domain_id)
+    .bind(filter.acknowledged)
+    .bind(filter.from)
+    .bind(filter.to)
+    .bind(after)
+    .bind(filter.sort.unwrap_or_default().is_newest_first())
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(split_total(rows))
+}
+
+/// Find an alert of an organization
+pub async fn find_organization_alert(
+    pool: &PgPool,
+    organization_id: Uuid,
+    alert_id: Uuid,
+) -> AppResult<Option<Alert>> {
+    sqlx::query_as::<_, Alert>(
+        "SELECT * FROM alerts WHERE id = $1 AND organization_id = $2"
+    )
+    .bind(alert_id)
+    .bind(organization_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Acknowledge an alert; acknowledging it again keeps the first acknowledgement
+pub async fn acknowledge_alert(pool: &PgPool, alert_id: Uuid, user_id: Uuid) -> AppResult<Alert> {
+    sqlx::query_as::<_, Alert>(
+        r#"
+        UPDATE alerts
+        SET acknowledged_at = COALESCE(acknowledged_at, NOW()),
+            acknowledged_by = CASE WHEN acknowledged_at IS NULL THEN $2 ELSE acknowledged_by END
+        WHERE id = $1
+        RETURNING *
+        "#
+    )
+    .bind(alert_id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Create an alert
+pub async fn create_alert(
+    pool: &PgPool,
+    organization_id: Uuid,
+    domain_id: Uuid,
+    alert_type: &str,
+    severity: AlertSeverity,
+    title: &str,
+    description: Option<&str>,
+    metadata: &serde_json::Value,
+) -> AppResult<Alert> {
+    let alert = sqlx::query_as::<_, Alert>(
+        r#"
+        INSERT INTO alerts (
+            organization_id, domain_id, alert_type, severity, title, description, metadata
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING *
+        "#
+    )
+    .bind(organization_id)
+    .bind(domain_id)
+    .bind(alert_type)
+    .bind(severity)
+    .bind(title)
+    .bind(description)
+    .bind(metadata)
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(alert)
+}
+
+/// Create a simple alert (helper function for scheduler)
+pub async fn create_simple_alert(
+    pool: &PgPool,
+    domain_id: Uuid,
+    title: &str,
+    description: &str,
+) -> AppResult<Alert> {
+    // Get organization_id from domain
+    let domain = find_domain_by_id(pool, domain_id).await?
+        .ok_or_else(|| AppError::not_found("Domain not found"))?;
+
+    create_alert(
+        pool,
+        domain.organization_id,
+        domain_id,
+        "monitoring",
+        AlertSeverity::Warning,
+        title,
+        Some(description),
+        &serde_json::json!({}),
+    ).await
+}
+
+/// Update alert webhook status
+pub async fn update_alert_webhook_status(
+    pool: &PgPool,
+    alert_id: Uuid,
+    success: bool,
+    status_code: Option<i32>,
+) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        UPDATE alerts
+        SET webhook_sent_at = NOW(),
+            webhook_success = $1,
+            webhook_status_code = $2
+        WHERE id = $3
+        "#
+    )
+    .bind(success)
+    .bind(status_code)
+    .bind(alert_id)
+    .execute(pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(())
+}
+
+/// List recent alerts not delivered yet, with the webhooks to post them to
+///
+/// An alert goes to every route matching its domain and severity, or to the
+/// organization's webhook if none does. Alerts without any destination and
+/// alerts older than `max_age_minutes` are never delivered.
+pub async fn list_pending_alert_deliveries(
+    pool: &PgPool,
+    max_age_minutes: i32,
+    limit: i64,
+) -> AppResult<Vec<PendingAlertDelivery>> {
+    sqlx::query_as::<_, PendingAlertDelivery>(
+        r#"
+        SELECT a.*, dest.urls AS destinations
+        FROM alerts a
+        JOIN domains d ON d.id = a.domain_id
+        JOIN organizations o ON o.id = a.organization_id
+        CROSS JOIN LATERAL (
+            SELECT COALESCE(
+                NULLIF(ARRAY(
+                    SELECT DISTINCT r.webhook_url FROM alert_routes r
+                    WHERE r.organization_id = a.organization_id
+                      AND array_position(ARRAY['info', 'warning', 'critical'], a.severity)
+                          >= array_position(ARRAY['info', 'warning', 'critical'], r.min_severity)
+                      AND d.tags @> r.tags
+                      AND (r.group_id IS NULL OR d.group_id = ANY(domain_group_subtree(ARRAY[r.group_id])))
+                ), '{}'),
+                CASE WHEN o.webhook_url IS NULL THEN NULL ELSE ARRAY[o.webhook_url] END
+            ) AS urls
+        ) dest
+        WHERE a.webhook_sent_at IS NULL
+          AND a.created_at > NOW() - make_interval(mins => $1)
+          AND dest.urls IS NOT NULL
+          AND o.deleted_at IS NULL
+        ORDER BY a.created_at
+        LIMIT $2
+        "#
+    )
+    .bind(max_age_minutes)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// List the alert routes of an organization
+pub async fn list_alert_routes(pool: &PgPool, organization_id: Uuid) -> AppResult<Vec<AlertRoute>> {
+    sqlx::query_as::<_, AlertRoute>(
+        "SELECT * FROM alert_routes WHERE organization_id = $1 ORDER BY name"
+    )
+    .bind(organization_id)
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Find an alert route of an organization
+pub async fn find_alert_route(pool: &PgPool, organization_id: Uuid, route_id: Uuid) -> AppResult<Option<AlertRoute>> {
+    sqlx::query_as::<_, AlertRoute>(
+        "SELECT * FROM alert_routes WHERE id = $1 AND organization_id = $2"
+    )
+    .bind(route_id)
+    .bind(organization_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Check if another route of the organization already uses a name
+pub async fn alert_route_name_taken(
+    pool: &PgPool,
+    organization_id: Uuid,
+    name: &str,
+    except_id: Option<Uuid>,
+) -> AppResult<bool> {
+    sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT EXISTS(
+            SELECT 1 FROM alert_routes
+            WHERE organization_id = $1 AND name = $2 AND id IS DISTINCT FROM $3
+        )
+        "#
+    )
+    .bind(organization_id)
+    .bind(name)
+    .bind(except_id)
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Create an alert route
+pub async fn create_alert_route(
+    pool: &PgPool,
+    organization_id: Uuid,
+    input: &UpsertAlertRoute,
+) -> AppResult<AlertRoute> {
+    sqlx::query_as::<_, AlertRoute>(
+        r#"
+        INSERT INTO alert_routes (organization_id, name, group_id, tags, min_severity, webhook_url)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING *
+        "#
+    )
+    .bind(organization_id)
+    .bind(&input.name)
+    .bind(input.group_id)
+    .bind(sqlx::types::Json(&input.tags))
+    .bind(&input.min_severity)
+    .bind(&input.webhook_url)
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Replace an alert route
+pub async fn update_alert_route(
+    pool: &PgPool,
+    organization_id: Uuid,
+    route_id: Uuid,
+    input: &UpsertAlertRoute,
+) -> AppResult<Option<AlertRoute>> {
+    sqlx::query_as::<_, AlertRoute>(
+        r#"
+        UPDATE alert_routes
+        SET name = $3, group_id = $4, tags = $5, min_severity = $6, webhook_url = $7
+        WHERE id = $1 AND organization_id = $2
+        RETURNING *
+        "#
+    )
+    .bind(route_id)
+    .bind(organization_id)
+    .bind(&input.name)
+    .bind(input.group_id)
+    .bind(sqlx::types::Json(&input.tags))
+    .bind(&input.min_severity)
+    .bind(&input.webhook_url)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Delete an alert route, returning whether it existed
+pub async fn delete_alert_route(pool: &PgPool, organization_id: Uuid, route_id: Uuid) -> AppResult<bool> {
+    let result = sqlx::query("DELETE FROM alert_routes WHERE id = $1 AND organization_id = $2")
+        .bind(route_id)
+        .bind(organization_id)
+        .execute(pool)
+        .await
+        .map_err(AppError::from)?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// ============================================================================
+// Audit Queries
+// ============================================================================
+
+/// Record an audit event
+pub async fn insert_audit_event(pool: &PgPool, event: &NewAuditEvent) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO audit_events (
+            organization_id, actor_user_id, actor_api_key_id, action, target_type,
+            target_id, changes, ip_address, user_agent
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        "#
+    )
+    .bind(event.organization_id)
+    .bind(event.actor_user_id)
+    .bind(event.actor_api_key_id)
+    .bind(event.action.as_str())
+    .bind(event.action.target_type())
+    .bind(event.target_id)
+    .bind(&event.changes)
+    .bind(&event.ip_address)
+    .bind(&event.user_agent)
+    .execute(pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(())
+}
+
+/// List audit events of an organization, newest first
+///
+/// `before` is the ID of the last event of the previous page.
+pub async fn list_audit_events(
+    pool: &PgPool,
+    organization_id: Uuid,
+    filter: &AuditEventFilter,
+    before: Option<Uuid>,
+    limit: i64,
+) -> AppResult<Vec<AuditEvent>> {
+    sqlx::query_as::<_, AuditEvent>(
+        r#"
+        SELECT e.*, u.email AS actor_email
+        FROM audit_events e
+        LEFT JOIN users u ON u.id = e.actor_user_id
+        WHERE e.organization_id = $1
+          AND ($2::text IS NULL OR e.action = $2)
+          AND ($3::text IS NULL OR e.target_type = $3)
+          AND ($4::uuid IS NULL OR e.target_id = $4)
+          AND ($5::uuid IS NULL OR e.actor_user_id = $5)
+          AND ($6::timestamptz IS NULL OR e.created_at >= $6)
+          AND ($7::timestamptz IS NULL OR e.created_at < $7)
+          AND ($8::uuid IS NULL OR (e.created_at, e.id) < (
+              SELECT created_at, id FROM audit_events WHERE id = $8
+          ))
+        ORDER BY e.created_at DESC, e.id DESC
+        LIMIT $9
+        "#
+    )
+    .bind(organization_id)
+    .bind(&filter.action)
+    .bind(&filter.target_type)
+    .bind(filter.target_id)
+    .bind(filter.actor_id)
+    .bind(filter.from)
+    .bind(filter.to)
+    .bind(before)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+// ============================================================================
+// Refresh Token Queries
+// ============================================================================
+
+/// Create a refresh token
+pub async fn create_refresh_token(
+    pool: &PgPool,
+    user_id: Uuid,
+    session_id: Uuid,
+    token_hash: &str,
+    expires_at: chrono::DateTime<chrono::Utc>,
+) -> AppResult<RefreshToken> {
+    let token = sqlx::query_as::<_, RefreshToken>(
+        r#"
+        INSERT INTO refresh_tokens (user_id, session_id, token_hash, expires_at)
+        VALUES ($1, $2, $3, $4)
+        RETURNING *
+        "#
+    )
+    .bind(user_id)
+    .bind(session_id)
+    .bind(token_hash)
+    .bind(expires_at)
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(token)
+}
+
+/// Find an unexpired refresh token by hash
+///
+/// Revoked tokens are returned too, so that reuse of a rotated token can be detected.
+pub async fn find_refresh_token(pool: &PgPool, token_hash: &str) -> AppResult<Option<RefreshToken>> {
+    sqlx::query_as::<_, RefreshToken>(
+        r#"
+        SELECT * FROM refresh_tokens
+        WHERE token_hash = $1
+        AND expires_at > NOW()
+        "#
+    )
+    .bind(token_hash)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Revoke a refresh token
+pub async fn revoke_refresh_token(pool: &PgPool, token_id: Uuid) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        UPDATE refresh_tokens
+        SET is_revoked = true, revoked_at = NOW()
+        WHERE id = $1
+        "#
+    )
+    .bind(token_id)
+    .execute(pool)
+    .await
+    .map_err(AppError::from)?;
 
     Ok(())
 }
 
-/// Delete a domain
-pub async fn delete_domain(pool: &PgPool, domain_id: Uuid) -> AppResult<()> {
-    sqlx::query("DELETE FROM domains WHERE id = $1")
-        .bind(domain_id)
+/// Mark a refresh token as rotated into a new one
+///
+/// Returns `false` if the token was already revoked, e.g. by a concurrent refresh.
+pub async fn rotate_refresh_token(pool: &PgPool, token_id: Uuid, replaced_by: Uuid) -> AppResult<bool> {
+    let result = sqlx::query(
+        r#"
+        UPDATE refresh_tokens
+        SET is_revoked = true, revoked_at = NOW(), replaced_by = $2
+        WHERE id = $1 AND is_revoked = false
+        "#
+    )
+    .bind(token_id)
+    .bind(replaced_by)
+    .execute(pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Revoke all refresh tokens for a user
+pub async fn revoke_all_user_tokens(pool: &PgPool, user_id: Uuid) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        UPDATE refresh_tokens
+        SET is_revoked = true, revoked_at = NOW()
+        WHERE user_id = $1 AND is_revoked = false
+        "#
+    )
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(())
+}
+
+/// Revocation state of an access token, `None` if the user no longer exists
+pub async fn get_access_token_status(
+    pool: &PgPool,
+    user_id: Uuid,
+    jti: Uuid,
+    session_id: Option<Uuid>,
+) -> AppResult<Option<AccessTokenStatus>> {
+    sqlx::query_as::<_, AccessTokenStatus>(
+        r#"
+        SELECT
+            COALESCE(u.is_active, false) AS is_active,
+            u.token_version,
+            EXISTS(SELECT 1 FROM revoked_access_tokens r WHERE r.jti = $2) AS is_denied,
+            EXISTS(SELECT 1 FROM sessions s WHERE s.id = $3 AND s.revoked_at IS NOT NULL) AS session_revoked
+        FROM users u
+        WHERE u.id = $1
+        "#
+    )
+    .bind(user_id)
+    .bind(jti)
+    .bind(session_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Add an access token to the denylist until it expires
+pub async fn revoke_access_token(
+    pool: &PgPool,
+    jti: Uuid,
+    user_id: Uuid,
+    expires_at: chrono::DateTime<chrono::Utc>,
+) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO revoked_access_tokens (jti, user_id, expires_at)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (jti) DO NOTHING
+        "#
+    )
+    .bind(jti)
+    .bind(user_id)
+    .bind(expires_at)
+    .execute(pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(())
+}
+
+/// Drop denylist entries of access tokens that have expired anyway
+pub async fn delete_expired_revoked_access_tokens(pool: &PgPool) -> AppResult<u64> {
+    let result = sqlx::query("DELETE FROM revoked_access_tokens WHERE expires_at <= NOW()")
         .execute(pool)
         .await
         .map_err(AppError::from)?;
 
-    Ok(())
+    Ok(result.rows_affected())
 }
 
 // ============================================================================
-// Monitor Queries
+// Session Queries
 // ============================================================================
 
-/// List monitors for a domain
-pub async fn list_domain_monitors(pool: &PgPool, domain_id: Uuid) -> AppResult<Vec<Monitor>> {
-    sqlx::query_as::<_, Monitor>(
-        "SELECT * FROM monitors WHERE domain_id = $1"
+/// Create a login session
+pub async fn create_session(
+    pool: &PgPool,
+    user_id: Uuid,
+    device_name: Option<&str>,
+    ip_address: Option<&str>,
+    user_agent: Option<&str>,
+    sso_organization_id: Option<Uuid>,
+    expires_at: chrono::DateTime<chrono::Utc>,
+) -> AppResult<Session> {
+    sqlx::query_as::<_, Session>(
+        r#"
+        INSERT INTO sessions (user_id, device_name, ip_address, user_agent, sso_organization_id, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING *
+        "#
     )
-    .bind(domain_id)
-    .fetch_all(pool)
+    .bind(user_id)
+    .bind(device_name)
+    .bind(ip_address)
+    .bind(user_agent)
+    .bind(sso_organization_id)
+    .bind(expires_at)
+    .fetch_one(pool)
     .await
     .map_err(AppError::from)
 }
 
-/// Find a monitor by ID
-pub async fn find_monitor_by_id(pool: &PgPool, id: Uuid) -> AppResult<Option<Monitor>> {
-    sqlx::query_as::<_, Monitor>(
-        "SELECT * FROM monitors WHERE id = $1"
+/// Find an active session by ID
+pub async fn find_active_session(pool: &PgPool, session_id: Uuid) -> AppResult<Option<Session>> {
+    sqlx::query_as::<_, Session>(
+        r#"
+        SELECT * FROM sessions
+        WHERE id = $1 AND revoked_at IS NULL AND expires_at > NOW()
+        "#
     )
-    .bind(id)
+    .bind(session_id)
     .fetch_optional(pool)
     .await
     .map_err(AppError::from)
 }
 
-/// Create or update a monitor
-pub async fn upsert_monitor(
-    pool: &PgPool,
-    domain_id: Uuid,
-    monitor_type: MonitorType,
-    is_enabled: bool,
-    config: &serde_json::Value,
-) -> AppResult<Monitor> {
-    let monitor = sqlx::query_as::<_, Monitor>(
+/// List active sessions of a user, most recently used first
+pub async fn list_active_user_sessions(pool: &PgPool, user_id: Uuid) -> AppResult<Vec<Session>> {
+    sqlx::query_as::<_, Session>(
         r#"
-        INSERT INTO monitors (domain_id, type, is_enabled, config)
-        VALUES ($1, $2, $3, $4)
-        ON CONFLICT (domain_id, type)
-        DO UPDATE SET is_enabled = $3, config = $4, updated_at = NOW()
-        RETURNING *
+        SELECT * FROM sessions
+        WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > NOW()
+        ORDER BY last_used_at DESC
         "#
     )
-    .bind(domain_id)
-    .bind(monitor_type)
-    .bind(is_enabled)
-    .bind(config)
-    .fetch_one(pool)
+    .bind(user_id)
+    .fetch_all(pool)
     .await
-    .map_err(AppError::from)?;
-
-    Ok(monitor)
+    .map_err(AppError::from)
 }
 
-/// Update monitor
-pub async fn update_monitor(
+/// Record use of a session and extend its expiry
+pub async fn touch_session(
     pool: &PgPool,
-    monitor_id: Uuid,
-    is_enabled: Option<bool>,
-    config: Option<&serde_json::Value>,
+    session_id: Uuid,
+    ip_address: Option<&str>,
+    expires_at: chrono::DateTime<chrono::Utc>,
 ) -> AppResult<()> {
     sqlx::query(
         r#"
-        UPDATE monitors
-        SET is_enabled = COALESCE($1, is_enabled),
-            config = COALESCE($2, config),
-            updated_at = NOW()
-        WHERE id = $3
+        UPDATE sessions
+        SET last_used_at = NOW(), ip_address = COALESCE($2, ip_address), expires_at = $3
+        WHERE id = $1
         "#
     )
-    .bind(is_enabled)
-    .bind(config)
-    .bind(monitor_id)
+    .bind(session_id)
+    .bind(ip_address)
+    .bind(expires_at)
     .execute(pool)
     .await
     .map_err(AppError::from)?;
@@ -378,10 +3457,15 @@ pub async fn update_monitor(
     Ok(())
 }
 
-/// Delete a monitor
-pub async fn delete_monitor(pool: &PgPool, monitor_id: Uuid) -> AppResult<()> {
-    sqlx::query("DELETE FROM monitors WHERE id = $1")
-        .bind(monitor_id)
+/// Remember the organization a session's access tokens are scoped to
+pub async fn set_session_organization(
+    pool: &PgPool,
+    session_id: Uuid,
+    organization_id: Option<Uuid>,
+) -> AppResult<()> {
+    sqlx::query("UPDATE sessions SET organization_id = $2 WHERE id = $1")
+        .bind(session_id)
+        .bind(organization_id)
         .execute(pool)
         .await
         .map_err(AppError::from)?;
@@ -389,152 +3473,244 @@ pub async fn delete_monitor(pool: &PgPool, monitor_id: Uuid) -> AppResult<()> {
     Ok(())
 }
 
-/// Get all active monitors with their domain information
-pub async fn get_all_active_monitors(pool: &PgPool) -> AppResult<Vec<Monitor>> {
-    sqlx::query_as::<_, Monitor>(
+/// Revoke a session of a user and every refresh token in it
+///
+/// Returns `false` if the session does not exist or is already revoked.
+pub async fn revoke_session(pool: &PgPool, user_id: Uuid, session_id: Uuid) -> AppResult<bool> {
+    let mut tx = pool.begin().await.map_err(AppError::from)?;
+
+    let result = sqlx::query(
         r#"
-        SELECT m.*, d.name as "domain_name?"
-        FROM monitors m
-        INNER JOIN domains d ON d.id = m.domain_id
-        WHERE m.is_enabled = true AND d.is_active = true
-        ORDER BY m.created_at ASC
+        UPDATE sessions
+        SET revoked_at = NOW()
+        WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL
         "#
     )
-    .fetch_all(pool)
+    .bind(session_id)
+    .bind(user_id)
+    .execute(&mut *tx)
     .await
-    .map_err(AppError::from)
-}
+    .map_err(AppError::from)?;
 
-/// List all active monitors (without domain name)
-pub async fn list_all_active_monitors(pool: &PgPool) -> AppResult<Vec<Monitor>> {
-    sqlx::query_as::<_, Monitor>(
+    sqlx::query(
         r#"
-        SELECT m.*
-        FROM monitors m
-        INNER JOIN domains d ON d.id = m.domain_id
-        WHERE m.is_enabled = true AND d.is_active = true
-        ORDER BY m.created_at ASC
+        UPDATE refresh_tokens
+        SET is_revoked = true, revoked_at = NOW()
+        WHERE session_id = $1 AND user_id = $2 AND is_revoked = false
         "#
     )
-    .fetch_all(pool)
+    .bind(session_id)
+    .bind(user_id)
+    .execute(&mut *tx)
     .await
-    .map_err(AppError::from)
+    .map_err(AppError::from)?;
+
+    tx.commit().await.map_err(AppError::from)?;
+
+    Ok(result.rows_affected() > 0)
 }
 
-// ============================================================================
-// Task Queries
-// ============================================================================
+/// Revoke all sessions of a user but one, together with their refresh tokens
+pub async fn revoke_other_user_sessions(pool: &PgPool, user_id: Uuid, keep_session_id: Option<Uuid>) -> AppResult<u64> {
+    let mut tx = pool.begin().await.map_err(AppError::from)?;
 
-/// Fetch pending tasks for execution
-pub async fn fetch_pending_tasks(
-    pool: &PgPool,
-    limit: i64,
-) -> AppResult<Vec<Task>> {
-    sqlx::query_as::<_, Task>(
+    let result = sqlx::query(
         r#"
-        SELECT * FROM tasks
-        WHERE status = 'pending' AND scheduled_at <= NOW()
-        ORDER BY priority DESC, scheduled_at ASC
-        LIMIT $1
-        FOR UPDATE SKIP LOCKED
+        UPDATE sessions
+        SET revoked_at = NOW()
+        WHERE user_id = $1 AND revoked_at IS NULL AND id IS DISTINCT FROM $2
         "#
     )
-    .bind(limit)
-    .fetch_all(pool)
+    .bind(user_id)
+    .bind(keep_session_id)
+    .execute(&mut *tx)
     .await
-    .map_err(AppError::from)
+    .map_err(AppError::from)?;
+
+    sqlx::query(
+        r#"
+        UPDATE refresh_tokens
+        SET is_revoked = true, revoked_at = NOW()
+        WHERE user_id = $1 AND is_revoked = false AND session_id IS DISTINCT FROM $2
+        "#
+    )
+    .bind(user_id)
+    .bind(keep_session_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+
+    tx.commit().await.map_err(AppError::from)?;
+
+    Ok(result.rows_affected())
 }
 
-/// Update task status to running
-pub async fn update_task_status(
+/// Revoke all sessions of a user
+pub async fn revoke_all_user_sessions(pool: &PgPool, user_id: Uuid) -> AppResult<u64> {
+    let result = sqlx::query(
+        r#"
+        UPDATE sessions
+        SET revoked_at = NOW()
+        WHERE user_id = $1 AND revoked_at IS NULL
+        "#
+    )
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(result.rows_affected())
+}
+
+// ============================================================================
+// API Key Queries
+// ============================================================================
+
+/// Create an API key, failing when the organization's API key quota is used up
+pub async fn create_api_key(
     pool: &PgPool,
-    task_id: Uuid,
-    status: TaskStatus,
-) -> AppResult<()> {
-    sqlx::query(
+    organization_id: Uuid,
+    created_by: Uuid,
+    key_prefix: &str,
+    key_hash: &str,
+    input: &CreateApiKey,
+) -> AppResult<ApiKey> {
+    let mut tx = pool.begin().await.map_err(AppError::from)?;
+    let limit = lock_organization_quotas(&mut tx, organization_id).await?.max_api_keys;
+    let used = count_usable_api_keys(&mut *tx, organization_id).await?;
+    crate::quota::check_api_key_quota(&QuotaUsage { used, limit: i64::from(limit) })?;
+
+    let api_key = sqlx::query_as::<_, ApiKey>(
         r#"
-        UPDATE tasks
-        SET status = $1,
-            started_at = CASE WHEN $1 = 'running' THEN NOW() ELSE started_at END,
-            completed_at = CASE WHEN $1 IN ('completed', 'failed', 'cancelled') THEN NOW() ELSE completed_at END
-        WHERE id = $2
+        INSERT INTO api_keys (organization_id, created_by, name, key_prefix, key_hash, scopes, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING *
         "#
     )
-    .bind(status)
-    .bind(task_id)
-    .execute(pool)
+    .bind(organization_id)
+    .bind(created_by)
+    .bind(&input.name)
+    .bind(key_prefix)
+    .bind(key_hash)
+    .bind(CreateApiKey::scope_names(&input.scopes))
+    .bind(input.expires_at)
+    .fetch_one(&mut *tx)
     .await
     .map_err(AppError::from)?;
 
-    Ok(())
+    tx.commit().await.map_err(AppError::from)?;
+    Ok(api_key)
 }
 
-/// Complete a task with result
-pub async fn complete_task(
-    pool: &PgPool,
-    task_id: Uuid,
-    result: &serde_json::Value,
-) -> AppResult<()> {
-    sqlx::query(
+/// Find an API key by hash, with the status of its creator
+pub async fn find_api_key_by_hash(pool: &PgPool, key_hash: &str) -> AppResult<Option<ApiKeyCredential>> {
+    sqlx::query_as::<_, ApiKeyCredential>(
         r#"
-        UPDATE tasks
-        SET status = 'completed',
-            completed_at = NOW(),
-            result = $1
-        WHERE id = $2
+        SELECT
+            k.*,
+            COALESCE(u.is_active, false) AND m.user_id IS NOT NULL AS creator_active
+        FROM api_keys k
+        JOIN organizations o ON o.id = k.organization_id
+        JOIN users u ON u.id = k.created_by
+        LEFT JOIN organization_members m ON m.organization_id = k.organization_id AND m.user_id = k.created_by
+        WHERE k.key_hash = $1 AND o.deleted_at IS NULL
         "#
     )
-    .bind(result)
-    .bind(task_id)
-    .execute(pool)
-    .await
-    .map_err(AppError::from)?;
+        .bind(key_hash)
+        .fetch_optional(pool)
+        .await
+        .map_err(AppError::from)
+}
 
-    Ok(())
+/// Find an API key by ID, with the status of its creator
+pub async fn find_api_key_credential(pool: &PgPool, id: Uuid) -> AppResult<Option<ApiKeyCredential>> {
+    sqlx::query_as::<_, ApiKeyCredential>(
+        r#"
+        SELECT
+            k.*,
+            COALESCE(u.is_active, false) AND m.user_id IS NOT NULL AS creator_active
+        FROM api_keys k
+        JOIN organizations o ON o.id = k.organization_id
+        JOIN users u ON u.id = k.created_by
+        LEFT JOIN organization_members m ON m.organization_id = k.organization_id AND m.user_id = k.created_by
+        WHERE k.id = $1 AND o.deleted_at IS NULL
+        "#
+    )
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(AppError::from)
 }
 
-/// Fail a task with error
-pub async fn fail_task(
+/// Find an API key of an organization
+pub async fn find_api_key(pool: &PgPool, organization_id: Uuid, id: Uuid) -> AppResult<Option<ApiKey>> {
+    sqlx::query_as::<_, ApiKey>("SELECT * FROM api_keys WHERE id = $1 AND organization_id = $2")
+        .bind(id)
+        .bind(organization_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(AppError::from)
+}
+
+/// List API keys of an organization, including revoked ones
+pub async fn list_organization_api_keys(pool: &PgPool, organization_id: Uuid) -> AppResult<Vec<ApiKey>> {
+    sqlx::query_as::<_, ApiKey>(
+        "SELECT * FROM api_keys WHERE organization_id = $1 ORDER BY created_at DESC"
+    )
+    .bind(organization_id)
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Update an API key's name, scopes and expiry
+pub async fn update_api_key(
     pool: &PgPool,
-    task_id: Uuid,
-    error_message: &str,
-) -> AppResult<()> {
-    sqlx::query(
+    id: Uuid,
+    name: &str,
+    scopes: &[String],
+    expires_at: Option<chrono::DateTime<chrono::Utc>>,
+) -> AppResult<ApiKey> {
+    sqlx::query_as::<_, ApiKey>(
         r#"
-        UPDATE tasks
-        SET status = 'failed',
-            completed_at = NOW(),
-            error_message = $1,
-            attempt_count = attempt_count + 1
-        WHERE id = $2
+        UPDATE api_keys
+        SET name = $2, scopes = $3, expires_at = $4
+        WHERE id = $1
+        RETURNING *
         "#
     )
-    .bind(error_message)
-    .bind(task_id)
-    .execute(pool)
+    .bind(id)
+    .bind(name)
+    .bind(scopes)
+    .bind(expires_at)
+    .fetch_one(pool)
     .await
-    .map_err(AppError::from)?;
+    .map_err(AppError::from)
+}
+
+/// Revoke an API key
+pub async fn revoke_api_key(pool: &PgPool, id: Uuid) -> AppResult<()> {
+    sqlx::query("UPDATE api_keys SET revoked_at = NOW() WHERE id = $1 AND revoked_at IS NULL")
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(AppError::from)?;
 
     Ok(())
 }
 
-/// Increment task attempt count and retry
-pub async fn retry_task(
-    pool: &PgPool,
-    task_id: Uuid,
-    retry_after_seconds: i64,
-) -> AppResult<()> {
+/// Record API key usage, at most once a minute to limit writes
+pub async fn touch_api_key(pool: &PgPool, id: Uuid, ip: Option<&str>) -> AppResult<()> {
     sqlx::query(
         r#"
-        UPDATE tasks
-        SET status = 'pending',
-            attempt_count = attempt_count + 1,
-            scheduled_at = NOW() + INTERVAL '1 second' * $1
-        WHERE id = $2
+        UPDATE api_keys
+        SET last_used_at = NOW(), last_used_ip = $2
+        WHERE id = $1
+        AND (last_used_at IS NULL OR last_used_at < NOW() - INTERVAL '1 minute')
         "#
     )
-    .bind(retry_after_seconds)
-    .bind(task_id)
+    .bind(id)
+    .bind(ip)
     .execute(pool)
     .await
     .map_err(AppError::from)?;
@@ -542,42 +3718,40 @@ pub async fn retry_task(
     Ok(())
 }
 
-/// Create a new task
-pub async fn create_task(
-    pool: &PgPool,
-    monitor_id: Uuid,
-    task_type: &str,
-    scheduled_at: chrono::DateTime<chrono::Utc>,
-) -> AppResult<Task> {
-    let task = sqlx::query_as::<_, Task>(
+/// Count the usable API keys of an organization: neither revoked nor expired,
+/// and created by a still active member
+pub async fn count_usable_api_keys<'e>(
+    executor: impl sqlx::PgExecutor<'e>,
+    organization_id: Uuid,
+) -> AppResult<i64> {
+    sqlx::query_scalar::<_, i64>(
         r#"
-        INSERT INTO tasks (monitor_id, type, status, scheduled_at)
-        VALUES ($1, $2, 'pending', $3)
-        RETURNING *
+        SELECT COUNT(*) FROM api_keys k
+        JOIN users u ON u.id = k.created_by
+        JOIN organization_members m ON m.organization_id = k.organization_id AND m.user_id = k.created_by
+        WHERE k.organization_id = $1 AND k.revoked_at IS NULL
+        AND (k.expires_at IS NULL OR k.expires_at > NOW())
+        AND COALESCE(u.is_active, false)
         "#
     )
-    .bind(monitor_id)
-    .bind(task_type)
-    .bind(scheduled_at)
-    .fetch_one(pool)
+    .bind(organization_id)
+    .fetch_one(executor)
     .await
-    .map_err(AppError::from)?;
-
-    Ok(task)
+    .map_err(AppError::from)
 }
 
 // ============================================================================
-// Snapshot Queries
+// Enhanced Monitoring Queries
 // ============================================================================
 
-/// Get latest DNS snapshot for a domain
-pub async fn get_latest_dns_snapshot(
+/// Get latest uptime snapshot for a domain
+pub async fn get_latest_uptime_snapshot(
     pool: &PgPool,
     domain_id: Uuid,
-) -> AppResult<Option<DomainDnsSnapshot>> {
-    sqlx::query_as::<_, DomainDnsSnapshot>(
+) -> AppResult<Option<UptimeSnapshot>> {
+    sqlx::query_as::<_, UptimeSnapshot>(
         r#"
-        SELECT * FROM domain_dns_snapshots
+        SELECT * FROM uptime_snapshots
         WHERE domain_id = $1
         ORDER BY check_time DESC
         LIMIT 1
@@ -589,87 +3763,134 @@ pub async fn get_latest_dns_snapshot(
     .map_err(AppError::from)
 }
 
-/// Save DNS snapshot
-pub async fn save_dns_snapshot(
+/// Get the uptime history of a domain in time buckets
+///
+/// Each bucket carries the ID of its latest snapshot. Returns up to `limit`
+/// buckets after the bucket `after` in the requested order, and the number of
+/// buckets matching the filters.
+pub async fn get_uptime_history(
     pool: &PgPool,
-    snapshot: &DomainDnsSnapshot,
-) -> AppResult<()> {
-    sqlx::query(
+    domain_id: Uuid,
+    filter: &UptimeHistoryFilter,
+    after: Option<Uuid>,
+    limit: i64,
+) -> AppResult<(Vec<UptimeSnapshot>, i64)> {
+    let rows = sqlx::query_as::<_, Counted<UptimeSnapshot>>(
         r#"
-        INSERT INTO domain_dns_snapshots (
-            domain_id, check_time, is_resolvable, a_records, aaaa_records,
-            cname_records, nameservers, registrar, registry_expires_at,
-            registry_status, has_changed_since_last, changes
-        )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+        WITH time_buckets AS (
+            SELECT
+                date_trunc('minute',
+                    check_time - INTERVAL '1 minute' * (
+                        EXTRACT(MINUTE FROM check_time)::int % $3
+                    )
+                ) as bucket_time,
+                bool_and(is_up) as is_up,
+                COALESCE(avg(response_time_ms)::int, 0) as avg_response_time_ms,
+                max(status_code) as status_code,
+                (array_agg(id ORDER BY check_time DESC))[1] as id,
+                $1 as domain_id,
+                max(check_time) as check_time,
+                max(consecutive_failures) as consecutive_failures,
+                (array_agg(error_type ORDER BY check_time DESC))[1] as error_type
+            FROM uptime_snapshots
+            WHERE domain_id = $1
+              AND check_time >= COALESCE($4::timestamptz, NOW() - INTERVAL '1 hour' * $2)
+              AND ($5::timestamptz IS NULL OR check_time < $5)
+            GROUP BY bucket_time
+        ),
+        matching AS (
+            SELECT
+                id, domain_id, check_time, status_code,
+                avg_response_time_ms as response_time_ms,
+                is_up, error_type, consecutive_failures
+            FROM time_buckets
+            WHERE $6::boolean IS NULL OR is_up = $6
+        ),
+        after AS (SELECT * FROM matching WHERE id = $7)
+        SELECT m.*, (SELECT COUNT(*) FROM matching) AS total
+        FROM matching m
+        WHERE $7::uuid IS NULL OR CASE WHEN $8
+            THEN (m.check_time, m.id) < (SELECT check_time, id FROM after)
+            ELSE (m.check_time, m.id) > (SELECT check_time, id FROM after)
+        END
+        ORDER BY
+            CASE WHEN $8 THEN m.check_time END DESC,
+            CASE WHEN $8 THEN m.id END DESC,
+            m.check_time,
+            m.id
+        LIMIT $9
         "#
     )
-    .bind(snapshot.domain_id)
-    .bind(snapshot.check_time)
-    .bind(snapshot.is_resolvable)
-    .bind(&snapshot.a_records)
-    .bind(&snapshot.aaaa_records)
-    .bind(&snapshot.cname_records)
-    .bind(&snapshot.nameservers)
-    .bind(&snapshot.registrar)
-    .bind(snapshot.registry_expires_at)
-    .bind(&snapshot.registry_status)
-    .bind(snapshot.has_changed_since_last)
-    .bind(&snapshot.changes)
-    .execute(pool)
+    .bind(domain_id)
+    .bind(filter.hours)
+    .bind(filter.interval_minutes)
+    .bind(filter.from)
+    .bind(filter.to)
+    .bind(filter.status.map(UptimeStatus::is_up))
+    .bind(after)
+    .bind(filter.sort.unwrap_or_default().is_newest_first())
+    .bind(limit)
+    .fetch_all(pool)
     .await
     .map_err(AppError::from)?;
 
-    Ok(())
+    Ok(split_total(rows))
 }
 
-/// Get latest SSL snapshot for a domain
-pub async fn get_latest_ssl_snapshot(
+/// Compute and save uptime aggregate statistics from raw snapshots
+///
+/// Periods without any snapshot are skipped rather than stored as 0% uptime.
+pub async fn compute_uptime_aggregate(
     pool: &PgPool,
     domain_id: Uuid,
-) -> AppResult<Option<SslCertSnapshot>> {
-    sqlx::query_as::<_, SslCertSnapshot>(
-        r#"
-        SELECT * FROM ssl_cert_snapshots
-        WHERE domain_id = $1
-        ORDER BY check_time DESC
-        LIMIT 1
-        "#
-    )
-    .bind(domain_id)
-    .fetch_optional(pool)
-    .await
-    .map_err(AppError::from)
-}
-
-/// Save SSL snapshot
-pub async fn save_ssl_snapshot(
-    pool: &PgPool,
-    snapshot: &SslCertSnapshot,
+    period_start: chrono::DateTime<chrono::Utc>,
+    period_end: chrono::DateTime<chrono::Utc>,
+    period_type: &str,
 ) -> AppResult<()> {
     sqlx::query(
         r#"
-        INSERT INTO ssl_cert_snapshots (
-            domain_id, check_time, is_valid, issuer, subject, sans,
-            valid_from, valid_until, days_until_expiry, is_expiring_soon,
-            is_expired, chain_is_valid, hostname_matches
+        INSERT INTO uptime_aggregates (
+            domain_id, period_type, period_start, period_end,
+            uptime_percentage, avg_response_time_ms,
+            p95_response_time_ms, p99_response_time_ms,
+            total_checks, successful_checks
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+        SELECT
+            $1 as domain_id,
+            $2 as period_type,
+            $3 as period_start,
+            $4 as period_end,
+            CASE
+                WHEN COUNT(*) = 0 THEN 0
+                ELSE (COUNT(*) FILTER (WHERE is_up = true)::float / COUNT(*)::float * 100)
+            END as uptime_percentage,
+            COALESCE(AVG(response_time_ms) FILTER (WHERE is_up = true), 0)::int as avg_response_time_ms,
+            (percentile_cont(0.95) WITHIN GROUP (ORDER BY response_time_ms)
+                FILTER (WHERE is_up = true))::int as p95_response_time_ms,
+            (percentile_cont(0.99) WITHIN GROUP (ORDER BY response_time_ms)
+                FILTER (WHERE is_up = true))::int as p99_response_time_ms,
+            COUNT(*) as total_checks,
+            COUNT(*) FILTER (WHERE is_up = true) as successful_checks
+        FROM uptime_snapshots
+        WHERE domain_id = $1
+          AND check_time >= $3
+          AND check_time < $4
+        HAVING COUNT(*) > 0
+        ON CONFLICT (domain_id, period_start, period_type)
+        DO UPDATE SET
+            period_end = EXCLUDED.period_end,
+            uptime_percentage = EXCLUDED.uptime_percentage,
+            avg_response_time_ms = EXCLUDED.avg_response_time_ms,
+            p95_response_time_ms = EXCLUDED.p95_response_time_ms,
+            p99_response_time_ms = EXCLUDED.p99_response_time_ms,
+            total_checks = EXCLUDED.total_checks,
+            successful_checks = EXCLUDED.successful_checks
         "#
     )
-    .bind(snapshot.domain_id)
-    .bind(snapshot.check_time)
-    .bind(snapshot.is_valid)
-    .bind(&snapshot.issuer)
-    .bind(&snapshot.subject)
-    .bind(&snapshot.sans)
-    .bind(snapshot.valid_from)
-    .bind(snapshot.valid_until)
-    .bind(snapshot.days_until_expiry)
-    .bind(snapshot.is_expiring_soon)
-    .bind(snapshot.is_expired)
-    .bind(snapshot.chain_is_valid)
-    .bind(snapshot.hostname_matches)
+    .bind(domain_id)
+    .bind(period_type)
+    .bind(period_start)
+    .bind(period_end)
     .execute(pool)
     .await
     .map_err(AppError::from)?;
@@ -677,35 +3898,65 @@ pub async fn save_ssl_snapshot(
     Ok(())
 }
 
-/// Create SSL certificate snapshot (helper for scheduler)
-pub async fn create_ssl_cert_snapshot(
+/// Roll up hourly aggregates into a longer period (day, week or month)
+///
+/// Check counts are summed exactly and the average response time is weighted by
+/// successful checks. Percentiles are approximated as the percentile of the
+/// hourly percentile values, since raw samples are not kept in the hourly rows.
+pub async fn rollup_uptime_aggregate(
     pool: &PgPool,
     domain_id: Uuid,
-    issuer: &str,
-    subject: &str,
-    valid_from: chrono::DateTime<chrono::Utc>,
-    valid_until: chrono::DateTime<chrono::Utc>,
-    is_expired: bool,
-    days_until_expiry: i64,
-    is_self_signed: bool,
+    period_start: chrono::DateTime<chrono::Utc>,
+    period_end: chrono::DateTime<chrono::Utc>,
+    period_type: &str,
 ) -> AppResult<()> {
     sqlx::query(
         r#"
-        INSERT INTO ssl_cert_snapshots (
-            domain_id, check_time, is_valid, issuer, subject,
-            valid_from, valid_until, days_until_expiry, is_expired
+        INSERT INTO uptime_aggregates (
+            domain_id, period_type, period_start, period_end,
+            uptime_percentage, avg_response_time_ms,
+            p95_response_time_ms, p99_response_time_ms,
+            total_checks, successful_checks
         )
-        VALUES ($1, NOW(), $2, $3, $4, $5, $6, $7, $8)
+        SELECT
+            $1 as domain_id,
+            $2 as period_type,
+            $3 as period_start,
+            $4 as period_end,
+            CASE
+                WHEN SUM(total_checks) = 0 THEN 0
+                ELSE (SUM(successful_checks)::float / SUM(total_checks)::float * 100)
+            END as uptime_percentage,
+            COALESCE(
+                SUM(avg_response_time_ms::bigint * successful_checks)
+                    / NULLIF(SUM(successful_checks) FILTER (WHERE avg_response_time_ms IS NOT NULL), 0),
+                0
+            )::int as avg_response_time_ms,
+            (percentile_cont(0.95) WITHIN GROUP (ORDER BY p95_response_time_ms))::int as p95_response_time_ms,
+            (percentile_cont(0.99) WITHIN GROUP (ORDER BY p99_response_time_ms))::int as p99_response_time_ms,
+            SUM(total_checks) as total_checks,
+            SUM(successful_checks) as successful_checks
+        FROM uptime_aggregates
+        WHERE domain_id = $1
+          AND period_type = 'hour'
+          AND period_start >= $3
+          AND period_start < $4
+        HAVING COUNT(*) > 0
+        ON CONFLICT (domain_id, period_start, period_type)
+        DO UPDATE SET
+            period_end = EXCLUDED.period_end,
+            uptime_percentage = EXCLUDED.uptime_percentage,
+            avg_response_time_ms = EXCLUDED.avg_response_time_ms,
+            p95_response_time_ms = EXCLUDED.p95_response_time_ms,
+            p99_response_time_ms = EXCLUDED.p99_response_time_ms,
+            total_checks = EXCLUDED.total_checks,
+            successful_checks = EXCLUDED.successful_checks
         "#
     )
     .bind(domain_id)
-    .bind(!is_expired)
-    .bind(issuer)
-    .bind(subject)
-    .bind(valid_from)
-    .bind(valid_until)
-    .bind(days_until_expiry)
-    .bind(is_expired)
+    .bind(period_type)
+    .bind(period_start)
+    .bind(period_end)
     .execute(pool)
     .await
     .map_err(AppError::from)?;
@@ -713,127 +3964,108 @@ pub async fn create_ssl_cert_snapshot(
     Ok(())
 }
 
-/// Get uptime snapshots for a domain
-pub async fn get_uptime_snapshots(
+/// Get uptime aggregates for a domain
+pub async fn get_uptime_aggregates(
     pool: &PgPool,
     domain_id: Uuid,
+    period_type: &str,
     limit: i64,
-) -> AppResult<Vec<UptimeSnapshot>> {
-    sqlx::query_as::<_, UptimeSnapshot>(
+) -> AppResult<Vec<UptimeAggregate>> {
+    sqlx::query_as::<_, UptimeAggregate>(
         r#"
-        SELECT * FROM uptime_snapshots
-        WHERE domain_id = $1
-        ORDER BY check_time DESC
-        LIMIT $2
+        SELECT * FROM uptime_aggregates
+        WHERE domain_id = $1 AND period_type = $2
+        ORDER BY period_start DESC
+        LIMIT $3
         "#
     )
     .bind(domain_id)
+    .bind(period_type)
     .bind(limit)
     .fetch_all(pool)
     .await
     .map_err(AppError::from)
 }
 
-/// Save uptime snapshot
-pub async fn save_uptime_snapshot(
+/// Get latest uptime aggregate for a domain (typically used for dashboard)
+pub async fn get_latest_uptime_aggregate(
     pool: &PgPool,
-    snapshot: &UptimeSnapshot,
-) -> AppResult<()> {
-    sqlx::query(
+    domain_id: Uuid,
+    period_type: &str,
+) -> AppResult<Option<UptimeAggregate>> {
+    sqlx::query_as::<_, UptimeAggregate>(
         r#"
-        INSERT INTO uptime_snapshots (
-            domain_id, check_time, status_code, response_time_ms,
-            is_up, error_type, consecutive_failures
-        )
-        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        SELECT * FROM uptime_aggregates
+        WHERE domain_id = $1 AND period_type = $2
+        ORDER BY period_start DESC
+        LIMIT 1
         "#
     )
-    .bind(snapshot.domain_id)
-    .bind(snapshot.check_time)
-    .bind(snapshot.status_code)
-    .bind(snapshot.response_time_ms)
-    .bind(snapshot.is_up)
-    .bind(&snapshot.error_type)
-    .bind(snapshot.consecutive_failures)
-    .execute(pool)
+    .bind(domain_id)
+    .bind(period_type)
+    .fetch_optional(pool)
     .await
-    .map_err(AppError::from)?;
-
-    Ok(())
+    .map_err(AppError::from)
 }
 
-/// Create uptime snapshot (helper for scheduler)
-pub async fn create_uptime_snapshot(
+/// List active domains with their organization's timezone (for aggregate computation)
+pub async fn list_aggregation_targets(
     pool: &PgPool,
-    domain_id: Uuid,
-    is_up: bool,
-    status_code: Option<i32>,
-    response_time_ms: i32,
-    error_message: Option<&str>,
-) -> AppResult<()> {
-    sqlx::query(
+    domain_id: Option<Uuid>,
+) -> AppResult<Vec<AggregationTarget>> {
+    sqlx::query_as::<_, AggregationTarget>(
         r#"
-        INSERT INTO uptime_snapshots (
-            domain_id, check_time, is_up, status_code, response_time_ms, error_type
-        )
-        VALUES ($1, NOW(), $2, $3, $4, $5)
+        SELECT d.id as domain_id, d.created_at, o.timezone
+        FROM domains d
+        INNER JOIN organizations o ON o.id = d.organization_id
+        WHERE d.is_active = true
+          AND ($1::uuid IS NULL OR d.id = $1)
+        ORDER BY d.created_at ASC
         "#
     )
     .bind(domain_id)
-    .bind(is_up)
-    .bind(status_code)
-    .bind(response_time_ms)
-    .bind(error_message)
-    .execute(pool)
+    .fetch_all(pool)
     .await
-    .map_err(AppError::from)?;
-
-    Ok(())
+    .map_err(AppError::from)
 }
 
-/// Get security header snapshot for a domain
-pub async fn get_latest_security_snapshot(
+/// Get the start of the last completed aggregate period for a domain
+pub async fn get_aggregate_watermark(
     pool: &PgPool,
     domain_id: Uuid,
-) -> AppResult<Option<SecurityHeaderSnapshot>> {
-    sqlx::query_as::<_, SecurityHeaderSnapshot>(
+    period_type: &str,
+) -> AppResult<Option<chrono::DateTime<chrono::Utc>>> {
+    sqlx::query_scalar::<_, chrono::DateTime<chrono::Utc>>(
         r#"
-        SELECT * FROM security_header_snapshots
-        WHERE domain_id = $1
-        ORDER BY check_time DESC
-        LIMIT 1
+        SELECT last_period_start FROM aggregate_watermarks
+        WHERE domain_id = $1 AND period_type = $2
         "#
     )
     .bind(domain_id)
+    .bind(period_type)
     .fetch_optional(pool)
     .await
     .map_err(AppError::from)
 }
 
-/// Save security header snapshot
-pub async fn save_security_snapshot(
+/// Record the last completed aggregate period for a domain
+pub async fn set_aggregate_watermark(
     pool: &PgPool,
-    snapshot: &SecurityHeaderSnapshot,
+    domain_id: Uuid,
+    period_type: &str,
+    last_period_start: chrono::DateTime<chrono::Utc>,
 ) -> AppResult<()> {
     sqlx::query(
         r#"
-        INSERT INTO security_header_snapshots (
-            domain_id, check_time, has_https_redirect, has_csp, has_hsts,
-            has_x_frame_options, has_x_content_type_options, csp_header, hsts_header, score
-        )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        INSERT INTO aggregate_watermarks (domain_id, period_type, last_period_start)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (domain_id, period_type)
+        DO UPDATE SET last_period_start = EXCLUDED.last_period_start, updated_at = NOW()
         "#
     )
-    .bind(snapshot.domain_id)
-    .bind(snapshot.check_time)
-    .bind(snapshot.has_https_redirect)
-    .bind(snapshot.has_csp)
-    .bind(snapshot.has_hsts)
-    .bind(snapshot.has_x_frame_options)
-    .bind(snapshot.has_x_content_type_options)
-    .bind(&snapshot.csp_header)
-    .bind(&snapshot.hsts_header)
-    .bind(snapshot.score)
+    .bind(domain_id)
+    .bind(period_type)
+    .bind(last_period_start)
     .execute(pool)
     .await
     .map_err(AppError::from)?;
@@ -841,305 +4073,379 @@ pub async fn save_security_snapshot(
     Ok(())
 }
 
+/// List all active domains (for scheduler)
+pub async fn list_all_active_domains(pool: &PgPool) -> AppResult<Vec<Domain>> {
+    sqlx::query_as::<_, Domain>(
+        "SELECT * FROM domains WHERE is_active = true ORDER BY created_at ASC"
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
 // ============================================================================
-// Alert Queries
+// Retention Queries
 // ============================================================================
 
-/// List alerts for an organization
-pub async fn list_organization_alerts(
+/// Get an organization's retention overrides
+pub async fn get_retention_policy(
     pool: &PgPool,
     organization_id: Uuid,
-    limit: i64,
-) -> AppResult<Vec<Alert>> {
-    sqlx::query_as::<_, Alert>(
+) -> AppResult<Option<RetentionPolicy>> {
+    sqlx::query_as::<_, RetentionPolicy>(
         r#"
-        SELECT * FROM alerts
+        SELECT uptime_snapshots_days, ssl_cert_snapshots_days, domain_dns_snapshots_days,
+               alerts_days, hourly_aggregates_days
+        FROM retention_policies
         WHERE organization_id = $1
-        ORDER BY created_at DESC
-        LIMIT $2
         "#
     )
     .bind(organization_id)
-    .bind(limit)
-    .fetch_all(pool)
+    .fetch_optional(pool)
     .await
     .map_err(AppError::from)
 }
 
-/// Create an alert
-pub async fn create_alert(
+/// Create or replace an organization's retention overrides
+pub async fn upsert_retention_policy(
     pool: &PgPool,
     organization_id: Uuid,
-    domain_id: Uuid,
-    alert_type: &str,
-    severity: AlertSeverity,
-    title: &str,
-    description: Option<&str>,
-    metadata: &serde_json::Value,
-) -> AppResult<Alert> {
-    let alert = sqlx::query_as::<_, Alert>(
+    policy: &RetentionPolicy,
+) -> AppResult<RetentionPolicy> {
+    sqlx::query_as::<_, RetentionPolicy>(
         r#"
-        INSERT INTO alerts (
-            organization_id, domain_id, alert_type, severity, title, description, metadata
+        INSERT INTO retention_policies (
+            organization_id, uptime_snapshots_days, ssl_cert_snapshots_days,
+            domain_dns_snapshots_days, alerts_days, hourly_aggregates_days
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7)
-        RETURNING *
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (organization_id)
+        DO UPDATE SET
+            uptime_snapshots_days = EXCLUDED.uptime_snapshots_days,
+            ssl_cert_snapshots_days = EXCLUDED.ssl_cert_snapshots_days,
+            domain_dns_snapshots_days = EXCLUDED.domain_dns_snapshots_days,
+            alerts_days = EXCLUDED.alerts_days,
+            hourly_aggregates_days = EXCLUDED.hourly_aggregates_days
+        RETURNING uptime_snapshots_days, ssl_cert_snapshots_days, domain_dns_snapshots_days,
+                  alerts_days, hourly_aggregates_days
         "#
     )
     .bind(organization_id)
-    .bind(domain_id)
-    .bind(alert_type)
-    .bind(severity)
-    .bind(title)
-    .bind(description)
-    .bind(metadata)
+    .bind(policy.uptime_snapshots_days)
+    .bind(policy.ssl_cert_snapshots_days)
+    .bind(policy.domain_dns_snapshots_days)
+    .bind(policy.alerts_days)
+    .bind(policy.hourly_aggregates_days)
     .fetch_one(pool)
     .await
-    .map_err(AppError::from)?;
-
-    Ok(alert)
+    .map_err(AppError::from)
 }
 
-/// Create a simple alert (helper function for scheduler)
-pub async fn create_simple_alert(
+/// Delete one batch of expired rows, returning the number of rows deleted
+///
+/// The cutoff is the organization's override or `default_days`, capped by the
+/// organization's `max_retention_days`. Rows that feed an aggregate are only
+/// deleted once that aggregate's watermark has passed them.
+pub async fn prune_expired_rows(
     pool: &PgPool,
-    domain_id: Uuid,
-    title: &str,
-    description: &str,
-) -> AppResult<Alert> {
-    // Get organization_id from domain
-    let domain = find_domain_by_id(pool, domain_id).await?
-        .ok_or_else(|| AppError::not_found("Domain not found"))?;
+    target: RetentionTarget,
+    default_days: i32,
+    batch_size: i64,
+) -> AppResult<u64> {
+    let table = target.table();
+    let time_column = target.time_column();
 
-    create_alert(
-        pool,
-        domain.organization_id,
-        domain_id,
-        "monitoring",
-        AlertSeverity::Warning,
-        title,
-        Some(description),
-        &serde_json::json!({}),
-    ).await
+    let (watermark_join, watermark_filter) = match target.required_aggregate() {
+        Some(period) => (
+            format!(
+                "INNER JOIN aggregate_watermarks w ON w.domain_id = t.domain_id AND w.period_type = '{}'",
+                period
+            ),
+            format!("AND t.{} < w.last_period_start", time_column),
+        ),
+        None => (String::new(), String::new()),
+    };
+    let type_filter = if target == RetentionTarget::HourlyAggregates {
+        "AND t.period_type = 'hour'"
+    } else {
+        ""
+    };
+
+    let sql = format!(
+        r#"
+        DELETE FROM {table} WHERE id IN (
+            SELECT t.id
+            FROM {table} t
+            INNER JOIN domains d ON d.id = t.domain_id
+            INNER JOIN organizations o ON o.id = d.organization_id
+            LEFT JOIN retention_policies rp ON rp.organization_id = d.organization_id
+            {watermark_join}
+            WHERE t.{time_column} < NOW() - make_interval(
+                days => LEAST(COALESCE(rp.{policy_column}, $1), o.max_retention_days)
+            )
+              {watermark_filter}
+              {type_filter}
+            LIMIT $2
+        )
+        "#,
+        policy_column = target.policy_column(),
+    );
+
+    let result = sqlx::query(&sql)
+        .bind(default_days)
+        .bind(batch_size)
+        .execute(pool)
+        .await
+        .map_err(AppError::from)?;
+
+    Ok(result.rows_affected())
 }
 
-/// Update alert webhook status
-pub async fn update_alert_webhook_status(
-    pool: &PgPool,
-    alert_id: Uuid,
-    success: bool,
-    status_code: Option<i32>,
-) -> AppResult<()> {
-    sqlx::query(
+/// Convert uptime_snapshots to a partitioned table (no-op if already partitioned)
+pub async fn partition_uptime_snapshots(pool: &PgPool) -> AppResult<()> {
+    sqlx::query("SELECT partition_uptime_snapshots()")
+        .execute(pool)
+        .await
+        .map_err(AppError::from)?;
+
+    Ok(())
+}
+
+/// Make sure monthly uptime_snapshots partitions exist for the coming months
+pub async fn ensure_uptime_snapshot_partitions(pool: &PgPool, months_ahead: i32) -> AppResult<()> {
+    sqlx::query("SELECT ensure_uptime_snapshot_partitions(NOW(), $1)")
+        .bind(months_ahead)
+        .execute(pool)
+        .await
+        .map_err(AppError::from)?;
+
+    Ok(())
+}
+
+/// Drop uptime_snapshots partitions that are expired for every organization
+///
+/// A partition is only dropped when it is older than the longest retention in use
+/// and hourly aggregates exist for it for all active domains.
+pub async fn drop_expired_uptime_partitions(pool: &PgPool, default_days: i32) -> AppResult<i32> {
+    sqlx::query_scalar::<_, i32>(
         r#"
-        UPDATE alerts
-        SET webhook_sent_at = NOW(),
-            webhook_success = $1,
-            webhook_status_code = $2
-        WHERE id = $3
+        SELECT drop_uptime_snapshot_partitions_before(LEAST(
+            NOW() - make_interval(days => GREATEST(
+                $1,
+                COALESCE((SELECT MAX(uptime_snapshots_days) FROM retention_policies), 0)
+            )),
+            COALESCE((
+                SELECT MIN(w.last_period_start)
+                FROM aggregate_watermarks w
+                INNER JOIN domains d ON d.id = w.domain_id
+                WHERE w.period_type = 'hour' AND d.is_active = true
+            ), '-infinity'::timestamptz)
+        ))
         "#
     )
-    .bind(success)
-    .bind(status_code)
-    .bind(alert_id)
-    .execute(pool)
+    .bind(default_days)
+    .fetch_one(pool)
     .await
-    .map_err(AppError::from)?;
-
-    Ok(())
+    .map_err(AppError::from)
 }
 
 // ============================================================================
-// Refresh Token Queries
+// SLO Queries
 // ============================================================================
 
-/// Create a refresh token
-pub async fn create_refresh_token(
-    pool: &PgPool,
-    user_id: Uuid,
-    token_hash: &str,
-    expires_at: chrono::DateTime<chrono::Utc>,
-) -> AppResult<RefreshToken> {
-    let token = sqlx::query_as::<_, RefreshToken>(
-        r#"
-        INSERT INTO refresh_tokens (user_id, token_hash, expires_at)
-        VALUES ($1, $2, $3)
-        RETURNING *
-        "#
+/// List SLOs defined for a domain
+pub async fn list_domain_slos(pool: &PgPool, domain_id: Uuid) -> AppResult<Vec<Slo>> {
+    sqlx::query_as::<_, Slo>(
+        "SELECT * FROM slos WHERE domain_id = $1 ORDER BY created_at"
     )
-    .bind(user_id)
-    .bind(token_hash)
-    .bind(expires_at)
-    .fetch_one(pool)
+    .bind(domain_id)
+    .fetch_all(pool)
     .await
-    .map_err(AppError::from)?;
-
-    Ok(token)
+    .map_err(AppError::from)
 }
 
-/// Find a refresh token by hash
-pub async fn find_refresh_token(pool: &PgPool, token_hash: &str) -> AppResult<Option<RefreshToken>> {
-    sqlx::query_as::<_, RefreshToken>(
+/// List enabled SLOs of active domains
+pub async fn list_enabled_slos(pool: &PgPool) -> AppResult<Vec<Slo>> {
+    sqlx::query_as::<_, Slo>(
         r#"
-        SELECT * FROM refresh_tokens
-        WHERE token_hash = $1
-        AND is_revoked = false
-        AND expires_at > NOW()
+        SELECT s.* FROM slos s
+        INNER JOIN domains d ON d.id = s.domain_id
+        WHERE s.is_enabled = true AND d.is_active = true
         "#
     )
-    .bind(token_hash)
-    .fetch_optional(pool)
+    .fetch_all(pool)
     .await
     .map_err(AppError::from)
 }
 
-/// Revoke a refresh token
-pub async fn revoke_refresh_token(pool: &PgPool, token_id: Uuid) -> AppResult<()> {
-    sqlx::query(
+/// Find an SLO by ID
+pub async fn find_slo_by_id(pool: &PgPool, id: Uuid) -> AppResult<Option<Slo>> {
+    sqlx::query_as::<_, Slo>("SELECT * FROM slos WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Create an SLO for a domain
+pub async fn create_slo(pool: &PgPool, domain_id: Uuid, input: &SloInput) -> AppResult<Slo> {
+    sqlx::query_as::<_, Slo>(
         r#"
-        UPDATE refresh_tokens
-        SET is_revoked = true, revoked_at = NOW()
-        WHERE id = $1
+        INSERT INTO slos (
+            domain_id, name, availability_target, latency_threshold_ms, latency_percentile,
+            window_days, fast_burn_threshold, slow_burn_threshold, is_enabled
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        RETURNING *
         "#
     )
-    .bind(token_id)
-    .execute(pool)
+    .bind(domain_id)
+    .bind(&input.name)
+    .bind(input.availability_target)
+    .bind(input.latency_threshold_ms)
+    .bind(input.latency_percentile)
+    .bind(input.window_days)
+    .bind(input.fast_burn_threshold)
+    .bind(input.slow_burn_threshold)
+    .bind(input.is_enabled)
+    .fetch_one(pool)
     .await
-    .map_err(AppError::from)?;
-
-    Ok(())
+    .map_err(AppError::from)
 }
 
-/// Revoke all refresh tokens for a user
-pub async fn revoke_all_user_tokens(pool: &PgPool, user_id: Uuid) -> AppResult<()> {
-    sqlx::query(
+/// Replace an SLO's definition
+pub async fn update_slo(pool: &PgPool, id: Uuid, input: &SloInput) -> AppResult<Slo> {
+    sqlx::query_as::<_, Slo>(
         r#"
-        UPDATE refresh_tokens
-        SET is_revoked = true, revoked_at = NOW()
-        WHERE user_id = $1 AND is_revoked = false
+        UPDATE slos
+        SET name = $2,
+            availability_target = $3,
+            latency_threshold_ms = $4,
+            latency_percentile = $5,
+            window_days = $6,
+            fast_burn_threshold = $7,
+            slow_burn_threshold = $8,
+            is_enabled = $9
+        WHERE id = $1
+        RETURNING *
         "#
     )
-    .bind(user_id)
-    .execute(pool)
+    .bind(id)
+    .bind(&input.name)
+    .bind(input.availability_target)
+    .bind(input.latency_threshold_ms)
+    .bind(input.latency_percentile)
+    .bind(input.window_days)
+    .bind(input.fast_burn_threshold)
+    .bind(input.slow_burn_threshold)
+    .bind(input.is_enabled)
+    .fetch_one(pool)
     .await
-    .map_err(AppError::from)?;
+    .map_err(AppError::from)
+}
+
+/// Delete an SLO and its history
+pub async fn delete_slo(pool: &PgPool, id: Uuid) -> AppResult<()> {
+    sqlx::query("DELETE FROM slos WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(AppError::from)?;
 
     Ok(())
 }
 
-// ============================================================================
-// Enhanced Monitoring Queries
-// ============================================================================
+/// Record the burn state an SLO was last evaluated in
+pub async fn set_slo_burn_state(pool: &PgPool, id: Uuid, state: BurnState) -> AppResult<()> {
+    sqlx::query("UPDATE slos SET burn_state = $2 WHERE id = $1")
+        .bind(id)
+        .bind(state)
+        .execute(pool)
+        .await
+        .map_err(AppError::from)?;
 
-/// Get latest uptime snapshot for a domain
-pub async fn get_latest_uptime_snapshot(
-    pool: &PgPool,
-    domain_id: Uuid,
-) -> AppResult<Option<UptimeSnapshot>> {
-    sqlx::query_as::<_, UptimeSnapshot>(
-        r#"
-        SELECT * FROM uptime_snapshots
-        WHERE domain_id = $1
-        ORDER BY check_time DESC
-        LIMIT 1
-        "#
-    )
-    .bind(domain_id)
-    .fetch_optional(pool)
-    .await
-    .map_err(AppError::from)
+    Ok(())
 }
 
-/// Get uptime history for a domain with time bucketing
-/// Returns data points grouped by the specified interval
-pub async fn get_uptime_history(
-    pool: &PgPool,
-    domain_id: Uuid,
-    hours_back: i64,
-    interval_minutes: i64,
-) -> AppResult<Vec<UptimeSnapshot>> {
-    sqlx::query_as::<_, UptimeSnapshot>(
+/// Count total and good checks for an SLO from hourly aggregates
+///
+/// Windows end at the latest hourly aggregate. A successful check is good unless
+/// its hour breached the SLO's latency threshold.
+pub async fn get_slo_counts(pool: &PgPool, slo: &Slo) -> AppResult<SloCounts> {
+    sqlx::query_as::<_, SloCounts>(
         r#"
-        WITH time_buckets AS (
+        WITH latest AS (
+            SELECT MAX(period_end) AS as_of
+            FROM uptime_aggregates
+            WHERE domain_id = $1 AND period_type = 'hour'
+        ),
+        hours AS (
             SELECT
-                date_trunc('minute',
-                    check_time - INTERVAL '1 minute' * (
-                        EXTRACT(MINUTE FROM check_time)::int % $3
-                    )
-                ) as bucket_time,
-                bool_and(is_up) as is_up,
-                COALESCE(avg(response_time_ms)::int, 0) as avg_response_time_ms,
-                max(status_code) as status_code,
-                (array_agg(id ORDER BY check_time DESC))[1] as id,
-                $1 as domain_id,
-                max(check_time) as check_time,
-                max(consecutive_failures) as consecutive_failures,
-                (array_agg(error_type ORDER BY check_time DESC))[1] as error_type
-            FROM uptime_snapshots
-            WHERE domain_id = $1
-              AND check_time >= NOW() - INTERVAL '1 hour' * $2
-            GROUP BY bucket_time
-            ORDER BY bucket_time DESC
+                a.period_start,
+                a.total_checks,
+                CASE
+                    WHEN $3::INTEGER IS NULL THEN a.successful_checks
+                    WHEN (CASE WHEN $4 = 'p99' THEN a.p99_response_time_ms ELSE a.p95_response_time_ms END) <= $3
+                        THEN a.successful_checks
+                    ELSE 0
+                END AS good_checks
+            FROM uptime_aggregates a, latest
+            WHERE a.domain_id = $1
+              AND a.period_type = 'hour'
+              AND a.period_end <= latest.as_of
+              AND a.period_start >= latest.as_of - make_interval(days => $2)
         )
         SELECT
-            id, domain_id, check_time, status_code,
-            avg_response_time_ms as response_time_ms,
-            is_up, error_type, consecutive_failures
-        FROM time_buckets
+            latest.as_of,
+            COALESCE(SUM(h.total_checks), 0)::BIGINT AS total_checks,
+            COALESCE(SUM(h.good_checks), 0)::BIGINT AS good_checks,
+            COALESCE(SUM(h.total_checks) FILTER (WHERE h.period_start >= latest.as_of - INTERVAL '1 hour'), 0)::BIGINT AS total_checks_1h,
+            COALESCE(SUM(h.good_checks) FILTER (WHERE h.period_start >= latest.as_of - INTERVAL '1 hour'), 0)::BIGINT AS good_checks_1h,
+            COALESCE(SUM(h.total_checks) FILTER (WHERE h.period_start >= latest.as_of - INTERVAL '6 hours'), 0)::BIGINT AS total_checks_6h,
+            COALESCE(SUM(h.good_checks) FILTER (WHERE h.period_start >= latest.as_of - INTERVAL '6 hours'), 0)::BIGINT AS good_checks_6h
+        FROM latest
+        LEFT JOIN hours h ON true
+        GROUP BY latest.as_of
         "#
     )
-    .bind(domain_id)
-    .bind(hours_back)
-    .bind(interval_minutes)
-    .fetch_all(pool)
+    .bind(slo.domain_id)
+    .bind(slo.window_days)
+    .bind(slo.latency_threshold_ms)
+    .bind(slo.latency_percentile.to_string())
+    .fetch_one(pool)
     .await
     .map_err(AppError::from)
 }
 
-/// Compute and save uptime aggregate statistics
-pub async fn compute_uptime_aggregate(
-    pool: &PgPool,
-    domain_id: Uuid,
-    period_start: chrono::DateTime<chrono::Utc>,
-    period_end: chrono::DateTime<chrono::Utc>,
-    period_type: &str,
-) -> AppResult<()> {
+/// Save an SLO evaluation, replacing any previous one for the same hour
+pub async fn save_slo_snapshot(pool: &PgPool, slo_id: Uuid, snapshot: &SloSnapshot) -> AppResult<()> {
     sqlx::query(
         r#"
-        INSERT INTO uptime_aggregates (
-            domain_id, period_type, period_start, period_end,
-            uptime_percentage, avg_response_time_ms,
-            total_checks, successful_checks
+        INSERT INTO slo_snapshots (
+            slo_id, as_of, total_checks, good_checks, sli_percentage,
+            error_budget_remaining, burn_rate_1h, burn_rate_6h, burn_state
         )
-        SELECT
-            $1 as domain_id,
-            $2 as period_type,
-            $3 as period_start,
-            $4 as period_end,
-            CASE
-                WHEN COUNT(*) = 0 THEN 0
-                ELSE (COUNT(*) FILTER (WHERE is_up = true)::float / COUNT(*)::float * 100)
-            END as uptime_percentage,
-            COALESCE(AVG(response_time_ms) FILTER (WHERE is_up = true), 0)::int as avg_response_time_ms,
-            COUNT(*) as total_checks,
-            COUNT(*) FILTER (WHERE is_up = true) as successful_checks
-        FROM uptime_snapshots
-        WHERE domain_id = $1
-          AND check_time >= $3
-          AND check_time < $4
-        ON CONFLICT (domain_id, period_start, period_type)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        ON CONFLICT (slo_id, as_of)
         DO UPDATE SET
-            period_end = EXCLUDED.period_end,
-            uptime_percentage = EXCLUDED.uptime_percentage,
-            avg_response_time_ms = EXCLUDED.avg_response_time_ms,
             total_checks = EXCLUDED.total_checks,
-            successful_checks = EXCLUDED.successful_checks
+            good_checks = EXCLUDED.good_checks,
+            sli_percentage = EXCLUDED.sli_percentage,
+            error_budget_remaining = EXCLUDED.error_budget_remaining,
+            burn_rate_1h = EXCLUDED.burn_rate_1h,
+            burn_rate_6h = EXCLUDED.burn_rate_6h,
+            burn_state = EXCLUDED.burn_state
         "#
     )
-    .bind(domain_id)
-    .bind(period_type)
-    .bind(period_start)
-    .bind(period_end)
+    .bind(slo_id)
+    .bind(snapshot.as_of)
+    .bind(snapshot.total_checks)
+    .bind(snapshot.good_checks)
+    .bind(snapshot.sli_percentage)
+    .bind(snapshot.error_budget_remaining)
+    .bind(snapshot.burn_rate_1h)
+    .bind(snapshot.burn_rate_6h)
+    .bind(snapshot.burn_state)
     .execute(pool)
     .await
     .map_err(AppError::from)?;
@@ -1147,55 +4453,41 @@ pub async fn compute_uptime_aggregate(
     Ok(())
 }
 
-/// Get uptime aggregates for a domain
-pub async fn get_uptime_aggregates(
-    pool: &PgPool,
-    domain_id: Uuid,
-    period_type: &str,
-    limit: i64,
-) -> AppResult<Vec<UptimeAggregate>> {
-    sqlx::query_as::<_, UptimeAggregate>(
+/// Get the most recent evaluation of an SLO
+pub async fn get_latest_slo_snapshot(pool: &PgPool, slo_id: Uuid) -> AppResult<Option<SloSnapshot>> {
+    sqlx::query_as::<_, SloSnapshot>(
         r#"
-        SELECT * FROM uptime_aggregates
-        WHERE domain_id = $1 AND period_type = $2
-        ORDER BY period_start DESC
-        LIMIT $3
+        SELECT as_of, total_checks, good_checks, sli_percentage, error_budget_remaining,
+               burn_rate_1h, burn_rate_6h, burn_state
+        FROM slo_snapshots
+        WHERE slo_id = $1
+        ORDER BY as_of DESC
+        LIMIT 1
         "#
     )
-    .bind(domain_id)
-    .bind(period_type)
-    .bind(limit)
-    .fetch_all(pool)
+    .bind(slo_id)
+    .fetch_optional(pool)
     .await
     .map_err(AppError::from)
 }
 
-/// Get latest uptime aggregate for a domain (typically used for dashboard)
-pub async fn get_latest_uptime_aggregate(
+/// Get the evaluation history of an SLO since the given time
+pub async fn get_slo_history(
     pool: &PgPool,
-    domain_id: Uuid,
-    period_type: &str,
-) -> AppResult<Option<UptimeAggregate>> {
-    sqlx::query_as::<_, UptimeAggregate>(
+    slo_id: Uuid,
+    since: chrono::DateTime<chrono::Utc>,
+) -> AppResult<Vec<SloSnapshot>> {
+    sqlx::query_as::<_, SloSnapshot>(
         r#"
-        SELECT * FROM uptime_aggregates
-        WHERE domain_id = $1 AND period_type = $2
-        ORDER BY period_start DESC
-        LIMIT 1
+        SELECT as_of, total_checks, good_checks, sli_percentage, error_budget_remaining,
+               burn_rate_1h, burn_rate_6h, burn_state
+        FROM slo_snapshots
+        WHERE slo_id = $1 AND as_of >= $2
+        ORDER BY as_of ASC
         "#
     )
-    .bind(domain_id)
-    .bind(period_type)
-    .fetch_optional(pool)
-    .await
-    .map_err(AppError::from)
-}
-
-/// List all active domains (for scheduler)
-pub async fn list_all_active_domains(pool: &PgPool) -> AppResult<Vec<Domain>> {
-    sqlx::query_as::<_, Domain>(
-        "SELECT * FROM domains WHERE is_active = true ORDER BY created_at ASC"
-    )
+    .bind(slo_id)
+    .bind(since)
     .fetch_all(pool)
     .await
     .map_err(AppError::from)
@@ -1205,10 +4497,14 @@ pub async fn list_all_active_domains(pool: &PgPool) -> AppResult<Vec<Domain>> {
 // Statistics & Analytics Queries
 // ============================================================================
 
-/// Get organization-level statistics
+/// Get organization-level statistics, optionally only for domains in the given
+/// groups, in the subtree of `group_id` and matching `tags`
 pub async fn get_organization_stats(
     pool: &PgPool,
     organization_id: Uuid,
+    group_ids: Option<&[Uuid]>,
+    group_id: Option<Uuid>,
+    tags: &TagSelector,
 ) -> AppResult<OrganizationStats> {
     sqlx::query_as::<_, OrganizationStats>(
         r#"
@@ -1235,6 +4531,10 @@ pub async fn get_organization_stats(
                 LIMIT 1
             ) ssl ON true
             WHERE d.organization_id = $1
+              AND ($2::uuid[] IS NULL OR d.group_id = ANY($2))
+              AND ($3::uuid IS NULL OR d.group_id = ANY(domain_group_subtree(ARRAY[$3]::uuid[])))
+              AND ($4::jsonb IS NULL OR d.tags @> $4)
+              AND ($5::text[] IS NULL OR d.tags ?& $5)
         )
         SELECT
             COUNT(*)::bigint as total_domains,
@@ -1243,16 +4543,25 @@ pub async fn get_organization_stats(
             COUNT(*) FILTER (WHERE is_valid = true AND is_expired = false)::bigint as ssl_valid_domains,
             (
                 SELECT COUNT(*)::bigint
-                FROM alerts
-                WHERE organization_id = $1
-                  AND severity = 'critical'
-                  AND created_at > NOW() - INTERVAL '24 hours'
+                FROM alerts a
+                INNER JOIN domains d ON d.id = a.domain_id
+                WHERE a.organization_id = $1
+                  AND ($2::uuid[] IS NULL OR d.group_id = ANY($2))
+                  AND ($3::uuid IS NULL OR d.group_id = ANY(domain_group_subtree(ARRAY[$3]::uuid[])))
+                  AND ($4::jsonb IS NULL OR d.tags @> $4)
+                  AND ($5::text[] IS NULL OR d.tags ?& $5)
+                  AND a.severity = 'critical'
+                  AND a.created_at > NOW() - INTERVAL '24 hours'
             ) as critical_alerts_24h,
             (
                 SELECT AVG(uptime_percentage)::decimal(5,2)
                 FROM uptime_aggregates ua
                 INNER JOIN domains d ON d.id = ua.domain_id
                 WHERE d.organization_id = $1
+                  AND ($2::uuid[] IS NULL OR d.group_id = ANY($2))
+                  AND ($3::uuid IS NULL OR d.group_id = ANY(domain_group_subtree(ARRAY[$3]::uuid[])))
+                  AND ($4::jsonb IS NULL OR d.tags @> $4)
+                  AND ($5::text[] IS NULL OR d.tags ?& $5)
                   AND ua.period_type = 'week'
                   AND ua.period_start >= NOW() - INTERVAL '7 days'
             ) as avg_uptime_7d
@@ -1260,58 +4569,120 @@ pub async fn get_organization_stats(
         "#
     )
     .bind(organization_id)
+    .bind(group_ids)
+    .bind(group_id)
+    .bind(tags.values_json())
+    .bind(tags.keys())
     .fetch_one(pool)
     .await
     .map_err(AppError::from)
 }
 
-/// List domains with monitoring status (enhanced for domain list page)
+/// List domains of an organization with their latest uptime and SSL status
+///
+/// `group_ids` restricts the list to some groups (subgroups already included).
+/// Returns up to `limit` domains after the domain `after` in the requested
+/// order, and the number of domains matching the filters.
 pub async fn list_organization_domains_with_status(
     pool: &PgPool,
     organization_id: Uuid,
-) -> AppResult<Vec<DomainWithStatus>> {
-    sqlx::query_as::<_, DomainWithStatus>(
+    group_ids: Option<&[Uuid]>,
+    filter: &DomainListFilter,
+    tags: &TagSelector,
+    after: Option<Uuid>,
+    limit: i64,
+) -> AppResult<(Vec<DomainWithStatus>, i64)> {
+    let rows = sqlx::query_as::<_, Counted<DomainWithStatus>>(
         r#"
-        SELECT
-            d.id,
-            d.organization_id,
-            d.name,
-            COALESCE(d.url, d.normalized_name) as url,
-            d.normalized_name,
-            d.is_active,
-            d.created_at,
-            d.updated_at,
-            us.is_up as uptime_is_up,
-            us.response_time_ms as uptime_response_time_ms,
-            us.status_code as uptime_status_code,
-            us.consecutive_failures as uptime_consecutive_failures,
-            ssl.is_valid as ssl_is_valid,
-            ssl.days_until_expiry as ssl_days_until_expiry,
-            ssl.is_expiring_soon as ssl_is_expiring_soon,
-            ssl.is_expired as ssl_is_expired
-        FROM domains d
-        LEFT JOIN LATERAL (
-            SELECT is_up, response_time_ms, status_code, consecutive_failures
-            FROM uptime_snapshots
-            WHERE domain_id = d.id
-            ORDER BY check_time DESC
-            LIMIT 1
-        ) us ON true
-        LEFT JOIN LATERAL (
-            SELECT is_valid, days_until_expiry, is_expiring_soon, is_expired
-            FROM ssl_cert_snapshots
-            WHERE domain_id = d.id
-            ORDER BY check_time DESC
-            LIMIT 1
-        ) ssl ON true
-        WHERE d.organization_id = $1
-        ORDER BY d.created_at DESC
+        WITH matching AS (
+            SELECT
+                d.id,
+                d.organization_id,
+                d.name,
+                COALESCE(d.url, d.normalized_name) as url,
+                d.normalized_name,
+                d.is_active,
+                d.group_id,
+                d.tags,
+                d.created_at,
+                d.updated_at,
+                us.is_up as uptime_is_up,
+                us.response_time_ms as uptime_response_time_ms,
+                us.status_code as uptime_status_code,
+                us.consecutive_failures as uptime_consecutive_failures,
+                ssl.is_valid as ssl_is_valid,
+                ssl.days_until_expiry as ssl_days_until_expiry,
+                ssl.is_expiring_soon as ssl_is_expiring_soon,
+                ssl.is_expired as ssl_is_expired,
+                CASE WHEN $6::text IS NULL THEN 0
+                     ELSE GREATEST(word_similarity($6, d.display_name), word_similarity($6, d.url))
+                END as relevance
+            FROM domains d
+            LEFT JOIN LATERAL (
+                SELECT is_up, response_time_ms, status_code, consecutive_failures
+                FROM uptime_snapshots
+                WHERE domain_id = d.id
+                ORDER BY check_time DESC
+                LIMIT 1
+            ) us ON true
+            LEFT JOIN LATERAL (
+                SELECT is_valid, days_until_expiry, is_expiring_soon, is_expired
+                FROM ssl_cert_snapshots
+                WHERE domain_id = d.id
+                ORDER BY check_time DESC
+                LIMIT 1
+            ) ssl ON true
+            WHERE d.organization_id = $1
+              AND ($2::uuid[] IS NULL OR d.group_id = ANY($2))
+              AND ($3::uuid IS NULL OR d.group_id = ANY(domain_group_subtree(ARRAY[$3]::uuid[])))
+              AND ($4::jsonb IS NULL OR d.tags @> $4)
+              AND ($5::text[] IS NULL OR d.tags ?& $5)
+              AND ($6::text IS NULL OR GREATEST(word_similarity($6, d.display_name), word_similarity($6, d.url)) >= 0.3)
+              AND ($7::boolean IS NULL OR us.is_up = $7)
+              AND ($8::boolean IS NULL OR COALESCE(ssl.is_expiring_soon OR ssl.is_expired, false) = $8)
+        ),
+        after AS (SELECT * FROM matching WHERE id = $9)
+        SELECT m.*, (SELECT COUNT(*) FROM matching) AS total
+        FROM matching m
+        WHERE $9::uuid IS NULL OR CASE $10::text
+            WHEN 'created_at' THEN (m.created_at, m.id) > (SELECT created_at, id FROM after)
+            WHEN 'name' THEN (m.name, m.id) > (SELECT name, id FROM after)
+            WHEN '-name' THEN (m.name, m.id) < (SELECT name, id FROM after)
+            WHEN 'url' THEN (m.url, m.id) > (SELECT url, id FROM after)
+            WHEN '-url' THEN (m.url, m.id) < (SELECT url, id FROM after)
+            WHEN 'relevance' THEN (m.relevance, m.id) < (SELECT relevance, id FROM after)
+            ELSE (m.created_at, m.id) < (SELECT created_at, id FROM after)
+        END
+        ORDER BY
+            CASE WHEN $10 = 'created_at' THEN m.created_at END,
+            CASE WHEN $10 = 'name' THEN m.name END,
+            CASE WHEN $10 = '-name' THEN m.name END DESC,
+            CASE WHEN $10 = 'url' THEN m.url END,
+            CASE WHEN $10 = '-url' THEN m.url END DESC,
+            CASE WHEN $10 = 'relevance' THEN m.relevance END DESC,
+            CASE WHEN $10 IN ('created_at', 'name', 'url') THEN m.id END,
+            CASE WHEN $10 IN ('-name', '-url', 'relevance') THEN m.id END DESC,
+            m.created_at DESC,
+            m.id DESC
+        LIMIT $11
         "#
     )
     .bind(organization_id)
+    .bind(group_ids)
+    .bind(filter.group_id)
+    .bind(tags.values_json())
+    .bind(tags.keys())
+    .bind(&filter.q)
+    .bind(filter.status.map(UptimeStatus::is_up))
+    .bind(filter.ssl_expiring)
+    .bind(after)
+    .bind(filter.sort().as_str())
+    .bind(limit)
     .fetch_all(pool)
     .await
-    .map_err(AppError::from)
+    .map_err(AppError::from)?;
+
+    Ok(split_total(rows))
 }
 
 /// Get comprehensive domain statistics (for domain detail page)
@@ -1386,9 +4757,10 @@ pub async fn find_organization_by_slug_with_domains(
     slug: &str,
 ) -> AppResult<Option<OrganizationWithDomains>> {
     // Get organization
+    // Organizations scheduled for deletion have no public page
     let org = match find_organization_by_slug(pool, slug).await? {
-        Some(o) => o,
-        None => return Ok(None),
+        Some(o) if o.deleted_at.is_none() => o,
+        _ => return Ok(None),
     };
 
     // Get domains with latest status
@@ -1399,6 +4771,8 @@ pub async fn find_organization_by_slug_with_domains(
             d.name,
             COALESCE(d.url, d.normalized_name) as url,
             d.is_active,
+            d.group_id,
+            d.tags,
             us.is_up,
             us.response_time_ms,
             us.check_time as last_check_time,
@@ -1433,8 +4807,40 @@ pub async fn find_organization_by_slug_with_domains(
     .await
     .map_err(AppError::from)?;
 
+    // Sections depend on the page's options and are added by the handler
     Ok(Some(OrganizationWithDomains {
         organization: org,
         domains,
+        sections: Vec::new(),
     }))
 }
+
+/// Send an event about a domain on a notification channel, with the domain's
+/// organization and group added to its payload
+pub async fn notify_domain_event(
+    pool: &PgPool,
+    channel: &str,
+    domain_id: Uuid,
+    event: &serde_json::Value,
+) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        SELECT pg_notify($1, (jsonb_build_object(
+            'organization_id', d.organization_id,
+            'domain_id', d.id,
+            'group_id', d.group_id,
+            'at', NOW()
+        ) || $3)::text)
+        FROM domains d
+        WHERE d.id = $2
+        "#
+    )
+    .bind(channel)
+    .bind(domain_id)
+    .bind(event)
+    .execute(pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(())
+}
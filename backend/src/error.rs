@@ -1,10 +1,11 @@
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Json, Response},
 };
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::fmt;
+use std::time::Duration;
 use thiserror::Error;
 
 /// Error response structure for API documentation
@@ -42,6 +43,21 @@ pub enum AppError {
     #[error("Not found: {0}")]
     NotFound(String),
 
+    #[error("Rate limited: {message}")]
+    RateLimited {
+        message: String,
+        /// How long the client should wait before retrying
+        retry_after: Duration,
+    },
+
+    #[error("Quota exceeded: {message}")]
+    QuotaExceeded {
+        message: String,
+        /// Name of the exceeded quota, e.g. `max_monitors`
+        quota: &'static str,
+        limit: i64,
+    },
+
     #[error("Internal server error: {0}")]
     Internal(String),
 }
@@ -65,6 +81,10 @@ impl fmt::Display for ErrorDetail {
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        let retry_after = match &self {
+            AppError::RateLimited { retry_after, .. } => Some(*retry_after),
+            _ => None,
+        };
         let (status, error_detail) = self.to_error_detail();
 
         let body = json!({
@@ -75,10 +95,19 @@ impl IntoResponse for AppError {
             }
         });
 
-        (status, Json(body)).into_response()
+        let mut response = (status, Json(body)).into_response();
+        if let Some(retry_after) = retry_after {
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs(retry_after)));
+        }
+        response
     }
 }
 
+/// Whole seconds to wait, rounded up so that clients never retry too early
+fn retry_after_secs(retry_after: Duration) -> u64 {
+    retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0)
+}
+
 impl AppError {
     /// Convert AppError to ErrorDetail and StatusCode
     fn to_error_detail(self) -> (StatusCode, ErrorDetail) {
@@ -127,6 +156,31 @@ impl AppError {
                     details: None,
                 },
             ),
+            AppError::RateLimited { message, retry_after } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                ErrorDetail {
+                    code: "RATE_LIMITED".to_string(),
+                    message,
+                    details: Some({
+                        let mut map = std::collections::HashMap::new();
+                        map.insert("retry_after".to_string(), retry_after_secs(retry_after).to_string());
+                        map
+                    }),
+                },
+            ),
+            AppError::QuotaExceeded { message, quota, limit } => (
+                StatusCode::FORBIDDEN,
+                ErrorDetail {
+                    code: "QUOTA_EXCEEDED".to_string(),
+                    message,
+                    details: Some({
+                        let mut map = std::collections::HashMap::new();
+                        map.insert("quota".to_string(), quota.to_string());
+                        map.insert("limit".to_string(), limit.to_string());
+                        map
+                    }),
+                },
+            ),
             AppError::Task(msg) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 ErrorDetail {
@@ -190,6 +244,25 @@ impl AppError {
         Self::NotFound(msg.into())
     }
 
+    /// Create a rate limit error, answered with a `Retry-After` header
+    #[must_use]
+    pub fn rate_limited(msg: impl Into<String>, retry_after: Duration) -> Self {
+        Self::RateLimited {
+            message: msg.into(),
+            retry_after,
+        }
+    }
+
+    /// Create a quota error for an organization limit
+    #[must_use]
+    pub fn quota_exceeded(msg: impl Into<String>, quota: &'static str, limit: i64) -> Self {
+        Self::QuotaExceeded {
+            message: msg.into(),
+            quota,
+            limit,
+        }
+    }
+
     /// Create a task error
     #[must_use]
     pub fn task(msg: impl Into<String>) -> Self {
@@ -272,4 +345,19 @@ mod tests {
         let err = AppError::not_found("User not found");
         assert!(matches!(err, AppError::NotFound(_)));
     }
+
+    #[test]
+    fn test_rate_limited_response() {
+        let response = AppError::rate_limited("Too many requests", Duration::from_millis(1500)).into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "2");
+    }
+
+    #[test]
+    fn test_quota_exceeded_response() {
+        let (status, detail) = AppError::quota_exceeded("Monitor limit reached", "max_monitors", 10).to_error_detail();
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(detail.code, "QUOTA_EXCEEDED");
+        assert_eq!(detail.details.unwrap()["limit"], "10");
+    }
 }
@@ -0,0 +1,308 @@
+//! Real-time organization events
+//!
+//! Checks, incidents and alerts are published with Postgres `NOTIFY` on
+//! [`CHANNEL`] by whichever process produces them. Every API instance runs an
+//! [`EventBus`] that `LISTEN`s on the channel and fans events out to its SSE and
+//! WebSocket subscribers, so clients get all events of their organization
+//! whichever instance they are connected to. Publishing is best-effort: a
+//! failure is logged and never fails the check or request behind the event.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::auth::Access;
+use crate::db::models::{Alert, AlertSeverity, Permission};
+use crate::db::queries;
+use crate::error::{AppError, AppResult};
+
+/// Postgres notification channel carrying events
+pub const CHANNEL: &str = "webguard_events";
+
+/// Events buffered per subscriber before it starts missing some
+const BUFFER_SIZE: usize = 1024;
+
+/// Wait before listening again after the connection failed
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Event about a domain of an organization
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Event {
+    pub organization_id: Uuid,
+    pub domain_id: Uuid,
+    /// Group of the domain, to only deliver the event to members with access
+    pub group_id: Option<Uuid>,
+    pub at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+/// What happened, serialized as `type` and `data`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum EventKind {
+    UptimeChecked {
+        is_up: bool,
+        status_code: Option<i32>,
+        response_time_ms: i32,
+        error: Option<String>,
+    },
+    SslChecked {
+        is_expired: bool,
+        days_until_expiry: i64,
+    },
+    /// The domain went down
+    IncidentOpened {
+        error: Option<String>,
+    },
+    /// The domain is up again
+    IncidentResolved,
+    AlertCreated {
+        alert_id: Uuid,
+        severity: AlertSeverity,
+        title: String,
+    },
+}
+
+impl EventKind {
+    /// Name of the event type, as in the `type` field
+    #[must_use]
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::UptimeChecked { .. } => "uptime_checked",
+            Self::SslChecked { .. } => "ssl_checked",
+            Self::IncidentOpened { .. } => "incident_opened",
+            Self::IncidentResolved => "incident_resolved",
+            Self::AlertCreated { .. } => "alert_created",
+        }
+    }
+}
+
+/// Publish an event about a domain
+pub async fn publish(pool: &PgPool, domain_id: Uuid, kind: EventKind) {
+    let name = kind.name();
+    let result = match serde_json::to_value(kind) {
+        Ok(kind) => queries::notify_domain_event(pool, CHANNEL, domain_id, &kind).await,
+        Err(e) => Err(AppError::internal(format!("Failed to encode event: {}", e))),
+    };
+
+    if let Err(e) = result {
+        tracing::warn!("Failed to publish {} event: {}", name, e);
+    }
+}
+
+/// Publish the result of an uptime check, and the incident it opens or
+/// resolves given the result of the previous check
+pub async fn publish_uptime_check(
+    pool: &PgPool,
+    domain_id: Uuid,
+    was_up: Option<bool>,
+    is_up: bool,
+    status_code: Option<i32>,
+    response_time_ms: i32,
+    error: Option<String>,
+) {
+    let incident = match (was_up, is_up) {
+        (Some(true) | None, false) => Some(EventKind::IncidentOpened { error: error.clone() }),
+        (Some(false), true) => Some(EventKind::IncidentResolved),
+        _ => None,
+    };
+
+    publish(pool, domain_id, EventKind::UptimeChecked { is_up, status_code, response_time_ms, error }).await;
+    if let Some(incident) = incident {
+        publish(pool, domain_id, incident).await;
+    }
+}
+
+/// Publish the creation of an alert
+pub async fn publish_alert(pool: &PgPool, alert: &Alert) {
+    let kind = EventKind::AlertCreated {
+        alert_id: alert.id,
+        severity: alert.severity.clone(),
+        title: alert.title.clone(),
+    };
+    publish(pool, alert.domain_id, kind).await;
+}
+
+/// Fan-out of the events received by this instance to its subscribers
+pub struct EventBus {
+    sender: broadcast::Sender<Event>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    #[must_use]
+    pub fn new() -> Self {
+        Self { sender: broadcast::channel(BUFFER_SIZE).0 }
+    }
+
+    /// Receive the events of an organization that the caller has access to
+    ///
+    /// `types` limits the event types and `domain_id` the domain.
+    #[must_use]
+    pub fn subscribe(&self, access: Access, types: Option<Vec<String>>, domain_id: Option<Uuid>) -> Subscription {
+        Subscription { receiver: self.sender.subscribe(), access, types, domain_id }
+    }
+
+    /// Forward notifications to subscribers, listening again whenever the
+    /// connection fails
+    pub async fn listen(&self, pool: PgPool) {
+        loop {
+            if let Err(e) = self.forward(&pool).await {
+                tracing::error!("Event listener failed: {}", e);
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    }
+
+    async fn forward(&self, pool: &PgPool) -> AppResult<()> {
+        let mut listener = PgListener::connect_with(pool).await?;
+        listener.listen(CHANNEL).await?;
+
+        loop {
+            let notification = listener.recv().await?;
+            match serde_json::from_str::<Event>(notification.payload()) {
+                // Nobody may be subscribed, which is fine
+                Ok(event) => drop(self.sender.send(event)),
+                Err(e) => tracing::warn!("Ignoring malformed event: {}", e),
+            }
+        }
+    }
+}
+
+/// Events delivered to a subscriber
+#[derive(Debug, Clone, PartialEq)]
+pub enum Delivery {
+    Event(Event),
+    /// The subscriber was too slow and missed this many events; it should
+    /// refetch the state it displays
+    Lagged(u64),
+}
+
+/// Stream of events for one subscriber
+pub struct Subscription {
+    receiver: broadcast::Receiver<Event>,
+    access: Access,
+    types: Option<Vec<String>>,
+    domain_id: Option<Uuid>,
+}
+
+impl Subscription {
+    /// Wait for the next event for the subscriber, `None` once the bus is gone
+    pub async fn next(&mut self) -> Option<Delivery> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) if self.wants(&event) => return Some(Delivery::Event(event)),
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(missed)) => return Some(Delivery::Lagged(missed)),
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// Caller's access to the organization, as checked when subscribing or last
+    /// re-authorizing
+    #[must_use]
+    pub const fn access(&self) -> &Access {
+        &self.access
+    }
+
+    /// Deliver the following events according to the caller's current access
+    pub fn set_access(&mut self, access: Access) {
+        self.access = access;
+    }
+
+    fn wants(&self, event: &Event) -> bool {
+        event.organization_id == self.access.organization_id
+            && self.access.can_access_group(event.group_id)
+            && (!matches!(event.kind, EventKind::AlertCreated { .. }) || self.access.has(Permission::AlertsRead))
+            && self.domain_id.is_none_or(|id| id == event.domain_id)
+            && self.types.as_ref().is_none_or(|types| types.iter().any(|t| t == event.kind.name()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::MemberRole;
+
+    fn event(organization_id: Uuid, group_id: Option<Uuid>, kind: EventKind) -> Event {
+        Event { organization_id, domain_id: Uuid::new_v4(), group_id, at: Utc::now(), kind }
+    }
+
+    #[test]
+    fn test_event_round_trip() {
+        let event = event(Uuid::new_v4(), None, EventKind::IncidentOpened { error: Some("timeout".to_string()) });
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "incident_opened");
+        assert_eq!(json["data"]["error"], "timeout");
+        assert_eq!(serde_json::from_value::<Event>(json).unwrap(), event);
+
+        let resolved = serde_json::to_value(EventKind::IncidentResolved).unwrap();
+        assert_eq!(resolved, serde_json::json!({ "type": "incident_resolved" }));
+
+        // Payload as built by Postgres, with jsonb's key order
+        let payload = r#"{"at": "2026-10-18T23:09:20.062017+00:00", "data": {"is_expired": false, "days_until_expiry": 12}, "type": "ssl_checked", "group_id": null, "domain_id": "4424f927-f43a-4578-b36a-8fff8f162102", "organization_id": "f7e28e63-38ea-4711-8862-a56a4d792b4e"}"#;
+        let event: Event = serde_json::from_str(payload).unwrap();
+        assert_eq!(event.kind, EventKind::SslChecked { is_expired: false, days_until_expiry: 12 });
+    }
+
+    #[tokio::test]
+    async fn test_subscription_filters() {
+        let bus = EventBus::new();
+        let org_id = Uuid::new_v4();
+        let group_id = Uuid::new_v4();
+        let access = |role: MemberRole, groups: Option<Vec<Uuid>>| Access {
+            organization_id: org_id,
+            permissions: role.permissions().to_vec(),
+            role,
+            domain_group_ids: groups,
+        };
+        let mut admin = bus.subscribe(access(MemberRole::Admin, None), None, None);
+        let mut restricted = bus.subscribe(access(MemberRole::Member, Some(vec![group_id])), Some(vec!["ssl_checked".to_string()]), None);
+
+        let other_org = event(Uuid::new_v4(), None, EventKind::IncidentResolved);
+        let ungrouped = event(org_id, None, EventKind::SslChecked { is_expired: false, days_until_expiry: 90 });
+        let grouped = event(org_id, Some(group_id), EventKind::SslChecked { is_expired: true, days_until_expiry: 0 });
+        for event in [&other_org, &ungrouped, &grouped] {
+            bus.sender.send(event.clone()).unwrap();
+        }
+
+        assert_eq!(admin.next().await, Some(Delivery::Event(ungrouped)));
+        assert_eq!(admin.next().await, Some(Delivery::Event(grouped.clone())));
+        assert_eq!(restricted.next().await, Some(Delivery::Event(grouped)));
+    }
+
+    #[tokio::test]
+    async fn test_subscription_access_change() {
+        let bus = EventBus::new();
+        let org_id = Uuid::new_v4();
+        let group_id = Uuid::new_v4();
+        let access = |groups: Option<Vec<Uuid>>| Access {
+            organization_id: org_id,
+            permissions: MemberRole::Member.permissions().to_vec(),
+            role: MemberRole::Member,
+            domain_group_ids: groups,
+        };
+        let mut subscription = bus.subscribe(access(None), None, None);
+        subscription.set_access(access(Some(vec![group_id])));
+
+        let ungrouped = event(org_id, None, EventKind::IncidentResolved);
+        let grouped = event(org_id, Some(group_id), EventKind::IncidentResolved);
+        for event in [&ungrouped, &grouped] {
+            bus.sender.send(event.clone()).unwrap();
+        }
+
+        assert_eq!(subscription.next().await, Some(Delivery::Event(grouped)));
+    }
+}
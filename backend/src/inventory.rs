@@ -0,0 +1,373 @@
+//! Domain inventory files
+//!
+//! Domains can be imported from and exported to CSV or JSON files listing the
+//! display name, URL, tags and monitors of each domain. JSON files are an array
+//! of [`DomainSpec`]. CSV files have a `display_name,url,tags,monitors` header;
+//! tags are written as `key=value` pairs separated by `;` and monitors as a JSON
+//! object, both optional.
+
+use std::collections::{BTreeMap, HashSet};
+use uuid::Uuid;
+
+use crate::db::models::{
+    DomainSpec, ImportRow, ImportRowStatus, MonitorSpec, MonitorType, NewDomain, NewMonitor, Tags,
+};
+use crate::error::AppError;
+use crate::monitors::monitor_config;
+use crate::tags;
+
+/// Maximum number of domains in one import
+pub const MAX_ENTRIES: usize = 5000;
+const MAX_URL_LEN: usize = 2048;
+const MAX_DISPLAY_NAME_LEN: usize = 255;
+
+/// Entry of an import file with its position, or why it could not be read
+pub type Entry = (usize, Result<DomainSpec, String>);
+
+/// Normalize a domain URL: trim it, lowercase its scheme and host and remove
+/// trailing slashes
+///
+/// The path and query are kept as entered, since servers may treat them as
+/// case-sensitive.
+///
+/// # Errors
+///
+/// Returns a message for an empty or overlong URL, a URL containing whitespace
+/// or a scheme other than http(s)
+pub fn normalize_url(url: &str) -> Result<String, String> {
+    let url = url.trim().trim_end_matches('/');
+    let (scheme, rest) = match url.split_once("://") {
+        Some((scheme, rest)) => (Some(scheme.to_lowercase()), rest),
+        None => (None, url),
+    };
+    let host_end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    let (host, path) = rest.split_at(host_end);
+    let url = match scheme {
+        Some(scheme) => format!("{}://{}{}", scheme, host.to_lowercase(), path),
+        None => format!("{}{}", host.to_lowercase(), path),
+    };
+
+    if url.is_empty() {
+        return Err("URL is required".to_string());
+    }
+    if url.len() > MAX_URL_LEN {
+        return Err(format!("URL is longer than {} characters", MAX_URL_LEN));
+    }
+    if url.chars().any(char::is_whitespace) {
+        return Err("URL cannot contain whitespace".to_string());
+    }
+    if let Some((scheme, _)) = url.split_once("://") {
+        if scheme != "http" && scheme != "https" {
+            return Err(format!("Unsupported URL scheme \"{}\"", scheme));
+        }
+    }
+
+    Ok(url)
+}
+
+/// Parse a JSON import file, numbering entries from 1
+///
+/// # Errors
+///
+/// Returns a message when the file is not an array of domains
+pub fn parse_json(body: &str) -> Result<Vec<Entry>, String> {
+    let entries: Vec<serde_json::Value> = serde_json::from_str(body)
+        .map_err(|e| format!("Invalid JSON: {}", e))?;
+
+    Ok(entries
+        .into_iter()
+        .enumerate()
+        .map(|(i, entry)| (i + 1, serde_json::from_value(entry).map_err(|e| e.to_string())))
+        .collect())
+}
+
+/// Parse a CSV import file, returning each entry with its line number
+///
+/// # Errors
+///
+/// Returns a message when the header is missing or lacks a required column
+pub fn parse_csv(body: &str) -> Result<Vec<Entry>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(body.as_bytes());
+
+    let headers = reader.headers().map_err(|e| format!("Invalid CSV: {}", e))?.clone();
+    let column = |name: &str| headers.iter().position(|h| h.eq_ignore_ascii_case(name));
+    let (Some(display_name), Some(url)) = (column("display_name"), column("url")) else {
+        return Err("CSV header must have display_name and url columns".to_string());
+    };
+    let (tags, monitors) = (column("tags"), column("monitors"));
+
+    let mut entries = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| format!("Invalid CSV: {}", e))?;
+        let line = record.position().map_or(entries.len() + 2, |p| p.line() as usize);
+        let field = |index: Option<usize>| index.and_then(|i| record.get(i)).unwrap_or_default();
+
+        let entry = parse_csv_tags(field(tags)).and_then(|tags| {
+            Ok(DomainSpec {
+                display_name: field(Some(display_name)).to_string(),
+                url: field(Some(url)).to_string(),
+                tags,
+                monitors: parse_csv_monitors(field(monitors))?,
+            })
+        });
+        entries.push((line, entry));
+    }
+
+    Ok(entries)
+}
+
+fn parse_csv_tags(field: &str) -> Result<Tags, String> {
+    field
+        .split(';')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(|term| match term.split_once('=') {
+            Some((key, value)) => Ok((key.trim().to_string(), value.trim().to_string())),
+            None => Err(format!("Tag \"{}\" must be written as key=value", term)),
+        })
+        .collect()
+}
+
+fn parse_csv_monitors(field: &str) -> Result<Option<BTreeMap<String, MonitorSpec>>, String> {
+    if field.is_empty() {
+        return Ok(None);
+    }
+    serde_json::from_str(field)
+        .map(Some)
+        .map_err(|e| format!("Invalid monitors: {}", e))
+}
+
+/// Write domains as a CSV file
+///
+/// # Errors
+///
+/// Returns a message if a row cannot be written
+pub fn to_csv(specs: &[DomainSpec]) -> Result<String, String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(["display_name", "url", "tags", "monitors"]).map_err(|e| e.to_string())?;
+
+    for spec in specs {
+        let tags = spec.tags.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<_>>().join(";");
+        let monitors = match &spec.monitors {
+            Some(monitors) => serde_json::to_string(monitors).map_err(|e| e.to_string())?,
+            None => String::new(),
+        };
+        writer.write_record([spec.display_name.as_str(), spec.url.as_str(), &tags, &monitors])
+            .map_err(|e| e.to_string())?;
+    }
+
+    let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+    String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+/// Check a domain entry and turn it into a domain to create
+///
+/// # Errors
+///
+/// Returns every problem found with the entry
+pub fn to_new_domain(spec: &DomainSpec, group_id: Option<Uuid>) -> Result<NewDomain, Vec<String>> {
+    let mut errors = Vec::new();
+
+    let display_name = spec.display_name.trim();
+    if display_name.is_empty() || display_name.len() > MAX_DISPLAY_NAME_LEN {
+        errors.push(format!("Display name must be between 1 and {} characters", MAX_DISPLAY_NAME_LEN));
+    }
+    let url = normalize_url(&spec.url).map_err(|e| errors.push(e)).unwrap_or_default();
+    if let Err(e) = tags::validate(&spec.tags) {
+        errors.push(message(e));
+    }
+    let monitors = monitors(spec.monitors.as_ref()).map_err(|e| errors.extend(e)).unwrap_or_default();
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(NewDomain {
+        display_name: display_name.to_string(),
+        url,
+        group_id,
+        tags: spec.tags.clone(),
+        monitors,
+    })
+}
+
+/// Monitors to create for an entry: the listed ones, or SSL and uptime monitors
+fn monitors(specs: Option<&BTreeMap<String, MonitorSpec>>) -> Result<Vec<NewMonitor>, Vec<String>> {
+    let Some(specs) = specs else {
+        return Ok([MonitorType::SslCert, MonitorType::Uptime]
+            .into_iter()
+            .map(|monitor_type| NewMonitor {
+                monitor_type,
+                is_enabled: true,
+                config: serde_json::json!({}),
+            })
+            .collect());
+    };
+
+    let mut monitors = Vec::new();
+    let mut errors = Vec::new();
+    for (name, spec) in specs {
+        match name.parse::<MonitorType>() {
+            Ok(monitor_type) => match monitor_config::validate(&monitor_type, &spec.config) {
+                Ok(()) => monitors.push(NewMonitor {
+                    monitor_type,
+                    is_enabled: spec.enabled,
+                    config: spec.config.clone(),
+                }),
+                Err(e) => errors.push(e),
+            },
+            Err(e) => errors.push(e),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(monitors)
+    } else {
+        Err(errors)
+    }
+}
+
+fn message(error: AppError) -> String {
+    match error {
+        AppError::Validation(message) => message,
+        other => other.to_string(),
+    }
+}
+
+/// Check every entry of an import
+///
+/// Entries whose URL is already used by a domain of the organization are
+/// skipped when `skip_existing` is set and are errors otherwise; URLs listed
+/// twice in the file are always errors. Returns the report rows along with the
+/// domains to create.
+pub fn plan(
+    entries: Vec<Entry>,
+    existing_urls: &HashSet<String>,
+    group_id: Option<Uuid>,
+    skip_existing: bool,
+) -> (Vec<ImportRow>, Vec<NewDomain>) {
+    let mut rows = Vec::with_capacity(entries.len());
+    let mut domains = Vec::new();
+    let mut seen = HashSet::new();
+
+    for (row, entry) in entries {
+        let mut report = ImportRow {
+            row,
+            display_name: None,
+            url: None,
+            status: ImportRowStatus::Error,
+            errors: Vec::new(),
+            domain_id: None,
+        };
+
+        let spec = match entry {
+            Ok(spec) => spec,
+            Err(e) => {
+                report.errors.push(e);
+                rows.push(report);
+                continue;
+            }
+        };
+        report.display_name = Some(spec.display_name.trim().to_string());
+        report.url = normalize_url(&spec.url).ok();
+
+        match to_new_domain(&spec, group_id) {
+            Ok(domain) if !seen.insert(domain.url.clone()) => {
+                report.errors.push(format!("URL {} is listed more than once", domain.url));
+            }
+            Ok(domain) if existing_urls.contains(&domain.url) => {
+                if skip_existing {
+                    report.status = ImportRowStatus::Skip;
+                } else {
+                    report.errors.push(format!("A domain with URL {} already exists", domain.url));
+                }
+            }
+            Ok(domain) => {
+                report.status = ImportRowStatus::Create;
+                domains.push(domain);
+            }
+            Err(errors) => report.errors = errors,
+        }
+
+        rows.push(report);
+    }
+
+    (rows, domains)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_url() {
+        assert_eq!(normalize_url(" https://Example.com/ ").unwrap(), "https://example.com");
+        assert_eq!(normalize_url("example.com").unwrap(), "example.com");
+        assert_eq!(
+            normalize_url("HTTPS://X.com/Health?Token=AbC/").unwrap(),
+            "https://x.com/Health?Token=AbC"
+        );
+        assert_eq!(normalize_url("X.com/Status").unwrap(), "x.com/Status");
+        assert!(normalize_url("  ").is_err());
+        assert!(normalize_url("ftp://example.com").is_err());
+        assert!(normalize_url("https://exa mple.com").is_err());
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let csv = "display_name,url,tags,monitors\n\
+                   Shop,https://shop.example.com,env=prod;team=web,\"{\"\"uptime\"\":{\"\"config\"\":{\"\"interval_seconds\"\":60}}}\"\n\
+                   Blog,blog.example.com,,\n\
+                   Bad,bad.example.com,oops,\n";
+        let entries = parse_csv(csv).unwrap();
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].0, 2);
+        let shop = entries[0].1.as_ref().unwrap();
+        assert_eq!(shop.tags.get("team").map(String::as_str), Some("web"));
+        assert!(!shop.monitors.as_ref().unwrap()["uptime"].config.is_null());
+        assert!(entries[1].1.as_ref().unwrap().monitors.is_none());
+        assert!(entries[2].1.is_err());
+
+        let specs: Vec<DomainSpec> = entries.into_iter().filter_map(|(_, e)| e.ok()).collect();
+        let written = to_csv(&specs).unwrap();
+        let reparsed: Vec<DomainSpec> = parse_csv(&written).unwrap().into_iter().map(|(_, e)| e.unwrap()).collect();
+        assert_eq!(reparsed, specs);
+
+        assert!(parse_csv("name,address\nA,a.com\n").is_err());
+    }
+
+    #[test]
+    fn test_plan_detects_duplicates() {
+        let spec = |url: &str| DomainSpec {
+            display_name: "Site".to_string(),
+            url: url.to_string(),
+            tags: Tags::new(),
+            monitors: None,
+        };
+        let entries = vec![
+            (1, Ok(spec("https://a.example.com"))),
+            (2, Ok(spec("https://A.example.com/"))),
+            (3, Ok(spec("https://b.example.com"))),
+            (4, Ok(spec("gopher://c.example.com"))),
+        ];
+        let existing = HashSet::from(["https://b.example.com".to_string()]);
+
+        let (rows, domains) = plan(entries.clone(), &existing, None, false);
+        let statuses: Vec<_> = rows.iter().map(|r| r.status).collect();
+        assert_eq!(statuses, [
+            ImportRowStatus::Create,
+            ImportRowStatus::Error,
+            ImportRowStatus::Error,
+            ImportRowStatus::Error,
+        ]);
+        assert_eq!(domains.len(), 1);
+        assert_eq!(domains[0].monitors.len(), 2);
+
+        let (rows, _) = plan(entries, &existing, None, true);
+        assert_eq!(rows[2].status, ImportRowStatus::Skip);
+    }
+}
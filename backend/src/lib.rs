@@ -3,11 +3,18 @@
 //! A production-grade, multi-tenant security monitoring platform.
 
 pub mod api;
+pub mod audit;
 pub mod auth;
 pub mod config;
 pub mod db;
 pub mod error;
+pub mod events;
+pub mod mailer;
 pub mod monitors;
+pub mod quota;
+pub mod tags;
+pub mod inventory;
+pub mod manifest;
 
 pub use auth::JwtService;
 pub use config::Config;
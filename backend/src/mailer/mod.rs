@@ -0,0 +1,180 @@
+//! Outgoing email
+//!
+//! Emails are sent through a [`Mailer`], selected by `mail.transport`: SMTP in
+//! production, or a file/log sink for tests and development.
+
+mod sink;
+mod smtp;
+
+pub use sink::{FileMailer, LogMailer};
+pub use smtp::SmtpMailer;
+
+use axum::async_trait;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::{MailConfig, MailTransport};
+use crate::error::AppResult;
+
+/// Plain-text email
+#[derive(Debug, Clone, Serialize)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+impl Email {
+    /// Password reset link
+    #[must_use]
+    pub fn password_reset(to: &str, link: &str, valid_for: Duration) -> Self {
+        Self {
+            to: to.to_string(),
+            subject: "Reset your WebGuard password".to_string(),
+            body: format!(
+                "A password reset was requested for your WebGuard account.\n\n\
+                 Open the link below to choose a new password:\n{}\n\n\
+                 The link expires in {} and can only be used once. If you did not \
+                 request a reset, you can ignore this email.\n",
+                link,
+                format_validity(valid_for)
+            ),
+        }
+    }
+
+    /// Email address verification link
+    #[must_use]
+    pub fn email_verification(to: &str, link: &str, valid_for: Duration) -> Self {
+        Self {
+            to: to.to_string(),
+            subject: "Verify your WebGuard email address".to_string(),
+            body: format!(
+                "Welcome to WebGuard!\n\n\
+                 Open the link below to verify your email address:\n{}\n\n\
+                 The link expires in {}.\n",
+                link,
+                format_validity(valid_for)
+            ),
+        }
+    }
+
+    /// Invitation to join an organization
+    #[must_use]
+    pub fn invitation(to: &str, organization: &str, invited_by: &str, link: &str, valid_for: Duration) -> Self {
+        Self {
+            to: to.to_string(),
+            subject: format!("You have been invited to join {} on WebGuard", organization),
+            body: format!(
+                "{} has invited you to join the organization \"{}\" on WebGuard.\n\n\
+                 Open the link below to accept the invitation, signing in or \
+                 creating an account with this email address:\n{}\n\n\
+                 The invitation expires in {}. If you were not expecting it, you \
+                 can ignore this email.\n",
+                invited_by,
+                organization,
+                link,
+                format_validity(valid_for)
+            ),
+        }
+    }
+
+    /// Request to accept the ownership of an organization
+    #[must_use]
+    pub fn ownership_transfer(to: &str, organization: &str, owner: &str, link: &str, valid_for: Duration) -> Self {
+        Self {
+            to: to.to_string(),
+            subject: format!("You have been asked to take over {} on WebGuard", organization),
+            body: format!(
+                "{} would like to transfer the ownership of the organization \"{}\" \
+                 on WebGuard to you. They will stay on as an admin.\n\n\
+                 Open the link below to accept or decline:\n{}\n\n\
+                 The request expires in {}.\n",
+                owner,
+                organization,
+                link,
+                format_validity(valid_for)
+            ),
+        }
+    }
+
+    /// Confirmation of signing in to an existing account through an organization's SSO
+    #[must_use]
+    pub fn sso_link(to: &str, organization: &str, link: &str, valid_for: Duration) -> Self {
+        Self {
+            to: to.to_string(),
+            subject: format!("Confirm signing in to WebGuard with {} single sign-on", organization),
+            body: format!(
+                "Someone signed in through the single sign-on of the organization \"{}\" \
+                 with your email address. To let this identity sign in to your existing \
+                 WebGuard account, open the link below:\n{}\n\n\
+                 The link expires in {}. If this wasn't you, ignore this email: your \
+                 account stays unchanged.\n",
+                organization,
+                link,
+                format_validity(valid_for)
+            ),
+        }
+    }
+}
+
+/// Human-readable validity of a link, in hours or days
+fn format_validity(valid_for: Duration) -> String {
+    let hours = (valid_for.as_secs() / 3600).max(1);
+    match hours {
+        1 => "1 hour".to_string(),
+        h if h % 24 == 0 && h > 24 => format!("{} days", h / 24),
+        24 => "1 day".to_string(),
+        h => format!("{} hours", h),
+    }
+}
+
+/// Email delivery backend
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    /// Deliver an email
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the email cannot be delivered
+    async fn send(&self, email: &Email) -> AppResult<()>;
+}
+
+/// Build the mailer selected by configuration
+///
+/// # Errors
+///
+/// Returns an error if the SMTP settings are invalid
+pub fn from_config(config: &MailConfig) -> AppResult<Arc<dyn Mailer>> {
+    Ok(match config.transport {
+        MailTransport::Smtp => Arc::new(SmtpMailer::from_config(config)?),
+        MailTransport::File => Arc::new(FileMailer::new(&config.file_path)),
+        MailTransport::Log => Arc::new(LogMailer),
+    })
+}
+
+/// Send an email without waiting for delivery, logging failures
+///
+/// Keeps request latency independent of the mail server, which also avoids
+/// revealing through timing whether an account exists.
+pub fn send_in_background(mailer: Arc<dyn Mailer>, email: Email) {
+    tokio::spawn(async move {
+        if let Err(e) = mailer.send(&email).await {
+            tracing::error!("Failed to send \"{}\" email to {}: {}", email.subject, email.to, e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_validity() {
+        assert_eq!(format_validity(Duration::from_secs(600)), "1 hour");
+        assert_eq!(format_validity(Duration::from_secs(3600)), "1 hour");
+        assert_eq!(format_validity(Duration::from_secs(7200)), "2 hours");
+        assert_eq!(format_validity(Duration::from_secs(86400)), "1 day");
+        assert_eq!(format_validity(Duration::from_secs(172_800)), "2 days");
+    }
+}
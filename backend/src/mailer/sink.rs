@@ -0,0 +1,58 @@
+use axum::async_trait;
+use chrono::Utc;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use super::{Email, Mailer};
+use crate::error::{AppError, AppResult};
+
+/// Mailer that only logs emails
+pub struct LogMailer;
+
+#[async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, email: &Email) -> AppResult<()> {
+        tracing::info!("Email to {}: {}\n{}", email.to, email.subject, email.body);
+        Ok(())
+    }
+}
+
+/// Mailer that appends emails to a file, one JSON object per line
+pub struct FileMailer {
+    path: String,
+    /// Serializes writes so that lines are never interleaved
+    lock: Mutex<()>,
+}
+
+impl FileMailer {
+    #[must_use]
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            lock: Mutex::new(()),
+        }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, email: &Email) -> AppResult<()> {
+        let mut line = serde_json::to_value(email)
+            .map_err(|e| AppError::internal(format!("Failed to serialize email: {}", e)))?;
+        line["sent_at"] = serde_json::json!(Utc::now());
+        let mut line = line.to_string();
+        line.push('\n');
+
+        let _guard = self.lock.lock().await;
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .map_err(|e| AppError::internal(format!("Failed to open mail file {}: {}", self.path, e)))?;
+
+        file.write_all(line.as_bytes())
+            .await
+            .map_err(|e| AppError::internal(format!("Failed to write mail file {}: {}", self.path, e)))
+    }
+}
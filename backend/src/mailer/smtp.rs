@@ -0,0 +1,70 @@
+use axum::async_trait;
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+use super::{Email, Mailer};
+use crate::config::MailConfig;
+use crate::error::{AppError, AppResult};
+
+/// Mailer that delivers through an SMTP server
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    /// Build the SMTP transport from configuration
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the sender address or relay settings are invalid
+    pub fn from_config(config: &MailConfig) -> AppResult<Self> {
+        let from = config
+            .from
+            .parse::<Mailbox>()
+            .map_err(|e| AppError::internal(format!("Invalid mail sender {}: {}", config.from, e)))?;
+
+        let mut builder = if config.smtp_starttls {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_host)
+                .map_err(|e| AppError::internal(format!("Invalid SMTP host {}: {}", config.smtp_host, e)))?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.smtp_host)
+        };
+        builder = builder.port(config.smtp_port);
+
+        if let (Some(username), Some(password)) = (&config.smtp_username, &config.smtp_password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from,
+        })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, email: &Email) -> AppResult<()> {
+        let to = email
+            .to
+            .parse::<Mailbox>()
+            .map_err(|e| AppError::validation(format!("Invalid recipient {}: {}", email.to, e)))?;
+
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(&email.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(email.body.clone())
+            .map_err(|e| AppError::internal(format!("Failed to build email: {}", e)))?;
+
+        self.transport
+            .send(message)
+            .await
+            .map_err(|e| AppError::external(format!("SMTP delivery failed: {}", e)))?;
+
+        Ok(())
+    }
+}
@@ -1,3 +1,7 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::PgPool;
+use std::net::SocketAddr;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::net::TcpListener;
 use tower_http::{
@@ -11,6 +15,8 @@ use web_guard::{
     JwtService,
     db::create_pool,
     api::create_router,
+    events::EventBus,
+    monitors::{aggregates, HttpClientFactory},
 };
 
 #[tokio::main]
@@ -37,6 +43,12 @@ async fn main() -> anyhow::Result<()> {
     web_guard::db::run_migrations(&pool).await?;
     tracing::info!("Database migrations completed");
 
+    // Maintenance commands run against the database and exit
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("backfill-aggregates") {
+        return run_backfill_aggregates(&pool, &args[1..]).await;
+    }
+
     // Create JWT service
     let jwt_service = JwtService::new(
         &config.auth.jwt_secret,
@@ -44,11 +56,18 @@ async fn main() -> anyhow::Result<()> {
         config.auth.refresh_token_duration,
     );
 
+    // Create shared HTTP client for outbound checks
+    let http = Arc::new(HttpClientFactory::from_config(&config.http)?);
+
+    // Create mailer for account emails
+    let mailer = web_guard::mailer::from_config(&config.mail)?;
+
     // Create and start monitoring scheduler
     tracing::info!("Starting monitoring scheduler...");
     let scheduler = web_guard::monitors::MonitorScheduler::new(
         pool.clone(),
-        config.clone()
+        config.clone(),
+        http.clone(),
     );
     let scheduler_handle = tokio::spawn(async move {
         if let Err(e) = scheduler.start().await {
@@ -57,8 +76,16 @@ async fn main() -> anyhow::Result<()> {
     });
     tracing::info!("Monitoring scheduler started");
 
+    // Receive events from every instance for the event streams
+    let events = Arc::new(EventBus::new());
+    tokio::spawn({
+        let events = events.clone();
+        let pool = pool.clone();
+        async move { events.listen(pool).await }
+    });
+
     // Build application router
-    let app = create_router(pool, jwt_service, config.clone())
+    let app = create_router(pool, jwt_service, config.clone(), http, mailer, events)
         .layer(
             TraceLayer::new_for_http()
                 .make_span_with(|_request: &axum::http::Request<_>| {
@@ -78,7 +105,7 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!("Server listening on {}", addr);
 
     // Start server
-    axum::serve(listener, app)
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
         .with_graceful_shutdown(shutdown_signal())
         .await?;
 
@@ -86,6 +113,43 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Recompute historical uptime aggregates
+///
+/// Usage: `web-guard backfill-aggregates [--from YYYY-MM-DD] [--to YYYY-MM-DD] [--domain UUID]`
+/// Defaults to the last 30 days for all active domains.
+async fn run_backfill_aggregates(pool: &PgPool, args: &[String]) -> anyhow::Result<()> {
+    let parse_date = |value: &str| -> anyhow::Result<DateTime<Utc>> {
+        let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")?;
+        Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc())
+    };
+
+    let mut to = Utc::now();
+    let mut from = to - chrono::Duration::days(30);
+    let mut domain_id = None;
+
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        let value = iter
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Missing value for {}", flag))?;
+        match flag.as_str() {
+            "--from" => from = parse_date(value)?,
+            "--to" => to = parse_date(value)?,
+            "--domain" => domain_id = Some(value.parse()?),
+            _ => anyhow::bail!("Unknown option: {}", flag),
+        }
+    }
+
+    tracing::info!("Backfilling uptime aggregates from {} to {}", from, to);
+    let summary = aggregates::backfill_aggregates(pool, domain_id, from, to).await?;
+    tracing::info!(
+        "Backfill complete: {} domains, {} periods computed, {} failures",
+        summary.domains, summary.periods, summary.failures
+    );
+
+    Ok(())
+}
+
 /// Wait for CTRL+C signal
 async fn shutdown_signal() {
     let ctrl_c = async {
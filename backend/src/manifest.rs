@@ -0,0 +1,533 @@
+//! Monitoring as code
+//!
+//! The groups, domains, monitors and alerting of an organization can be
+//! described in a YAML or JSON [`Manifest`]. [`plan`] compares a manifest with
+//! the organization and returns the changes along with the operations making
+//! them; applying the same manifest twice changes nothing the second time.
+//! Resources that exist but are not in the manifest are only deleted when
+//! pruning.
+
+use std::collections::{HashMap, HashSet};
+
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::db::models::{
+    AlertRoute, Domain, DomainGroup, DomainSpec, Manifest, ManifestAction, ManifestAlertRoute, ManifestAlerting,
+    ManifestChange, ManifestDomain, ManifestDomainOp, ManifestGroup, ManifestOps, ManifestResource, Monitor,
+    MonitorSpec, NewMonitor, UpsertAlertRoute,
+};
+use crate::inventory;
+use crate::tags;
+
+/// Current configuration of an organization
+#[derive(Debug, Clone, Default)]
+pub struct Current {
+    pub webhook_url: Option<String>,
+    pub groups: Vec<DomainGroup>,
+    pub domains: Vec<Domain>,
+    pub monitors: Vec<Monitor>,
+    pub routes: Vec<AlertRoute>,
+}
+
+/// Parse a manifest written in YAML, or in JSON when `json` is set
+///
+/// # Errors
+///
+/// Returns a message describing the syntax error or unknown field
+pub fn parse(body: &str, json: bool) -> Result<Manifest, String> {
+    if json {
+        serde_json::from_str(body).map_err(|e| format!("Invalid manifest: {}", e))
+    } else {
+        serde_yaml::from_str(body).map_err(|e| format!("Invalid manifest: {}", e))
+    }
+}
+
+/// Describe the current configuration of an organization as a manifest
+#[must_use]
+pub fn export(current: &Current) -> Manifest {
+    let group_names = group_names(&current.groups);
+    let group_name = |id: Option<Uuid>| id.and_then(|id| group_names.get(&id)).map(|n| (*n).to_string());
+
+    let mut groups: Vec<ManifestGroup> = current
+        .groups
+        .iter()
+        .map(|g| ManifestGroup { name: g.name.clone(), parent: group_name(g.parent_id) })
+        .collect();
+    groups.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut domains: Vec<ManifestDomain> = current
+        .domains
+        .iter()
+        .map(|d| ManifestDomain {
+            spec: DomainSpec {
+                display_name: d.display_name.clone(),
+                url: d.url.clone(),
+                tags: d.tags.clone(),
+                monitors: Some(
+                    domain_monitors(current, d.id)
+                        .map(|m| (m.monitor_type.to_string(), MonitorSpec { enabled: m.is_enabled, config: m.config.clone() }))
+                        .collect(),
+                ),
+            },
+            group: group_name(d.group_id),
+            active: d.is_active,
+        })
+        .collect();
+    domains.sort_by(|a, b| a.spec.url.cmp(&b.spec.url));
+
+    let mut routes: Vec<ManifestAlertRoute> = current
+        .routes
+        .iter()
+        .map(|r| ManifestAlertRoute {
+            name: r.name.clone(),
+            group: group_name(r.group_id),
+            tags: r.tags.clone(),
+            min_severity: r.min_severity.clone(),
+            webhook_url: r.webhook_url.clone(),
+        })
+        .collect();
+    routes.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Manifest {
+        groups: Some(groups),
+        domains: Some(domains),
+        alerting: Some(ManifestAlerting { webhook_url: current.webhook_url.clone(), routes }),
+    }
+}
+
+fn group_names(groups: &[DomainGroup]) -> HashMap<Uuid, &str> {
+    groups.iter().map(|g| (g.id, g.name.as_str())).collect()
+}
+
+fn domain_monitors(current: &Current, domain_id: Uuid) -> impl Iterator<Item = &Monitor> {
+    current.monitors.iter().filter(move |m| m.domain_id == domain_id)
+}
+
+fn change(resource: ManifestResource, name: &str, action: ManifestAction) -> ManifestChange {
+    ManifestChange { resource, name: name.to_string(), action, fields: Vec::new() }
+}
+
+/// Compare a manifest with the current configuration
+///
+/// # Errors
+///
+/// Returns every problem found in the manifest, such as invalid or duplicate
+/// entries and references to unknown groups
+pub fn plan(manifest: &Manifest, current: &Current, prune: bool) -> Result<(Vec<ManifestChange>, ManifestOps), Vec<String>> {
+    let mut planner = Planner {
+        current,
+        prune,
+        group_names: group_names(&current.groups),
+        final_groups: current.groups.iter().map(|g| g.name.clone()).collect(),
+        changes: Vec::new(),
+        ops: ManifestOps::default(),
+        errors: Vec::new(),
+    };
+
+    if let Some(groups) = &manifest.groups {
+        planner.groups(groups);
+    }
+    if let Some(domains) = &manifest.domains {
+        planner.domains(domains);
+    }
+    if let Some(alerting) = &manifest.alerting {
+        planner.alerting(alerting);
+    }
+    planner.cascaded_routes();
+
+    if planner.errors.is_empty() {
+        Ok((planner.changes, planner.ops))
+    } else {
+        Err(planner.errors)
+    }
+}
+
+struct Planner<'a> {
+    current: &'a Current,
+    prune: bool,
+    group_names: HashMap<Uuid, &'a str>,
+    /// Names of the groups existing once the manifest is applied
+    final_groups: HashSet<String>,
+    changes: Vec<ManifestChange>,
+    ops: ManifestOps,
+    errors: Vec<String>,
+}
+
+impl<'a> Planner<'a> {
+    fn current_group(&self, id: Option<Uuid>) -> Option<&'a str> {
+        id.and_then(|id| self.group_names.get(&id).copied())
+    }
+
+    fn check_group_ref(&mut self, what: &str, group: Option<&String>) {
+        if let Some(group) = group.filter(|g| !self.final_groups.contains(*g)) {
+            self.errors.push(format!("{}: unknown group \"{}\"", what, group));
+        }
+    }
+
+    fn groups(&mut self, groups: &[ManifestGroup]) {
+        let mut listed = HashSet::new();
+        for group in groups {
+            if group.name.trim().is_empty() || group.name.len() > 255 {
+                self.errors.push(format!("Group \"{}\": name must be between 1 and 255 characters", group.name));
+            }
+            if !listed.insert(group.name.as_str()) {
+                self.errors.push(format!("Group \"{}\" is listed more than once", group.name));
+            }
+        }
+
+        for existing in &self.current.groups {
+            if listed.contains(existing.name.as_str()) {
+                continue;
+            }
+            if self.prune {
+                self.ops.delete_groups.push(existing.id);
+                self.final_groups.remove(&existing.name);
+                self.changes.push(change(ManifestResource::Group, &existing.name, ManifestAction::Delete));
+            } else {
+                self.changes.push(change(ManifestResource::Group, &existing.name, ManifestAction::Unmanaged));
+            }
+        }
+        self.final_groups.extend(listed.iter().map(|n| (*n).to_string()));
+
+        // Parent of every group once applied, to catch cycles
+        let current = self.current;
+        let mut parents: HashMap<&str, Option<&str>> = current
+            .groups
+            .iter()
+            .filter(|g| self.final_groups.contains(&g.name))
+            .map(|g| (g.name.as_str(), self.current_group(g.parent_id)))
+            .collect();
+        parents.extend(groups.iter().map(|g| (g.name.as_str(), g.parent.as_deref())));
+
+        for group in groups {
+            self.check_group_ref(&format!("Group \"{}\"", group.name), group.parent.as_ref());
+
+            let mut parent = group.parent.as_deref();
+            let mut steps = 0;
+            while let Some(p) = parent {
+                if p == group.name || steps > parents.len() {
+                    self.errors.push(format!("Group \"{}\" cannot be its own ancestor", group.name));
+                    break;
+                }
+                parent = parents.get(p).copied().flatten();
+                steps += 1;
+            }
+
+            match self.current.groups.iter().find(|g| g.name == group.name) {
+                None => {
+                    self.ops.create_groups.push(group.name.clone());
+                    if group.parent.is_some() {
+                        self.ops.group_parents.push((group.name.clone(), group.parent.clone()));
+                    }
+                    self.changes.push(change(ManifestResource::Group, &group.name, ManifestAction::Create));
+                }
+                Some(existing) if self.current_group(existing.parent_id) != group.parent.as_deref() => {
+                    self.ops.group_parents.push((group.name.clone(), group.parent.clone()));
+                    let mut update = change(ManifestResource::Group, &group.name, ManifestAction::Update);
+                    update.fields.push("parent".to_string());
+                    self.changes.push(update);
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    fn domains(&mut self, domains: &[ManifestDomain]) {
+        if domains.len() > inventory::MAX_ENTRIES {
+            self.errors.push(format!("A manifest can list at most {} domains", inventory::MAX_ENTRIES));
+            return;
+        }
+
+        let mut listed = HashSet::new();
+        for (index, entry) in domains.iter().enumerate() {
+            let what = format!("Domain {}", if entry.spec.url.is_empty() { index.to_string() } else { entry.spec.url.clone() });
+            let new = match inventory::to_new_domain(&entry.spec, None) {
+                Ok(new) => new,
+                Err(errors) => {
+                    self.errors.extend(errors.into_iter().map(|e| format!("{}: {}", what, e)));
+                    continue;
+                }
+            };
+            if !listed.insert(new.url.clone()) {
+                self.errors.push(format!("{} is listed more than once", what));
+                continue;
+            }
+            self.check_group_ref(&what, entry.group.as_ref());
+
+            let Some(existing) = self.current.domains.iter().find(|d| d.url == new.url) else {
+                self.ops.monitor_delta += new.monitors.len() as i64;
+                self.changes.push(change(ManifestResource::Domain, &new.url, ManifestAction::Create));
+                self.ops.upsert_domains.push(ManifestDomainOp {
+                    id: None,
+                    domain: new,
+                    group: entry.group.clone(),
+                    is_active: entry.active,
+                    replace_monitors: true,
+                });
+                continue;
+            };
+
+            let mut fields = Vec::new();
+            if existing.display_name != new.display_name {
+                fields.push("display_name");
+            }
+            if self.current_group(existing.group_id) != entry.group.as_deref() {
+                fields.push("group");
+            }
+            if existing.is_active != entry.active {
+                fields.push("active");
+            }
+            if existing.tags != new.tags {
+                fields.push("tags");
+            }
+            let monitors: Vec<&Monitor> = domain_monitors(self.current, existing.id).collect();
+            let replace_monitors = entry.spec.monitors.is_some() && !same_monitors(&monitors, &new.monitors);
+            if replace_monitors {
+                fields.push("monitors");
+                self.ops.monitor_delta += new.monitors.len() as i64 - monitors.len() as i64;
+            }
+
+            if !fields.is_empty() {
+                let mut update = change(ManifestResource::Domain, &new.url, ManifestAction::Update);
+                update.fields = fields.into_iter().map(String::from).collect();
+                self.changes.push(update);
+                self.ops.upsert_domains.push(ManifestDomainOp {
+                    id: Some(existing.id),
+                    domain: new,
+                    group: entry.group.clone(),
+                    is_active: entry.active,
+                    replace_monitors,
+                });
+            }
+        }
+
+        for existing in self.current.domains.iter().filter(|d| !listed.contains(&d.url)) {
+            if self.prune {
+                self.ops.delete_domains.push(existing.id);
+                self.ops.monitor_delta -= domain_monitors(self.current, existing.id).count() as i64;
+                self.changes.push(change(ManifestResource::Domain, &existing.url, ManifestAction::Delete));
+            } else {
+                self.changes.push(change(ManifestResource::Domain, &existing.url, ManifestAction::Unmanaged));
+            }
+        }
+    }
+
+    fn alerting(&mut self, alerting: &ManifestAlerting) {
+        if let Some(url) = alerting.webhook_url.as_deref().filter(|u| !validator::validate_url(*u)) {
+            self.errors.push(format!("Invalid webhook URL \"{}\"", url));
+        }
+        if self.current.webhook_url != alerting.webhook_url {
+            self.ops.webhook_url = Some(alerting.webhook_url.clone());
+            let mut update = change(ManifestResource::Webhook, "webhook_url", ManifestAction::Update);
+            update.fields.push("webhook_url".to_string());
+            self.changes.push(update);
+        }
+
+        let mut listed = HashSet::new();
+        for route in &alerting.routes {
+            let what = format!("Alert route \"{}\"", route.name);
+            if !listed.insert(route.name.as_str()) {
+                self.errors.push(format!("{} is listed more than once", what));
+                continue;
+            }
+            let input = UpsertAlertRoute {
+                name: route.name.clone(),
+                group_id: None,
+                tags: route.tags.clone(),
+                min_severity: route.min_severity.clone(),
+                webhook_url: route.webhook_url.clone(),
+            };
+            if let Err(e) = input.validate() {
+                self.errors.push(format!("{}: {}", what, e));
+            }
+            if let Err(e) = tags::validate(&route.tags) {
+                self.errors.push(format!("{}: {}", what, e));
+            }
+            self.check_group_ref(&what, route.group.as_ref());
+
+            match self.current.routes.iter().find(|r| r.name == route.name) {
+                None => {
+                    self.ops.upsert_routes.push((None, route.clone()));
+                    self.changes.push(change(ManifestResource::AlertRoute, &route.name, ManifestAction::Create));
+                }
+                Some(existing) => {
+                    let mut fields = Vec::new();
+                    if self.current_group(existing.group_id) != route.group.as_deref() {
+                        fields.push("group");
+                    }
+                    if existing.tags != route.tags {
+                        fields.push("tags");
+                    }
+                    if existing.min_severity != route.min_severity {
+                        fields.push("min_severity");
+                    }
+                    if existing.webhook_url != route.webhook_url {
+                        fields.push("webhook_url");
+                    }
+                    if !fields.is_empty() {
+                        self.ops.upsert_routes.push((Some(existing.id), route.clone()));
+                        let mut update = change(ManifestResource::AlertRoute, &route.name, ManifestAction::Update);
+                        update.fields = fields.into_iter().map(String::from).collect();
+                        self.changes.push(update);
+                    }
+                }
+            }
+        }
+
+        for existing in self.current.routes.iter().filter(|r| !listed.contains(r.name.as_str())) {
+            if self.prune {
+                self.ops.delete_routes.push(existing.id);
+                self.changes.push(change(ManifestResource::AlertRoute, &existing.name, ManifestAction::Delete));
+            } else {
+                self.changes.push(change(ManifestResource::AlertRoute, &existing.name, ManifestAction::Unmanaged));
+            }
+        }
+    }
+
+    /// Routes are deleted along with their group; report those the manifest
+    /// does not delete itself
+    fn cascaded_routes(&mut self) {
+        for route in &self.current.routes {
+            let group_deleted = route.group_id.is_some_and(|id| self.ops.delete_groups.contains(&id));
+            let updated = self.ops.upsert_routes.iter().any(|(id, _)| *id == Some(route.id));
+            if !group_deleted || updated || self.ops.delete_routes.contains(&route.id) {
+                continue;
+            }
+
+            self.changes.retain(|c| !(c.resource == ManifestResource::AlertRoute && c.name == route.name));
+            self.changes.push(change(ManifestResource::AlertRoute, &route.name, ManifestAction::Delete));
+        }
+    }
+}
+
+/// Whether a domain's monitors already match the desired ones
+fn same_monitors(current: &[&Monitor], desired: &[NewMonitor]) -> bool {
+    current.len() == desired.len()
+        && desired.iter().all(|d| {
+            current.iter().any(|m| {
+                m.monitor_type.to_string() == d.monitor_type.to_string()
+                    && m.is_enabled == d.is_enabled
+                    && m.config == d.config
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::{MonitorType, Tags};
+    use chrono::Utc;
+
+    fn current() -> Current {
+        let org = Uuid::new_v4();
+        let group = DomainGroup {
+            id: Uuid::new_v4(),
+            organization_id: org,
+            name: "Web".to_string(),
+            created_at: Utc::now(),
+            parent_id: None,
+        };
+        let domain = Domain {
+            id: Uuid::new_v4(),
+            organization_id: org,
+            name: "Shop".to_string(),
+            normalized_name: "https://shop.example.com".to_string(),
+            display_name: "Shop".to_string(),
+            url: "https://shop.example.com".to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            is_active: true,
+            group_id: Some(group.id),
+            tags: Tags::from([("env".to_string(), "prod".to_string())]),
+        };
+        let monitor = Monitor {
+            id: Uuid::new_v4(),
+            domain_id: domain.id,
+            monitor_type: MonitorType::Uptime,
+            is_enabled: true,
+            config: serde_json::json!({}),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            paused_until: None,
+        };
+
+        Current {
+            webhook_url: None,
+            groups: vec![group],
+            domains: vec![domain],
+            monitors: vec![monitor],
+            routes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_exported_manifest_has_no_changes() {
+        let current = current();
+        let manifest = export(&current);
+
+        let yaml = serde_yaml::to_string(&manifest).unwrap();
+        assert_eq!(parse(&yaml, false).unwrap(), manifest);
+
+        let (changes, ops) = plan(&manifest, &current, true).unwrap();
+        assert!(changes.is_empty());
+        assert!(ops.is_empty());
+    }
+
+    #[test]
+    fn test_plan_changes() {
+        let current = current();
+        let manifest = parse(
+            r#"
+groups:
+  - name: Customers
+  - name: Acme
+    parent: Customers
+domains:
+  - display_name: Shop
+    url: https://shop.example.com/
+    group: Acme
+    tags: { env: prod }
+  - display_name: Blog
+    url: https://blog.example.com
+"#,
+            false,
+        )
+        .unwrap();
+
+        let (changes, ops) = plan(&manifest, &current, false).unwrap();
+        let summary: Vec<_> = changes.iter().map(|c| (c.name.as_str(), c.action)).collect();
+        assert_eq!(summary, [
+            ("Web", ManifestAction::Unmanaged),
+            ("Customers", ManifestAction::Create),
+            ("Acme", ManifestAction::Create),
+            ("https://shop.example.com", ManifestAction::Update),
+            ("https://blog.example.com", ManifestAction::Create),
+        ]);
+        assert_eq!(changes[3].fields, ["group"]);
+        assert_eq!(ops.monitor_delta, 2);
+
+        let (_, ops) = plan(&manifest, &current, true).unwrap();
+        assert_eq!(ops.delete_groups, [current.groups[0].id]);
+    }
+
+    #[test]
+    fn test_plan_rejects_invalid_manifest() {
+        let current = current();
+        let manifest = parse(
+            r#"{
+                "groups": [{ "name": "A", "parent": "B" }, { "name": "B", "parent": "A" }],
+                "domains": [
+                    { "display_name": "X", "url": "x.com", "group": "Nope" },
+                    { "display_name": "X", "url": "X.com/" }
+                ]
+            }"#,
+            true,
+        )
+        .unwrap();
+
+        let errors = plan(&manifest, &current, false).unwrap_err();
+        assert_eq!(errors.len(), 4);
+        assert!(parse("domain: []", false).is_err());
+    }
+}
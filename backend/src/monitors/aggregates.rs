@@ -0,0 +1,255 @@
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDateTime, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db::models::{AggregatePeriod, AggregationTarget};
+use crate::db::queries;
+use crate::error::{AppError, AppResult};
+
+/// Rollup periods, in the order they must be computed
+pub const ROLLUP_PERIODS: [AggregatePeriod; 3] = [
+    AggregatePeriod::Day,
+    AggregatePeriod::Week,
+    AggregatePeriod::Month,
+];
+
+/// Periods whose end is more recent than this are not computed yet, so that
+/// checks finishing right at a period boundary are still included.
+const CATCHUP_GRACE_SECS: i64 = 300;
+
+/// Parse an organization's timezone, falling back to UTC
+#[must_use]
+pub fn parse_timezone(name: &str) -> Tz {
+    name.parse().unwrap_or_else(|_| {
+        tracing::warn!("Unknown timezone {:?}, using UTC", name);
+        Tz::UTC
+    })
+}
+
+/// Resolve a local time to UTC, moving forward past DST gaps
+fn from_local(tz: Tz, local: NaiveDateTime) -> DateTime<Utc> {
+    tz.from_local_datetime(&local)
+        .earliest()
+        .or_else(|| tz.from_local_datetime(&(local + Duration::hours(1))).earliest())
+        .map_or_else(|| Utc.from_utc_datetime(&local), |dt| dt.with_timezone(&Utc))
+}
+
+/// Start of the period containing `ts`, with boundaries in the given timezone
+///
+/// Weeks start on Sunday, matching the original weekly aggregates.
+#[must_use]
+pub fn period_start(ts: DateTime<Utc>, period: AggregatePeriod, tz: Tz) -> DateTime<Utc> {
+    let local = ts.with_timezone(&tz).naive_local();
+    let date = local.date();
+
+    let start = match period {
+        AggregatePeriod::Hour => date.and_hms_opt(local.hour(), 0, 0),
+        AggregatePeriod::Day => date.and_hms_opt(0, 0, 0),
+        AggregatePeriod::Week => {
+            let days_since_sunday = i64::from(date.weekday().num_days_from_sunday());
+            (date - Duration::days(days_since_sunday)).and_hms_opt(0, 0, 0)
+        }
+        AggregatePeriod::Month => date.with_day(1).and_then(|d| d.and_hms_opt(0, 0, 0)),
+    };
+
+    from_local(tz, start.unwrap_or(local))
+}
+
+/// End (exclusive) of the period starting at `start`
+#[must_use]
+pub fn period_end(start: DateTime<Utc>, period: AggregatePeriod, tz: Tz) -> DateTime<Utc> {
+    let local = start.with_timezone(&tz).naive_local();
+
+    match period {
+        AggregatePeriod::Hour => start + Duration::hours(1),
+        AggregatePeriod::Day => from_local(tz, local + Duration::days(1)),
+        AggregatePeriod::Week => from_local(tz, local + Duration::weeks(1)),
+        AggregatePeriod::Month => local
+            .checked_add_months(Months::new(1))
+            .map_or_else(|| start + Duration::days(31), |end| from_local(tz, end)),
+    }
+}
+
+/// Compute a single aggregate period for a domain
+///
+/// Hourly aggregates come from raw snapshots; longer periods are rolled up from
+/// the hourly rows, so hours must be computed first.
+pub async fn compute_period(
+    pool: &PgPool,
+    domain_id: Uuid,
+    period: AggregatePeriod,
+    start: DateTime<Utc>,
+    tz: Tz,
+) -> AppResult<()> {
+    let end = period_end(start, period, tz);
+    let period_type = period.to_string();
+
+    match period {
+        AggregatePeriod::Hour => {
+            queries::compute_uptime_aggregate(pool, domain_id, start, end, &period_type).await
+        }
+        _ => queries::rollup_uptime_aggregate(pool, domain_id, start, end, &period_type).await,
+    }
+}
+
+/// Compute every completed period since the domain's watermark, up to `until`
+///
+/// The watermark is advanced after each period, so an interrupted run resumes
+/// where it stopped. Returns the end of the last period covered.
+async fn catch_up_period(
+    pool: &PgPool,
+    target: &AggregationTarget,
+    period: AggregatePeriod,
+    tz: Tz,
+    until: DateTime<Utc>,
+) -> AppResult<DateTime<Utc>> {
+    let period_type = period.to_string();
+
+    let mut start = match queries::get_aggregate_watermark(pool, target.domain_id, &period_type).await? {
+        Some(last) => period_end(last, period, tz),
+        None => period_start(target.created_at, period, tz),
+    };
+
+    loop {
+        let end = period_end(start, period, tz);
+        if end > until {
+            return Ok(start);
+        }
+
+        compute_period(pool, target.domain_id, period, start, tz).await?;
+        queries::set_aggregate_watermark(pool, target.domain_id, &period_type, start).await?;
+        start = end;
+    }
+}
+
+/// Catch up on all missing aggregate periods for all active domains
+///
+/// Safe to run repeatedly: already computed periods are skipped using the
+/// per-domain watermarks, and missed ticks or restarts are recovered on the
+/// next run. Rollups never run past the hourly data they are built from.
+pub async fn catch_up_aggregates(pool: &PgPool) -> AppResult<()> {
+    tracing::info!("Computing uptime aggregates...");
+
+    let targets = queries::list_aggregation_targets(pool, None).await?;
+    let until = Utc::now() - Duration::seconds(CATCHUP_GRACE_SECS);
+
+    for target in targets {
+        let tz = parse_timezone(&target.timezone);
+
+        let hours_until = match catch_up_period(pool, &target, AggregatePeriod::Hour, tz, until).await {
+            Ok(end) => end,
+            Err(e) => {
+                tracing::error!("Failed to compute hourly aggregates for domain {}: {}", target.domain_id, e);
+                continue;
+            }
+        };
+
+        for period in ROLLUP_PERIODS {
+            if let Err(e) = catch_up_period(pool, &target, period, tz, hours_until).await {
+                tracing::error!("Failed to compute {} aggregates for domain {}: {}", period, target.domain_id, e);
+            }
+        }
+    }
+
+    tracing::info!("Finished computing uptime aggregates");
+    Ok(())
+}
+
+/// Result of an aggregate backfill run
+#[derive(Debug, Default, Clone)]
+pub struct BackfillSummary {
+    pub domains: usize,
+    pub periods: usize,
+    pub failures: usize,
+}
+
+/// Recompute all completed aggregate periods between `from` and `to`
+///
+/// Intended to be run after a fix to the aggregate computation. Existing rows are
+/// overwritten, so the backfill can safely be re-run.
+pub async fn backfill_aggregates(
+    pool: &PgPool,
+    domain_id: Option<Uuid>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> AppResult<BackfillSummary> {
+    if from >= to {
+        return Err(AppError::validation("Backfill start must be before its end"));
+    }
+
+    let targets = queries::list_aggregation_targets(pool, domain_id).await?;
+    if domain_id.is_some() && targets.is_empty() {
+        return Err(AppError::not_found("Domain not found"));
+    }
+
+    let mut summary = BackfillSummary {
+        domains: targets.len(),
+        ..BackfillSummary::default()
+    };
+
+    let periods = std::iter::once(AggregatePeriod::Hour).chain(ROLLUP_PERIODS);
+    for period in periods {
+        for target in &targets {
+            let tz = parse_timezone(&target.timezone);
+            let mut start = period_start(from, period, tz);
+            while period_end(start, period, tz) <= to {
+                match compute_period(pool, target.domain_id, period, start, tz).await {
+                    Ok(()) => summary.periods += 1,
+                    Err(e) => {
+                        summary.failures += 1;
+                        tracing::error!(
+                            "Failed to backfill {} aggregate at {} for domain {}: {}",
+                            period, start, target.domain_id, e
+                        );
+                    }
+                }
+                start = period_end(start, period, tz);
+            }
+        }
+        tracing::info!("Backfilled {} aggregates", period);
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_period_start() {
+        // 2026-03-18 is a Wednesday
+        let ts = at("2026-03-18T13:45:12Z");
+        assert_eq!(period_start(ts, AggregatePeriod::Hour, Tz::UTC), at("2026-03-18T13:00:00Z"));
+        assert_eq!(period_start(ts, AggregatePeriod::Day, Tz::UTC), at("2026-03-18T00:00:00Z"));
+        assert_eq!(period_start(ts, AggregatePeriod::Week, Tz::UTC), at("2026-03-15T00:00:00Z"));
+        assert_eq!(period_start(ts, AggregatePeriod::Month, Tz::UTC), at("2026-03-01T00:00:00Z"));
+    }
+
+    #[test]
+    fn test_period_end() {
+        let feb = at("2026-02-01T00:00:00Z");
+        assert_eq!(period_end(feb, AggregatePeriod::Month, Tz::UTC), at("2026-03-01T00:00:00Z"));
+        assert_eq!(period_end(feb, AggregatePeriod::Week, Tz::UTC), at("2026-02-08T00:00:00Z"));
+    }
+
+    #[test]
+    fn test_periods_in_timezone() {
+        let tz = parse_timezone("Asia/Shanghai");
+        let ts = at("2026-03-18T17:30:00Z"); // 01:30 on the 19th in Shanghai
+        let day = period_start(ts, AggregatePeriod::Day, tz);
+        assert_eq!(day, at("2026-03-18T16:00:00Z"));
+        assert_eq!(period_end(day, AggregatePeriod::Day, tz), at("2026-03-19T16:00:00Z"));
+
+        // Across the spring DST change a New York day is 23 hours long
+        let tz = parse_timezone("America/New_York");
+        let day = period_start(at("2026-03-08T12:00:00Z"), AggregatePeriod::Day, tz);
+        assert_eq!(day, at("2026-03-08T05:00:00Z"));
+        assert_eq!(period_end(day, AggregatePeriod::Day, tz), at("2026-03-09T04:00:00Z"));
+    }
+}
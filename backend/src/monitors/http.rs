@@ -0,0 +1,258 @@
+use reqwest::{Certificate, Client, Proxy, RequestBuilder};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::RwLock;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::config::{HttpConfig, IpPreference};
+use crate::db::models::MonitorType;
+use crate::db::queries;
+use crate::error::{AppError, AppResult};
+
+/// Per-monitor HTTP overrides, read from `monitors.config`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct HttpOverrides {
+    /// Request timeout in seconds
+    pub timeout_secs: Option<u64>,
+    /// Maximum number of redirects to follow (0 disables redirects)
+    pub max_redirects: Option<u32>,
+    /// Custom User-Agent header
+    pub user_agent: Option<String>,
+}
+
+impl HttpOverrides {
+    /// Extract overrides from a monitor's JSON config, ignoring unrelated keys
+    #[must_use]
+    pub fn from_monitor_config(config: &serde_json::Value) -> Self {
+        serde_json::from_value(config.clone()).unwrap_or_default()
+    }
+
+    /// Overrides of a domain's uptime monitor, the defaults if it has none
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the domain's monitors cannot be loaded
+    pub async fn for_domain(pool: &PgPool, domain_id: Uuid) -> AppResult<Self> {
+        Ok(queries::list_domain_monitors(pool, domain_id)
+            .await?
+            .into_iter()
+            .find(|m| matches!(m.monitor_type, MonitorType::Uptime))
+            .map(|m| Self::from_monitor_config(&m.config))
+            .unwrap_or_default())
+    }
+}
+
+/// Factory for the shared outbound HTTP clients used by all checks
+///
+/// Clients are built once from `HttpConfig` and reused so that connections are
+/// pooled across checks. Since reqwest fixes the redirect policy per client, one
+/// client is cached for every distinct redirect limit requested by a monitor.
+pub struct HttpClientFactory {
+    config: HttpConfig,
+    ca_certs: Vec<Certificate>,
+    default_client: Client,
+    clients: RwLock<HashMap<u32, Client>>,
+}
+
+impl HttpClientFactory {
+    /// Build the factory and its default client from configuration
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the CA bundle cannot be read or the proxy URL is invalid
+    pub fn from_config(config: &HttpConfig) -> AppResult<Self> {
+        let ca_certs = match &config.ca_bundle_path {
+            Some(path) => {
+                let pem = std::fs::read(path).map_err(|e| {
+                    AppError::internal(format!("Failed to read CA bundle {}: {}", path, e))
+                })?;
+                Certificate::from_pem_bundle(&pem)
+                    .map_err(|e| AppError::internal(format!("Invalid CA bundle {}: {}", path, e)))?
+            }
+            None => Vec::new(),
+        };
+
+        let default_client = build_client(config, &ca_certs, config.max_redirects)?;
+
+        Ok(Self {
+            config: config.clone(),
+            ca_certs,
+            default_client,
+            clients: RwLock::new(HashMap::new()),
+        })
+    }
+
+    /// Get a client honoring the redirect limit of the given overrides
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a new client has to be built and building it fails
+    pub fn client(&self, overrides: &HttpOverrides) -> AppResult<Client> {
+        let max_redirects = match overrides.max_redirects {
+            Some(n) if n != self.config.max_redirects => n,
+            _ => return Ok(self.default_client.clone()),
+        };
+
+        if let Some(client) = self.clients.read().ok().and_then(|c| c.get(&max_redirects).cloned()) {
+            return Ok(client);
+        }
+
+        let client = build_client(&self.config, &self.ca_certs, max_redirects)?;
+        if let Ok(mut clients) = self.clients.write() {
+            clients.entry(max_redirects).or_insert_with(|| client.clone());
+        }
+
+        Ok(client)
+    }
+
+    /// Start a GET request with per-monitor timeout and user agent applied
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the underlying client cannot be built
+    pub fn get(&self, url: &str, overrides: &HttpOverrides) -> AppResult<RequestBuilder> {
+        let mut request = self.client(overrides)?.get(url);
+
+        if let Some(secs) = overrides.timeout_secs {
+            request = request.timeout(Duration::from_secs(secs));
+        }
+        if let Some(user_agent) = &overrides.user_agent {
+            request = request.header(reqwest::header::USER_AGENT, user_agent);
+        }
+
+        Ok(request)
+    }
+}
+
+/// Build a pooled client from configuration
+fn build_client(config: &HttpConfig, ca_certs: &[Certificate], max_redirects: u32) -> AppResult<Client> {
+    let redirect = if max_redirects == 0 {
+        reqwest::redirect::Policy::none()
+    } else {
+        reqwest::redirect::Policy::limited(max_redirects as usize)
+    };
+
+    let mut builder = Client::builder()
+        .timeout(config.timeout)
+        .connect_timeout(config.connect_timeout)
+        .redirect(redirect)
+        .user_agent(&config.user_agent)
+        .pool_max_idle_per_host(config.pool_max_idle_per_host)
+        .pool_idle_timeout(config.pool_idle_timeout)
+        .tcp_keepalive(Duration::from_secs(60));
+
+    // Binding to the unspecified address of a family restricts connections to it
+    builder = match config.ip_preference {
+        IpPreference::Any => builder,
+        IpPreference::Ipv4 => builder.local_address(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+        IpPreference::Ipv6 => builder.local_address(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+    };
+
+    if let Some(proxy_url) = &config.proxy {
+        let proxy = Proxy::all(proxy_url)
+            .map_err(|e| AppError::internal(format!("Invalid proxy URL {}: {}", proxy_url, e)))?;
+        builder = builder.proxy(proxy);
+    }
+
+    for cert in ca_certs {
+        builder = builder.add_root_certificate(cert.clone());
+    }
+
+    builder
+        .build()
+        .map_err(|e| AppError::internal(format!("Failed to create HTTP client: {}", e)))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use axum::{http::HeaderMap, response::Redirect, routing::get, Router};
+    use std::net::SocketAddr;
+
+    /// Configuration with the defaults of `Config::from_env`
+    pub(crate) fn config(ip_preference: IpPreference) -> HttpConfig {
+        HttpConfig {
+            timeout: Duration::from_secs(10),
+            max_redirects: 3,
+            user_agent: "WebGuard/1.0 (+https://webguard.io)".to_string(),
+            proxy: None,
+            ca_bundle_path: None,
+            ip_preference,
+            connect_timeout: Duration::from_secs(5),
+            pool_max_idle_per_host: 32,
+            pool_idle_timeout: Duration::from_secs(90),
+        }
+    }
+
+    /// Serve a local site to check on 127.0.0.1
+    pub(crate) async fn serve() -> SocketAddr {
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .route("/redirect", get(|| async { Redirect::temporary("/") }))
+            .route("/slow", get(|| async {
+                tokio::time::sleep(Duration::from_secs(3)).await;
+                "slow"
+            }))
+            .route("/user-agent", get(|headers: HeaderMap| async move {
+                headers[axum::http::header::USER_AGENT].to_str().unwrap_or_default().to_string()
+            }));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        addr
+    }
+
+    #[test]
+    fn test_overrides_from_monitor_config() {
+        let config = serde_json::json!({ "timeout_secs": 5, "max_redirects": 0, "path": "/health" });
+        let overrides = HttpOverrides::from_monitor_config(&config);
+        assert_eq!(overrides.timeout_secs, Some(5));
+        assert_eq!(overrides.max_redirects, Some(0));
+        assert_eq!(overrides.user_agent, None);
+
+        let overrides = HttpOverrides::from_monitor_config(&serde_json::json!({}));
+        assert_eq!(overrides, HttpOverrides::default());
+    }
+
+    #[tokio::test]
+    async fn test_overrides_applied() {
+        let addr = serve().await;
+        let http = HttpClientFactory::from_config(&config(IpPreference::Any)).unwrap();
+
+        let followed = http.get(&format!("http://{}/redirect", addr), &HttpOverrides::default()).unwrap()
+            .send().await.unwrap();
+        assert_eq!(followed.status(), reqwest::StatusCode::OK);
+
+        let no_redirects = HttpOverrides { max_redirects: Some(0), ..HttpOverrides::default() };
+        let response = http.get(&format!("http://{}/redirect", addr), &no_redirects).unwrap()
+            .send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::TEMPORARY_REDIRECT);
+
+        let user_agent = HttpOverrides { user_agent: Some("Probe/2.0".to_string()), ..HttpOverrides::default() };
+        let response = http.get(&format!("http://{}/user-agent", addr), &user_agent).unwrap()
+            .send().await.unwrap();
+        assert_eq!(response.text().await.unwrap(), "Probe/2.0");
+
+        let timeout = HttpOverrides { timeout_secs: Some(1), ..HttpOverrides::default() };
+        let error = http.get(&format!("http://{}/slow", addr), &timeout).unwrap()
+            .send().await.unwrap_err();
+        assert!(error.is_timeout());
+    }
+
+    #[tokio::test]
+    async fn test_ip_preference() {
+        let addr = serve().await;
+        let url = format!("http://{}/", addr);
+
+        let ipv4 = HttpClientFactory::from_config(&config(IpPreference::Ipv4)).unwrap();
+        assert!(ipv4.get(&url, &HttpOverrides::default()).unwrap().send().await.is_ok());
+
+        // An IPv6-bound client can't reach an IPv4 address
+        let ipv6 = HttpClientFactory::from_config(&config(IpPreference::Ipv6)).unwrap();
+        assert!(ipv6.get(&url, &HttpOverrides::default()).unwrap().send().await.is_err());
+    }
+}
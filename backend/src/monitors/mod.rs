@@ -1,7 +1,14 @@
+pub mod aggregates;
+pub mod http;
+pub mod monitor_config;
 pub mod ssl;
 pub mod uptime;
+pub mod notify;
+pub mod retention;
+pub mod slo;
 pub mod scheduler;
 
+pub use http::*;
 pub use ssl::*;
 pub use uptime::*;
 pub use scheduler::*;
@@ -0,0 +1,96 @@
+//! Typed monitor settings
+//!
+//! `monitors.config` is stored as a JSON object whose keys depend on the
+//! monitor type. Configs are checked against the type's schema before they are
+//! saved, so that typos and out-of-range values are rejected instead of being
+//! silently ignored by the checks.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::db::models::MonitorType;
+
+/// Settings of an uptime monitor
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct UptimeConfig {
+    /// Seconds between checks; defaults to the scheduler's poll interval and
+    /// cannot be shorter than the organization's minimum
+    #[validate(range(min = 1, max = 86400))]
+    pub interval_seconds: Option<i32>,
+    /// Request timeout in seconds
+    #[validate(range(min = 1, max = 120))]
+    pub timeout_secs: Option<u64>,
+    /// Maximum number of redirects to follow (0 disables redirects)
+    #[validate(range(max = 20))]
+    pub max_redirects: Option<u32>,
+    /// Custom User-Agent header
+    #[validate(length(min = 1, max = 512))]
+    pub user_agent: Option<String>,
+}
+
+/// Settings of SSL certificate, DNS and security header monitors
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CheckConfig {
+    /// Seconds between checks; defaults to the scheduler's poll interval and
+    /// cannot be shorter than the organization's minimum
+    #[validate(range(min = 1, max = 86400))]
+    pub interval_seconds: Option<i32>,
+}
+
+/// Check the config of a monitor against the schema of its type
+///
+/// # Errors
+///
+/// Returns a message for a config that is not an object, has unknown keys or
+/// out-of-range values
+pub fn validate(monitor_type: &MonitorType, config: &Value) -> Result<(), String> {
+    if !config.is_object() {
+        return Err(format!("Configuration of monitor {} must be an object", monitor_type));
+    }
+
+    let result = match monitor_type {
+        MonitorType::Uptime => check::<UptimeConfig>(config),
+        MonitorType::SslCert | MonitorType::DomainDns | MonitorType::SecurityHeaders => check::<CheckConfig>(config),
+    };
+    result.map_err(|e| format!("Invalid configuration of monitor {}: {}", monitor_type, e))
+}
+
+fn check<T: serde::de::DeserializeOwned + Validate>(config: &Value) -> Result<(), String> {
+    let typed: T = serde_json::from_value(config.clone()).map_err(|e| e.to_string())?;
+    typed.validate().map_err(|e| e.to_string())
+}
+
+/// Interval set in a monitor's config, if any
+#[must_use]
+pub fn interval_seconds(config: &Value) -> Option<i32> {
+    config
+        .get("interval_seconds")
+        .and_then(Value::as_i64)
+        .and_then(|secs| i32::try_from(secs).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate_config() {
+        assert!(validate(&MonitorType::Uptime, &json!({})).is_ok());
+        assert!(validate(&MonitorType::Uptime, &json!({ "timeout_secs": 10, "interval_seconds": 300 })).is_ok());
+        assert!(validate(&MonitorType::SslCert, &json!({ "interval_seconds": 3600 })).is_ok());
+
+        assert!(validate(&MonitorType::Uptime, &json!([])).is_err());
+        assert!(validate(&MonitorType::Uptime, &json!({ "timeout": 10 })).is_err());
+        assert!(validate(&MonitorType::Uptime, &json!({ "timeout_secs": 0 })).is_err());
+        assert!(validate(&MonitorType::Uptime, &json!({ "max_redirects": "3" })).is_err());
+        assert!(validate(&MonitorType::SslCert, &json!({ "user_agent": "x" })).is_err());
+
+        assert_eq!(interval_seconds(&json!({ "interval_seconds": 300 })), Some(300));
+        assert_eq!(interval_seconds(&json!({})), None);
+    }
+}
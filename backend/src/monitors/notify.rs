@@ -0,0 +1,63 @@
+//! Alert delivery to webhooks
+//!
+//! Alerts are created without being sent; a periodic job posts each new alert
+//! to the webhooks of the alert routes matching its domain, or to the
+//! organization's webhook, and records the outcome on the alert.
+
+use reqwest::Client;
+use serde_json::json;
+use sqlx::PgPool;
+use std::time::Duration;
+
+use crate::config::WebhookConfig;
+use crate::db::models::Alert;
+use crate::db::queries;
+use crate::error::AppResult;
+use crate::monitors::{HttpClientFactory, HttpOverrides};
+
+/// Alerts older than this are not delivered anymore
+const MAX_ALERT_AGE_MINUTES: i32 = 60;
+/// Alerts delivered per run
+const BATCH_SIZE: i64 = 100;
+
+/// Deliver alerts that have not been sent yet, returning how many were processed
+pub async fn deliver_pending_alerts(pool: &PgPool, http: &HttpClientFactory, config: &WebhookConfig) -> AppResult<usize> {
+    let deliveries = queries::list_pending_alert_deliveries(pool, MAX_ALERT_AGE_MINUTES, BATCH_SIZE).await?;
+    let client = http.client(&HttpOverrides::default())?;
+
+    for delivery in &deliveries {
+        let mut success = true;
+        let mut status_code = None;
+
+        for url in &delivery.destinations {
+            let status = post_alert(&client, url, &delivery.alert, config).await;
+            success &= status.is_some_and(|s| s.is_success());
+            status_code = status.map(|s| i32::from(s.as_u16())).or(status_code);
+        }
+
+        queries::update_alert_webhook_status(pool, delivery.alert.id, success, status_code).await?;
+    }
+
+    Ok(deliveries.len())
+}
+
+/// Post an alert to a webhook, retrying failures; `None` if no response came back
+async fn post_alert(client: &Client, url: &str, alert: &Alert, config: &WebhookConfig) -> Option<reqwest::StatusCode> {
+    let payload = json!({ "event": "alert.created", "alert": alert });
+    let attempts = config.retry_attempts.max(1);
+    let mut status = None;
+
+    for attempt in 1..=attempts {
+        match client.post(url).timeout(config.timeout).json(&payload).send().await {
+            Ok(response) if response.status().is_success() => return Some(response.status()),
+            Ok(response) => status = Some(response.status()),
+            Err(e) => tracing::warn!("Failed to post alert {} to webhook: {}", alert.id, e),
+        }
+
+        if attempt < attempts {
+            tokio::time::sleep(Duration::from_secs(u64::from(attempt))).await;
+        }
+    }
+
+    status
+}
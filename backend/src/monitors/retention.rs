@@ -0,0 +1,104 @@
+use sqlx::PgPool;
+
+use crate::config::RetentionConfig;
+use crate::db::models::RetentionTarget;
+use crate::db::queries;
+use crate::error::AppResult;
+
+/// Number of future monthly uptime_snapshots partitions kept ready
+const PARTITIONS_AHEAD: i32 = 3;
+
+/// Global default retention (in days) for a target
+#[must_use]
+pub const fn default_days(config: &RetentionConfig, target: RetentionTarget) -> i32 {
+    match target {
+        RetentionTarget::UptimeSnapshots => config.uptime_snapshots_days,
+        RetentionTarget::SslCertSnapshots => config.ssl_cert_snapshots_days,
+        RetentionTarget::DomainDnsSnapshots => config.domain_dns_snapshots_days,
+        RetentionTarget::Alerts => config.alerts_days,
+        RetentionTarget::HourlyAggregates => config.hourly_aggregates_days,
+    }
+}
+
+/// Delete expired rows from one table in batches, returning the number deleted
+async fn prune_target(pool: &PgPool, config: &RetentionConfig, target: RetentionTarget) -> AppResult<u64> {
+    let days = default_days(config, target);
+    let mut total = 0;
+
+    loop {
+        let deleted = queries::prune_expired_rows(pool, target, days, config.batch_size).await?;
+        total += deleted;
+
+        // Keep batches short so pruning does not hold locks for long
+        if deleted < config.batch_size.unsigned_abs() {
+            return Ok(total);
+        }
+        tokio::task::yield_now().await;
+    }
+}
+
+/// Apply retention policies to all snapshot, alert and aggregate tables
+///
+/// Raw uptime snapshots and hourly aggregates are only deleted once the
+/// aggregates built from them exist. When partitioning is enabled, whole
+/// monthly partitions are dropped first, which is much cheaper than deleting.
+pub async fn prune_expired_data(pool: &PgPool, config: &RetentionConfig) -> AppResult<()> {
+    tracing::info!("Pruning expired monitoring data...");
+
+    if config.partition_uptime_snapshots {
+        queries::partition_uptime_snapshots(pool).await?;
+        queries::ensure_uptime_snapshot_partitions(pool, PARTITIONS_AHEAD).await?;
+
+        let dropped = queries::drop_expired_uptime_partitions(pool, config.uptime_snapshots_days).await?;
+        if dropped > 0 {
+            tracing::info!("Dropped {} expired uptime_snapshots partitions", dropped);
+        }
+    }
+
+    for target in RetentionTarget::ALL {
+        match prune_target(pool, config, target).await {
+            Ok(0) => {}
+            Ok(deleted) => tracing::info!("Pruned {} rows from {}", deleted, target),
+            Err(e) => tracing::error!("Failed to prune {}: {}", target, e),
+        }
+    }
+
+    match queries::delete_expired_revoked_access_tokens(pool).await {
+        Ok(0) => {}
+        Ok(deleted) => tracing::info!("Pruned {} expired access token denylist entries", deleted),
+        Err(e) => tracing::error!("Failed to prune access token denylist: {}", e),
+    }
+
+    match queries::delete_expired_user_tokens(pool).await {
+        Ok(0) => {}
+        Ok(deleted) => tracing::info!("Pruned {} expired password reset and verification tokens", deleted),
+        Err(e) => tracing::error!("Failed to prune user tokens: {}", e),
+    }
+
+    match queries::delete_expired_invitations(pool).await {
+        Ok(0) => {}
+        Ok(deleted) => tracing::info!("Pruned {} expired organization invitations", deleted),
+        Err(e) => tracing::error!("Failed to prune organization invitations: {}", e),
+    }
+
+    match queries::delete_expired_sso_login_states(pool).await {
+        Ok(0) => {}
+        Ok(deleted) => tracing::info!("Pruned {} expired single sign-on requests", deleted),
+        Err(e) => tracing::error!("Failed to prune single sign-on requests: {}", e),
+    }
+
+    match queries::delete_expired_sso_link_requests(pool).await {
+        Ok(0) => {}
+        Ok(deleted) => tracing::info!("Pruned {} expired single sign-on account links", deleted),
+        Err(e) => tracing::error!("Failed to prune single sign-on account links: {}", e),
+    }
+
+    match queries::purge_deleted_organizations(pool).await {
+        Ok(0) => {}
+        Ok(purged) => tracing::info!("Purged {} organizations past their deletion grace period", purged),
+        Err(e) => tracing::error!("Failed to purge deleted organizations: {}", e),
+    }
+
+    tracing::info!("Finished pruning expired monitoring data");
+    Ok(())
+}
@@ -1,42 +1,61 @@
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::{RwLock, Semaphore};
 use tokio::time::{interval, MissedTickBehavior};
 use uuid::Uuid;
 
 use crate::config::Config;
-use crate::db::models::{Monitor, MonitorType};
+use crate::db::models::{MonitorType, ScheduledMonitor};
 use crate::db::queries;
 use crate::error::AppResult;
-use crate::monitors::{check_ssl_certificate, check_uptime};
+use crate::events::{self, EventKind};
+use crate::monitors::{aggregates, monitor_config, notify, retention, slo, check_ssl_certificate, check_uptime, HttpClientFactory, HttpOverrides};
+
+/// Tolerance when deciding whether a monitor is due, so that a monitor whose
+/// interval equals the poll interval is not skipped because of timer jitter
+const DUE_SLACK: Duration = Duration::from_secs(1);
+
+/// Last time a monitor was scheduled
+#[derive(Debug, Clone, Copy)]
+struct LastScheduled {
+    at: Instant,
+    /// `updated_at` of the monitor at the time, to notice later changes
+    updated_at: DateTime<Utc>,
+}
 
 /// Task type for monitoring
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum MonitorTask {
     SslCheck { domain_id: Uuid, domain_name: String },
-    UptimeCheck { domain_id: Uuid, domain_name: String },
+    UptimeCheck { domain_id: Uuid, domain_name: String, http: HttpOverrides },
 }
 
 /// Task scheduler for monitoring
 pub struct MonitorScheduler {
     pool: PgPool,
     config: Config,
+    http: Arc<HttpClientFactory>,
     task_queue: Arc<RwLock<Vec<MonitorTask>>>,
     running_tasks: Arc<RwLock<HashMap<Uuid, bool>>>,
+    /// When each monitor was last scheduled
+    last_scheduled: Arc<RwLock<HashMap<Uuid, LastScheduled>>>,
     semaphore: Arc<Semaphore>,
 }
 
 impl MonitorScheduler {
     /// Create a new scheduler
-    pub fn new(pool: PgPool, config: Config) -> Self {
+    pub fn new(pool: PgPool, config: Config, http: Arc<HttpClientFactory>) -> Self {
         let max_concurrent = config.monitoring.max_concurrent_checks;
         Self {
             pool,
             config,
+            http,
             task_queue: Arc::new(RwLock::new(Vec::new())),
             running_tasks: Arc::new(RwLock::new(HashMap::new())),
+            last_scheduled: Arc::new(RwLock::new(HashMap::new())),
             semaphore: Arc::new(Semaphore::new(max_concurrent as usize)),
         }
     }
@@ -47,8 +66,8 @@ impl MonitorScheduler {
         let mut ticker = interval(Duration::from_secs(poll_interval_secs));
         ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
 
-        // Separate ticker for aggregate computation (every 30 minutes)
-        let mut aggregate_ticker = interval(Duration::from_secs(1800)); // 30 minutes
+        // Separate ticker for aggregate catch-up
+        let mut aggregate_ticker = interval(self.config.monitoring.aggregate_interval);
         aggregate_ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
 
         // Spawn aggregate computation task
@@ -56,9 +75,41 @@ impl MonitorScheduler {
         tokio::spawn(async move {
             loop {
                 aggregate_ticker.tick().await;
-                if let Err(e) = Self::compute_aggregates(pool_clone.clone()).await {
+                if let Err(e) = aggregates::catch_up_aggregates(&pool_clone).await {
                     eprintln!("Failed to compute aggregates: {}", e);
                 }
+                if let Err(e) = slo::evaluate_slos(&pool_clone).await {
+                    eprintln!("Failed to evaluate SLOs: {}", e);
+                }
+            }
+        });
+
+        // Spawn retention pruning task
+        let mut retention_ticker = interval(self.config.retention.interval);
+        retention_ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        let pool_clone = self.pool.clone();
+        let retention_config = self.config.retention.clone();
+        tokio::spawn(async move {
+            loop {
+                retention_ticker.tick().await;
+                if let Err(e) = retention::prune_expired_data(&pool_clone, &retention_config).await {
+                    eprintln!("Failed to prune expired data: {}", e);
+                }
+            }
+        });
+
+        // Spawn alert delivery task
+        let mut dispatch_ticker = interval(self.config.webhook.dispatch_interval);
+        dispatch_ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        let pool_clone = self.pool.clone();
+        let http = self.http.clone();
+        let webhook_config = self.config.webhook.clone();
+        tokio::spawn(async move {
+            loop {
+                dispatch_ticker.tick().await;
+                if let Err(e) = notify::deliver_pending_alerts(&pool_clone, &http, &webhook_config).await {
+                    eprintln!("Failed to deliver alerts: {}", e);
+                }
             }
         });
 
@@ -78,24 +129,35 @@ impl MonitorScheduler {
     }
 
     /// Load tasks from database and schedule them
+    ///
+    /// Monitors are scheduled once their check interval has elapsed since they
+    /// were last scheduled, or right away when they changed since then (e.g.
+    /// their domain's URL was edited).
     async fn load_and_schedule_tasks(&self) -> AppResult<()> {
         // Get all active monitors
         let monitors = queries::list_all_active_monitors(&self.pool).await?;
+        let now = Instant::now();
 
         let mut queue = self.task_queue.write().await;
         queue.clear();
 
-        for monitor in monitors {
-            // Get domain name
-            let domain = queries::find_domain_by_id(&self.pool, monitor.domain_id).await?;
-            let domain_name = match domain {
-                Some(d) => d.name,
-                None => {
-                    eprintln!("Domain not found for monitor: {}", monitor.domain_id);
-                    continue;
-                }
-            };
+        let mut last_scheduled = self.last_scheduled.write().await;
+        // Monitors that were deleted, disabled or paused start over when they come back
+        last_scheduled.retain(|id, _| monitors.iter().any(|m| m.monitor.id == *id));
+
+        for scheduled in monitors {
+            let due = last_scheduled
+                .get(&scheduled.monitor.id)
+                .is_none_or(|last| {
+                    last.updated_at != scheduled.monitor.updated_at
+                        || now.duration_since(last.at) + DUE_SLACK >= check_interval(&scheduled)
+                });
+            if !due {
+                continue;
+            }
 
+            let monitor = scheduled.monitor;
+            let domain_name = scheduled.url;
             let task = match monitor.monitor_type {
                 MonitorType::DomainDns => continue, // Skip DNS for now
                 MonitorType::SslCert => MonitorTask::SslCheck {
@@ -105,11 +167,13 @@ impl MonitorScheduler {
                 MonitorType::Uptime => MonitorTask::UptimeCheck {
                     domain_id: monitor.domain_id,
                     domain_name,
+                    http: HttpOverrides::from_monitor_config(&monitor.config),
                 },
                 MonitorType::SecurityHeaders => continue, // Skip for now
             };
 
             queue.push(task);
+            last_scheduled.insert(monitor.id, LastScheduled { at: now, updated_at: monitor.updated_at });
         }
 
         Ok(())
@@ -127,6 +191,7 @@ impl MonitorScheduler {
 
             let pool = self.pool.clone();
             let config = self.config.clone();
+            let http = self.http.clone();
             let running_tasks = self.running_tasks.clone();
 
             tokio::spawn(async move {
@@ -156,8 +221,8 @@ impl MonitorScheduler {
                     MonitorTask::SslCheck { domain_id, domain_name } => {
                         Self::execute_ssl_check(pool.clone(), *domain_id, domain_name).await
                     }
-                    MonitorTask::UptimeCheck { domain_id, domain_name } => {
-                        Self::execute_uptime_check(pool.clone(), *domain_id, domain_name, config).await
+                    MonitorTask::UptimeCheck { domain_id, domain_name, http: overrides } => {
+                        Self::execute_uptime_check(pool.clone(), &http, *domain_id, domain_name, overrides, config).await
                     }
                 };
 
@@ -196,10 +261,14 @@ impl MonitorScheduler {
             cert_info.days_until_expiry,
             cert_info.is_self_signed,
         ).await?;
+        events::publish(&pool, domain_id, EventKind::SslChecked {
+            is_expired: cert_info.is_expired,
+            days_until_expiry: cert_info.days_until_expiry,
+        }).await;
 
         // Check if cert is expiring soon and create alert
         if cert_info.days_until_expiry < 30 && cert_info.days_until_expiry > 0 {
-            queries::create_simple_alert(
+            raise_alert(
                 &pool,
                 domain_id,
                 "SSL Certificate Expiring Soon",
@@ -214,7 +283,7 @@ impl MonitorScheduler {
 
         // Create alert if expired
         if cert_info.is_expired {
-            queries::create_simple_alert(
+            raise_alert(
                 &pool,
                 domain_id,
                 "SSL Certificate Expired",
@@ -232,11 +301,14 @@ impl MonitorScheduler {
     /// Execute uptime check
     async fn execute_uptime_check(
         pool: PgPool,
+        http: &HttpClientFactory,
         domain_id: Uuid,
         domain_name: &str,
+        overrides: &HttpOverrides,
         config: Config,
     ) -> AppResult<()> {
-        let uptime_result = check_uptime(domain_name, None).await?;
+        let uptime_result = check_uptime(http, domain_name, None, overrides).await?;
+        let was_up = queries::get_latest_uptime_snapshot(&pool, domain_id).await?.map(|s| s.is_up);
 
         // Save uptime snapshot
         queries::create_uptime_snapshot(
@@ -247,10 +319,19 @@ impl MonitorScheduler {
             uptime_result.response_time_ms as i32,
             uptime_result.error_message.as_deref(),
         ).await?;
+        events::publish_uptime_check(
+            &pool,
+            domain_id,
+            was_up,
+            uptime_result.is_up,
+            uptime_result.status_code.map(|c| c as i32),
+            uptime_result.response_time_ms as i32,
+            uptime_result.error_message.clone(),
+        ).await;
 
         // Create alert if site is down
         if !uptime_result.is_up {
-            queries::create_simple_alert(
+            raise_alert(
                 &pool,
                 domain_id,
                 "Website Down",
@@ -265,7 +346,7 @@ impl MonitorScheduler {
 
         // Create alert if response time is high
         if uptime_result.response_time_ms > config.monitoring.slow_threshold_ms {
-            queries::create_simple_alert(
+            raise_alert(
                 &pool,
                 domain_id,
                 "Slow Response Time",
@@ -290,86 +371,35 @@ impl MonitorScheduler {
             }
         });
 
-        // Trigger uptime check
+        // Trigger uptime check, with the same overrides as scheduled checks
+        let overrides = HttpOverrides::for_domain(&self.pool, domain_id).await?;
         let pool = self.pool.clone();
         let domain_name_clone2 = domain_name.to_string();
         let config = self.config.clone();
+        let http = self.http.clone();
         tokio::spawn(async move {
-            if let Err(e) = Self::execute_uptime_check(pool.clone(), domain_id, &domain_name_clone2, config).await {
+            if let Err(e) = Self::execute_uptime_check(pool.clone(), &http, domain_id, &domain_name_clone2, &overrides, config).await {
                 eprintln!("Manual uptime check failed: {}", e);
             }
         });
 
         Ok(())
     }
+}
 
-    /// Compute and save uptime aggregates for all active domains
-    async fn compute_aggregates(pool: PgPool) -> AppResult<()> {
-        use chrono::{Datelike, Timelike, Utc};
-
-        tracing::info!("Computing uptime aggregates...");
-
-        // Get all active domains
-        let domains = queries::list_all_active_domains(&pool).await?;
-
-        let now = Utc::now();
-
-        for domain in domains {
-            // Compute hourly aggregate for the last completed hour
-            let current_hour = now.with_minute(0).and_then(|dt| dt.with_second(0)).and_then(|dt| dt.with_nanosecond(0));
-            if let Some(hour_end) = current_hour {
-                let hour_start = hour_end - chrono::Duration::hours(1);
-
-                if let Err(e) = queries::compute_uptime_aggregate(
-                    &pool,
-                    domain.id,
-                    hour_start,
-                    hour_end,
-                    "hour",
-                ).await {
-                    eprintln!("Failed to compute hourly aggregate for domain {}: {}", domain.id, e);
-                }
-            }
-
-            // Compute daily aggregate for the last completed day (once per day)
-            // Only compute if it's the first hour of the day
-            if now.hour() == 0 {
-                if let Some(day_end) = now.date_naive().and_hms_opt(0, 0, 0) {
-                    let day_end = day_end.and_utc();
-                    let day_start = day_end - chrono::Duration::days(1);
-
-                    if let Err(e) = queries::compute_uptime_aggregate(
-                        &pool,
-                        domain.id,
-                        day_start,
-                        day_end,
-                        "day",
-                    ).await {
-                        eprintln!("Failed to compute daily aggregate for domain {}: {}", domain.id, e);
-                    }
-                }
-            }
+/// Create an alert about a domain and publish it to event subscribers
+async fn raise_alert(pool: &PgPool, domain_id: Uuid, title: &str, description: &str) -> AppResult<()> {
+    let alert = queries::create_simple_alert(pool, domain_id, title, description).await?;
+    events::publish_alert(pool, &alert).await;
+    Ok(())
+}
 
-            // Compute weekly aggregate (once per week on Sunday)
-            if now.weekday() == chrono::Weekday::Sun && now.hour() == 0 {
-                if let Some(week_end) = now.date_naive().and_hms_opt(0, 0, 0) {
-                    let week_end = week_end.and_utc();
-                    let week_start = week_end - chrono::Duration::weeks(1);
-
-                    if let Err(e) = queries::compute_uptime_aggregate(
-                        &pool,
-                        domain.id,
-                        week_start,
-                        week_end,
-                        "week",
-                    ).await {
-                        eprintln!("Failed to compute weekly aggregate for domain {}: {}", domain.id, e);
-                    }
-                }
-            }
-        }
+/// Time between two checks of a monitor: its own interval, but never less than
+/// its organization allows
+fn check_interval(scheduled: &ScheduledMonitor) -> Duration {
+    let seconds = monitor_config::interval_seconds(&scheduled.monitor.config)
+        .unwrap_or(0)
+        .max(scheduled.min_check_interval_seconds);
 
-        tracing::info!("Finished computing uptime aggregates");
-        Ok(())
-    }
+    Duration::from_secs(u64::try_from(seconds).unwrap_or(0))
 }
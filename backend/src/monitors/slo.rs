@@ -0,0 +1,184 @@
+use sqlx::PgPool;
+
+use crate::db::models::{AlertSeverity, BurnState, Slo, SloCounts, SloSnapshot};
+use crate::db::queries;
+use crate::error::AppResult;
+use crate::events;
+
+/// Rate at which the error budget is consumed over a window
+///
+/// A burn rate of 1 exhausts the budget exactly at the end of the SLO window.
+#[must_use]
+pub fn burn_rate(total: i64, good: i64, target: f64) -> f64 {
+    if total <= 0 {
+        return 0.0;
+    }
+
+    let bad_fraction = (total - good) as f64 / total as f64;
+    let allowed_fraction = 1.0 - target / 100.0;
+
+    bad_fraction / allowed_fraction
+}
+
+/// Evaluate an SLO from its check counts
+///
+/// Returns `None` when no hourly aggregates exist yet.
+#[must_use]
+pub fn evaluate(slo: &Slo, counts: &SloCounts) -> Option<SloSnapshot> {
+    let as_of = counts.as_of?;
+    let target = slo.availability_target;
+
+    let burn_rate_1h = burn_rate(counts.total_checks_1h, counts.good_checks_1h, target);
+    let burn_rate_6h = burn_rate(counts.total_checks_6h, counts.good_checks_6h, target);
+
+    let burn_state = if burn_rate_1h >= slo.fast_burn_threshold {
+        BurnState::FastBurn
+    } else if burn_rate_6h >= slo.slow_burn_threshold {
+        BurnState::SlowBurn
+    } else {
+        BurnState::Ok
+    };
+
+    let sli_percentage = (counts.total_checks > 0)
+        .then(|| counts.good_checks as f64 / counts.total_checks as f64 * 100.0);
+
+    Some(SloSnapshot {
+        as_of,
+        total_checks: counts.total_checks,
+        good_checks: counts.good_checks,
+        sli_percentage,
+        error_budget_remaining: (1.0 - burn_rate(counts.total_checks, counts.good_checks, target)) * 100.0,
+        burn_rate_1h,
+        burn_rate_6h,
+        burn_state,
+    })
+}
+
+/// Raise an alert when an SLO starts burning faster, or has recovered
+async fn alert_on_change(pool: &PgPool, slo: &Slo, snapshot: &SloSnapshot) -> AppResult<()> {
+    let (severity, title) = match snapshot.burn_state {
+        BurnState::FastBurn => (AlertSeverity::Critical, format!("SLO \"{}\" is burning its error budget fast", slo.name)),
+        BurnState::SlowBurn => (AlertSeverity::Warning, format!("SLO \"{}\" is burning its error budget", slo.name)),
+        BurnState::Ok => (AlertSeverity::Info, format!("SLO \"{}\" recovered", slo.name)),
+    };
+
+    // Only escalations and recoveries are worth notifying about
+    if snapshot.burn_state == BurnState::Ok || snapshot.burn_state > slo.burn_state {
+        let domain = queries::find_domain_by_id(pool, slo.domain_id).await?;
+        if let Some(domain) = domain {
+            let description = format!(
+                "Burn rate {:.1}x over 1h, {:.1}x over 6h; {:.1}% of the error budget remaining",
+                snapshot.burn_rate_1h, snapshot.burn_rate_6h, snapshot.error_budget_remaining
+            );
+            let metadata = serde_json::json!({
+                "slo_id": slo.id,
+                "burn_state": snapshot.burn_state,
+                "burn_rate_1h": snapshot.burn_rate_1h,
+                "burn_rate_6h": snapshot.burn_rate_6h,
+                "error_budget_remaining": snapshot.error_budget_remaining,
+            });
+
+            let alert = queries::create_alert(
+                pool,
+                domain.organization_id,
+                domain.id,
+                "slo_burn",
+                severity,
+                &title,
+                Some(&description),
+                &metadata,
+            ).await?;
+            events::publish_alert(pool, &alert).await;
+        }
+    }
+
+    queries::set_slo_burn_state(pool, slo.id, snapshot.burn_state).await
+}
+
+/// Evaluate all enabled SLOs, record their history and alert on burn rate changes
+///
+/// Should run after the hourly aggregates are caught up.
+pub async fn evaluate_slos(pool: &PgPool) -> AppResult<()> {
+    let slos = queries::list_enabled_slos(pool).await?;
+
+    for slo in slos {
+        let counts = match queries::get_slo_counts(pool, &slo).await {
+            Ok(counts) => counts,
+            Err(e) => {
+                tracing::error!("Failed to evaluate SLO {}: {}", slo.id, e);
+                continue;
+            }
+        };
+
+        let Some(snapshot) = evaluate(&slo, &counts) else {
+            continue;
+        };
+
+        if let Err(e) = queries::save_slo_snapshot(pool, slo.id, &snapshot).await {
+            tracing::error!("Failed to save SLO snapshot for {}: {}", slo.id, e);
+            continue;
+        }
+
+        if snapshot.burn_state != slo.burn_state {
+            if let Err(e) = alert_on_change(pool, &slo, &snapshot).await {
+                tracing::error!("Failed to alert on SLO {}: {}", slo.id, e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::LatencyPercentile;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    fn slo() -> Slo {
+        Slo {
+            id: Uuid::new_v4(),
+            domain_id: Uuid::new_v4(),
+            name: "availability".to_string(),
+            availability_target: 99.0,
+            latency_threshold_ms: None,
+            latency_percentile: LatencyPercentile::P95,
+            window_days: 30,
+            fast_burn_threshold: 14.4,
+            slow_burn_threshold: 6.0,
+            is_enabled: true,
+            burn_state: BurnState::Ok,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_burn_rate() {
+        assert_eq!(burn_rate(0, 0, 99.0), 0.0);
+        assert!((burn_rate(100, 99, 99.0) - 1.0).abs() < 1e-9);
+        assert!((burn_rate(100, 90, 99.0) - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_evaluate() {
+        assert!(evaluate(&slo(), &SloCounts::default()).is_none());
+
+        let counts = SloCounts {
+            as_of: Some(Utc::now()),
+            total_checks: 1000,
+            good_checks: 995,
+            total_checks_1h: 60,
+            good_checks_1h: 60,
+            total_checks_6h: 360,
+            good_checks_6h: 330,
+        };
+        let snapshot = evaluate(&slo(), &counts).unwrap();
+        assert!((snapshot.error_budget_remaining - 50.0).abs() < 1e-9);
+        assert_eq!(snapshot.burn_state, BurnState::SlowBurn);
+
+        let counts = SloCounts { good_checks_1h: 50, ..counts };
+        assert_eq!(evaluate(&slo(), &counts).unwrap().burn_state, BurnState::FastBurn);
+    }
+}
@@ -1,9 +1,10 @@
 use chrono::{DateTime, Utc};
 use serde::{Serialize, Deserialize};
-use std::time::{Duration, Instant};
+use std::time::Instant;
 use thiserror::Error;
 
-use crate::error::{AppError, AppResult};
+use crate::error::AppResult;
+use crate::monitors::http::{HttpClientFactory, HttpOverrides};
 
 /// Uptime check result
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,7 +29,12 @@ pub enum UptimeError {
 }
 
 /// Check if a domain is up and responding
-pub async fn check_uptime(domain: &str, path: Option<&str>) -> AppResult<UptimeCheckResult> {
+pub async fn check_uptime(
+    http: &HttpClientFactory,
+    domain: &str,
+    path: Option<&str>,
+    overrides: &HttpOverrides,
+) -> AppResult<UptimeCheckResult> {
     let domain = domain.trim().trim_start_matches("https://").trim_start_matches("http://");
 
     let path = path.unwrap_or("/");
@@ -36,19 +42,12 @@ pub async fn check_uptime(domain: &str, path: Option<&str>) -> AppResult<UptimeC
 
     let start = Instant::now();
 
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(10))
-        .redirect(reqwest::redirect::Policy::limited(3))
-        .user_agent("WebGuard-Monitor/1.0")
-        .build()
-        .map_err(|e| AppError::internal(format!("Failed to create HTTP client: {}", e)))?;
-
-    let response = match client.get(&url).send().await {
+    let response = match http.get(&url, overrides)?.send().await {
         Ok(resp) => resp,
-        Err(e) => {
+        Err(_) => {
             // Try HTTP if HTTPS fails
             let http_url = format!("http://{}{}", domain, path);
-            match client.get(&http_url).send().await {
+            match http.get(&http_url, overrides)?.send().await {
                 Ok(resp) => resp,
                 Err(http_e) => {
                     return Ok(UptimeCheckResult {
@@ -82,13 +81,15 @@ pub async fn check_uptime(domain: &str, path: Option<&str>) -> AppResult<UptimeC
 
 /// Check multiple endpoints for a domain
 pub async fn check_multiple_endpoints(
+    http: &HttpClientFactory,
     domain: &str,
     paths: &[&str],
+    overrides: &HttpOverrides,
 ) -> AppResult<Vec<UptimeCheckResult>> {
     let mut results = Vec::new();
 
     for path in paths {
-        match check_uptime(domain, Some(path)).await {
+        match check_uptime(http, domain, Some(path), overrides).await {
             Ok(result) => results.push(result),
             Err(e) => {
                 results.push(UptimeCheckResult {
@@ -110,10 +111,14 @@ pub async fn check_multiple_endpoints(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::IpPreference;
+    use crate::monitors::http::tests::{config, serve};
 
     #[tokio::test]
     async fn test_check_uptime() {
-        let result = check_uptime("google.com", None).await;
+        let addr = serve().await;
+        let http = HttpClientFactory::from_config(&config(IpPreference::Any)).expect("Failed to build HTTP client");
+        let result = check_uptime(&http, &addr.to_string(), None, &HttpOverrides::default()).await;
         assert!(result.is_ok());
         let uptime = result.unwrap();
         assert!(uptime.is_up);
@@ -0,0 +1,134 @@
+//! Per-organization quotas
+//!
+//! Limits live on the organization row and are set by the operator. Creating
+//! anything that counts against a quota fails with [`AppError::QuotaExceeded`]
+//! when the limit would be exceeded. The queries creating monitors and API keys
+//! enforce the count quotas in their transaction while holding a lock on the
+//! organization row, so concurrent requests can't exceed a quota together;
+//! [`ensure_monitor_capacity`] only reports early, e.g. for dry runs.
+
+use sqlx::PgPool;
+
+use crate::db::models::{NewMonitor, Organization, OrganizationUsage, QuotaUsage, RetentionPolicy};
+use crate::db::queries;
+use crate::error::{AppError, AppResult};
+use crate::monitors::monitor_config;
+
+/// Current usage of an organization against its limits
+pub async fn usage(pool: &PgPool, organization: &Organization) -> AppResult<OrganizationUsage> {
+    let by_type = queries::count_monitors_by_type(pool, organization.id).await?;
+    let api_keys = queries::count_usable_api_keys(pool, organization.id).await?;
+
+    Ok(OrganizationUsage {
+        monitors: QuotaUsage {
+            used: by_type.iter().map(|(_, count)| count).sum(),
+            limit: i64::from(organization.max_monitors),
+        },
+        monitors_by_type: by_type.into_iter().map(|(t, count)| (t.to_string(), count)).collect(),
+        api_keys: QuotaUsage {
+            used: api_keys,
+            limit: i64::from(organization.max_api_keys),
+        },
+        min_check_interval_seconds: organization.min_check_interval_seconds,
+        max_retention_days: organization.max_retention_days,
+    })
+}
+
+/// Make sure `additional` monitors currently fit in the organization's monitor quota
+pub async fn ensure_monitor_capacity(pool: &PgPool, organization: &Organization, additional: i64) -> AppResult<()> {
+    let used: i64 = queries::count_monitors_by_type(pool, organization.id).await?
+        .iter()
+        .map(|(_, count)| count)
+        .sum();
+
+    check_monitor_quota(&QuotaUsage { used, limit: i64::from(organization.max_monitors) }, additional)
+}
+
+/// Make sure `additional` monitors fit in a monitor quota
+pub fn check_monitor_quota(quota: &QuotaUsage, additional: i64) -> AppResult<()> {
+    if !quota.allows(additional) {
+        return Err(AppError::quota_exceeded(
+            format!("This organization is limited to {} monitors", quota.limit),
+            "max_monitors",
+            quota.limit,
+        ));
+    }
+
+    Ok(())
+}
+
+/// Make sure one more API key fits in an API key quota
+pub fn check_api_key_quota(quota: &QuotaUsage) -> AppResult<()> {
+    if !quota.allows(1) {
+        return Err(AppError::quota_exceeded(
+            format!("This organization is limited to {} API keys", quota.limit),
+            "max_api_keys",
+            quota.limit,
+        ));
+    }
+
+    Ok(())
+}
+
+/// Make sure a monitor check interval is not shorter than the organization allows
+pub fn check_interval(organization: &Organization, interval_seconds: i32) -> AppResult<()> {
+    if interval_seconds < organization.min_check_interval_seconds {
+        return Err(AppError::quota_exceeded(
+            format!(
+                "Monitors of this organization cannot be checked more often than every {} seconds",
+                organization.min_check_interval_seconds
+            ),
+            "min_check_interval_seconds",
+            i64::from(organization.min_check_interval_seconds),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Make sure none of the monitors to create is checked more often than the
+/// organization allows
+pub fn check_monitor_intervals<'a>(
+    organization: &Organization,
+    monitors: impl IntoIterator<Item = &'a NewMonitor>,
+) -> AppResult<()> {
+    monitors
+        .into_iter()
+        .filter_map(|monitor| monitor_config::interval_seconds(&monitor.config))
+        .try_for_each(|interval| check_interval(organization, interval))
+}
+
+/// Make sure no retention override exceeds the organization's maximum
+pub fn check_retention(organization: &Organization, policy: &RetentionPolicy) -> AppResult<()> {
+    let days = [
+        policy.uptime_snapshots_days,
+        policy.ssl_cert_snapshots_days,
+        policy.domain_dns_snapshots_days,
+        policy.alerts_days,
+        policy.hourly_aggregates_days,
+    ];
+
+    if days.iter().flatten().any(|&d| d > organization.max_retention_days) {
+        return Err(AppError::quota_exceeded(
+            format!("Data of this organization cannot be kept longer than {} days", organization.max_retention_days),
+            "max_retention_days",
+            i64::from(organization.max_retention_days),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quota_allows_up_to_limit() {
+        let quota = QuotaUsage { used: 8, limit: 10 };
+
+        assert!(quota.allows(2));
+        assert!(!quota.allows(3));
+        assert!(!QuotaUsage { used: 12, limit: 10 }.allows(0));
+    }
+}
@@ -0,0 +1,136 @@
+//! Domain tags
+//!
+//! Tags are key/value labels stored as a JSON object on the domain. Listings
+//! filter on them with a [`TagSelector`], written as comma-separated `key` or
+//! `key=value` terms.
+
+use serde_json::Value;
+
+use crate::db::models::Tags;
+use crate::error::{AppError, AppResult};
+
+/// Maximum number of tags on a domain
+pub const MAX_TAGS: usize = 50;
+const MAX_KEY_LEN: usize = 63;
+const MAX_VALUE_LEN: usize = 255;
+
+/// Check that a tag key is non-empty and only uses letters, digits and `-_./:`
+///
+/// # Errors
+///
+/// Returns a validation error for an invalid key
+pub fn validate_key(key: &str) -> AppResult<()> {
+    let valid = !key.is_empty()
+        && key.len() <= MAX_KEY_LEN
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || "-_./:".contains(c));
+
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::validation(format!(
+            "Invalid tag key \"{}\": use up to {} letters, digits or -_./:",
+            key, MAX_KEY_LEN
+        )))
+    }
+}
+
+/// Check the tags of a domain
+///
+/// # Errors
+///
+/// Returns a validation error for an invalid key, a value that is too long or
+/// too many tags
+pub fn validate(tags: &Tags) -> AppResult<()> {
+    if tags.len() > MAX_TAGS {
+        return Err(AppError::validation(format!("A domain can have at most {} tags", MAX_TAGS)));
+    }
+    for (key, value) in tags {
+        validate_key(key)?;
+        if value.len() > MAX_VALUE_LEN {
+            return Err(AppError::validation(format!(
+                "Value of tag \"{}\" is longer than {} characters",
+                key, MAX_VALUE_LEN
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Tags a domain must have to match a filter
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagSelector {
+    /// Tags that must have the given value
+    pub values: Tags,
+    /// Tags that must be present with any value
+    pub keys: Vec<String>,
+}
+
+impl TagSelector {
+    /// Parse a filter such as `env=prod,team`
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error for an invalid key
+    pub fn parse(filter: Option<&str>) -> AppResult<Self> {
+        let mut selector = Self::default();
+
+        for term in filter.unwrap_or_default().split(',').map(str::trim).filter(|t| !t.is_empty()) {
+            match term.split_once('=') {
+                Some((key, value)) => {
+                    validate_key(key.trim())?;
+                    selector.values.insert(key.trim().to_string(), value.trim().to_string());
+                }
+                None => {
+                    validate_key(term)?;
+                    selector.keys.push(term.to_string());
+                }
+            }
+        }
+
+        Ok(selector)
+    }
+
+    /// Tags with values as a JSON object for `@>`, `None` when there are none
+    #[must_use]
+    pub fn values_json(&self) -> Option<Value> {
+        (!self.values.is_empty()).then(|| serde_json::to_value(&self.values).unwrap_or_default())
+    }
+
+    /// Required keys for `?&`, `None` when there are none
+    #[must_use]
+    pub fn keys(&self) -> Option<&[String]> {
+        (!self.keys.is_empty()).then_some(self.keys.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_selector() {
+        let selector = TagSelector::parse(Some("env=prod, team ,tier = 1")).unwrap();
+
+        assert_eq!(selector.keys, vec!["team"]);
+        assert_eq!(selector.values.get("env").map(String::as_str), Some("prod"));
+        assert_eq!(selector.values.get("tier").map(String::as_str), Some("1"));
+        assert_eq!(selector.values_json(), Some(serde_json::json!({ "env": "prod", "tier": "1" })));
+
+        assert_eq!(TagSelector::parse(None).unwrap(), TagSelector::default());
+        assert!(TagSelector::parse(Some("bad key=1")).is_err());
+    }
+
+    #[test]
+    fn test_validate_tags() {
+        let mut tags = Tags::new();
+        tags.insert("k8s.io/cluster".to_string(), "eu-1".to_string());
+        assert!(validate(&tags).is_ok());
+
+        tags.insert("owner".to_string(), "x".repeat(MAX_VALUE_LEN + 1));
+        assert!(validate(&tags).is_err());
+
+        let too_many: Tags = (0..=MAX_TAGS).map(|i| (format!("k{}", i), String::new())).collect();
+        assert!(validate(&too_many).is_err());
+    }
+}